@@ -1,4 +1,7 @@
-use crate::routers::{accounts, auth, transactions, users};
+use crate::routers::{
+    accounts, audit, auth, banks, boletos, employers, fees, holds, interest, limits, overdrafts,
+    payments, pix_keys, reconciliation, remittances, schedules, statements, transactions, users,
+};
 use utoipa::{
     openapi::security::{Http, HttpAuthScheme, SecurityScheme},
     Modify, OpenApi,
@@ -13,12 +16,69 @@ use utoipa::{
         users::create_user,
         users::update_user,
         users::delete_user,
+        users::restore_user,
         accounts::get_accounts,
         accounts::get_account,
         accounts::create_account,
+        accounts::update_account,
         accounts::delete_account,
+        accounts::freeze_account,
+        accounts::unfreeze_account,
+        accounts::close_account,
+        accounts::restore_account,
         transactions::get_account_transactions,
         transactions::create_account_transaction,
+        transactions::create_transaction_from_qr,
+        transactions::create_transaction_batch,
+        transactions::import_payment_file,
+        transactions::reverse_transaction,
+        holds::create_hold,
+        holds::get_hold,
+        holds::get_account_holds,
+        holds::capture_hold,
+        holds::release_hold,
+        schedules::get_schedules,
+        schedules::get_schedule,
+        schedules::create_schedule,
+        schedules::update_schedule,
+        schedules::delete_schedule,
+        interest::get_interest_products,
+        interest::create_interest_product,
+        interest::get_account_interest,
+        interest::assign_account_interest,
+        fees::get_fee_schedules,
+        fees::get_fee_schedule,
+        fees::create_fee_schedule,
+        fees::update_fee_schedule,
+        fees::delete_fee_schedule,
+        overdrafts::update_account_overdraft,
+        limits::get_limits,
+        limits::get_limit,
+        limits::create_limit,
+        limits::update_limit,
+        limits::delete_limit,
+        statements::get_account_statement,
+        reconciliation::reconcile_accounts,
+        reconciliation::reconcile_account,
+        remittances::create_remittance,
+        remittances::process_return_file,
+        pix_keys::get_pix_keys,
+        pix_keys::create_pix_key,
+        pix_keys::delete_pix_key,
+        payments::create_qr_code,
+        boletos::get_account_boletos,
+        boletos::create_boleto,
+        boletos::get_account_boleto_payments,
+        boletos::validate_boleto,
+        boletos::pay_boleto,
+        banks::get_banks,
+        banks::get_bank,
+        banks::import_banks,
+        employers::get_employers,
+        employers::create_employer,
+        employers::delete_employer,
+        audit::get_audit_records,
+        audit::verify_audit_log,
     ),
     modifiers(&SecurityAddon),
 )]
@@ -1,3 +1,4 @@
+use database::validation::FieldError;
 use serde::Serialize;
 use utoipa::ToSchema;
 
@@ -6,3 +7,12 @@ pub struct ValidationField {
     pub field: String,
     pub message: String,
 }
+
+impl From<FieldError> for ValidationField {
+    fn from(error: FieldError) -> Self {
+        Self {
+            field: error.field.to_string(),
+            message: error.message,
+        }
+    }
+}
@@ -10,14 +10,33 @@ use axum::{
     response::IntoResponse,
     Json, Router,
 };
-use database::{get_database_pool, load_master_key};
+use database::{
+    get_database_pool, load_master_key,
+    services::{
+        account::Service as AccountService, journal::Service as JournalService,
+        transaction::Service as TransactionService,
+    },
+};
 use dotenv::dotenv;
 use http::response::HttpResponse;
-use middlewares::auth::auth;
+use middlewares::{
+    audit::{audit, REQUEST_ID_HEADER},
+    auth::auth,
+};
 use routers::{
-    accounts::get_router as get_accounts_router, auth::get_router as get_auth_router,
+    accounts::get_router as get_accounts_router, audit::get_router as get_audit_router,
+    auth::get_router as get_auth_router, banks::get_router as get_banks_router,
+    boletos::get_router as get_boletos_router, employers::get_router as get_employers_router,
+    fees::get_router as get_fees_router, holds::get_router as get_holds_router,
+    interest::get_router as get_interest_router, limits::get_router as get_limits_router,
+    overdrafts::get_router as get_overdrafts_router, payments::get_router as get_payments_router,
+    pix_keys::get_router as get_pix_keys_router,
+    reconciliation::get_router as get_reconciliation_router,
+    remittances::get_router as get_remittances_router,
+    schedules::get_router as get_schedules_router, statements::get_router as get_statements_router,
     transactions::get_router as get_transactions_router, users::get_router as get_users_router,
 };
+use sqlx::PgPool;
 use state::application::ApplicationState;
 
 #[cfg(not(target_env = "msvc"))]
@@ -87,14 +106,41 @@ async fn async_main(threads: usize) {
 
     let db_pool = get_database_pool(min, max).await;
     let master_key: Vec<u8> = load_master_key().expect("Failed to load master key");
+    upgrade_legacy_data(&db_pool).await;
     let jwt_key = std::env::var("JWT_KEY")
         .unwrap_or("eaccbdc5-dd87-40dc-a998-6a6fa26a5fa5.simple_bank_api".to_string());
 
-    let app_state = Arc::new(ApplicationState::new(db_pool, master_key, jwt_key));
+    let idempotency_key_ttl = std::env::var("IDEMPOTENCY_KEY_TTL_SECS")
+        .unwrap_or((24 * 60 * 60).to_string())
+        .parse::<i64>()
+        .map(chrono::Duration::seconds)
+        .expect("IDEMPOTENCY_KEY_TTL_SECS must be a number of seconds");
+
+    let app_state = Arc::new(ApplicationState::new(
+        db_pool,
+        master_key,
+        jwt_key,
+        idempotency_key_ttl,
+    ));
 
     let user_router = get_users_router();
     let accounts_router = get_accounts_router();
     let transactions_router = get_transactions_router();
+    let holds_router = get_holds_router();
+    let schedules_router = get_schedules_router();
+    let interest_router = get_interest_router();
+    let fees_router = get_fees_router();
+    let overdrafts_router = get_overdrafts_router();
+    let limits_router = get_limits_router();
+    let statements_router = get_statements_router();
+    let reconciliation_router = get_reconciliation_router();
+    let remittances_router = get_remittances_router();
+    let pix_keys_router = get_pix_keys_router();
+    let payments_router = get_payments_router();
+    let boletos_router = get_boletos_router();
+    let banks_router = get_banks_router();
+    let employers_router = get_employers_router();
+    let audit_router = get_audit_router();
     let auth_router = get_auth_router();
 
     let openapi_router = Router::new()
@@ -106,6 +152,25 @@ async fn async_main(threads: usize) {
         .merge(user_router)
         .merge(accounts_router)
         .merge(transactions_router)
+        .merge(holds_router)
+        .merge(schedules_router)
+        .merge(interest_router)
+        .merge(fees_router)
+        .merge(overdrafts_router)
+        .merge(limits_router)
+        .merge(statements_router)
+        .merge(reconciliation_router)
+        .merge(remittances_router)
+        .merge(pix_keys_router)
+        .merge(payments_router)
+        .merge(boletos_router)
+        .merge(banks_router)
+        .merge(employers_router)
+        .merge(audit_router)
+        .route_layer(axum_middleware::from_fn_with_state(
+            app_state.clone(),
+            audit,
+        ))
         .layer(axum_middleware::from_fn_with_state(app_state.clone(), auth));
 
     let unprotected_routers = Router::new().merge(auth_router);
@@ -128,6 +193,39 @@ async fn async_main(threads: usize) {
     handle.abort();
 }
 
+async fn upgrade_legacy_data(db_pool: &PgPool) {
+    let mut tx = db_pool.begin().await.expect("Failed to begin transaction");
+
+    let accounts = AccountService::new()
+        .upgrade_legacy_balances(db_pool, &mut tx)
+        .await
+        .expect("Failed to upgrade legacy account balances");
+    let transactions = TransactionService::new()
+        .upgrade_legacy_amounts(db_pool, &mut tx)
+        .await
+        .expect("Failed to upgrade legacy transaction amounts");
+
+    let journaled = JournalService::new()
+        .backfill(db_pool, &mut tx)
+        .await
+        .expect("Failed to backfill journal entries");
+
+    tx.commit().await.expect("Failed to commit data upgrade");
+
+    if accounts > 0 || transactions > 0 {
+        println!(
+            "Re-encrypted {} account balances and {} transaction amounts as minor units.",
+            accounts, transactions
+        );
+    }
+    if journaled > 0 {
+        println!(
+            "Wrote journal entries for {} earlier transactions.",
+            journaled
+        );
+    }
+}
+
 async fn deal_with_it() -> (StatusCode, Json<HttpResponse>) {
     (
         StatusCode::NOT_FOUND,
@@ -160,6 +258,7 @@ async fn address_serve(rt: Router, threads: usize) -> impl IntoResponse {
                 ACCESS_CONTROL_ALLOW_ORIGIN,
                 REFERER,
                 HeaderName::from_static("api_scopes"),
+                REQUEST_ID_HEADER,
             ]),
         None => CorsLayer::new()
             .allow_origin(Any)
@@ -178,6 +277,7 @@ async fn address_serve(rt: Router, threads: usize) -> impl IntoResponse {
                 ACCESS_CONTROL_ALLOW_ORIGIN,
                 REFERER,
                 HeaderName::from_static("api_scopes"),
+                REQUEST_ID_HEADER,
             ]),
     };
     let address = env::var("BIND_ADDRESS").expect("BIND_ADDRESS must be set.");
@@ -194,7 +294,13 @@ async fn address_serve(rt: Router, threads: usize) -> impl IntoResponse {
         .await
         .expect("Failed to bind to server address.");
 
-    axum::serve(listener, rt.layer(concurrency_limit_layer).layer(cors))
-        .await
-        .unwrap()
+    // the audit log records the address each request came from
+    axum::serve(
+        listener,
+        rt.layer(concurrency_limit_layer)
+            .layer(cors)
+            .into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap()
 }
@@ -0,0 +1,179 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, MatchedPath, RawPathParams, Request, State},
+    http::{HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use database::{
+    models::{
+        audit_dto::{AuditEntry, AuditTarget},
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::audit::Service as AuditService,
+};
+use uuid::Uuid;
+
+use crate::state::application::ApplicationState;
+
+/// Where the routers are nested, which audit actions leave out.
+const API_PREFIX: &str = "/api/v1";
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Routes under each prefix act on the record their `:id` names, or on the one they create.
+const TARGETS: [(&str, AuditTarget); 9] = [
+    ("/accounts", AuditTarget::Account),
+    ("/fees/schedules", AuditTarget::FeeSchedule),
+    ("/holds", AuditTarget::Hold),
+    ("/interest/products", AuditTarget::InterestProduct),
+    ("/limits", AuditTarget::TransactionLimit),
+    ("/pix-keys", AuditTarget::PixKey),
+    ("/schedules", AuditTarget::Schedule),
+    ("/transactions", AuditTarget::Transaction),
+    ("/users", AuditTarget::User),
+];
+
+fn find_target(route: &str) -> Option<AuditTarget> {
+    TARGETS.iter().find_map(|(prefix, target)| {
+        route
+            .strip_prefix(prefix)
+            .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+            .map(|_| *target)
+    })
+}
+
+/// Records every request that can change something, along with the target's row before and
+/// after it, once the handler has answered.
+///
+/// Runs after `auth`, so the actor is known, and as a route layer, so the route is. Requests
+/// carry their `X-Request-Id` into the record, or are given one, and it is echoed back.
+///
+/// The record is written after the handler has committed, so it covers failed requests too,
+/// and the snapshots are read outside the handler's transaction: a change another request
+/// commits between the read of `before` and the handler shows up in `before` as well. A
+/// record that cannot be written, even after retrying, is kept aside in `audit_failures`
+/// instead, and the request still gets the handler's response, since its change stands.
+pub async fn audit(
+    State(state): State<Arc<ApplicationState>>,
+    matched_path: MatchedPath,
+    params: RawPathParams,
+    req: Request,
+    next: Next,
+) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+
+    let route = matched_path.as_str();
+    let route = route.strip_prefix(API_PREFIX).unwrap_or(route);
+    let target = find_target(route);
+    let mut target_id = params
+        .iter()
+        .find(|(name, _)| *name == "id")
+        .and_then(|(_, value)| Uuid::parse_str(value).ok())
+        .filter(|_| target.is_some());
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 255)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::now_v7().to_string());
+    let action = format!("{} {}", req.method(), route);
+    let actor_id = req.extensions().get::<User>().map(|user| user.id);
+    let scopes = req
+        .extensions()
+        .get::<Vec<String>>()
+        .cloned()
+        .unwrap_or_default();
+    let ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip().to_string());
+
+    let audit_service = AuditService::new();
+    let before = match (target, target_id) {
+        (Some(target), Some(id)) => {
+            audit_service
+                .get_snapshot(&state.db_pool, target, &id)
+                .await
+        }
+        _ => None,
+    };
+
+    let mut response = next.run(req).await;
+
+    // creates name their record only in the response
+    if target.is_some() && target_id.is_none() && response.status().is_success() {
+        let (parts, body) = response.into_parts();
+        let bytes = match to_bytes(body, usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+        target_id = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .ok()
+            .and_then(|body| {
+                body.get("id")?
+                    .as_str()
+                    .and_then(|id| Uuid::parse_str(id).ok())
+            });
+        response = Response::from_parts(parts, Body::from(bytes));
+    }
+
+    let after = match (target, target_id) {
+        (Some(target), Some(id)) => {
+            audit_service
+                .get_snapshot(&state.db_pool, target, &id)
+                .await
+        }
+        _ => None,
+    };
+    let entry = AuditEntry {
+        actor_id,
+        scopes,
+        action,
+        target,
+        target_id,
+        status: response.status().as_u16(),
+        before,
+        after,
+        ip,
+        request_id: request_id.clone(),
+    };
+
+    let recorded = with_transaction_retry(&state.db_pool, |tx| {
+        let entry = entry.clone();
+        let master_key = state.master_key.clone();
+
+        Box::pin(async move { AuditService::new().record(tx, &entry, &master_key).await })
+    })
+    .await;
+    if let Err(e) = recorded {
+        let error = format!("{:#}", e);
+        let kept = with_transaction_retry(&state.db_pool, |tx| {
+            let entry = entry.clone();
+            let error = error.clone();
+            let master_key = state.master_key.clone();
+
+            Box::pin(async move {
+                AuditService::new()
+                    .record_failure(tx, &entry, &error, &master_key)
+                    .await
+            })
+        })
+        .await;
+        if kept.is_err() {
+            // if we had a logging system, we would log the error here
+        }
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
@@ -104,12 +104,13 @@ pub async fn auth(
         .get_one_by_id(&state.db_pool, &payload.user_id)
         .await
     {
-        Some(user) => {
+        // deleted users are signed out along with their tokens
+        Some(user) if user.deleted_at.is_none() => {
             req.extensions_mut().insert(user);
             req.extensions_mut().insert(payload.scopes);
             Ok(next.run(req).await)
         }
-        None => Err((
+        _ => Err((
             StatusCode::UNAUTHORIZED,
             Json(HttpResponse {
                 status: StatusCode::UNAUTHORIZED.as_u16(),
@@ -1,4 +1,19 @@
 pub mod accounts;
+pub mod audit;
 pub mod auth;
+pub mod banks;
+pub mod boletos;
+pub mod employers;
+pub mod fees;
+pub mod holds;
+pub mod interest;
+pub mod limits;
+pub mod overdrafts;
+pub mod payments;
+pub mod pix_keys;
+pub mod reconciliation;
+pub mod remittances;
+pub mod schedules;
+pub mod statements;
 pub mod transactions;
 pub mod users;
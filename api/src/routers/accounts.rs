@@ -3,29 +3,83 @@ use std::sync::Arc;
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    routing::get,
+    routing::{get, post},
     Extension, Json, Router,
 };
 use database::{
     filters::account::Filter as AccountFilter,
     models::{
-        account_dto::{AccountCreate, AccountModel},
+        account_dto::{
+            Account, AccountClose, AccountCreate, AccountModel, AccountStatusError,
+            AccountTypeError, AccountUpdate,
+        },
         user_dto::User,
     },
-    services::{account::Service as AccountService, user::Service as UserService},
+    retry::with_transaction_retry,
+    services::{
+        account::Service as AccountService, hold::Service as HoldService,
+        user::Service as UserService,
+    },
+    validation::InvalidBankDetails,
 };
 use futures::{stream, StreamExt};
 use uuid::Uuid;
 
 use crate::{
-    http::response::{HttpPaginatedResponse, HttpResponse, ReturnTypes},
+    http::{
+        response::{HttpPaginatedResponse, HttpResponse, ReturnTypes},
+        validation::ValidationField,
+    },
     state::application::ApplicationState,
 };
 
 pub fn get_router() -> Router<Arc<ApplicationState>> {
     Router::new()
         .route("/accounts", get(get_accounts).post(create_account))
-        .route("/accounts/:id", get(get_account).delete(delete_account))
+        .route(
+            "/accounts/:id",
+            get(get_account).put(update_account).delete(delete_account),
+        )
+        .route("/accounts/:id/freeze", post(freeze_account))
+        .route("/accounts/:id/unfreeze", post(unfreeze_account))
+        .route("/accounts/:id/close", post(close_account))
+        .route("/accounts/:id/restore", post(restore_account))
+}
+
+/// Bank details with wrong check digits come back as 400 along with the fields at fault, and
+/// a status change the account cannot make as 409.
+fn account_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let (status, message, fields) = match e.downcast::<InvalidBankDetails>() {
+        Ok(InvalidBankDetails(errors)) => (
+            StatusCode::BAD_REQUEST,
+            "Invalid bank details".to_string(),
+            Some(errors.into_iter().map(ValidationField::from).collect()),
+        ),
+        Err(e) => {
+            let status = match e.downcast_ref::<AccountStatusError>() {
+                Some(AccountStatusError::InvalidTransition { .. }) => StatusCode::CONFLICT,
+                Some(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                None if e.downcast_ref::<AccountTypeError>().is_some() => {
+                    StatusCode::UNPROCESSABLE_ENTITY
+                }
+                None => match e.downcast_ref::<sqlx::Error>() {
+                    Some(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+                    _ if e.to_string() == "Account not found" => StatusCode::NOT_FOUND,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                },
+            };
+            let message = match status {
+                StatusCode::NOT_FOUND => "Account not found".to_string(),
+                _ => e.to_string(),
+            };
+            (status, message, None)
+        }
+    };
+
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, fields)),
+    )
 }
 
 #[utoipa::path(
@@ -35,6 +89,7 @@ pub fn get_router() -> Router<Arc<ApplicationState>> {
     params(
         ("id" = Option<Uuid>, Query, description = "Account ID"),
         ("user_id" = Option<Uuid>, Query, description = "User ID"),
+        ("include_deleted" = Option<bool>, Query, description = "List deleted accounts too; admins only"),
         ("offset" = Option<usize>, Query, description = "Pagination offset"),
         ("limit" = Option<usize>, Query, description = "Pagination limit"),
     ),
@@ -58,6 +113,7 @@ pub async fn get_accounts(
             .await
             .unwrap();
         filters.user_id = Some(user.id);
+        filters.include_deleted = None;
     }
     filters.enforce_pagination();
 
@@ -74,7 +130,13 @@ pub async fn get_accounts(
                     .get_one_by_id(&db_pool, &account.user_id)
                     .await
                     .unwrap();
-                AccountModel::from_dto(&account, &user).unwrap()
+                let held = HoldService::new()
+                    .get_held_amount(&db_pool, &account, &user)
+                    .await
+                    .unwrap();
+                AccountModel::from_dto(&account, &user)
+                    .unwrap()
+                    .with_held_amount(held)
             }
         })
         .buffered(10)
@@ -98,6 +160,7 @@ pub async fn get_accounts(
     request_body = AccountCreate,
     responses(
         (status = 200, description = "Successful response", body = ReturnTypes<AccountModel>),
+        (status = 400, description = "Invalid bank details", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Invalid bank details", "fields": [{"field": "bank_account_digit", "message": "Check digit does not match the account number"}]}"#)),
         (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
     ),
 )]
@@ -126,14 +189,7 @@ pub async fn create_account(
         }
         Err(e) => {
             tx.rollback().await.unwrap();
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(HttpResponse::new(
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                    e.to_string(),
-                    None,
-                )),
-            ))
+            Err(account_error(e))
         }
     }
 }
@@ -158,7 +214,11 @@ pub async fn get_account(
     let account_service = AccountService::new();
     let user_service = UserService::new();
 
-    match account_service.get_one_by_id(&state.db_pool, &id).await {
+    match account_service
+        .get_one_by_id(&state.db_pool, &id)
+        .await
+        .filter(|account| account.deleted_at.is_none() || scopes.contains(&"admin".to_string()))
+    {
         Some(account) => {
             if !scopes.contains(&"admin".to_string()) {
                 let user = user_service
@@ -182,7 +242,13 @@ pub async fn get_account(
                 .get_one_by_id(&state.db_pool, &account.user_id)
                 .await
                 .unwrap();
-            let account_model = AccountModel::from_dto(&account, &user).unwrap();
+            let held = HoldService::new()
+                .get_held_amount(&state.db_pool, &account, &user)
+                .await
+                .unwrap();
+            let account_model = AccountModel::from_dto(&account, &user)
+                .unwrap()
+                .with_held_amount(held);
             Ok(Json(ReturnTypes::Single(account_model)))
         }
         None => Err((
@@ -197,76 +263,320 @@ pub async fn get_account(
 }
 
 #[utoipa::path(
-    delete,
+    put,
     path = "/accounts/:id",
     context_path = "/api/v1",
     params(("id" = Uuid, Path, description = "Account ID")),
+    request_body = AccountUpdate,
     responses(
-        (status = 204, description = "Account deleted", body = HttpResponse, example = json!(r#"{"status": 200, "message": "Account deleted"}"#)),
+        (status = 200, description = "Successful response", body = ReturnTypes<AccountModel>),
+        (status = 400, description = "Invalid bank details", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Invalid bank details", "fields": [{"field": "bank_agency_digit", "message": "Check digit does not match the agency number"}]}"#)),
         (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
         (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
         (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
     ),
 )]
-pub async fn delete_account(
+pub async fn update_account(
     State(state): State<Arc<ApplicationState>>,
     Extension(current_user): Extension<User>,
     Extension(scopes): Extension<Vec<String>>,
     Path(id): Path<Uuid>,
-) -> Result<Json<HttpResponse>, (StatusCode, Json<HttpResponse>)> {
-    let mut tx = state.db_pool.begin().await.unwrap();
-    let user_service = UserService::new();
+    Json(update): Json<AccountUpdate>,
+) -> Result<Json<ReturnTypes<AccountModel>>, (StatusCode, Json<HttpResponse>)> {
     let account_service = AccountService::new();
+    let user_service = UserService::new();
 
-    match account_service.get_one_by_id(&state.db_pool, &id).await {
-        Some(account) => {
-            if !scopes.contains(&"admin".to_string()) {
-                let user = user_service
-                    .get_one_by_id(&state.db_pool, &current_user.id)
-                    .await
-                    .unwrap();
+    let Some(account) = account_service
+        .get_one_by_id(&state.db_pool, &id)
+        .await
+        .filter(|account| account.deleted_at.is_none() || scopes.contains(&"admin".to_string()))
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(HttpResponse::new(
+                StatusCode::NOT_FOUND.as_u16(),
+                "Account not found".to_string(),
+                None,
+            )),
+        ));
+    };
 
-                if user.id != account.user_id {
-                    return Err((
-                        StatusCode::FORBIDDEN,
-                        Json(HttpResponse::new(
-                            StatusCode::FORBIDDEN.as_u16(),
-                            "Forbidden".to_string(),
-                            None,
-                        )),
-                    ));
-                }
-            }
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(HttpResponse::new(
+                StatusCode::FORBIDDEN.as_u16(),
+                "Forbidden".to_string(),
+                None,
+            )),
+        ));
+    }
 
-            match account_service.delete(&mut tx, &id).await {
-                true => {
-                    tx.commit().await.unwrap();
-                    Ok(Json(HttpResponse::new(
-                        StatusCode::OK.as_u16(),
-                        "Account deleted".to_string(),
-                        None,
-                    )))
-                }
-                false => {
-                    tx.rollback().await.unwrap();
-                    Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(HttpResponse::new(
-                            StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                            "Account not deleted".to_string(),
-                            None,
-                        )),
-                    ))
-                }
-            }
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match account_service.update(&mut tx, &id, &update).await {
+        Ok(account) => {
+            tx.commit().await.unwrap();
+            let user = user_service
+                .get_one_by_id(&state.db_pool, &account.user_id)
+                .await
+                .unwrap();
+            let held = HoldService::new()
+                .get_held_amount(&state.db_pool, &account, &user)
+                .await
+                .unwrap();
+            let account_model = AccountModel::from_dto(&account, &user)
+                .unwrap()
+                .with_held_amount(held);
+            Ok(Json(ReturnTypes::Single(account_model)))
         }
-        None => Err((
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            Err(account_error(e))
+        }
+    }
+}
+
+async fn account_model(state: &ApplicationState, account: &Account) -> AccountModel {
+    let user = UserService::new()
+        .get_one_by_id(&state.db_pool, &account.user_id)
+        .await
+        .unwrap();
+    let held = HoldService::new()
+        .get_held_amount(&state.db_pool, account, &user)
+        .await
+        .unwrap();
+    AccountModel::from_dto(account, &user)
+        .unwrap()
+        .with_held_amount(held)
+}
+
+/// Checks the account exists and, unless an admin is asking, belongs to the current user and
+/// was not deleted.
+async fn authorize(
+    state: &ApplicationState,
+    current_user: &User,
+    scopes: &[String],
+    id: &Uuid,
+) -> Result<Account, (StatusCode, Json<HttpResponse>)> {
+    let Some(account) = AccountService::new()
+        .get_one_by_id(&state.db_pool, id)
+        .await
+    else {
+        return Err((
             StatusCode::NOT_FOUND,
             Json(HttpResponse::new(
                 StatusCode::NOT_FOUND.as_u16(),
                 "Account not found".to_string(),
                 None,
             )),
+        ));
+    };
+
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(HttpResponse::new(
+                StatusCode::FORBIDDEN.as_u16(),
+                "Forbidden".to_string(),
+                None,
+            )),
+        ));
+    }
+
+    Ok(account)
+}
+
+fn forbid_non_admin(scopes: &[String]) -> Result<(), (StatusCode, Json<HttpResponse>)> {
+    match scopes.contains(&"admin".to_string()) {
+        true => Ok(()),
+        false => Err((
+            StatusCode::FORBIDDEN,
+            Json(HttpResponse::new(
+                StatusCode::FORBIDDEN.as_u16(),
+                "Forbidden".to_string(),
+                None,
+            )),
         )),
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/accounts/:id/freeze",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Nothing can be taken out of the account until it is unfrozen", body = ReturnTypes<AccountModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 409, description = "The account cannot be frozen", body = HttpResponse, example = json!(r#"{"status": 409, "message": "A closed account cannot become frozen"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn freeze_account(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<AccountModel>>, (StatusCode, Json<HttpResponse>)> {
+    forbid_non_admin(&scopes)?;
+
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match AccountService::new().freeze(&mut tx, &id).await {
+        Ok(account) => {
+            tx.commit().await.unwrap();
+            Ok(Json(ReturnTypes::Single(
+                account_model(&state, &account).await,
+            )))
+        }
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            Err(account_error(e))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/accounts/:id/unfreeze",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "The account is active again", body = ReturnTypes<AccountModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 409, description = "The account is not frozen", body = HttpResponse, example = json!(r#"{"status": 409, "message": "A closing account cannot become active"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn unfreeze_account(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<AccountModel>>, (StatusCode, Json<HttpResponse>)> {
+    forbid_non_admin(&scopes)?;
+
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match AccountService::new().unfreeze(&mut tx, &id).await {
+        Ok(account) => {
+            tx.commit().await.unwrap();
+            Ok(Json(ReturnTypes::Single(
+                account_model(&state, &account).await,
+            )))
+        }
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            Err(account_error(e))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/accounts/:id/close",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    request_body = AccountClose,
+    responses(
+        (status = 200, description = "The account is closed, or closing while funds are held on it", body = ReturnTypes<AccountModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 409, description = "The account is already closed", body = HttpResponse, example = json!(r#"{"status": 409, "message": "A closed account cannot become closing"}"#)),
+        (status = 422, description = "The balance cannot be paid out", body = HttpResponse, example = json!(r#"{"status": 422, "message": "The balance must be paid out to another account before closing"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn close_account(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+    Json(close): Json<AccountClose>,
+) -> Result<Json<ReturnTypes<AccountModel>>, (StatusCode, Json<HttpResponse>)> {
+    authorize(&state, &current_user, &scopes, &id).await?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let db_pool = state.db_pool.clone();
+        let close = close.clone();
+        Box::pin(async move { AccountService::new().close(&db_pool, tx, &id, &close).await })
+    })
+    .await;
+
+    match result {
+        Ok(account) => Ok(Json(ReturnTypes::Single(
+            account_model(&state, &account).await,
+        ))),
+        Err(e) => Err(account_error(e)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/accounts/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Account closed and deleted; its history is kept", body = HttpResponse, example = json!(r#"{"status": 200, "message": "Account deleted"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 422, description = "The account still has funds", body = HttpResponse, example = json!(r#"{"status": 422, "message": "The balance must be paid out to another account before closing"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn delete_account(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<HttpResponse>, (StatusCode, Json<HttpResponse>)> {
+    authorize(&state, &current_user, &scopes, &id).await?;
+
+    // an account with a balance is closed through /close first
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let db_pool = state.db_pool.clone();
+        Box::pin(async move { AccountService::new().delete(&db_pool, tx, &id).await })
+    })
+    .await;
+
+    match result {
+        Ok(_) => Ok(Json(HttpResponse::new(
+            StatusCode::OK.as_u16(),
+            "Account deleted".to_string(),
+            None,
+        ))),
+        Err(e) => Err(account_error(e)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/accounts/:id/restore",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "The account is listed again; it stays closed", body = ReturnTypes<AccountModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn restore_account(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<AccountModel>>, (StatusCode, Json<HttpResponse>)> {
+    forbid_non_admin(&scopes)?;
+
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match AccountService::new().restore(&mut tx, &id).await {
+        Ok(account) => {
+            tx.commit().await.unwrap();
+            Ok(Json(ReturnTypes::Single(
+                account_model(&state, &account).await,
+            )))
+        }
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            Err(account_error(e))
+        }
+    }
+}
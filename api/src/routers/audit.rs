@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+    Extension, Json, Router,
+};
+use database::{
+    filters::audit::Filter as AuditFilter,
+    models::{
+        audit_dto::{AuditRecord, AuditVerification},
+        user_dto::User,
+    },
+    services::audit::Service as AuditService,
+};
+
+use crate::{
+    http::response::{HttpPaginatedResponse, HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route("/audit", get(get_audit_records))
+        .route("/audit/verify", get(verify_audit_log))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit",
+    context_path = "/api/v1",
+    params(
+        ("actor_id" = Option<Uuid>, Query, description = "User who made the request"),
+        ("action" = Option<String>, Query, description = "Method and route, such as `POST /accounts/:id/close`"),
+        ("target_type" = Option<String>, Query, description = "Table of the record acted on, such as `accounts`"),
+        ("target_id" = Option<Uuid>, Query, description = "ID of the record acted on"),
+        ("request_id" = Option<String>, Query, description = "Request ID"),
+        ("offset" = Option<usize>, Query, description = "Pagination offset"),
+        ("limit" = Option<usize>, Query, description = "Pagination limit"),
+    ),
+    responses(
+        (status = 200, description = "Audit records, newest first", body = ReturnTypes<AuditRecord>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_audit_records(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Query(mut filters): Query<AuditFilter>,
+) -> Result<Json<ReturnTypes<AuditRecord>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+    filters.enforce_pagination();
+
+    let (records, total) = AuditService::new().get_all(&state.db_pool, &filters).await;
+
+    match filters.offset {
+        Some(offset) => {
+            let paginated = HttpPaginatedResponse::new(records, offset, filters.limit, total);
+            Ok(Json(ReturnTypes::Paginated(paginated)))
+        }
+        None => Ok(Json(ReturnTypes::Multiple(records))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit/verify",
+    context_path = "/api/v1",
+    responses(
+        (status = 200, description = "Whether every audit record still follows from the one before it", body = ReturnTypes<AuditVerification>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn verify_audit_log(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+) -> Result<Json<ReturnTypes<AuditVerification>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    match AuditService::new().verify(&state.db_pool).await {
+        Ok(verification) => Ok(Json(ReturnTypes::Single(verification))),
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use database::{
+    filters::bank::Filter as BankFilter,
+    models::bank_dto::{Bank, BankError, BankImportReport},
+    retry::with_transaction_retry,
+    services::bank::Service as BankService,
+};
+
+use crate::{
+    http::response::{HttpPaginatedResponse, HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route("/banks", get(get_banks))
+        .route("/banks/:id", get(get_bank))
+        .route("/banks/import", post(import_banks))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/banks",
+    context_path = "/api/v1",
+    params(
+        ("id" = Option<i32>, Query, description = "COMPE code"),
+        ("ispb" = Option<String>, Query, description = "ISPB code"),
+        ("active" = Option<bool>, Query, description = "Only banks still operating, or only those that stopped"),
+        ("search" = Option<String>, Query, description = "Part of the short or full name of the bank"),
+        ("offset" = Option<usize>, Query, description = "Pagination offset"),
+        ("limit" = Option<usize>, Query, description = "Pagination limit"),
+    ),
+    responses(
+        (status = 200, description = "Banks by COMPE code", body = ReturnTypes<Bank>),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_banks(
+    State(state): State<Arc<ApplicationState>>,
+    Query(mut filters): Query<BankFilter>,
+) -> Result<Json<ReturnTypes<Bank>>, (StatusCode, Json<HttpResponse>)> {
+    filters.enforce_pagination();
+
+    let (banks, total) = BankService::new().get_all(&state.db_pool, &filters).await;
+
+    match filters.offset {
+        Some(offset) => {
+            let paginated = HttpPaginatedResponse::new(banks, offset, filters.limit, total);
+            Ok(Json(ReturnTypes::Paginated(paginated)))
+        }
+        None => Ok(Json(ReturnTypes::Multiple(banks))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/banks/:id",
+    context_path = "/api/v1",
+    params(("id" = i32, Path, description = "COMPE code")),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<Bank>),
+        (status = 404, description = "Bank not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Bank not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_bank(
+    State(state): State<Arc<ApplicationState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<ReturnTypes<Bank>>, (StatusCode, Json<HttpResponse>)> {
+    match BankService::new().get_one_by_id(&state.db_pool, id).await {
+        Some(bank) => Ok(Json(ReturnTypes::Single(bank))),
+        None => Err(error(StatusCode::NOT_FOUND, "Bank not found".to_string())),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/banks/import",
+    context_path = "/api/v1",
+    request_body(content = String, description = "The participant list of the central bank, ParticipantesSTR.csv", content_type = "text/csv"),
+    responses(
+        (status = 200, description = "What the refresh changed; banks no longer listed are deactivated", body = ReturnTypes<BankImportReport>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Line 3 of the registry: \"0000000A\" is not an ISPB"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn import_banks(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
+    body: String,
+) -> Result<Json<ReturnTypes<BankImportReport>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let body = body.clone();
+        Box::pin(async move { BankService::new().import(tx, &body).await })
+    })
+    .await;
+
+    match result {
+        Ok(report) => Ok(Json(ReturnTypes::Single(report))),
+        Err(e) => {
+            let status = match e.downcast_ref::<BankError>() {
+                Some(_) => StatusCode::BAD_REQUEST,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            Err(error(status, e.to_string()))
+        }
+    }
+}
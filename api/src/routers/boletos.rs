@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        account_dto::{AccountStatusError, AccountTypeError},
+        boleto_dto::{
+            BarcodeError, BoletoCreate, BoletoError, BoletoLookup, BoletoModel, BoletoPayment,
+            BoletoPaymentCreate, BoletoQuote,
+        },
+        limit_dto::LimitExceeded,
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::{account::Service as AccountService, boleto::Service as BoletoService},
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route(
+            "/accounts/:id/boletos",
+            get(get_account_boletos).post(create_boleto),
+        )
+        .route(
+            "/accounts/:id/boletos/payments",
+            get(get_account_boleto_payments),
+        )
+        .route("/boletos/validate", post(validate_boleto))
+        .route("/boletos/payments", post(pay_boleto))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn boleto_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<BoletoError>() {
+        Some(BoletoError::AlreadyPaid) => StatusCode::CONFLICT,
+        Some(BoletoError::MissingBankDetails) => StatusCode::UNPROCESSABLE_ENTITY,
+        Some(_) => StatusCode::BAD_REQUEST,
+        None if e.downcast_ref::<BarcodeError>().is_some() => StatusCode::BAD_REQUEST,
+        None if e.downcast_ref::<LimitExceeded>().is_some()
+            || e.downcast_ref::<AccountTypeError>().is_some()
+            || e.downcast_ref::<AccountStatusError>().is_some() =>
+        {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(StatusCode::NOT_FOUND, "Account not found".to_string())
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+/// Refuses anyone but the owner of `account_id` or an admin.
+async fn authorize(
+    state: &ApplicationState,
+    current_user: &User,
+    scopes: &[String],
+    account_id: &Uuid,
+) -> Result<(), (StatusCode, Json<HttpResponse>)> {
+    let account = AccountService::new()
+        .get_one_by_id(&state.db_pool, account_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/:id/boletos",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Slips the account issued, newest first", body = ReturnTypes<BoletoModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_account_boletos(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<BoletoModel>>, (StatusCode, Json<HttpResponse>)> {
+    authorize(&state, &current_user, &scopes, &account_id).await?;
+
+    let boletos = BoletoService::new()
+        .get_all_by_account_id(&state.db_pool, &account_id)
+        .await
+        .into_iter()
+        .map(BoletoModel::from)
+        .collect();
+
+    Ok(Json(ReturnTypes::Multiple(boletos)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/accounts/:id/boletos",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    request_body = BoletoCreate,
+    responses(
+        (status = 200, description = "The slip, with the typeable line to hand to the payer", body = ReturnTypes<BoletoModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Slips cannot be due in the past"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 422, description = "The account is not held at a bank", body = HttpResponse, example = json!(r#"{"status": 422, "message": "Slips are issued on accounts with a bank, agency and account number"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_boleto(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+    Json(boleto): Json<BoletoCreate>,
+) -> Result<Json<ReturnTypes<BoletoModel>>, (StatusCode, Json<HttpResponse>)> {
+    authorize(&state, &current_user, &scopes, &account_id).await?;
+
+    let today = chrono::Utc::now().date_naive();
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let boleto = boleto.clone();
+        Box::pin(async move {
+            BoletoService::new()
+                .issue(tx, &account_id, &boleto, today)
+                .await
+        })
+    })
+    .await;
+
+    match result {
+        Ok(boleto) => Ok(Json(ReturnTypes::Single(BoletoModel::from(boleto)))),
+        Err(e) => Err(boleto_error(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/:id/boletos/payments",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Slips the account paid, newest first", body = ReturnTypes<BoletoPayment>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_account_boleto_payments(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<BoletoPayment>>, (StatusCode, Json<HttpResponse>)> {
+    authorize(&state, &current_user, &scopes, &account_id).await?;
+
+    let payments = BoletoService::new()
+        .get_payments_by_account_id(&state.db_pool, &account_id)
+        .await;
+
+    Ok(Json(ReturnTypes::Multiple(payments)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/boletos/validate",
+    context_path = "/api/v1",
+    request_body = BoletoLookup,
+    responses(
+        (status = 200, description = "What paying the slip today takes", body = ReturnTypes<BoletoQuote>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Check digit of field 2 of the typeable line does not match"}"#)),
+        (status = 409, description = "Already paid", body = HttpResponse, example = json!(r#"{"status": 409, "message": "The slip has already been paid"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn validate_boleto(
+    State(state): State<Arc<ApplicationState>>,
+    Json(lookup): Json<BoletoLookup>,
+) -> Result<Json<ReturnTypes<BoletoQuote>>, (StatusCode, Json<HttpResponse>)> {
+    let today = chrono::Utc::now().date_naive();
+    match BoletoService::new()
+        .quote(&state.db_pool, &lookup.line, today)
+        .await
+    {
+        Ok(quote) => Ok(Json(ReturnTypes::Single(quote))),
+        Err(e) => Err(boleto_error(e)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/boletos/payments",
+    context_path = "/api/v1",
+    request_body = BoletoPaymentCreate,
+    responses(
+        (status = 200, description = "The slip was paid", body = ReturnTypes<BoletoPayment>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Slips of other banks need the payee they pay"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 409, description = "Already paid", body = HttpResponse, example = json!(r#"{"status": 409, "message": "The slip has already been paid"}"#)),
+        (status = 422, description = "Transaction limit exceeded or not allowed for the account type or status", body = HttpResponse, example = json!(r#"{"status": 422, "message": "Amount exceeds the 5000.00 per-operation limit"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn pay_boleto(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Json(payment): Json<BoletoPaymentCreate>,
+) -> Result<Json<ReturnTypes<BoletoPayment>>, (StatusCode, Json<HttpResponse>)> {
+    // like transfers, slips are only paid by the owner of the paying account
+    authorize(&state, &current_user, &[], &payment.from_account_id).await?;
+
+    let today = chrono::Utc::now().date_naive();
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let db_pool = state.db_pool.clone();
+        let payment = payment.clone();
+        let current_user_id = current_user.id;
+        Box::pin(async move {
+            BoletoService::new()
+                .pay(&db_pool, tx, &payment, &current_user_id, today)
+                .await
+        })
+    })
+    .await;
+
+    match result {
+        Ok(payment) => Ok(Json(ReturnTypes::Single(payment))),
+        Err(e) => Err(boleto_error(e)),
+    }
+}
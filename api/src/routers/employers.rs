@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        employer_dto::{Employer, EmployerCreate, EmployerError},
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::{account::Service as AccountService, employer::Service as EmployerService},
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route(
+            "/accounts/:id/employers",
+            get(get_employers).post(create_employer),
+        )
+        .route(
+            "/accounts/:id/employers/:employer_account_id",
+            delete(delete_employer),
+        )
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn employer_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<EmployerError>() {
+        Some(EmployerError::AlreadyRegistered) => StatusCode::CONFLICT,
+        Some(EmployerError::NotSalaryAccount) => StatusCode::UNPROCESSABLE_ENTITY,
+        Some(_) => StatusCode::BAD_REQUEST,
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(StatusCode::NOT_FOUND, "Account not found".to_string())
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/:id/employers",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Accounts allowed to pay into the salary account", body = ReturnTypes<Employer>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_employers(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<Employer>>, (StatusCode, Json<HttpResponse>)> {
+    let account = AccountService::new()
+        .get_one_by_id(&state.db_pool, &account_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    let employers = EmployerService::new()
+        .get_all_by_account_id(&state.db_pool, &account_id)
+        .await;
+
+    Ok(Json(ReturnTypes::Multiple(employers)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/accounts/:id/employers",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    request_body = EmployerCreate,
+    responses(
+        (status = 200, description = "The employer may now pay into the account", body = ReturnTypes<Employer>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "An account cannot be its own employer"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 409, description = "Already registered", body = HttpResponse, example = json!(r#"{"status": 409, "message": "The employer is already registered"}"#)),
+        (status = 422, description = "Not a salary account", body = HttpResponse, example = json!(r#"{"status": 422, "message": "Only salary accounts have employers"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_employer(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+    Json(employer): Json<EmployerCreate>,
+) -> Result<Json<ReturnTypes<Employer>>, (StatusCode, Json<HttpResponse>)> {
+    // the bank registers employers, so owners cannot let anyone pay into the account
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let employer = employer.clone();
+        Box::pin(async move {
+            EmployerService::new()
+                .register(tx, &account_id, &employer)
+                .await
+        })
+    })
+    .await;
+
+    match result {
+        Ok(employer) => Ok(Json(ReturnTypes::Single(employer))),
+        Err(e) => Err(employer_error(e)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/accounts/:id/employers/:employer_account_id",
+    context_path = "/api/v1",
+    params(
+        ("id" = Uuid, Path, description = "Account ID"),
+        ("employer_account_id" = Uuid, Path, description = "Account ID of the employer"),
+    ),
+    responses(
+        (status = 200, description = "Employer removed", body = HttpResponse, example = json!(r#"{"status": 200, "message": "Employer removed"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Employer not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Employer not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn delete_employer(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path((account_id, employer_account_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<HttpResponse>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match EmployerService::new()
+        .remove(&mut tx, &account_id, &employer_account_id)
+        .await
+    {
+        true => {
+            tx.commit().await.unwrap();
+            Ok(Json(HttpResponse::new(
+                StatusCode::OK.as_u16(),
+                "Employer removed".to_string(),
+                None,
+            )))
+        }
+        false => {
+            tx.rollback().await.unwrap();
+            Err(error(
+                StatusCode::NOT_FOUND,
+                "Employer not found".to_string(),
+            ))
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        fee_dto::{FeeError, FeeSchedule, FeeScheduleCreate, FeeScheduleUpdate},
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::fee::Service as FeeService,
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route(
+            "/fees/schedules",
+            get(get_fee_schedules).post(create_fee_schedule),
+        )
+        .route(
+            "/fees/schedules/:id",
+            get(get_fee_schedule)
+                .put(update_fee_schedule)
+                .delete(delete_fee_schedule),
+        )
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn require_admin(scopes: &[String]) -> Result<(), (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    Ok(())
+}
+
+fn fee_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<FeeError>() {
+        Some(_) => StatusCode::BAD_REQUEST,
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(StatusCode::NOT_FOUND, "Fee schedule not found".to_string())
+            }
+            Some(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {
+                return error(
+                    StatusCode::CONFLICT,
+                    "A fee schedule with this name or account type already exists".to_string(),
+                )
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+#[utoipa::path(
+    get,
+    path = "/fees/schedules",
+    context_path = "/api/v1",
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<FeeSchedule>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_fee_schedules(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+) -> Result<Json<ReturnTypes<FeeSchedule>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let schedules = FeeService::new().get_all(&state.db_pool).await;
+
+    Ok(Json(ReturnTypes::Multiple(schedules)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/fees/schedules/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Fee schedule ID")),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<FeeSchedule>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Fee schedule not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Fee schedule not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_fee_schedule(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<FeeSchedule>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    match FeeService::new().get_one_by_id(&state.db_pool, &id).await {
+        Some(schedule) => Ok(Json(ReturnTypes::Single(schedule))),
+        None => Err(error(
+            StatusCode::NOT_FOUND,
+            "Fee schedule not found".to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/fees/schedules",
+    context_path = "/api/v1",
+    request_body = FeeScheduleCreate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<FeeSchedule>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Fees cannot be negative"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 409, description = "Conflict", body = HttpResponse, example = json!(r#"{"status": 409, "message": "A fee schedule with this name or account type already exists"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_fee_schedule(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Json(schedule): Json<FeeScheduleCreate>,
+) -> Result<Json<ReturnTypes<FeeSchedule>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let schedule = schedule.clone();
+        Box::pin(async move { FeeService::new().create(tx, &schedule).await })
+    })
+    .await;
+
+    match result {
+        Ok(schedule) => Ok(Json(ReturnTypes::Single(schedule))),
+        Err(e) => Err(fee_error(e)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/fees/schedules/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Fee schedule ID")),
+    request_body = FeeScheduleUpdate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<FeeSchedule>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Fees cannot be negative"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Fee schedule not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Fee schedule not found"}"#)),
+        (status = 409, description = "Conflict", body = HttpResponse, example = json!(r#"{"status": 409, "message": "A fee schedule with this name or account type already exists"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn update_fee_schedule(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+    Json(update): Json<FeeScheduleUpdate>,
+) -> Result<Json<ReturnTypes<FeeSchedule>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let update = update.clone();
+        Box::pin(async move { FeeService::new().update(tx, &id, &update).await })
+    })
+    .await;
+
+    match result {
+        Ok(schedule) => Ok(Json(ReturnTypes::Single(schedule))),
+        Err(e) => Err(fee_error(e)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/fees/schedules/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Fee schedule ID")),
+    responses(
+        (status = 200, description = "Fee schedule deleted", body = HttpResponse, example = json!(r#"{"status": 200, "message": "Fee schedule deleted"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Fee schedule not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Fee schedule not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn delete_fee_schedule(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<HttpResponse>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match FeeService::new().delete(&mut tx, &id).await {
+        true => {
+            tx.commit().await.unwrap();
+            Ok(Json(HttpResponse::new(
+                StatusCode::OK.as_u16(),
+                "Fee schedule deleted".to_string(),
+                None,
+            )))
+        }
+        false => {
+            tx.rollback().await.unwrap();
+            Err(error(
+                StatusCode::NOT_FOUND,
+                "Fee schedule not found".to_string(),
+            ))
+        }
+    }
+}
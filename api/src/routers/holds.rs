@@ -0,0 +1,275 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        account_dto::{AccountStatusError, AccountTypeError},
+        hold_dto::{HoldCapture, HoldCreate, HoldError, HoldModel},
+        limit_dto::LimitExceeded,
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::{
+        account::Service as AccountService, hold::Service as HoldService,
+        user::Service as UserService,
+    },
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route("/holds", post(create_hold))
+        .route("/holds/:id", get(get_hold))
+        .route("/holds/:id/capture", post(capture_hold))
+        .route("/holds/:id/release", post(release_hold))
+        .route("/accounts/:id/holds", get(get_account_holds))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+/// Loads the owner of `account_id`, refusing anyone but that owner or an admin.
+async fn authorize(
+    state: &ApplicationState,
+    current_user: &User,
+    scopes: &[String],
+    account_id: &Uuid,
+) -> Result<User, (StatusCode, Json<HttpResponse>)> {
+    let account = AccountService::new()
+        .get_one_by_id(&state.db_pool, account_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    UserService::new()
+        .get_one_by_id(&state.db_pool, &account.user_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "User not found".to_string()))
+}
+
+fn hold_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<HoldError>() {
+        Some(HoldError::InvalidAmount)
+        | Some(HoldError::InvalidExpiry)
+        | Some(HoldError::SameAccount) => StatusCode::BAD_REQUEST,
+        Some(HoldError::NotActive(_)) | Some(HoldError::Expired) => StatusCode::CONFLICT,
+        Some(HoldError::ExceedsHold(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+        None if e.downcast_ref::<LimitExceeded>().is_some()
+            || e.downcast_ref::<AccountTypeError>().is_some()
+            || e.downcast_ref::<AccountStatusError>().is_some() =>
+        {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(StatusCode::NOT_FOUND, "Hold not found".to_string())
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+#[utoipa::path(
+    post,
+    path = "/holds",
+    context_path = "/api/v1",
+    request_body = HoldCreate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<HoldModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Hold amount must be positive"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_hold(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Json(hold): Json<HoldCreate>,
+) -> Result<Json<ReturnTypes<HoldModel>>, (StatusCode, Json<HttpResponse>)> {
+    let owner = authorize(&state, &current_user, &scopes, &hold.account_id).await?;
+    if let Some(to_account_id) = &hold.to_account_id {
+        if AccountService::new()
+            .get_one_by_id(&state.db_pool, to_account_id)
+            .await
+            .is_none()
+        {
+            return Err(error(
+                StatusCode::NOT_FOUND,
+                "Account not found".to_string(),
+            ));
+        }
+    }
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let hold = hold.clone();
+        Box::pin(async move { HoldService::new().create(tx, &hold).await })
+    })
+    .await;
+
+    match result.and_then(|hold| HoldModel::from_dto(&hold, &owner)) {
+        Ok(hold_model) => Ok(Json(ReturnTypes::Single(hold_model))),
+        Err(e) => Err(hold_error(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/holds/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Hold ID")),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<HoldModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Hold not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Hold not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_hold(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<HoldModel>>, (StatusCode, Json<HttpResponse>)> {
+    let hold = HoldService::new()
+        .get_one_by_id(&state.db_pool, &id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Hold not found".to_string()))?;
+    let owner = authorize(&state, &current_user, &scopes, &hold.account_id).await?;
+
+    match HoldModel::from_dto(&hold, &owner) {
+        Ok(hold_model) => Ok(Json(ReturnTypes::Single(hold_model))),
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/:id/holds",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<HoldModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_account_holds(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<HoldModel>>, (StatusCode, Json<HttpResponse>)> {
+    let owner = authorize(&state, &current_user, &scopes, &account_id).await?;
+
+    let holds = HoldService::new()
+        .get_all_by_account_id(&state.db_pool, &account_id)
+        .await;
+    match holds
+        .iter()
+        .map(|hold| HoldModel::from_dto(hold, &owner))
+        .collect::<anyhow::Result<Vec<HoldModel>>>()
+    {
+        Ok(hold_models) => Ok(Json(ReturnTypes::Multiple(hold_models))),
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/holds/:id/capture",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Hold ID")),
+    request_body = HoldCapture,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<HoldModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Hold amount must be positive"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Hold not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Hold not found"}"#)),
+        (status = 409, description = "Conflict", body = HttpResponse, example = json!(r#"{"status": 409, "message": "Hold has expired"}"#)),
+        (status = 422, description = "Unprocessable Entity", body = HttpResponse, example = json!(r#"{"status": 422, "message": "Capture exceeds the 49.90 held"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn capture_hold(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+    Json(capture): Json<HoldCapture>,
+) -> Result<Json<ReturnTypes<HoldModel>>, (StatusCode, Json<HttpResponse>)> {
+    let hold = HoldService::new()
+        .get_one_by_id(&state.db_pool, &id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Hold not found".to_string()))?;
+    let owner = authorize(&state, &current_user, &scopes, &hold.account_id).await?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let db_pool = state.db_pool.clone();
+        let amount = capture.amount;
+        Box::pin(async move { HoldService::new().capture(&db_pool, tx, &id, amount).await })
+    })
+    .await;
+
+    match result.and_then(|hold| HoldModel::from_dto(&hold, &owner)) {
+        Ok(hold_model) => Ok(Json(ReturnTypes::Single(hold_model))),
+        Err(e) => Err(hold_error(e)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/holds/:id/release",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Hold ID")),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<HoldModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Hold not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Hold not found"}"#)),
+        (status = 409, description = "Conflict", body = HttpResponse, example = json!(r#"{"status": 409, "message": "Hold is Captured and can no longer change"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn release_hold(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<HoldModel>>, (StatusCode, Json<HttpResponse>)> {
+    let hold = HoldService::new()
+        .get_one_by_id(&state.db_pool, &id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Hold not found".to_string()))?;
+    let owner = authorize(&state, &current_user, &scopes, &hold.account_id).await?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        Box::pin(async move { HoldService::new().release(tx, &id).await })
+    })
+    .await;
+
+    match result.and_then(|hold| HoldModel::from_dto(&hold, &owner)) {
+        Ok(hold_model) => Ok(Json(ReturnTypes::Single(hold_model))),
+        Err(e) => Err(hold_error(e)),
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        interest_dto::{
+            AccountInterestAssign, AccountInterestModel, InterestError, InterestProduct,
+            InterestProductCreate,
+        },
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::{
+        account::Service as AccountService, interest::Service as InterestService,
+        user::Service as UserService,
+    },
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route(
+            "/interest/products",
+            get(get_interest_products).post(create_interest_product),
+        )
+        .route(
+            "/accounts/:id/interest",
+            get(get_account_interest).put(assign_account_interest),
+        )
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn require_admin(scopes: &[String]) -> Result<(), (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Loads the owner of `account_id`, whose key the accrued interest is encrypted with.
+async fn account_owner(
+    state: &ApplicationState,
+    account_id: &Uuid,
+) -> Result<User, (StatusCode, Json<HttpResponse>)> {
+    let account = AccountService::new()
+        .get_one_by_id(&state.db_pool, account_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+
+    UserService::new()
+        .get_one_by_id(&state.db_pool, &account.user_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "User not found".to_string()))
+}
+
+fn interest_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<InterestError>() {
+        Some(InterestError::InvalidRate) | Some(InterestError::InvalidName) => {
+            StatusCode::BAD_REQUEST
+        }
+        Some(InterestError::NotAssigned) => StatusCode::NOT_FOUND,
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(
+                    StatusCode::NOT_FOUND,
+                    "Interest product not found".to_string(),
+                )
+            }
+            Some(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {
+                return error(
+                    StatusCode::CONFLICT,
+                    "Interest product name already taken".to_string(),
+                )
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+#[utoipa::path(
+    get,
+    path = "/interest/products",
+    context_path = "/api/v1",
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<InterestProduct>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_interest_products(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+) -> Result<Json<ReturnTypes<InterestProduct>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let products = InterestService::new()
+        .get_all_products(&state.db_pool)
+        .await;
+
+    Ok(Json(ReturnTypes::Multiple(products)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/interest/products",
+    context_path = "/api/v1",
+    request_body = InterestProductCreate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<InterestProduct>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Interest rate cannot be negative"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 409, description = "Conflict", body = HttpResponse, example = json!(r#"{"status": 409, "message": "Interest product name already taken"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_interest_product(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Json(product): Json<InterestProductCreate>,
+) -> Result<Json<ReturnTypes<InterestProduct>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let product = product.clone();
+        Box::pin(async move { InterestService::new().create_product(tx, &product).await })
+    })
+    .await;
+
+    match result {
+        Ok(product) => Ok(Json(ReturnTypes::Single(product))),
+        Err(e) => Err(interest_error(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/:id/interest",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Interest accrued but not paid yet", body = ReturnTypes<AccountInterestModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account has no interest product"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_account_interest(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<AccountInterestModel>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+    let owner = account_owner(&state, &account_id).await?;
+
+    let interest = InterestService::new()
+        .get_account_interest(&state.db_pool, &account_id)
+        .await
+        .ok_or_else(|| {
+            error(
+                StatusCode::NOT_FOUND,
+                InterestError::NotAssigned.to_string(),
+            )
+        })?;
+
+    match AccountInterestModel::from_dto(&interest, &owner) {
+        Ok(interest_model) => Ok(Json(ReturnTypes::Single(interest_model))),
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/accounts/:id/interest",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    request_body = AccountInterestAssign,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<AccountInterestModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Interest product not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn assign_account_interest(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+    Json(assign): Json<AccountInterestAssign>,
+) -> Result<Json<ReturnTypes<AccountInterestModel>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+    let owner = account_owner(&state, &account_id).await?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let product_id = assign.product_id;
+        Box::pin(async move {
+            InterestService::new()
+                .assign(
+                    tx,
+                    &account_id,
+                    &product_id,
+                    chrono::Utc::now().date_naive(),
+                )
+                .await
+        })
+    })
+    .await;
+
+    match result.and_then(|interest| AccountInterestModel::from_dto(&interest, &owner)) {
+        Ok(interest_model) => Ok(Json(ReturnTypes::Single(interest_model))),
+        Err(e) => Err(interest_error(e)),
+    }
+}
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        limit_dto::{LimitError, TransactionLimit, TransactionLimitCreate, TransactionLimitUpdate},
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::limit::Service as LimitService,
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route("/limits", get(get_limits).post(create_limit))
+        .route(
+            "/limits/:id",
+            get(get_limit).put(update_limit).delete(delete_limit),
+        )
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn require_admin(scopes: &[String]) -> Result<(), (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    Ok(())
+}
+
+fn limit_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<LimitError>() {
+        Some(_) => StatusCode::BAD_REQUEST,
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(StatusCode::NOT_FOUND, "Limit not found".to_string())
+            }
+            Some(sqlx::Error::Database(db_error)) if db_error.is_foreign_key_violation() => {
+                return error(
+                    StatusCode::NOT_FOUND,
+                    "Account or user not found".to_string(),
+                )
+            }
+            Some(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {
+                return error(
+                    StatusCode::CONFLICT,
+                    "This operation already has a limit with the same scope".to_string(),
+                )
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+#[utoipa::path(
+    get,
+    path = "/limits",
+    context_path = "/api/v1",
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<TransactionLimit>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_limits(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+) -> Result<Json<ReturnTypes<TransactionLimit>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let limits = LimitService::new().get_all(&state.db_pool).await;
+
+    Ok(Json(ReturnTypes::Multiple(limits)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/limits/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Limit ID")),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<TransactionLimit>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Limit not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Limit not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_limit(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<TransactionLimit>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    match LimitService::new().get_one_by_id(&state.db_pool, &id).await {
+        Some(limit) => Ok(Json(ReturnTypes::Single(limit))),
+        None => Err(error(StatusCode::NOT_FOUND, "Limit not found".to_string())),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/limits",
+    context_path = "/api/v1",
+    request_body = TransactionLimitCreate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<TransactionLimit>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Limits must be positive"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account or user not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account or user not found"}"#)),
+        (status = 409, description = "Conflict", body = HttpResponse, example = json!(r#"{"status": 409, "message": "This operation already has a limit with the same scope"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_limit(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Json(limit): Json<TransactionLimitCreate>,
+) -> Result<Json<ReturnTypes<TransactionLimit>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let limit = limit.clone();
+        Box::pin(async move { LimitService::new().create(tx, &limit).await })
+    })
+    .await;
+
+    match result {
+        Ok(limit) => Ok(Json(ReturnTypes::Single(limit))),
+        Err(e) => Err(limit_error(e)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/limits/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Limit ID")),
+    request_body = TransactionLimitUpdate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<TransactionLimit>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Limits must be positive"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Limit not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Limit not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn update_limit(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+    Json(update): Json<TransactionLimitUpdate>,
+) -> Result<Json<ReturnTypes<TransactionLimit>>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let update = update.clone();
+        Box::pin(async move { LimitService::new().update(tx, &id, &update).await })
+    })
+    .await;
+
+    match result {
+        Ok(limit) => Ok(Json(ReturnTypes::Single(limit))),
+        Err(e) => Err(limit_error(e)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/limits/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Limit ID")),
+    responses(
+        (status = 200, description = "Limit deleted", body = HttpResponse, example = json!(r#"{"status": 200, "message": "Limit deleted"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Limit not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Limit not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn delete_limit(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<HttpResponse>, (StatusCode, Json<HttpResponse>)> {
+    require_admin(&scopes)?;
+
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match LimitService::new().delete(&mut tx, &id).await {
+        true => {
+            tx.commit().await.unwrap();
+            Ok(Json(HttpResponse::new(
+                StatusCode::OK.as_u16(),
+                "Limit deleted".to_string(),
+                None,
+            )))
+        }
+        false => {
+            tx.rollback().await.unwrap();
+            Err(error(StatusCode::NOT_FOUND, "Limit not found".to_string()))
+        }
+    }
+}
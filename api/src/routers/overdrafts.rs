@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::put,
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        account_dto::AccountModel,
+        overdraft_dto::{OverdraftError, OverdraftUpdate},
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::{
+        account::Service as AccountService, hold::Service as HoldService,
+        overdraft::Service as OverdraftService, user::Service as UserService,
+    },
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new().route("/accounts/:id/overdraft", put(update_account_overdraft))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn overdraft_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<OverdraftError>() {
+        Some(_) => StatusCode::BAD_REQUEST,
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(StatusCode::NOT_FOUND, "Account not found".to_string())
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+#[utoipa::path(
+    put,
+    path = "/accounts/:id/overdraft",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    request_body = OverdraftUpdate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<AccountModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Overdraft limit cannot be negative"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn update_account_overdraft(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+    Json(overdraft): Json<OverdraftUpdate>,
+) -> Result<Json<ReturnTypes<AccountModel>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+    let owner = match AccountService::new()
+        .get_one_by_id(&state.db_pool, &account_id)
+        .await
+    {
+        Some(account) => UserService::new()
+            .get_one_by_id(&state.db_pool, &account.user_id)
+            .await
+            .ok_or_else(|| error(StatusCode::NOT_FOUND, "User not found".to_string()))?,
+        None => {
+            return Err(error(
+                StatusCode::NOT_FOUND,
+                "Account not found".to_string(),
+            ))
+        }
+    };
+
+    let account = with_transaction_retry(&state.db_pool, |tx| {
+        let overdraft = overdraft.clone();
+        Box::pin(async move {
+            OverdraftService::new()
+                .update(tx, &account_id, &overdraft, chrono::Utc::now().date_naive())
+                .await
+        })
+    })
+    .await
+    .map_err(overdraft_error)?;
+
+    let held = HoldService::new()
+        .get_held_amount(&state.db_pool, &account, &owner)
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    match AccountModel::from_dto(&account, &owner) {
+        Ok(account_model) => Ok(Json(ReturnTypes::Single(
+            account_model.with_held_amount(held),
+        ))),
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
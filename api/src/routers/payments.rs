@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, routing::post, Extension, Json, Router};
+use database::{
+    models::{
+        pix_key_dto::PixKeyError,
+        qr_code_dto::{BrCodeError, QrCode, QrCodeCreate, QrCodeError},
+        user_dto::User,
+    },
+    services::{account::Service as AccountService, qr_code::Service as QrCodeService},
+};
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new().route("/payments/qr", post(create_qr_code))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/payments/qr",
+    context_path = "/api/v1",
+    request_body = QrCodeCreate,
+    responses(
+        (status = 200, description = "The BR Code payload to show to the payer", body = ReturnTypes<QrCode>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "The key is not registered to the account"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 422, description = "The account has no payment key", body = HttpResponse, example = json!(r#"{"status": 422, "message": "Account 01a14d6e-fdb9-7900-a29f-69bd5f764e02 has no payment key to be paid through"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_qr_code(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Json(qr_code): Json<QrCodeCreate>,
+) -> Result<Json<ReturnTypes<QrCode>>, (StatusCode, Json<HttpResponse>)> {
+    let account = match AccountService::new()
+        .get_one_by_id(&state.db_pool, &qr_code.account_id)
+        .await
+    {
+        Some(account) => account,
+        None => {
+            return Err(error(
+                StatusCode::NOT_FOUND,
+                "Account not found".to_string(),
+            ))
+        }
+    };
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    match QrCodeService::new().create(&state.db_pool, &qr_code).await {
+        Ok(qr_code) => Ok(Json(ReturnTypes::Single(qr_code))),
+        Err(e) => {
+            let status = match e.downcast_ref::<QrCodeError>() {
+                Some(QrCodeError::NoKey(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+                Some(_) => StatusCode::BAD_REQUEST,
+                None if e.downcast_ref::<BrCodeError>().is_some()
+                    || e.downcast_ref::<PixKeyError>().is_some() =>
+                {
+                    StatusCode::BAD_REQUEST
+                }
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            Err(error(status, e.to_string()))
+        }
+    }
+}
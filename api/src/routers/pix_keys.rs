@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        pix_key_dto::{PixKey, PixKeyCreate, PixKeyError},
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::{account::Service as AccountService, pix_key::Service as PixKeyService},
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route("/pix-keys", get(get_pix_keys).post(create_pix_key))
+        .route("/pix-keys/:id", delete(delete_pix_key))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn pix_key_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<PixKeyError>() {
+        Some(PixKeyError::KeyTaken) => StatusCode::CONFLICT,
+        Some(PixKeyError::TooManyKeys(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+        Some(PixKeyError::VerificationRequired(_)) => StatusCode::FORBIDDEN,
+        Some(_) => StatusCode::BAD_REQUEST,
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(StatusCode::NOT_FOUND, "Account not found".to_string())
+            }
+            Some(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {
+                return error(StatusCode::CONFLICT, PixKeyError::KeyTaken.to_string())
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+/// Refuses anyone but the owner of `account_id` or an admin.
+async fn authorize(
+    state: &ApplicationState,
+    current_user: &User,
+    scopes: &[String],
+    account_id: &Uuid,
+) -> Result<(), (StatusCode, Json<HttpResponse>)> {
+    let account = match AccountService::new()
+        .get_one_by_id(&state.db_pool, account_id)
+        .await
+    {
+        Some(account) => account,
+        None => {
+            return Err(error(
+                StatusCode::NOT_FOUND,
+                "Account not found".to_string(),
+            ))
+        }
+    };
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/pix-keys",
+    context_path = "/api/v1",
+    responses(
+        (status = 200, description = "Keys of every account of the current user", body = ReturnTypes<PixKey>),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_pix_keys(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+) -> Result<Json<ReturnTypes<PixKey>>, (StatusCode, Json<HttpResponse>)> {
+    let pix_keys = PixKeyService::new()
+        .get_all_by_user_id(&state.db_pool, &current_user.id)
+        .await;
+
+    Ok(Json(ReturnTypes::Multiple(pix_keys)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/pix-keys",
+    context_path = "/api/v1",
+    request_body = PixKeyCreate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<PixKey>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Email keys must be the email of the account owner"}"#)),
+        (status = 403, description = "Forbidden, or a tax id or phone key sent by someone other than an admin", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Only the bank registers phone keys, once it has verified them"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 409, description = "Key already registered", body = HttpResponse, example = json!(r#"{"status": 409, "message": "The key is already registered"}"#)),
+        (status = 422, description = "Too many keys", body = HttpResponse, example = json!(r#"{"status": 422, "message": "An account holds at most 5 keys"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_pix_key(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Json(pix_key): Json<PixKeyCreate>,
+) -> Result<Json<ReturnTypes<PixKey>>, (StatusCode, Json<HttpResponse>)> {
+    authorize(&state, &current_user, &scopes, &pix_key.account_id).await?;
+
+    let is_admin = scopes.contains(&"admin".to_string());
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let pix_key = pix_key.clone();
+        Box::pin(async move { PixKeyService::new().create(tx, &pix_key, is_admin).await })
+    })
+    .await;
+
+    match result {
+        Ok(pix_key) => Ok(Json(ReturnTypes::Single(pix_key))),
+        Err(e) => Err(pix_key_error(e)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/pix-keys/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Key ID")),
+    responses(
+        (status = 200, description = "Key deleted", body = HttpResponse, example = json!(r#"{"status": 200, "message": "Key deleted"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Key not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Key not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn delete_pix_key(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<HttpResponse>, (StatusCode, Json<HttpResponse>)> {
+    let pix_key_service = PixKeyService::new();
+    let pix_key = match pix_key_service.get_one_by_id(&state.db_pool, &id).await {
+        Some(pix_key) => pix_key,
+        None => return Err(error(StatusCode::NOT_FOUND, "Key not found".to_string())),
+    };
+    authorize(&state, &current_user, &scopes, &pix_key.account_id).await?;
+
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match pix_key_service.delete(&mut tx, &id).await {
+        true => {
+            tx.commit().await.unwrap();
+            Ok(Json(HttpResponse::new(
+                StatusCode::OK.as_u16(),
+                "Key deleted".to_string(),
+                None,
+            )))
+        }
+        false => {
+            tx.rollback().await.unwrap();
+            Err(error(StatusCode::NOT_FOUND, "Key not found".to_string()))
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        reconciliation_dto::{ReconciliationReport, ReconciliationRun},
+        user_dto::User,
+    },
+    services::reconciliation::Service as ReconciliationService,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationQuery {
+    pub discrepancies_only: Option<bool>,
+}
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route("/reconciliation", get(reconcile_accounts))
+        .route("/accounts/:id/reconciliation", get(reconcile_account))
+}
+
+fn forbidden() -> (StatusCode, Json<HttpResponse>) {
+    (
+        StatusCode::FORBIDDEN,
+        Json(HttpResponse::new(
+            StatusCode::FORBIDDEN.as_u16(),
+            "Forbidden".to_string(),
+            None,
+        )),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/reconciliation",
+    context_path = "/api/v1",
+    params(
+        ("discrepancies_only" = Option<bool>, Query, description = "Only return accounts whose balance does not match their history"),
+    ),
+    responses(
+        (status = 200, description = "Reports for every account, and the accounts that could not be reconciled", body = ReturnTypes<ReconciliationRun>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn reconcile_accounts(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<Json<ReturnTypes<ReconciliationRun>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(forbidden());
+    }
+
+    match ReconciliationService::new()
+        .reconcile_all(&state.db_pool)
+        .await
+    {
+        Ok(mut run) => {
+            if query.discrepancies_only.unwrap_or(false) {
+                run.reports.retain(|report| !report.is_consistent());
+            }
+            Ok(Json(ReturnTypes::Single(run)))
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(HttpResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                e.to_string(),
+                None,
+            )),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/:id/reconciliation",
+    context_path = "/api/v1",
+    params(
+        ("id" = Uuid, Path, description = "Account ID"),
+    ),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<ReconciliationReport>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn reconcile_account(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<ReconciliationReport>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(forbidden());
+    }
+
+    match ReconciliationService::new()
+        .reconcile_account(&state.db_pool, &id)
+        .await
+    {
+        Ok(report) => Ok(Json(ReturnTypes::Single(report))),
+        Err(e) => {
+            let status = match e.downcast_ref::<sqlx::Error>() {
+                Some(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            let message = match status {
+                StatusCode::NOT_FOUND => "Account not found".to_string(),
+                _ => e.to_string(),
+            };
+
+            Err((
+                status,
+                Json(HttpResponse::new(status.as_u16(), message, None)),
+            ))
+        }
+    }
+}
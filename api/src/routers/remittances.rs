@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Extension, Json, Router,
+};
+use database::{
+    models::{
+        remittance_dto::{CnabError, RemittanceError, ReturnReport},
+        user_dto::User,
+    },
+    payment_files::cnab240,
+    retry::with_transaction_retry,
+    services::{account::Service as AccountService, remittance::Service as RemittanceService},
+};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route("/accounts/:id/remittances", post(create_remittance))
+        .route("/remittances/returns", post(process_return_file))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn remittance_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<RemittanceError>() {
+        Some(RemittanceError::MissingBankDetails(_)) | Some(RemittanceError::NotAReturnFile) => {
+            StatusCode::BAD_REQUEST
+        }
+        Some(RemittanceError::UnknownAccount) => StatusCode::NOT_FOUND,
+        None if e.downcast_ref::<CnabError>().is_some() => StatusCode::BAD_REQUEST,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    error(status, e.to_string())
+}
+
+#[utoipa::path(
+    post,
+    path = "/accounts/:id/remittances",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Account ID")),
+    responses(
+        (status = 201, description = "CNAB 240 remittance file with every transfer not sent to the bank yet", content_type = "text/plain"),
+        (status = 204, description = "No transfers to remit"),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Account 01a14d6e-fdb9-7900-a29f-69bd5f764e02 needs its bank, agency and account numbers for CNAB files"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_remittance(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Response, (StatusCode, Json<HttpResponse>)> {
+    let account = match AccountService::new()
+        .get_one_by_id(&state.db_pool, &account_id)
+        .await
+    {
+        Some(account) => account,
+        None => {
+            return Err(error(
+                StatusCode::NOT_FOUND,
+                "Account not found".to_string(),
+            ))
+        }
+    };
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    let db_pool = state.db_pool.clone();
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let db_pool = db_pool.clone();
+        Box::pin(async move {
+            RemittanceService::new()
+                .generate(&db_pool, tx, &account_id)
+                .await
+        })
+    })
+    .await
+    .map_err(remittance_error)?;
+
+    let Some((remittance, file)) = result else {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    };
+    let filename = format!(
+        "remittance-{}-{:06}.rem",
+        remittance.account_id, remittance.file_sequence
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        [
+            (header::CONTENT_TYPE, "text/plain".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        file,
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/remittances/returns",
+    context_path = "/api/v1",
+    request_body(content = String, description = "CNAB 240 return file", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<ReturnReport>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Line 3 has 239 characters instead of 240"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "No account matches the one in the file header"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn process_return_file(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    body: String,
+) -> Result<Json<ReturnTypes<ReturnReport>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    let file = cnab240::parse(&body).map_err(|e| error(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let report = with_transaction_retry(&state.db_pool, |tx| {
+        let file = file.clone();
+        Box::pin(async move { RemittanceService::new().process_return(tx, &file).await })
+    })
+    .await
+    .map_err(remittance_error)?;
+
+    Ok(Json(ReturnTypes::Single(report)))
+}
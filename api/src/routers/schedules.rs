@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+    Extension, Json, Router,
+};
+use database::{
+    filters::schedule::Filter as ScheduleFilter,
+    models::{
+        schedule_dto::{Schedule, ScheduleCreate, ScheduleError, ScheduleModel, ScheduleUpdate},
+        user_dto::User,
+    },
+    retry::with_transaction_retry,
+    services::{
+        account::Service as AccountService, schedule::Service as ScheduleService,
+        user::Service as UserService,
+    },
+};
+use futures::{stream, StreamExt};
+use uuid::Uuid;
+
+use crate::{
+    http::response::{HttpPaginatedResponse, HttpResponse, ReturnTypes},
+    state::application::ApplicationState,
+};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new()
+        .route("/schedules", get(get_schedules).post(create_schedule))
+        .route(
+            "/schedules/:id",
+            get(get_schedule)
+                .put(update_schedule)
+                .delete(delete_schedule),
+        )
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+fn schedule_error(e: anyhow::Error) -> (StatusCode, Json<HttpResponse>) {
+    let status = match e.downcast_ref::<ScheduleError>() {
+        Some(ScheduleError::NotEditable(_)) => StatusCode::CONFLICT,
+        Some(_) => StatusCode::BAD_REQUEST,
+        None => match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => {
+                return error(StatusCode::NOT_FOUND, "Schedule not found".to_string())
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    };
+
+    error(status, e.to_string())
+}
+
+/// Loads a schedule and its owner, refusing anyone but that owner or an admin.
+async fn find_schedule(
+    state: &ApplicationState,
+    current_user: &User,
+    scopes: &[String],
+    id: &Uuid,
+) -> Result<(Schedule, User), (StatusCode, Json<HttpResponse>)> {
+    let schedule = ScheduleService::new()
+        .get_one_by_id(&state.db_pool, id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Schedule not found".to_string()))?;
+
+    if !scopes.contains(&"admin".to_string()) && schedule.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    let owner = UserService::new()
+        .get_one_by_id(&state.db_pool, &schedule.user_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    Ok((schedule, owner))
+}
+
+#[utoipa::path(
+    get,
+    path = "/schedules",
+    context_path = "/api/v1",
+    params(
+        ("id" = Option<Uuid>, Query, description = "Schedule ID"),
+        ("user_id" = Option<Uuid>, Query, description = "User ID"),
+        ("status" = Option<String>, Query, description = "Schedule status"),
+        ("account_id" = Option<Uuid>, Query, description = "Origin or destination account ID"),
+        ("offset" = Option<usize>, Query, description = "Pagination offset"),
+        ("limit" = Option<usize>, Query, description = "Pagination limit"),
+    ),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<ScheduleModel>),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_schedules(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Query(mut filters): Query<ScheduleFilter>,
+) -> Result<Json<ReturnTypes<ScheduleModel>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        filters.user_id = Some(current_user.id);
+    }
+    filters.enforce_pagination();
+
+    let (schedules, total) = ScheduleService::new()
+        .get_all(&state.db_pool, &filters)
+        .await;
+
+    let schedule_models = stream::iter(schedules)
+        .map(|schedule| {
+            let db_pool = state.db_pool.clone();
+            async move {
+                let owner = UserService::new()
+                    .get_one_by_id(&db_pool, &schedule.user_id)
+                    .await
+                    .ok_or_else(|| anyhow::anyhow!("User not found"))?;
+                ScheduleModel::from_dto(&schedule, &owner)
+            }
+        })
+        .buffered(10)
+        .collect::<Vec<anyhow::Result<ScheduleModel>>>()
+        .await
+        .into_iter()
+        .collect::<anyhow::Result<Vec<ScheduleModel>>>()
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    match filters.offset {
+        Some(offset) => {
+            let paginated =
+                HttpPaginatedResponse::new(schedule_models, offset, filters.limit, total);
+            Ok(Json(ReturnTypes::Paginated(paginated)))
+        }
+        None => Ok(Json(ReturnTypes::Multiple(schedule_models))),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/schedules",
+    context_path = "/api/v1",
+    request_body = ScheduleCreate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<ScheduleModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Cron schedules need a cron expression"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_schedule(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Json(schedule): Json<ScheduleCreate>,
+) -> Result<Json<ReturnTypes<ScheduleModel>>, (StatusCode, Json<HttpResponse>)> {
+    let account_service = AccountService::new();
+
+    let from_account = account_service
+        .get_one_by_id(&state.db_pool, &schedule.from_account_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+    if account_service
+        .get_one_by_id(&state.db_pool, &schedule.to_account_id)
+        .await
+        .is_none()
+    {
+        return Err(error(
+            StatusCode::NOT_FOUND,
+            "Account not found".to_string(),
+        ));
+    }
+    if !scopes.contains(&"admin".to_string()) && from_account.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+
+    // the schedule belongs to whoever owns the money it moves
+    let owner = UserService::new()
+        .get_one_by_id(&state.db_pool, &from_account.user_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let owner = owner.clone();
+        let schedule = schedule.clone();
+        Box::pin(async move { ScheduleService::new().create(tx, &owner, &schedule).await })
+    })
+    .await;
+
+    match result.and_then(|schedule| ScheduleModel::from_dto(&schedule, &owner)) {
+        Ok(schedule_model) => Ok(Json(ReturnTypes::Single(schedule_model))),
+        Err(e) => Err(schedule_error(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/schedules/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Schedule ID")),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<ScheduleModel>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Schedule not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Schedule not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_schedule(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<ScheduleModel>>, (StatusCode, Json<HttpResponse>)> {
+    let (schedule, owner) = find_schedule(&state, &current_user, &scopes, &id).await?;
+
+    match ScheduleModel::from_dto(&schedule, &owner) {
+        Ok(schedule_model) => Ok(Json(ReturnTypes::Single(schedule_model))),
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/schedules/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Schedule ID")),
+    request_body = ScheduleUpdate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<ScheduleModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Schedules can only be set to active or paused"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Schedule not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Schedule not found"}"#)),
+        (status = 409, description = "Conflict", body = HttpResponse, example = json!(r#"{"status": 409, "message": "Schedule is Completed and can no longer change"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn update_schedule(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+    Json(update): Json<ScheduleUpdate>,
+) -> Result<Json<ReturnTypes<ScheduleModel>>, (StatusCode, Json<HttpResponse>)> {
+    let (_, owner) = find_schedule(&state, &current_user, &scopes, &id).await?;
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let owner = owner.clone();
+        let update = update.clone();
+        Box::pin(async move {
+            ScheduleService::new()
+                .update(tx, &id, &owner, &update)
+                .await
+        })
+    })
+    .await;
+
+    match result.and_then(|schedule| ScheduleModel::from_dto(&schedule, &owner)) {
+        Ok(schedule_model) => Ok(Json(ReturnTypes::Single(schedule_model))),
+        Err(e) => Err(schedule_error(e)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/schedules/:id",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "Schedule ID")),
+    responses(
+        (status = 200, description = "Schedule deleted", body = HttpResponse, example = json!(r#"{"status": 200, "message": "Schedule deleted"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Schedule not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Schedule not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn delete_schedule(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<HttpResponse>, (StatusCode, Json<HttpResponse>)> {
+    find_schedule(&state, &current_user, &scopes, &id).await?;
+
+    let mut tx = state.db_pool.begin().await.unwrap();
+    match ScheduleService::new().delete(&mut tx, &id).await {
+        true => {
+            tx.commit().await.unwrap();
+            Ok(Json(HttpResponse::new(
+                StatusCode::OK.as_u16(),
+                "Schedule deleted".to_string(),
+                None,
+            )))
+        }
+        false => {
+            tx.rollback().await.unwrap();
+            Err(error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Schedule not deleted".to_string(),
+            ))
+        }
+    }
+}
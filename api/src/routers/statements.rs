@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use database::{
+    models::{statement_dto::StatementQuery, user_dto::User},
+    services::{account::Service as AccountService, statement::Service as StatementService},
+};
+use uuid::Uuid;
+
+use crate::{http::response::HttpResponse, state::application::ApplicationState};
+
+pub fn get_router() -> Router<Arc<ApplicationState>> {
+    Router::new().route("/accounts/:id/statement", get(get_account_statement))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<HttpResponse>) {
+    (
+        status,
+        Json(HttpResponse::new(status.as_u16(), message, None)),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/:id/statement",
+    context_path = "/api/v1",
+    params(
+        ("id" = Uuid, Path, description = "Account ID"),
+        ("from" = String, Query, description = "First day of the period, as YYYY-MM-DD"),
+        ("to" = String, Query, description = "Last day of the period, as YYYY-MM-DD"),
+        ("format" = Option<String>, Query, description = "File format, csv (default), ofx or camt053"),
+    ),
+    responses(
+        (status = 200, description = "Statement file", content_type = "text/csv"),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Statement period cannot end before it starts"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn get_account_statement(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(account_id): Path<Uuid>,
+    Query(query): Query<StatementQuery>,
+) -> Result<Response, (StatusCode, Json<HttpResponse>)> {
+    let account = match AccountService::new()
+        .get_one_by_id(&state.db_pool, &account_id)
+        .await
+    {
+        Some(account) => account,
+        None => {
+            return Err(error(
+                StatusCode::NOT_FOUND,
+                "Account not found".to_string(),
+            ))
+        }
+    };
+    if !scopes.contains(&"admin".to_string()) && account.user_id != current_user.id {
+        return Err(error(StatusCode::FORBIDDEN, "Forbidden".to_string()));
+    }
+    if let Err(e) = query.validate() {
+        return Err(error(StatusCode::BAD_REQUEST, e.to_string()));
+    }
+
+    let chunks = StatementService::new()
+        .render(&state.db_pool, &account, &query)
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let filename = format!(
+        "statement-{}-{}-{}.{}",
+        account.id,
+        query.from,
+        query.to,
+        query.format.extension()
+    );
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                query.format.content_type().to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
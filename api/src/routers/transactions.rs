@@ -1,23 +1,37 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     routing::{get, post},
     Extension, Json, Router,
 };
 use database::{
     filters::transaction::Filter as TransactionFilter,
     models::{
-        transaction_dto::{TransactionCreate, TransactionModel, TransactionOperation},
+        account_dto::{Account, AccountStatusError, AccountTypeError},
+        idempotency_dto::{IdempotencyClaim, IdempotencyError},
+        limit_dto::LimitExceeded,
+        payment_file_dto::PaymentFileError,
+        pix_key_dto::{PixKeyError, TransactionRecipient},
+        qr_code_dto::{QrCodeError, QrTransactionCreate},
+        transaction_dto::{
+            BatchItemOutcome, BatchItemResult, BatchItemStatus, BatchMode, ReversalCreate,
+            ReversalError, TransactionBatch, TransactionBatchResult, TransactionCreate,
+            TransactionError, TransactionModel, TransactionOperation,
+        },
         user_dto::User,
     },
+    payment_files::{brcode, pain001},
+    retry::with_transaction_retry,
     services::{
-        account::Service as AccountService, transaction::Service as TransactionService,
-        user::Service as UserService,
+        account::Service as AccountService, idempotency::Service as IdempotencyService,
+        pix_key::Service as PixKeyService, qr_code::Service as QrCodeService,
+        transaction::Service as TransactionService, user::Service as UserService,
     },
 };
 use futures::{stream, StreamExt};
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::{
@@ -28,6 +42,10 @@ use crate::{
 pub fn get_router() -> Router<Arc<ApplicationState>> {
     Router::new()
         .route("/transactions", post(create_account_transaction))
+        .route("/transactions/from-qr", post(create_transaction_from_qr))
+        .route("/transactions/batch", post(create_transaction_batch))
+        .route("/transactions/batch/pain001", post(import_payment_file))
+        .route("/transactions/:id/reversals", post(reverse_transaction))
         .route("/accounts/:id/transactions", get(get_account_transactions))
     // .route(
     //     "/accounts/:id/transactions/:transaction_id",
@@ -92,6 +110,13 @@ pub async fn get_account_transactions(
     filters.enforce_pagination();
 
     let (transactions, total) = transaction_service.get_all(&state.db_pool, &filters).await;
+    let ids: Vec<Uuid> = transactions
+        .iter()
+        .map(|transaction| transaction.id)
+        .collect();
+    let mut reversals = transaction_service
+        .get_reversal_ids(&state.db_pool, &ids)
+        .await;
     let transaction_models = stream::iter(transactions)
         .enumerate()
         .map(|(_index, transaction)| {
@@ -108,10 +133,11 @@ pub async fn get_account_transactions(
                     .await
                     .unwrap();
                 let res = TransactionModel::from_dto(&transaction, &user_to.encryption_key);
-                if res.is_err() && transaction.from_account_id.is_some() {
+                if let Some(from_account_id) = transaction.from_account_id.filter(|_| res.is_err())
+                {
                     dbg!(format!("Error: {:?}", res));
                     let from_account = account_service
-                        .get_one_by_id(&db_pool, &transaction.from_account_id.unwrap())
+                        .get_one_by_id(&db_pool, &from_account_id)
                         .await
                         .unwrap();
                     let user_from = user_service
@@ -134,6 +160,10 @@ pub async fn get_account_transactions(
         .await
         .into_iter()
         .filter_map(|x| x.ok())
+        .map(|model| {
+            let reversed_by = reversals.remove(&model.id).unwrap_or_default();
+            model.with_reversals(reversed_by)
+        })
         .collect::<Vec<TransactionModel>>();
 
     match filters.offset {
@@ -146,31 +176,35 @@ pub async fn get_account_transactions(
     }
 }
 
-#[utoipa::path(
-    post,
-    path = "/transactions",
-    context_path = "/api/v1",
-    request_body = TransactionCreate,
-    responses(
-        (status = 200, description = "Successful response", body = ReturnTypes<TransactionModel>),
-        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Transfer transactions need an origin account"}"#)),
-        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
-        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
-        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
-    ),
-)]
-pub async fn create_account_transaction(
-    State(state): State<Arc<ApplicationState>>,
-    Extension(current_user): Extension<User>,
-    Extension(scopes): Extension<Vec<String>>,
-    Json(transaction): Json<TransactionCreate>,
-) -> Result<Json<ReturnTypes<TransactionModel>>, (StatusCode, Json<HttpResponse>)> {
-    let mut tx = state.db_pool.begin().await.unwrap();
-    let transaction_service = TransactionService::new();
-    let account_service = AccountService::new();
-    let user_service = UserService::new();
+/// Points a transaction given by payment key at the account behind the key.
+async fn resolve_recipient(
+    state: &ApplicationState,
+    transaction: &mut TransactionCreate,
+) -> Result<Option<TransactionRecipient>, (StatusCode, Json<HttpResponse>)> {
+    PixKeyService::new()
+        .resolve_recipient(&state.db_pool, transaction)
+        .await
+        .map_err(|e| {
+            let status = match e.downcast_ref::<PixKeyError>() {
+                Some(PixKeyError::KeyNotFound) => StatusCode::NOT_FOUND,
+                Some(_) => StatusCode::BAD_REQUEST,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (
+                status,
+                Json(HttpResponse::new(status.as_u16(), e.to_string(), None)),
+            )
+        })
+}
 
-    let to_account = match account_service
+/// Checks that `current_user` may create `transaction`, and returns its destination account.
+async fn authorize(
+    state: &ApplicationState,
+    current_user: &User,
+    scopes: &[String],
+    transaction: &TransactionCreate,
+) -> Result<Account, (StatusCode, Json<HttpResponse>)> {
+    let to_account = match AccountService::new()
         .get_one_by_id(&state.db_pool, &transaction.to_account_id)
         .await
     {
@@ -188,17 +222,15 @@ pub async fn create_account_transaction(
     };
 
     match transaction.operation {
-        TransactionOperation::Withdrawal => {
-            if to_account.user_id != current_user.id {
-                return Err((
-                    StatusCode::FORBIDDEN,
-                    Json(HttpResponse::new(
-                        StatusCode::FORBIDDEN.as_u16(),
-                        "Forbidden".to_string(),
-                        None,
-                    )),
-                ));
-            }
+        TransactionOperation::Withdrawal if to_account.user_id != current_user.id => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(HttpResponse::new(
+                    StatusCode::FORBIDDEN.as_u16(),
+                    "Forbidden".to_string(),
+                    None,
+                )),
+            ));
         }
         TransactionOperation::Transfer => {
             let from_account_id = match &transaction.from_account_id {
@@ -214,7 +246,7 @@ pub async fn create_account_transaction(
                     ))
                 }
             };
-            let from_account = match account_service
+            let from_account = match AccountService::new()
                 .get_one_by_id(&state.db_pool, &from_account_id)
                 .await
             {
@@ -242,55 +274,653 @@ pub async fn create_account_transaction(
                 ));
             }
         }
-        TransactionOperation::Interest | TransactionOperation::Fee => {
-            if !scopes.contains(&"admin".to_string()) {
-                return Err((
-                    StatusCode::FORBIDDEN,
-                    Json(HttpResponse::new(
-                        StatusCode::FORBIDDEN.as_u16(),
-                        "Forbidden".to_string(),
-                        None,
-                    )),
-                ));
-            }
+        TransactionOperation::Reversal => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(HttpResponse::new(
+                    StatusCode::BAD_REQUEST.as_u16(),
+                    "Reversals are created through /transactions/:id/reversals".to_string(),
+                    None,
+                )),
+            ));
+        }
+        TransactionOperation::Interest | TransactionOperation::Fee
+            if !scopes.contains(&"admin".to_string()) =>
+        {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(HttpResponse::new(
+                    StatusCode::FORBIDDEN.as_u16(),
+                    "Forbidden".to_string(),
+                    None,
+                )),
+            ));
         }
         _ => {}
     }
 
+    Ok(to_account)
+}
+
+#[utoipa::path(
+    post,
+    path = "/transactions",
+    context_path = "/api/v1",
+    request_body = TransactionCreate,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replaying a key returns the original transaction instead of creating a new one"),
+    ),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<TransactionModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Transfer transactions need an origin account"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Account not found"}"#)),
+        (status = 409, description = "Idempotency key reused", body = HttpResponse, example = json!(r#"{"status": 409, "message": "Idempotency key was already used for a different request"}"#)),
+        (status = 422, description = "Transaction limit exceeded or not allowed for the account type or status", body = HttpResponse, example = json!(r#"{"status": 422, "message": "Amount exceeds the 5000.00 per-operation limit"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_account_transaction(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    headers: HeaderMap,
+    Json(transaction): Json<TransactionCreate>,
+) -> Result<Json<ReturnTypes<TransactionModel>>, (StatusCode, Json<HttpResponse>)> {
+    let idempotency_key = idempotency_key(&headers)?;
+
+    create_transaction(
+        &state,
+        &current_user,
+        &scopes,
+        idempotency_key,
+        None,
+        transaction,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/transactions/from-qr",
+    context_path = "/api/v1",
+    request_body = QrTransactionCreate,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replaying a key returns the original transaction instead of creating a new one. Dynamic BR Codes default to their transaction id"),
+    ),
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<TransactionModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "BR Code checksum 1D3E does not match the payload, expected 1D3D"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "No account under the key of the BR Code", body = HttpResponse, example = json!(r#"{"status": 404, "message": "No account is registered under that key"}"#)),
+        (status = 409, description = "Idempotency key reused, or the dynamic BR Code was already paid", body = HttpResponse, example = json!(r#"{"status": 409, "message": "The BR Code PEDIDO42 was already paid"}"#)),
+        (status = 422, description = "Transaction limit exceeded or not allowed for the account type or status", body = HttpResponse, example = json!(r#"{"status": 422, "message": "Amount exceeds the 5000.00 per-operation limit"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_transaction_from_qr(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    headers: HeaderMap,
+    Json(request): Json<QrTransactionCreate>,
+) -> Result<Json<ReturnTypes<TransactionModel>>, (StatusCode, Json<HttpResponse>)> {
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(HttpResponse::new(
+                StatusCode::BAD_REQUEST.as_u16(),
+                message,
+                None,
+            )),
+        )
+    };
+
+    let code = brcode::decode(request.payload.trim()).map_err(|e| bad_request(e.to_string()))?;
+    let transaction = request
+        .to_transaction(&code.key, code.amount)
+        .map_err(|e| bad_request(e.to_string()))?;
+
+    // a dynamic code is meant to be paid once, so scanning it twice must not pay it twice
+    let qr_transaction_id = code.transaction_id.filter(|_| code.dynamic);
+    let idempotency_key = match idempotency_key(&headers)? {
+        Some(key) => Some(key),
+        None => qr_transaction_id.clone(),
+    };
+
+    create_transaction(
+        &state,
+        &current_user,
+        &scopes,
+        idempotency_key,
+        qr_transaction_id,
+        transaction,
+    )
+    .await
+}
+
+/// The `Idempotency-Key` header, when the request has one.
+fn idempotency_key(
+    headers: &HeaderMap,
+) -> Result<Option<String>, (StatusCode, Json<HttpResponse>)> {
+    match headers.get("Idempotency-Key").map(|key| key.to_str()) {
+        Some(Ok(key)) => Ok(Some(key.to_string())),
+        Some(Err(_)) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(HttpResponse::new(
+                StatusCode::BAD_REQUEST.as_u16(),
+                IdempotencyError::InvalidKey.to_string(),
+                None,
+            )),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Resolves the recipient of `transaction`, authorizes it and creates it once per idempotency
+/// key. Payments of a dynamic BR Code carry its transaction id, and the code is paid only once.
+async fn create_transaction(
+    state: &ApplicationState,
+    current_user: &User,
+    scopes: &[String],
+    idempotency_key: Option<String>,
+    qr_transaction_id: Option<String>,
+    mut transaction: TransactionCreate,
+) -> Result<Json<ReturnTypes<TransactionModel>>, (StatusCode, Json<HttpResponse>)> {
+    let user_service = UserService::new();
+
+    let recipient = resolve_recipient(state, &mut transaction).await?;
+    let to_account = authorize(state, current_user, scopes, &transaction).await?;
+
     let user = user_service
         .get_one_by_id(&state.db_pool, &to_account.user_id)
         .await
         .unwrap();
-    match transaction_service
-        .create(&state.db_pool, &mut tx, &transaction, &current_user.id)
-        .await
-    {
-        Ok(transaction) => match TransactionModel::from_dto(&transaction, &user.encryption_key) {
-            Ok(transaction_model) => {
-                tx.commit().await.unwrap();
-                Ok(Json(ReturnTypes::Single(transaction_model)))
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let db_pool = state.db_pool.clone();
+        let transaction = transaction.clone();
+        let current_user_id = current_user.id;
+        let user_key = user.encryption_key.clone();
+        let idempotency_key = idempotency_key.clone();
+        let qr_transaction_id = qr_transaction_id.clone();
+        let idempotency_key_ttl = state.idempotency_key_ttl;
+        Box::pin(async move {
+            let transaction_service = TransactionService::new();
+            let idempotency_service = IdempotencyService::new();
+
+            if let Some(key) = &idempotency_key {
+                let claim = idempotency_service
+                    .claim(
+                        tx,
+                        &current_user_id,
+                        key,
+                        transaction.fingerprint()?,
+                        idempotency_key_ttl,
+                    )
+                    .await?;
+
+                if let IdempotencyClaim::Replay(transaction_id) = claim {
+                    let original = transaction_service
+                        .get_one_by_id(&db_pool, &transaction_id)
+                        .await
+                        .ok_or_else(|| anyhow::anyhow!("Transaction not found"))?;
+                    return TransactionModel::from_dto(&original, &user_key);
+                }
             }
-            Err(e) => {
-                tx.rollback().await.unwrap();
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(HttpResponse::new(
-                        StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                        format!("Error creating transaction: {}", e),
-                        None,
-                    )),
-                ))
+
+            let created = transaction_service
+                .create(&db_pool, tx, &transaction, &current_user_id)
+                .await?;
+            if let Some(key) = &idempotency_key {
+                idempotency_service
+                    .complete(tx, &current_user_id, key, &created.id)
+                    .await?;
             }
-        },
+            if let Some(qr_transaction_id) = &qr_transaction_id {
+                QrCodeService::new()
+                    .record_payment(tx, &created.to_account_id, qr_transaction_id, &created.id)
+                    .await?;
+            }
+            TransactionModel::from_dto(&created, &user_key)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(transaction_model) => Ok(Json(ReturnTypes::Single(
+            transaction_model.with_recipient(recipient),
+        ))),
         Err(e) => {
-            tx.rollback().await.unwrap();
+            let status = match e.downcast_ref::<IdempotencyError>() {
+                Some(IdempotencyError::KeyReused) => StatusCode::CONFLICT,
+                Some(IdempotencyError::InvalidKey) => StatusCode::BAD_REQUEST,
+                None if matches!(
+                    e.downcast_ref::<QrCodeError>(),
+                    Some(QrCodeError::AlreadyPaid(_))
+                ) =>
+                {
+                    StatusCode::CONFLICT
+                }
+                None if e.downcast_ref::<TransactionError>().is_some() => StatusCode::BAD_REQUEST,
+                None if e.downcast_ref::<LimitExceeded>().is_some()
+                    || e.downcast_ref::<AccountTypeError>().is_some()
+                    || e.downcast_ref::<AccountStatusError>().is_some() =>
+                {
+                    StatusCode::UNPROCESSABLE_ENTITY
+                }
+                None => {
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(HttpResponse::new(
+                            StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                            format!("Error creating transaction: {}", e),
+                            None,
+                        )),
+                    ))
+                }
+            };
+
             Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                status,
+                Json(HttpResponse::new(status.as_u16(), e.to_string(), None)),
+            ))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/transactions/batch",
+    context_path = "/api/v1",
+    request_body = TransactionBatch,
+    responses(
+        (status = 200, description = "The batch was applied; best-effort batches report the items that failed", body = ReturnTypes<TransactionBatchResult>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "A batch needs at least one transaction"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Transaction 2: Forbidden"}"#)),
+        (status = 404, description = "Account not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Transaction 0: Account not found"}"#)),
+        (status = 422, description = "An item failed and the all-or-nothing batch was rolled back", body = ReturnTypes<TransactionBatchResult>),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn create_transaction_batch(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Json(batch): Json<TransactionBatch>,
+) -> Result<(StatusCode, Json<ReturnTypes<TransactionBatchResult>>), (StatusCode, Json<HttpResponse>)>
+{
+    if let Err(e) = batch.validate() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(HttpResponse::new(
+                StatusCode::BAD_REQUEST.as_u16(),
+                e.to_string(),
+                None,
+            )),
+        ));
+    }
+
+    let mut items = Vec::with_capacity(batch.transactions.len());
+    for (index, mut transaction) in batch.transactions.into_iter().enumerate() {
+        let in_batch = |(status, Json(response)): (StatusCode, Json<HttpResponse>)| {
+            (
+                status,
                 Json(HttpResponse::new(
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                    format!("Error creating transaction: {}", e),
+                    response.status,
+                    format!("Transaction {}: {}", index, response.message),
                     None,
                 )),
+            )
+        };
+        resolve_recipient(&state, &mut transaction)
+            .await
+            .map_err(in_batch)?;
+        let to_account = authorize(&state, &current_user, &scopes, &transaction)
+            .await
+            .map_err(in_batch)?;
+        items.push(Ok((transaction, to_account.user_id)));
+    }
+
+    apply_batch(&state, &current_user, batch.mode, items, &[], None).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/transactions/batch/pain001",
+    context_path = "/api/v1",
+    params(
+        ("mode" = Option<BatchMode>, Query, description = "How the payments that fail are treated, all_or_nothing (default) or best_effort"),
+    ),
+    request_body(content = String, description = "ISO 20022 pain.001 customer credit transfer initiation", content_type = "application/xml"),
+    responses(
+        (status = 200, description = "The payments were applied; best-effort files report the ones that failed by EndToEndId", body = ReturnTypes<TransactionBatchResult>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "The group header declares 3 payments but the file holds 2"}"#)),
+        (status = 409, description = "A file with the same MsgId was already applied", body = HttpResponse, example = json!(r#"{"status": 409, "message": "Payment file MSG-1 was already applied"}"#)),
+        (status = 422, description = "A payment failed and the all-or-nothing file was rolled back", body = ReturnTypes<TransactionBatchResult>),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn import_payment_file(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Query(query): Query<PaymentFileQuery>,
+    body: String,
+) -> Result<(StatusCode, Json<ReturnTypes<TransactionBatchResult>>), (StatusCode, Json<HttpResponse>)>
+{
+    let payment_file = match pain001::parse(&body) {
+        Ok(payment_file) => payment_file,
+        Err(e) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(HttpResponse::new(
+                    StatusCode::BAD_REQUEST.as_u16(),
+                    e.to_string(),
+                    None,
+                )),
+            ))
+        }
+    };
+
+    // every payment is reported on by its EndToEndId, authorization failures included
+    let mut items = Vec::with_capacity(payment_file.entries.len());
+    let mut references = Vec::with_capacity(payment_file.entries.len());
+    for entry in payment_file.entries {
+        let item = match entry.transaction {
+            Ok(transaction) => authorize(&state, &current_user, &scopes, &transaction)
+                .await
+                .map(|to_account| (transaction, to_account.user_id))
+                .map_err(|(_, Json(response))| response.message),
+            Err(e) => Err(e.to_string()),
+        };
+        items.push(item);
+        references.push(entry.end_to_end_id);
+    }
+
+    apply_batch(
+        &state,
+        &current_user,
+        query.mode,
+        items,
+        &references,
+        Some(&payment_file.message_id),
+    )
+    .await
+}
+
+/// A batch item that passed authorization, with the owner of its destination account, or the
+/// reason it failed before reaching the database.
+type BatchItem = Result<(TransactionCreate, Uuid), String>;
+
+#[derive(Debug, Deserialize)]
+pub struct PaymentFileQuery {
+    #[serde(default)]
+    mode: BatchMode,
+}
+
+/// Applies the items of a batch still standing and reports on all of them, in order. An
+/// all-or-nothing batch that already has a failed item is not attempted at all. Batches read
+/// from a payment file carry its message id, so the file is only applied once.
+async fn apply_batch(
+    state: &ApplicationState,
+    current_user: &User,
+    mode: BatchMode,
+    items: Vec<BatchItem>,
+    references: &[String],
+    message_id: Option<&str>,
+) -> Result<(StatusCode, Json<ReturnTypes<TransactionBatchResult>>), (StatusCode, Json<HttpResponse>)>
+{
+    // amounts are shown with the key of each destination account's owner
+    let user_service = UserService::new();
+    let mut user_keys: HashMap<Uuid, Vec<u8>> = HashMap::new();
+    for (_, owner_id) in items.iter().flatten() {
+        if !user_keys.contains_key(owner_id) {
+            let owner = user_service
+                .get_one_by_id(&state.db_pool, owner_id)
+                .await
+                .ok_or_else(|| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(HttpResponse::new(
+                            StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                            "Account owner not found".to_string(),
+                            None,
+                        )),
+                    )
+                })?;
+            user_keys.insert(owner.id, owner.encryption_key);
+        }
+    }
+
+    let batch = TransactionBatch {
+        mode,
+        transactions: items
+            .iter()
+            .flatten()
+            .map(|(transaction, _)| transaction.clone())
+            .collect(),
+    };
+    let failed_early = batch.transactions.len() < items.len();
+    let mut applied =
+        if batch.transactions.is_empty() || (failed_early && mode == BatchMode::AllOrNothing) {
+            Vec::new()
+        } else {
+            let result = with_transaction_retry(&state.db_pool, |tx| {
+                let db_pool = state.db_pool.clone();
+                let batch = batch.clone();
+                let current_user_id = current_user.id;
+                let message_id = message_id.map(str::to_string);
+                Box::pin(async move {
+                    let transaction_service = TransactionService::new();
+                    match message_id {
+                        Some(message_id) => {
+                            transaction_service
+                                .create_payment_file_batch(
+                                    &db_pool,
+                                    tx,
+                                    &message_id,
+                                    &batch,
+                                    &current_user_id,
+                                )
+                                .await
+                        }
+                        None => {
+                            transaction_service
+                                .create_batch(&db_pool, tx, &batch, &current_user_id)
+                                .await
+                        }
+                    }
+                })
+            })
+            .await;
+
+            match result {
+                Ok(outcomes) => outcomes,
+                Err(e) if e.downcast_ref::<PaymentFileError>().is_some() => {
+                    return Err((
+                        StatusCode::CONFLICT,
+                        Json(HttpResponse::new(
+                            StatusCode::CONFLICT.as_u16(),
+                            e.to_string(),
+                            None,
+                        )),
+                    ))
+                }
+                Err(e) => {
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(HttpResponse::new(
+                            StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                            format!("Error creating transactions: {}", e),
+                            None,
+                        )),
+                    ))
+                }
+            }
+        }
+        .into_iter();
+
+    let mut outcomes = Vec::with_capacity(items.len());
+    for item in items {
+        let outcome = match item {
+            Ok((_, owner_id)) => (
+                applied.next().unwrap_or(BatchItemOutcome::RolledBack),
+                Some(owner_id),
+            ),
+            Err(error) => (BatchItemOutcome::Failed(error), None),
+        };
+        outcomes.push(outcome);
+    }
+
+    let committed = mode == BatchMode::BestEffort
+        || !outcomes
+            .iter()
+            .any(|(outcome, _)| matches!(outcome, BatchItemOutcome::Failed(_)));
+    let mut results = Vec::with_capacity(outcomes.len());
+    for (index, (outcome, owner_id)) in outcomes.into_iter().enumerate() {
+        let reference = references.get(index).cloned();
+        let item = match outcome {
+            BatchItemOutcome::Created(created) => {
+                // only authorized items reach the database, and those all carry their owner
+                let key = owner_id
+                    .map(|owner_id| user_keys[&owner_id].as_slice())
+                    .unwrap_or_default();
+                match TransactionModel::from_dto(&created, key) {
+                    Ok(transaction_model) => BatchItemResult {
+                        index,
+                        reference,
+                        status: BatchItemStatus::Created,
+                        transaction: Some(transaction_model),
+                        error: None,
+                    },
+                    Err(e) => {
+                        return Err((
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(HttpResponse::new(
+                                StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                                e.to_string(),
+                                None,
+                            )),
+                        ))
+                    }
+                }
+            }
+            BatchItemOutcome::RolledBack => BatchItemResult {
+                index,
+                reference,
+                status: BatchItemStatus::RolledBack,
+                transaction: None,
+                error: None,
+            },
+            BatchItemOutcome::Failed(error) => BatchItemResult {
+                index,
+                reference,
+                status: BatchItemStatus::Failed,
+                transaction: None,
+                error: Some(error),
+            },
+        };
+        results.push(item);
+    }
+
+    let status = match committed {
+        true => StatusCode::OK,
+        false => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+
+    Ok((
+        status,
+        Json(ReturnTypes::Single(TransactionBatchResult {
+            mode,
+            committed,
+            items: results,
+        })),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/transactions/:id/reversals",
+    context_path = "/api/v1",
+    params(
+        ("id" = Uuid, Path, description = "ID of the transaction to reverse"),
+    ),
+    request_body = ReversalCreate,
+    responses(
+        (status = 200, description = "Successful response", body = ReturnTypes<TransactionModel>),
+        (status = 400, description = "Bad Request", body = HttpResponse, example = json!(r#"{"status": 400, "message": "Reversal amount must be positive"}"#)),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "Transaction not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "Transaction not found"}"#)),
+        (status = 409, description = "Conflict", body = HttpResponse, example = json!(r#"{"status": 409, "message": "Transaction has already been fully reversed"}"#)),
+        (status = 422, description = "Unprocessable Entity", body = HttpResponse, example = json!(r#"{"status": 422, "message": "Reversal exceeds the 10.00 left to reverse"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn reverse_transaction(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(_current_user): Extension<User>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+    Json(reversal): Json<ReversalCreate>,
+) -> Result<Json<ReturnTypes<TransactionModel>>, (StatusCode, Json<HttpResponse>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(HttpResponse::new(
+                StatusCode::FORBIDDEN.as_u16(),
+                "Forbidden".to_string(),
+                None,
+            )),
+        ));
+    }
+
+    let result = with_transaction_retry(&state.db_pool, |tx| {
+        let db_pool = state.db_pool.clone();
+        let amount = reversal.amount;
+        Box::pin(async move {
+            let reversal = TransactionService::new()
+                .reverse(&db_pool, tx, &id, amount)
+                .await?;
+            let to_account = AccountService::new()
+                .get_one_by_id(&db_pool, &reversal.to_account_id)
+                .await
+                .ok_or_else(|| anyhow::anyhow!("Account not found"))?;
+            let user = UserService::new()
+                .get_one_by_id(&db_pool, &to_account.user_id)
+                .await
+                .ok_or_else(|| anyhow::anyhow!("User not found"))?;
+            TransactionModel::from_dto(&reversal, &user.encryption_key)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(transaction_model) => Ok(Json(ReturnTypes::Single(transaction_model))),
+        Err(e) => {
+            let status = match e.downcast_ref::<ReversalError>() {
+                Some(ReversalError::InvalidAmount) => StatusCode::BAD_REQUEST,
+                Some(ReversalError::NotReversible) | Some(ReversalError::AlreadyReversed) => {
+                    StatusCode::CONFLICT
+                }
+                Some(ReversalError::ExceedsRemaining(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+                None => match e.downcast_ref::<sqlx::Error>() {
+                    Some(sqlx::Error::RowNotFound) => {
+                        return Err((
+                            StatusCode::NOT_FOUND,
+                            Json(HttpResponse::new(
+                                StatusCode::NOT_FOUND.as_u16(),
+                                "Transaction not found".to_string(),
+                                None,
+                            )),
+                        ))
+                    }
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                },
+            };
+
+            Err((
+                status,
+                Json(HttpResponse::new(status.as_u16(), e.to_string(), None)),
             ))
         }
     }
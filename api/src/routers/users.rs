@@ -3,8 +3,8 @@ use std::sync::Arc;
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    routing::get,
-    Json, Router,
+    routing::{get, post},
+    Extension, Json, Router,
 };
 use database::{
     filters::user::Filter as UserFilter,
@@ -25,6 +25,7 @@ pub fn get_router() -> Router<Arc<ApplicationState>> {
             "/users/:id",
             get(get_user).put(update_user).delete(delete_user),
         )
+        .route("/users/:id/restore", post(restore_user))
 }
 
 #[utoipa::path(
@@ -35,6 +36,7 @@ pub fn get_router() -> Router<Arc<ApplicationState>> {
         ("id" = Option<Uuid>, Query, description = "User ID"),
         ("name" = Option<String>, Query, description = "User name"),
         ("email" = Option<String>, Query, description = "User email"),
+        ("include_deleted" = Option<bool>, Query, description = "List deleted users too; admins only"),
         ("offset" = Option<usize>, Query, description = "Pagination offset"),
         ("limit" = Option<usize>, Query, description = "Pagination limit"),
     ),
@@ -45,10 +47,14 @@ pub fn get_router() -> Router<Arc<ApplicationState>> {
 )]
 pub async fn get_users(
     State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
     Query(mut filters): Query<UserFilter>,
 ) -> Result<Json<ReturnTypes<User>>, (StatusCode, Json<HttpResponse>)> {
     let user_service = UserService::new();
 
+    if !scopes.contains(&"admin".to_string()) {
+        filters.include_deleted = None;
+    }
     filters.enforce_pagination();
     let (users, total) = user_service.get_all(&state.db_pool, &filters).await;
 
@@ -76,11 +82,16 @@ pub async fn get_users(
 )]
 pub async fn get_user(
     State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<ReturnTypes<User>>, (StatusCode, Json<String>)> {
     let user_service = UserService::new();
 
-    match user_service.get_one_by_id(&state.db_pool, &id).await {
+    match user_service
+        .get_one_by_id(&state.db_pool, &id)
+        .await
+        .filter(|user| user.deleted_at.is_none() || scopes.contains(&"admin".to_string()))
+    {
         Some(user) => Ok(Json(ReturnTypes::Single(user))),
         None => Err((StatusCode::NOT_FOUND, Json("User not found".to_string()))),
     }
@@ -153,6 +164,7 @@ pub async fn update_user(
     params(("id" = Uuid, Path, description = "User ID")),
     responses(
         (status = 200, description = "Successful response", body = HttpResponse, example = json!(r#"{"status": 200, "message": "User deleted successfully"}"#)),
+        (status = 404, description = "User not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "User not found"}"#)),
         (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
     ),
 )]
@@ -174,10 +186,48 @@ pub async fn delete_user(
         }
         false => {
             tx.rollback().await.unwrap();
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Internal Server Error".to_string()),
-            ))
+            Err((StatusCode::NOT_FOUND, Json("User not found".to_string())))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/:id/restore",
+    context_path = "/api/v1",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user can sign in again", body = ReturnTypes<User>),
+        (status = 403, description = "Forbidden", body = HttpResponse, example = json!(r#"{"status": 403, "message": "Forbidden"}"#)),
+        (status = 404, description = "User not found", body = HttpResponse, example = json!(r#"{"status": 404, "message": "User not found"}"#)),
+        (status = 500, description = "Internal Server Error", body = HttpResponse, example = json!(r#"{"status": 500, "message": "Internal Server Error"}"#))
+    ),
+)]
+pub async fn restore_user(
+    State(state): State<Arc<ApplicationState>>,
+    Extension(scopes): Extension<Vec<String>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReturnTypes<User>>, (StatusCode, Json<String>)> {
+    if !scopes.contains(&"admin".to_string()) {
+        return Err((StatusCode::FORBIDDEN, Json("Forbidden".to_string())));
+    }
+
+    let user_service = UserService::new();
+    let mut tx = state.db_pool.begin().await.unwrap();
+
+    match user_service.restore(&mut tx, &id).await {
+        Ok(user) => {
+            tx.commit().await.unwrap();
+            Ok(Json(ReturnTypes::Single(user)))
+        }
+        Err(err) => {
+            tx.rollback().await.unwrap();
+            match err.downcast_ref::<sqlx::Error>() {
+                Some(sqlx::Error::RowNotFound) => {
+                    Err((StatusCode::NOT_FOUND, Json("User not found".to_string())))
+                }
+                _ => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(err.to_string()))),
+            }
         }
     }
 }
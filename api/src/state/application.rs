@@ -6,14 +6,22 @@ pub struct ApplicationState {
     pub db_pool: PgPool,
     pub master_key: Vec<u8>,
     pub jwt_key: String,
+    /// How long an `Idempotency-Key` keeps answering with its original response.
+    pub idempotency_key_ttl: chrono::Duration,
 }
 
 impl ApplicationState {
-    pub fn new(db_pool: PgPool, master_key: Vec<u8>, jwt_key: String) -> Self {
+    pub fn new(
+        db_pool: PgPool,
+        master_key: Vec<u8>,
+        jwt_key: String,
+        idempotency_key_ttl: chrono::Duration,
+    ) -> Self {
         Self {
             db_pool,
             master_key,
             jwt_key,
+            idempotency_key_ttl,
         }
     }
 }
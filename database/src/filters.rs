@@ -1,3 +1,6 @@
 pub mod user;
 pub mod account;
 pub mod transaction;
+pub mod schedule;
+pub mod bank;
+pub mod audit;
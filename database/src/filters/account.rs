@@ -9,8 +9,10 @@ pub struct Filter {
     pub id: Option<Uuid>,
     pub user_id: Option<Uuid>,
     pub bank_id: Option<i32>,
-    pub bank_account_number: Option<i32>,
+    pub bank_account_number: Option<i64>,
     pub bank_agency_number: Option<i32>,
+    /// Lists soft-deleted rows too; only admins may ask for them.
+    pub include_deleted: Option<bool>,
     #[serde(skip_serializing, default)]
     pub offset: Option<usize>,
     #[serde(skip_serializing, default)]
@@ -28,5 +30,7 @@ impl_filterable!(
     ],
     range = [],
     multi_match = [],
+    search = [],
+    soft_delete = [include_deleted],
     order_by = [(created_at, asc), (id, asc)]
 );
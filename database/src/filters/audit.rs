@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use struct_iterable::Iterable;
+use uuid::Uuid;
+
+use crate::{impl_filterable, structs::range::Range};
+
+#[derive(Debug, Serialize, Deserialize, Default, Iterable)]
+pub struct Filter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub created_at: Option<Range<NaiveDateTime>>,
+    #[serde(skip_serializing, default)]
+    pub offset: Option<usize>,
+    #[serde(skip_serializing, default)]
+    pub limit: Option<usize>,
+}
+
+impl_filterable!(
+    Filter,
+    exact = [actor_id, action, target_type, target_id, request_id],
+    range = [created_at],
+    multi_match = [],
+    search = [],
+    soft_delete = [],
+    order_by = [(sequence, desc)]
+);
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use struct_iterable::Iterable;
+
+use crate::impl_filterable;
+
+#[derive(Debug, Serialize, Deserialize, Default, Iterable)]
+pub struct Filter {
+    pub id: Option<i32>,
+    pub ispb: Option<String>,
+    pub active: Option<bool>,
+    /// Part of the short or full name of the bank.
+    pub search: Option<String>,
+    #[serde(skip_serializing, default)]
+    pub offset: Option<usize>,
+    #[serde(skip_serializing, default)]
+    pub limit: Option<usize>,
+}
+
+impl_filterable!(
+    Filter,
+    exact = [id, ispb, active],
+    range = [],
+    multi_match = [],
+    search = [(search, [short_name, name])],
+    soft_delete = [],
+    order_by = [(id, asc)]
+);
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use struct_iterable::Iterable;
+use uuid::Uuid;
+
+use crate::{impl_filterable, models::schedule_dto::ScheduleStatus};
+
+#[derive(Debug, Serialize, Deserialize, Default, Iterable)]
+pub struct Filter {
+    pub id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub status: Option<ScheduleStatus>,
+    pub account_id: Option<Uuid>,
+    #[serde(skip_serializing, default)]
+    pub offset: Option<usize>,
+    #[serde(skip_serializing, default)]
+    pub limit: Option<usize>,
+}
+
+impl_filterable!(
+    Filter,
+    exact = [id, user_id, status],
+    range = [],
+    multi_match = [(account_id, [from_account_id, to_account_id])],
+    search = [],
+    soft_delete = [],
+    order_by = [(created_at, desc), (id, desc)]
+);
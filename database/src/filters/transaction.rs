@@ -21,5 +21,7 @@ impl_filterable!(
     exact = [id],
     range = [created_at],
     multi_match = [(account_id, [from_account_id, to_account_id])],
+    search = [],
+    soft_delete = [],
     order_by = [(created_at, desc), (id, desc)]
 );
@@ -9,6 +9,8 @@ pub struct Filter {
     pub id: Option<Uuid>,
     pub name: Option<String>,
     pub email: Option<String>,
+    /// Lists soft-deleted rows too; only admins may ask for them.
+    pub include_deleted: Option<bool>,
     #[serde(skip_serializing, default)]
     pub offset: Option<usize>,
     #[serde(skip_serializing, default)]
@@ -20,5 +22,7 @@ impl_filterable!(
     exact = [id, name, email],
     range = [],
     multi_match = [],
+    search = [],
+    soft_delete = [include_deleted],
     order_by = [(created_at, asc), (id, asc)]
 );
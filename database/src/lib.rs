@@ -1,9 +1,15 @@
 pub mod filters;
 pub mod models;
+pub mod payment_files;
 pub mod repositories;
+pub mod retry;
 pub mod services;
+pub mod statements;
 pub mod structs;
+#[cfg(test)]
+mod test_helpers;
 pub mod traits;
+pub mod validation;
 
 use aes_gcm::{aead::Aead, Aes256Gcm, Error as AesError, Nonce};
 use argon2::{password_hash::SaltString, Argon2, PasswordHasher, PasswordVerifier};
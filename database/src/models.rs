@@ -1,3 +1,22 @@
 pub mod account_dto;
+pub mod audit_dto;
+pub mod bank_dto;
+pub mod boleto_dto;
+pub mod employer_dto;
+pub mod fee_dto;
+pub mod hold_dto;
+pub mod idempotency_dto;
+pub mod interest_dto;
+pub mod journal_dto;
+pub mod limit_dto;
+pub mod notification_dto;
+pub mod overdraft_dto;
+pub mod payment_file_dto;
+pub mod pix_key_dto;
+pub mod qr_code_dto;
+pub mod reconciliation_dto;
+pub mod remittance_dto;
+pub mod schedule_dto;
+pub mod statement_dto;
 pub mod transaction_dto;
 pub mod user_dto;
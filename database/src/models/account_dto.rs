@@ -1,42 +1,182 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
+use sqlx::prelude::{FromRow, Type};
+use thiserror::Error;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::{
-    decrypt_user_key, load_master_key, structs::encrypted_field::EncryptedField,
+    decrypt_user_key, load_master_key,
+    structs::{
+        encrypted_field::EncryptedField,
+        money::{Money, MoneyEncoding},
+    },
     traits::encryptable::Encryptable,
+    validation::BankDetails,
 };
 
 use super::user_dto::User;
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "account_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AccountType {
+    Checking,
+    Savings,
+    /// Opened by an employer to pay wages into.
+    Salary,
+    /// Held at a payment institution rather than a bank.
+    Payment,
+}
+
+impl AccountType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountType::Checking => "checking",
+            AccountType::Savings => "savings",
+            AccountType::Salary => "salary",
+            AccountType::Payment => "payment",
+        }
+    }
+
+    pub fn rules(&self) -> AccountTypeRules {
+        AccountTypeRules {
+            can_pay_boletos: *self != AccountType::Savings,
+            employers_only: *self == AccountType::Salary,
+            monthly_withdrawals: match self {
+                AccountType::Checking => None,
+                AccountType::Savings => Some(2),
+                AccountType::Salary => Some(5),
+                AccountType::Payment => Some(4),
+            },
+        }
+    }
+}
+
+/// What an account may do given its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct AccountTypeRules {
+    /// Whether it may pay slips and make other payments out of the bank.
+    pub can_pay_boletos: bool,
+    /// Whether only its registered employers may pay into it.
+    pub employers_only: bool,
+    /// How many withdrawals it may make each calendar month; left out when unlimited.
+    #[schema(example = 2)]
+    pub monthly_withdrawals: Option<i64>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AccountTypeError {
+    #[error("{} accounts cannot pay slips or make other payments", .0.as_str())]
+    PaymentsNotAllowed(AccountType),
+    #[error("Salary accounts only receive from their registered employers")]
+    NotAnEmployer,
+    #[error("{} accounts allow {allowance} withdrawals a month", .account_type.as_str())]
+    WithdrawalAllowanceExceeded {
+        account_type: AccountType,
+        allowance: i64,
+    },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "account_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AccountStatus {
+    #[default]
+    Active,
+    /// Still credited, but nothing can be taken out of it until it is unfrozen.
+    Frozen,
+    /// Takes no new operations while the funds held on it are settled.
+    Closing,
+    /// Paid out and kept only for its history.
+    Closed,
+}
+
+impl AccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Active => "active",
+            AccountStatus::Frozen => "frozen",
+            AccountStatus::Closing => "closing",
+            AccountStatus::Closed => "closed",
+        }
+    }
+
+    /// Whether an account may move from this status to `status`.
+    pub fn can_become(&self, status: AccountStatus) -> bool {
+        matches!(
+            (self, status),
+            (AccountStatus::Active, AccountStatus::Frozen)
+                | (AccountStatus::Frozen, AccountStatus::Active)
+                | (
+                    AccountStatus::Active | AccountStatus::Frozen | AccountStatus::Closing,
+                    AccountStatus::Closing
+                )
+                | (AccountStatus::Closing, AccountStatus::Closed)
+        )
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AccountStatusError {
+    #[error("The account is frozen and cannot be debited")]
+    Frozen,
+    #[error("The account is {} and takes no new operations", .0.as_str())]
+    NotOpen(AccountStatus),
+    #[error("A {} account cannot become {}", .from.as_str(), .to.as_str())]
+    InvalidTransition {
+        from: AccountStatus,
+        to: AccountStatus,
+    },
+    #[error("The balance must be paid out to another account before closing")]
+    BalanceNotPaidOut,
+    #[error("An overdrawn account must be settled before closing")]
+    Overdrawn,
+    #[error("The balance cannot be paid out to the account being closed")]
+    SamePayoutAccount,
+    #[error("Funds are still held on the account")]
+    FundsHeld,
+}
+
+/// A debit the account's available balance, overdraft included, does not cover.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Not enough funds")]
+pub struct InsufficientFunds;
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Account {
     pub id: Uuid,
     pub user_id: Uuid, // Owner of the account
     pub bank_id: Option<i32>,
-    pub bank_account_number: Option<i32>,
+    pub bank_account_number: Option<i64>,
     pub bank_account_digit: Option<i32>,
     pub bank_agency_number: Option<i32>,
     pub bank_agency_digit: Option<i32>,
-    pub bank_account_type: Option<i32>,
-    pub balance: EncryptedField<f64>,
+    pub bank_account_type: Option<AccountType>,
+    pub balance: EncryptedField<Money>,
+    pub balance_encoding: MoneyEncoding,
+    /// How far below zero the balance may go.
+    pub overdraft_limit: Money,
+    /// Annual rate charged on the overdrawn amount, in basis points.
+    pub overdraft_rate_bps: i32,
+    pub status: AccountStatus,
+    pub closed_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 impl Account {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         user: &User,
-        balance: f64,
+        balance: Money,
         bank_id: Option<i32>,
-        bank_account_number: Option<i32>,
+        bank_account_number: Option<i64>,
         bank_account_digit: Option<i32>,
         bank_agency_number: Option<i32>,
         bank_agency_digit: Option<i32>,
-        bank_account_type: Option<i32>,
+        bank_account_type: Option<AccountType>,
     ) -> anyhow::Result<Self> {
         let master_key = load_master_key()?;
         let key = decrypt_user_key(&user.encryption_key, &master_key)?;
@@ -50,25 +190,62 @@ impl Account {
             bank_agency_digit,
             bank_account_type,
             balance: balance.encrypt(&key)?,
+            balance_encoding: MoneyEncoding::MinorUnits,
+            overdraft_limit: Money::ZERO,
+            overdraft_rate_bps: 0,
+            status: AccountStatus::Active,
+            closed_at: None,
             created_at: chrono::Utc::now().naive_utc(),
             updated_at: None,
+            deleted_at: None,
         })
     }
 
-    pub fn get_balance(&self, user: &User) -> anyhow::Result<f64> {
+    pub fn get_balance(&self, user: &User) -> anyhow::Result<Money> {
         let master_key = load_master_key()?;
         let key = decrypt_user_key(&user.encryption_key, &master_key)?;
-        Ok(f64::decrypt(&self.balance, &key)?)
+        self.balance_encoding.decrypt(&self.balance, &key)
     }
 
-    pub fn update_balance(&mut self, user: &User, new_balance: f64) -> Result<(), anyhow::Error> {
+    /// Sets a new balance, refusing to take it further below zero than the overdraft limit.
+    ///
+    /// A balance already past a limit that was lowered can still go up.
+    pub fn update_balance(&mut self, user: &User, new_balance: Money) -> Result<(), anyhow::Error> {
         let master_key = load_master_key()?;
         let key = decrypt_user_key(&user.encryption_key, &master_key)?;
-        if new_balance < 0.0 {
-            return Err(anyhow::anyhow!("Not enough funds"));
+        if new_balance < -self.overdraft_limit
+            && new_balance < self.balance_encoding.decrypt(&self.balance, &key)?
+        {
+            return Err(InsufficientFunds.into());
         }
 
         self.balance = new_balance.encrypt(&key)?;
+        self.balance_encoding = MoneyEncoding::MinorUnits;
+
+        Ok(())
+    }
+
+    /// Takes `amount` off the balance even past the overdraft limit, for the interest charged
+    /// on the overdraft itself.
+    pub fn overdraw(&mut self, user: &User, amount: Money) -> Result<(), anyhow::Error> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&user.encryption_key, &master_key)?;
+        let balance = self.balance_encoding.decrypt(&self.balance, &key)?;
+
+        self.balance = (balance - amount).encrypt(&key)?;
+        self.balance_encoding = MoneyEncoding::MinorUnits;
+
+        Ok(())
+    }
+
+    /// Re-encrypts a balance written under an older encoding as minor units.
+    pub fn reencrypt_balance(&mut self, user: &User) -> Result<(), anyhow::Error> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&user.encryption_key, &master_key)?;
+        let balance = self.balance_encoding.decrypt(&self.balance, &key)?;
+
+        self.balance = balance.encrypt(&key)?;
+        self.balance_encoding = MoneyEncoding::MinorUnits;
 
         Ok(())
     }
@@ -78,15 +255,28 @@ impl Account {
 pub struct AccountCreate {
     pub user_id: Uuid,
     pub bank_id: Option<i32>,
-    pub bank_account_number: Option<i32>,
+    pub bank_account_number: Option<i64>,
+    /// Checked against the account number for the banks we know; a digit printed as a letter
+    /// is given as 10.
     pub bank_account_digit: Option<i32>,
     pub bank_agency_number: Option<i32>,
     pub bank_agency_digit: Option<i32>,
-    pub bank_account_type: Option<i32>,
-    pub balance: f64,
+    pub bank_account_type: Option<AccountType>,
+    #[schema(value_type = String, example = "1000.00")]
+    pub balance: Money,
 }
 
 impl AccountCreate {
+    pub fn bank_details(&self) -> BankDetails {
+        BankDetails {
+            bank_id: self.bank_id,
+            agency_number: self.bank_agency_number,
+            agency_digit: self.bank_agency_digit,
+            account_number: self.bank_account_number,
+            account_digit: self.bank_account_digit,
+        }
+    }
+
     pub fn to_account(&self, user: &User) -> Result<Account, anyhow::Error> {
         let master_key = load_master_key()?;
         let key = decrypt_user_key(&user.encryption_key, &master_key)?;
@@ -100,31 +290,85 @@ impl AccountCreate {
             bank_agency_digit: self.bank_agency_digit,
             bank_account_type: self.bank_account_type,
             balance: self.balance.encrypt(&key)?,
+            balance_encoding: MoneyEncoding::MinorUnits,
+            overdraft_limit: Money::ZERO,
+            overdraft_rate_bps: 0,
+            status: AccountStatus::Active,
+            closed_at: None,
             created_at: chrono::Utc::now().naive_utc(),
             updated_at: None,
+            deleted_at: None,
         })
     }
 }
 
+/// Body of a request to close an account.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct AccountClose {
+    /// Where the remaining balance goes; needed unless the balance is already zero.
+    pub payout_account_id: Option<Uuid>,
+}
+
+/// Body of a change to the bank an account is held at. The account type is not part of it,
+/// since owners could otherwise shed the rules of theirs.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AccountUpdate {
+    pub bank_id: Option<i32>,
+    pub bank_account_number: Option<i64>,
+    pub bank_account_digit: Option<i32>,
+    pub bank_agency_number: Option<i32>,
+    pub bank_agency_digit: Option<i32>,
+}
+
+impl AccountUpdate {
+    pub fn bank_details(&self) -> BankDetails {
+        BankDetails {
+            bank_id: self.bank_id,
+            agency_number: self.bank_agency_number,
+            agency_digit: self.bank_agency_digit,
+            account_number: self.bank_account_number,
+            account_digit: self.bank_account_digit,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AccountModel {
     pub id: Uuid,
     pub user_id: Uuid,
     pub bank_id: Option<i32>,
-    pub bank_account_number: Option<i32>,
+    pub bank_account_number: Option<i64>,
     pub bank_account_digit: Option<i32>,
     pub bank_agency_number: Option<i32>,
     pub bank_agency_digit: Option<i32>,
-    pub bank_account_type: Option<i32>,
-    pub balance: f64,
+    pub bank_account_type: Option<AccountType>,
+    /// What the account type allows; left out for accounts without a type.
+    pub account_type_rules: Option<AccountTypeRules>,
+    /// Everything posted to the account.
+    #[schema(value_type = String, example = "1000.00")]
+    pub ledger_balance: Money,
+    /// The ledger balance less what active holds reserve, plus the unused overdraft.
+    #[schema(value_type = String, example = "750.00")]
+    pub available_balance: Money,
+    #[schema(value_type = String, example = "500.00")]
+    pub overdraft_limit: Money,
+    /// How much of the overdraft limit the ledger balance is using.
+    #[schema(value_type = String, example = "0.00")]
+    pub overdraft_used: Money,
+    #[schema(example = 1290)]
+    pub overdraft_rate_bps: i32,
+    pub status: AccountStatus,
+    pub closed_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 impl AccountModel {
     pub fn from_dto(account: &Account, user: &User) -> Result<Self, anyhow::Error> {
         let master_key = load_master_key()?;
         let key = decrypt_user_key(&user.encryption_key, &master_key)?;
+        let ledger_balance = account.balance_encoding.decrypt(&account.balance, &key)?;
         Ok(Self {
             id: account.id,
             user_id: account.user_id,
@@ -134,11 +378,26 @@ impl AccountModel {
             bank_agency_number: account.bank_agency_number,
             bank_agency_digit: account.bank_agency_digit,
             bank_account_type: account.bank_account_type,
-            balance: f64::decrypt(&account.balance, &key)?,
+            account_type_rules: account
+                .bank_account_type
+                .map(|account_type| account_type.rules()),
+            ledger_balance,
+            available_balance: ledger_balance + account.overdraft_limit,
+            overdraft_limit: account.overdraft_limit,
+            overdraft_used: (-ledger_balance).max(Money::ZERO),
+            overdraft_rate_bps: account.overdraft_rate_bps,
+            status: account.status,
+            closed_at: account.closed_at,
             created_at: account.created_at,
             updated_at: account.updated_at,
+            deleted_at: account.deleted_at,
         })
     }
+
+    pub fn with_held_amount(mut self, held: Money) -> Self {
+        self.available_balance = self.ledger_balance - held + self.overdraft_limit;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -154,7 +413,7 @@ mod tests {
             Some("test".to_string()),
         )
         .expect("User creation failed");
-        let initial_balance = 1000.0;
+        let initial_balance = Money::from_minor_units(100_000);
 
         let account = Account::new(&user, initial_balance, None, None, None, None, None, None)
             .expect("Account creation failed");
@@ -180,7 +439,7 @@ mod tests {
             Some("wrong".to_string()),
         )
         .expect("User creation failed");
-        let initial_balance = 1000.0;
+        let initial_balance = Money::from_minor_units(100_000);
 
         let account = Account::new(&user, initial_balance, None, None, None, None, None, None)
             .expect("Account creation failed");
@@ -192,4 +451,124 @@ mod tests {
             "Retrieving balance with wrong key should fail"
         );
     }
+
+    #[test]
+    fn test_legacy_float_balance_is_reencrypted() {
+        let user = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let master_key = load_master_key().expect("Failed to load master key");
+        let key = decrypt_user_key(&user.encryption_key, &master_key).expect("Invalid user key");
+
+        let mut account = Account::new(&user, Money::ZERO, None, None, None, None, None, None)
+            .expect("Account creation failed");
+        account.balance = 1234.56_f64.encrypt(&key).expect("Encryption failed").cast();
+        account.balance_encoding = MoneyEncoding::LegacyFloat;
+
+        assert_eq!(
+            account.get_balance(&user).expect("Failed to get balance"),
+            Money::from_minor_units(123_456)
+        );
+
+        account
+            .reencrypt_balance(&user)
+            .expect("Failed to re-encrypt balance");
+
+        assert_eq!(account.balance_encoding, MoneyEncoding::MinorUnits);
+        assert_eq!(
+            account.get_balance(&user).expect("Failed to get balance"),
+            Money::from_minor_units(123_456)
+        );
+    }
+
+    #[test]
+    fn test_overdraft_limit_bounds_negative_balances() {
+        let user = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let mut account = Account::new(
+            &user,
+            Money::from_minor_units(10_000),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Account creation failed");
+        account.overdraft_limit = Money::from_minor_units(50_000);
+
+        account
+            .update_balance(&user, Money::from_minor_units(-50_000))
+            .expect("Overdraft within the limit failed");
+        assert!(account
+            .update_balance(&user, Money::from_minor_units(-50_001))
+            .is_err());
+
+        // a lowered limit stops further debits but not deposits
+        account.overdraft_limit = Money::from_minor_units(20_000);
+        assert!(account
+            .update_balance(&user, Money::from_minor_units(-50_001))
+            .is_err());
+        account
+            .update_balance(&user, Money::from_minor_units(-30_000))
+            .expect("Deposit into an overdrawn account failed");
+
+        let model = AccountModel::from_dto(&account, &user).expect("Model conversion failed");
+        assert_eq!(model.overdraft_used, Money::from_minor_units(30_000));
+        assert_eq!(model.available_balance, Money::from_minor_units(-10_000));
+    }
+
+    #[test]
+    fn test_account_type_rules() {
+        let checking = AccountType::Checking.rules();
+        assert!(checking.can_pay_boletos && !checking.employers_only);
+        assert_eq!(checking.monthly_withdrawals, None);
+
+        assert!(!AccountType::Savings.rules().can_pay_boletos);
+        assert!(AccountType::Salary.rules().employers_only);
+        assert!(!AccountType::Payment.rules().employers_only);
+        for account_type in [
+            AccountType::Savings,
+            AccountType::Salary,
+            AccountType::Payment,
+        ] {
+            assert!(account_type.rules().monthly_withdrawals.is_some());
+        }
+    }
+
+    #[test]
+    fn test_account_status_transitions() {
+        use AccountStatus::*;
+
+        for (from, to) in [
+            (Active, Frozen),
+            (Frozen, Active),
+            (Active, Closing),
+            (Frozen, Closing),
+            (Closing, Closing),
+            (Closing, Closed),
+        ] {
+            assert!(from.can_become(to), "{:?} to {:?}", from, to);
+        }
+        for (from, to) in [
+            (Active, Closed),
+            (Closing, Active),
+            (Closing, Frozen),
+            (Closed, Active),
+            (Closed, Closing),
+            (Frozen, Frozen),
+        ] {
+            assert!(!from.can_become(to), "{:?} to {:?}", from, to);
+        }
+    }
 }
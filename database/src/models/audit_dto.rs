@@ -0,0 +1,263 @@
+use base64::{engine::general_purpose, Engine};
+use chrono::{NaiveDateTime, SubsecRound};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::traits::encryptable::Encryptable;
+
+/// Snapshot fields that are encrypted with the master key before they are recorded.
+pub const SENSITIVE_FIELDS: [&str; 4] = ["email", "password", "encryption_key", "key"];
+
+/// What an audited request acted on, named after the table its snapshots are read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditTarget {
+    Account,
+    FeeSchedule,
+    Hold,
+    InterestProduct,
+    PixKey,
+    Schedule,
+    Transaction,
+    TransactionLimit,
+    User,
+}
+
+impl AuditTarget {
+    pub fn table(&self) -> &'static str {
+        match self {
+            AuditTarget::Account => "accounts",
+            AuditTarget::FeeSchedule => "fee_schedules",
+            AuditTarget::Hold => "holds",
+            AuditTarget::InterestProduct => "interest_products",
+            AuditTarget::PixKey => "pix_keys",
+            AuditTarget::Schedule => "schedules",
+            AuditTarget::Transaction => "transactions",
+            AuditTarget::TransactionLimit => "transaction_limits",
+            AuditTarget::User => "users",
+        }
+    }
+}
+
+/// One mutating request, as the API saw it.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub actor_id: Option<Uuid>,
+    pub scopes: Vec<String>,
+    /// The method and route, such as `POST /accounts/:id/close`.
+    pub action: String,
+    pub target: Option<AuditTarget>,
+    pub target_id: Option<Uuid>,
+    pub status: u16,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    pub request_id: String,
+}
+
+/// A link in the audit chain. `hash` covers every other field, `previous_hash` included.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AuditRecord {
+    pub sequence: i64,
+    pub actor_id: Option<Uuid>,
+    pub scopes: Vec<String>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub status: i16,
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<Value>,
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    pub request_id: String,
+    pub created_at: NaiveDateTime,
+    pub previous_hash: Option<String>,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    /// Chains `entry` after `previous`, or starts the chain when there is none.
+    pub fn new(
+        entry: &AuditEntry,
+        previous: Option<&AuditRecord>,
+        master_key: &[u8],
+    ) -> anyhow::Result<Self> {
+        let seal = |snapshot| seal_snapshot(snapshot, master_key);
+        let mut record = Self {
+            sequence: previous.map_or(1, |previous| previous.sequence + 1),
+            actor_id: entry.actor_id,
+            scopes: entry.scopes.clone(),
+            action: entry.action.clone(),
+            target_type: entry.target.map(|target| target.table().to_string()),
+            target_id: entry.target_id,
+            status: entry.status as i16,
+            before: seal(&entry.before)?,
+            after: seal(&entry.after)?,
+            ip: entry.ip.clone(),
+            request_id: entry.request_id.clone(),
+            // the column keeps microseconds, and the hash must survive the round trip
+            created_at: chrono::Utc::now().naive_utc().trunc_subsecs(6),
+            previous_hash: previous.map(|previous| previous.hash.clone()),
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash()?;
+
+        Ok(record)
+    }
+
+    /// SHA-256, in hex, of the canonical JSON form of everything but the hash itself.
+    pub fn compute_hash(&self) -> anyhow::Result<String> {
+        let content = serde_json::to_vec(&(
+            self.sequence,
+            &self.actor_id,
+            &self.scopes,
+            &self.action,
+            &self.target_type,
+            &self.target_id,
+            self.status,
+            &self.before,
+            &self.after,
+            &self.ip,
+            &self.request_id,
+            self.created_at,
+            &self.previous_hash,
+        ))?;
+
+        Ok(format!("{:x}", Sha256::digest(content)))
+    }
+}
+
+/// An entry that could not be appended to the chain, kept aside along with why.
+#[derive(Debug, Clone, FromRow)]
+pub struct AuditFailure {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub scopes: Vec<String>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub status: i16,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    pub request_id: String,
+    pub error: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl AuditFailure {
+    pub fn new(entry: &AuditEntry, error: &str, master_key: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: Uuid::now_v7(),
+            actor_id: entry.actor_id,
+            scopes: entry.scopes.clone(),
+            action: entry.action.clone(),
+            target_type: entry.target.map(|target| target.table().to_string()),
+            target_id: entry.target_id,
+            status: entry.status as i16,
+            before: seal_snapshot(&entry.before, master_key)?,
+            after: seal_snapshot(&entry.after, master_key)?,
+            ip: entry.ip.clone(),
+            request_id: entry.request_id.clone(),
+            error: error.to_string(),
+            created_at: chrono::Utc::now().naive_utc(),
+        })
+    }
+}
+
+fn seal_snapshot(snapshot: &Option<Value>, master_key: &[u8]) -> anyhow::Result<Option<Value>> {
+    snapshot
+        .as_ref()
+        .map(|snapshot| seal_sensitive_fields(snapshot, master_key))
+        .transpose()
+}
+
+/// Replaces each sensitive field of a row snapshot with its ciphertext, base64 encoded.
+pub fn seal_sensitive_fields(snapshot: &Value, master_key: &[u8]) -> anyhow::Result<Value> {
+    let mut snapshot = snapshot.clone();
+
+    if let Some(fields) = snapshot.as_object_mut() {
+        for name in SENSITIVE_FIELDS {
+            if let Some(field) = fields.get_mut(name).filter(|field| !field.is_null()) {
+                let encrypted = field.to_string().encrypt(master_key)?;
+                *field = Value::String(
+                    general_purpose::STANDARD.encode(bincode::serialize(&encrypted)?),
+                );
+            }
+        }
+    }
+
+    Ok(snapshot)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditVerification {
+    pub records: u64,
+    /// The first record that does not follow from the one before it.
+    pub broken_at: Option<i64>,
+    pub last_hash: Option<String>,
+}
+
+impl AuditVerification {
+    pub fn is_intact(&self) -> bool {
+        self.broken_at.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::encrypted_field::EncryptedField;
+
+    fn entry(after: Value) -> AuditEntry {
+        AuditEntry {
+            actor_id: Some(Uuid::now_v7()),
+            scopes: vec!["admin".to_string()],
+            action: "POST /users".to_string(),
+            target: Some(AuditTarget::User),
+            target_id: Some(Uuid::now_v7()),
+            status: 200,
+            before: None,
+            after: Some(after),
+            ip: Some("127.0.0.1".to_string()),
+            request_id: Uuid::now_v7().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_records_chain_and_seal_sensitive_fields() {
+        let key = [0u8; 32];
+        let first = AuditRecord::new(
+            &entry(serde_json::json!({"name": "Ana", "email": "ana@localhost", "password": null})),
+            None,
+            &key,
+        )
+        .unwrap();
+        let second = AuditRecord::new(&entry(serde_json::json!({})), Some(&first), &key).unwrap();
+
+        assert_eq!((first.sequence, second.sequence), (1, 2));
+        assert_eq!(first.previous_hash, None);
+        assert_eq!(second.previous_hash.as_deref(), Some(first.hash.as_str()));
+        assert_eq!(first.hash, first.compute_hash().unwrap());
+
+        let after = first.after.as_ref().unwrap();
+        assert_eq!(after["name"], "Ana");
+        assert_eq!(after["password"], Value::Null);
+        let sealed = general_purpose::STANDARD
+            .decode(after["email"].as_str().unwrap())
+            .unwrap();
+        let encrypted: EncryptedField<String> = bincode::deserialize(&sealed).unwrap();
+        assert_eq!(
+            String::decrypt(&encrypted, &key).unwrap(),
+            "\"ana@localhost\""
+        );
+
+        let mut tampered = second.clone();
+        tampered.status = 204;
+        assert_ne!(tampered.compute_hash().unwrap(), second.hash);
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BankError {
+    #[error("Line {line} of the registry: {reason}")]
+    InvalidRow { line: u64, reason: String },
+    #[error("Bank code {0:03} is listed more than once")]
+    DuplicateCode(i32),
+    #[error("ISPB {0} is listed more than once")]
+    DuplicateIspb(String),
+    #[error("The registry lists no bank with a COMPE code")]
+    EmptyRegistry,
+}
+
+/// A bank accounts can be held at, known by its COMPE code.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Bank {
+    #[schema(example = 1)]
+    pub id: i32,
+    /// The code of the bank in the instant payment and reserve transfer systems.
+    #[schema(example = "00000000")]
+    pub ispb: Option<String>,
+    #[schema(example = "BCO DO BRASIL S.A.")]
+    pub short_name: String,
+    #[schema(example = "Banco do Brasil S.A.")]
+    pub name: String,
+    /// Off for banks that left the registry; accounts still pointing at them keep working.
+    pub active: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// A bank as listed in the registry file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankEntry {
+    pub id: i32,
+    pub ispb: String,
+    pub short_name: String,
+    pub name: String,
+}
+
+/// What refreshing the registry changed.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct BankImportReport {
+    pub added: u64,
+    pub updated: u64,
+    pub unchanged: u64,
+    /// Banks no longer listed, now inactive.
+    pub deactivated: u64,
+}
+
+/// A row of the participant list of the central bank, `ParticipantesSTR.csv`.
+#[derive(Debug, Deserialize)]
+struct Participant {
+    #[serde(rename = "ISPB")]
+    ispb: String,
+    #[serde(rename = "Nome_Reduzido")]
+    short_name: String,
+    #[serde(rename = "Número_Código")]
+    code: String,
+    #[serde(rename = "Nome_Extenso")]
+    name: String,
+}
+
+/// Reads the participant list of the central bank, keeping the banks that have a COMPE code.
+///
+/// Leading zeros that spreadsheets drop from ISPBs are put back.
+pub fn parse_registry(csv: &str) -> Result<Vec<BankEntry>, BankError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(csv.trim_start_matches('\u{feff}').as_bytes());
+
+    let invalid_csv = |e: csv::Error, line: u64| BankError::InvalidRow {
+        line: e.position().map_or(line, |position| position.line()),
+        reason: match e.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+            _ => e.to_string(),
+        },
+    };
+    let headers = reader.headers().map_err(|e| invalid_csv(e, 1))?.clone();
+
+    let mut entries = Vec::new();
+    let mut codes = HashSet::new();
+    let mut ispbs = HashSet::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| invalid_csv(e, 0))?;
+        let line = record.position().map_or(0, |position| position.line());
+        let participant: Participant = record
+            .deserialize(Some(&headers))
+            .map_err(|e| invalid_csv(e, line))?;
+        let invalid = |reason: String| BankError::InvalidRow { line, reason };
+
+        // settlement systems and clearing houses take part without a COMPE code
+        let Ok(id) = participant.code.parse::<i32>() else {
+            continue;
+        };
+        if !(1..=999).contains(&id) {
+            return Err(invalid(format!("{} is not a COMPE code", participant.code)));
+        }
+        if participant.ispb.is_empty()
+            || participant.ispb.len() > 8
+            || !participant.ispb.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid(format!("{:?} is not an ISPB", participant.ispb)));
+        }
+        if participant.short_name.is_empty() {
+            return Err(invalid("the bank has no name".to_string()));
+        }
+
+        let ispb = format!("{:0>8}", participant.ispb);
+        if !codes.insert(id) {
+            return Err(BankError::DuplicateCode(id));
+        }
+        if !ispbs.insert(ispb.clone()) {
+            return Err(BankError::DuplicateIspb(ispb));
+        }
+
+        entries.push(BankEntry {
+            id,
+            ispb,
+            name: match participant.name.is_empty() {
+                true => participant.short_name.clone(),
+                false => participant.name,
+            },
+            short_name: participant.short_name,
+        });
+    }
+
+    if entries.is_empty() {
+        return Err(BankError::EmptyRegistry);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "ISPB,Nome_Reduzido,Número_Código,Participa_da_Compe,Acesso_Principal,Nome_Extenso,Início_da_Operação\n";
+
+    #[test]
+    fn test_registry_keeps_banks_with_a_compe_code() {
+        let csv = format!(
+            "\u{feff}{}{}{}{}",
+            HEADER,
+            "00000000,BCO DO BRASIL S.A.,001,Sim,RSFN,Banco do Brasil S.A.,22/04/2002\n",
+            "00038121,BCB - SELIC,n/a,Não,RSFN,Banco Central do Brasil - Selic,22/04/2002\n",
+            "360305,CAIXA ECONOMICA FEDERAL,104,Sim,RSFN,\"Caixa Econômica Federal, CEF\",22/04/2002\n",
+        );
+
+        assert_eq!(
+            parse_registry(&csv),
+            Ok(vec![
+                BankEntry {
+                    id: 1,
+                    ispb: "00000000".to_string(),
+                    short_name: "BCO DO BRASIL S.A.".to_string(),
+                    name: "Banco do Brasil S.A.".to_string(),
+                },
+                BankEntry {
+                    id: 104,
+                    ispb: "00360305".to_string(),
+                    short_name: "CAIXA ECONOMICA FEDERAL".to_string(),
+                    name: "Caixa Econômica Federal, CEF".to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_registry_rejects_bad_rows() {
+        let csv = |rows: &str| format!("{}{}", HEADER, rows);
+
+        assert_eq!(parse_registry(&csv("")), Err(BankError::EmptyRegistry));
+        assert_eq!(
+            parse_registry(&csv(
+                "00000000,BB,001,Sim,RSFN,BB,\n00000001,BB2,1,Sim,RSFN,BB2,\n"
+            )),
+            Err(BankError::DuplicateCode(1))
+        );
+        assert_eq!(
+            parse_registry(&csv("0000000A,BB,001,Sim,RSFN,BB,\n")),
+            Err(BankError::InvalidRow {
+                line: 2,
+                reason: "\"0000000A\" is not an ISPB".to_string()
+            })
+        );
+        assert!(matches!(
+            parse_registry("ISPB,Nome\n00000000,BB\n"),
+            Err(BankError::InvalidRow { .. })
+        ));
+    }
+}
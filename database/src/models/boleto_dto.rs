@@ -0,0 +1,285 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    payment_files::boleto::typeable_line,
+    structs::money::{round_half_even, Money},
+};
+
+const BASIS_POINTS: i128 = 10_000;
+/// Interest rates are monthly and charged per day late, over months of 30 days.
+const DAYS_PER_MONTH: i128 = 30;
+
+/// Problems with a barcode or typeable line itself.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum BarcodeError {
+    #[error("A typeable line has 47 digits and a barcode 44, got {0}")]
+    InvalidLength(usize),
+    #[error("Barcodes and typeable lines are made of digits")]
+    NotDigits,
+    #[error("Check digit of field {0} of the typeable line does not match")]
+    FieldCheckDigit(u8),
+    #[error("Check digit of the barcode does not match")]
+    BarcodeCheckDigit,
+    #[error("Utility and tax bills are not payment slips")]
+    UtilityBill,
+    #[error("Unsupported currency code {0}, only BRL (9) is accepted")]
+    UnsupportedCurrency(char),
+    #[error("Due dates start on 2000-07-03")]
+    InvalidDueDate,
+    #[error("{0} does not fit in a barcode")]
+    TooLong(&'static str),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BoletoError {
+    #[error("Slip amount must be positive")]
+    InvalidAmount,
+    #[error("Slips cannot be due in the past")]
+    PastDueDate,
+    #[error("Fines and interest are 0 to 10000 basis points")]
+    InvalidRate,
+    #[error("Slips are issued on accounts with a bank, agency and account number")]
+    MissingBankDetails,
+    #[error("The slip has already been paid")]
+    AlreadyPaid,
+    #[error("A slip cannot be paid from the account that issued it")]
+    SameAccount,
+    #[error("The slip has no amount, so the payment needs one")]
+    MissingAmount,
+    #[error("The slip asks for {requested}, not {given}")]
+    AmountMismatch { requested: Money, given: Money },
+    #[error("Slips of other banks need the payee they pay")]
+    MissingPayee,
+    #[error("Fines and interest cannot be negative")]
+    NegativeCharges,
+    #[error("Fines and interest of slips issued here are worked out from their terms")]
+    ChargesGiven,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "boleto_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum BoletoStatus {
+    Open,
+    Paid,
+}
+
+/// A payment slip issued for receiving into an account.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Boleto {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    /// The number the account gives the slip, counting its slips.
+    pub our_number: i64,
+    #[schema(example = "00193373700000001000500940144816060680935031")]
+    pub barcode: String,
+    #[schema(value_type = String, example = "100.00")]
+    pub amount: Money,
+    pub due_date: NaiveDate,
+    /// Charged once on slips paid after the due date, in basis points of the amount.
+    pub fine_bps: i32,
+    /// Monthly rate charged per day late, in basis points of the amount.
+    pub interest_bps: i32,
+    pub status: BoletoStatus,
+    pub transaction_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl Boleto {
+    pub fn new(account_id: Uuid, our_number: i64, barcode: String, boleto: &BoletoCreate) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            account_id,
+            our_number,
+            barcode,
+            amount: boleto.amount,
+            due_date: boleto.due_date,
+            fine_bps: boleto.fine_bps,
+            interest_bps: boleto.interest_bps,
+            status: BoletoStatus::Open,
+            transaction_id: None,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+        }
+    }
+
+    /// The fine and the interest owed on top of the amount when paid on `paid_on`.
+    pub fn charges(&self, paid_on: NaiveDate) -> (Money, Money) {
+        let days_late = (paid_on - self.due_date).num_days();
+        if days_late <= 0 {
+            return (Money::ZERO, Money::ZERO);
+        }
+
+        let amount = self.amount.minor_units() as i128;
+        let fine = round_half_even(amount * self.fine_bps as i128, BASIS_POINTS);
+        let interest = round_half_even(
+            amount * self.interest_bps as i128 * days_late as i128,
+            BASIS_POINTS * DAYS_PER_MONTH,
+        );
+
+        (
+            Money::from_minor_units(fine as i64),
+            Money::from_minor_units(interest as i64),
+        )
+    }
+}
+
+/// Body of a new slip for an account to be paid through.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BoletoCreate {
+    #[schema(value_type = String, example = "100.00")]
+    pub amount: Money,
+    pub due_date: NaiveDate,
+    #[serde(default)]
+    #[schema(example = 200)]
+    pub fine_bps: i32,
+    #[serde(default)]
+    #[schema(example = 100)]
+    pub interest_bps: i32,
+}
+
+impl BoletoCreate {
+    pub fn validate(&self, today: NaiveDate) -> Result<(), BoletoError> {
+        if !self.amount.is_positive() {
+            return Err(BoletoError::InvalidAmount);
+        }
+        if self.due_date < today {
+            return Err(BoletoError::PastDueDate);
+        }
+        let rates = 0..=BASIS_POINTS as i32;
+        if !rates.contains(&self.fine_bps) || !rates.contains(&self.interest_bps) {
+            return Err(BoletoError::InvalidRate);
+        }
+
+        Ok(())
+    }
+}
+
+/// An issued slip along with the typeable line payers key in.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BoletoModel {
+    #[serde(flatten)]
+    pub boleto: Boleto,
+    #[schema(example = "00190.50095 40144.816069 06809.350314 3 37370000000100")]
+    pub typeable_line: String,
+}
+
+impl From<Boleto> for BoletoModel {
+    fn from(boleto: Boleto) -> Self {
+        Self {
+            typeable_line: typeable_line(&boleto.barcode),
+            boleto,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BoletoLookup {
+    /// The typeable line or the barcode, with or without its punctuation.
+    #[schema(example = "00190.50095 40144.816069 06809.350314 3 37370000000100")]
+    pub line: String,
+}
+
+/// What paying a slip today would take.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BoletoQuote {
+    pub barcode: String,
+    pub typeable_line: String,
+    pub bank_id: i32,
+    pub due_date: Option<NaiveDate>,
+    /// Left out, the payer chooses the amount.
+    #[schema(value_type = Option<String>, example = "100.00")]
+    pub amount: Option<Money>,
+    /// The slip issued here, when it is one of ours.
+    pub boleto_id: Option<Uuid>,
+    /// The owner of the issuing account, for slips issued here.
+    pub payee: Option<String>,
+    #[schema(value_type = String, example = "2.00")]
+    pub fine: Money,
+    #[schema(value_type = String, example = "0.33")]
+    pub interest: Money,
+}
+
+/// Body of a payment of a slip from an account.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BoletoPaymentCreate {
+    pub from_account_id: Uuid,
+    #[schema(example = "00190.50095 40144.816069 06809.350314 3 37370000000100")]
+    pub line: String,
+    /// Needed when the slip has no amount, and must match it when it has one.
+    #[serde(default)]
+    #[schema(value_type = Option<String>, example = "100.00")]
+    pub amount: Option<Money>,
+    /// Who the slip pays; needed for slips of other banks.
+    #[serde(default)]
+    pub payee: Option<String>,
+    /// Owed on late slips of other banks; slips issued here work it out themselves.
+    #[serde(default)]
+    #[schema(value_type = Option<String>, example = "2.00")]
+    pub fine: Option<Money>,
+    #[serde(default)]
+    #[schema(value_type = Option<String>, example = "0.33")]
+    pub interest: Option<Money>,
+}
+
+/// A slip paid from an account; the transaction moved the amount plus the fine and interest.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BoletoPayment {
+    pub transaction_id: Uuid,
+    pub from_account_id: Uuid,
+    /// The slip issued here, when it was one of ours.
+    pub boleto_id: Option<Uuid>,
+    pub barcode: String,
+    pub payee: String,
+    pub due_date: Option<NaiveDate>,
+    #[schema(value_type = String, example = "100.00")]
+    pub amount: Money,
+    #[schema(value_type = String, example = "2.00")]
+    pub fine: Money,
+    #[schema(value_type = String, example = "0.33")]
+    pub interest: Money,
+    pub created_at: NaiveDateTime,
+}
+
+impl BoletoPayment {
+    pub fn total(&self) -> Money {
+        self.amount + self.fine + self.interest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_late_slips_are_charged_their_fine_and_daily_interest() {
+        let due_date = NaiveDate::from_ymd_opt(2026, 10, 10).unwrap();
+        let boleto = Boleto::new(
+            Uuid::nil(),
+            1,
+            String::new(),
+            &BoletoCreate {
+                amount: Money::from_minor_units(10_000),
+                due_date,
+                fine_bps: 200,
+                interest_bps: 100,
+            },
+        );
+
+        assert_eq!(boleto.charges(due_date), (Money::ZERO, Money::ZERO));
+        assert_eq!(
+            boleto.charges(due_date + chrono::Duration::days(1)),
+            (Money::from_minor_units(200), Money::from_minor_units(3))
+        );
+        assert_eq!(
+            boleto.charges(due_date + chrono::Duration::days(30)),
+            (Money::from_minor_units(200), Money::from_minor_units(100))
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EmployerError {
+    #[error("Only salary accounts have employers")]
+    NotSalaryAccount,
+    #[error("An account cannot be its own employer")]
+    SameAccount,
+    #[error("The employer is already registered")]
+    AlreadyRegistered,
+}
+
+/// An account allowed to pay into a salary account.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Employer {
+    pub account_id: Uuid,
+    pub employer_account_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmployerCreate {
+    pub employer_account_id: Uuid,
+}
@@ -0,0 +1,372 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, types::Json};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::structs::money::{round_half_even, Money};
+
+use super::{account_dto::AccountType, transaction_dto::TransactionOperation};
+
+const BASIS_POINTS: i32 = 10_000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FeeError {
+    #[error("Fee schedule needs a name")]
+    InvalidName,
+    #[error("Fees cannot be negative")]
+    NegativeFee,
+    #[error("{0:?} operations cannot be charged a fee")]
+    UnsupportedOperation(TransactionOperation),
+    #[error("{0:?} operations are charged more than one fee")]
+    DuplicateOperation(TransactionOperation),
+    #[error("Waivers need a minimum balance of zero or more and 0 to 10000 basis points")]
+    InvalidWaiver,
+}
+
+/// A flat fee charged every time an account performs `operation`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OperationFee {
+    pub operation: TransactionOperation,
+    #[schema(value_type = String, example = "1.50")]
+    pub amount: Money,
+}
+
+/// Waives a share of every fee for accounts holding at least `min_balance`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeWaiver {
+    #[schema(value_type = String, example = "5000.00")]
+    pub min_balance: Money,
+    /// Share of the fee waived, in basis points: 10000 waives all of it.
+    #[schema(example = 10000)]
+    pub waived_bps: i32,
+}
+
+/// The fees charged to the accounts of one account type.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FeeSchedule {
+    pub id: Uuid,
+    pub name: String,
+    /// The account type it applies to; the schedule without one applies to every other account.
+    pub bank_account_type: Option<AccountType>,
+    /// Charged on the first run of the fee job in each calendar month.
+    #[schema(value_type = String, example = "12.90")]
+    pub monthly_fee: Money,
+    #[schema(value_type = Vec<OperationFee>)]
+    pub operation_fees: Json<Vec<OperationFee>>,
+    #[schema(value_type = Vec<FeeWaiver>)]
+    pub waivers: Json<Vec<FeeWaiver>>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl FeeSchedule {
+    pub fn new(schedule: &FeeScheduleCreate) -> anyhow::Result<Self> {
+        let new_schedule = Self {
+            id: Uuid::now_v7(),
+            name: schedule.name.trim().to_string(),
+            bank_account_type: schedule.bank_account_type,
+            monthly_fee: schedule.monthly_fee,
+            operation_fees: Json(schedule.operation_fees.clone()),
+            waivers: Json(schedule.waivers.clone()),
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+        };
+        new_schedule.validate()?;
+
+        Ok(new_schedule)
+    }
+
+    pub fn update(&mut self, update: &FeeScheduleUpdate) -> anyhow::Result<()> {
+        if let Some(name) = &update.name {
+            self.name = name.trim().to_string();
+        }
+        if let Some(monthly_fee) = update.monthly_fee {
+            self.monthly_fee = monthly_fee;
+        }
+        if let Some(operation_fees) = &update.operation_fees {
+            self.operation_fees = Json(operation_fees.clone());
+        }
+        if let Some(waivers) = &update.waivers {
+            self.waivers = Json(waivers.clone());
+        }
+        self.validate()?;
+        self.updated_at = Some(chrono::Utc::now().naive_utc());
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), FeeError> {
+        if self.name.is_empty() {
+            return Err(FeeError::InvalidName);
+        }
+        if self.monthly_fee.is_negative() {
+            return Err(FeeError::NegativeFee);
+        }
+
+        for (i, fee) in self.operation_fees.iter().enumerate() {
+            if !matches!(
+                fee.operation,
+                TransactionOperation::Deposit
+                    | TransactionOperation::Payment
+                    | TransactionOperation::Transfer
+                    | TransactionOperation::Withdrawal
+            ) {
+                return Err(FeeError::UnsupportedOperation(fee.operation.clone()));
+            }
+            if fee.amount.is_negative() {
+                return Err(FeeError::NegativeFee);
+            }
+            if self.operation_fees[..i]
+                .iter()
+                .any(|other| other.operation == fee.operation)
+            {
+                return Err(FeeError::DuplicateOperation(fee.operation.clone()));
+            }
+        }
+
+        if self.waivers.iter().any(|waiver| {
+            waiver.min_balance.is_negative() || !(0..=BASIS_POINTS).contains(&waiver.waived_bps)
+        }) {
+            return Err(FeeError::InvalidWaiver);
+        }
+
+        Ok(())
+    }
+
+    /// The fee for `operation` on an account holding `balance`, after waivers.
+    pub fn operation_charge(&self, operation: &TransactionOperation, balance: Money) -> Money {
+        let fee = self
+            .operation_fees
+            .iter()
+            .find(|fee| &fee.operation == operation)
+            .map_or(Money::ZERO, |fee| fee.amount);
+
+        self.waive(fee, balance)
+    }
+
+    /// The maintenance fee for an account holding `balance`, after waivers.
+    pub fn monthly_charge(&self, balance: Money) -> Money {
+        self.waive(self.monthly_fee, balance)
+    }
+
+    /// Applies the waiver with the highest minimum balance that `balance` reaches.
+    fn waive(&self, fee: Money, balance: Money) -> Money {
+        match self
+            .waivers
+            .iter()
+            .filter(|waiver| balance >= waiver.min_balance)
+            .max_by_key(|waiver| waiver.min_balance)
+        {
+            Some(waiver) => {
+                let waived = round_half_even(
+                    fee.minor_units() as i128 * waiver.waived_bps as i128,
+                    BASIS_POINTS as i128,
+                );
+                fee - Money::from_minor_units(waived as i64)
+            }
+            None => fee,
+        }
+    }
+}
+
+/// The month a maintenance fee charged on `day` is for, as its first day.
+pub fn fee_period(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).expect("every month has a first day")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeChargeOutcome {
+    Charged(Uuid),
+    Waived,
+    /// Not enough funds to pay it; the next run tries again.
+    Skipped,
+}
+
+/// Tally of one run of the maintenance fee job.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FeeRunSummary {
+    pub charged: u64,
+    pub waived: u64,
+    pub skipped: u64,
+    pub failed: u64,
+}
+
+impl FeeRunSummary {
+    pub fn record(&mut self, outcome: FeeChargeOutcome) {
+        match outcome {
+            FeeChargeOutcome::Charged(_) => self.charged += 1,
+            FeeChargeOutcome::Waived => self.waived += 1,
+            FeeChargeOutcome::Skipped => self.skipped += 1,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.charged + self.waived + self.skipped + self.failed
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FeeScheduleCreate {
+    pub name: String,
+    pub bank_account_type: Option<AccountType>,
+    #[serde(default)]
+    #[schema(value_type = String, example = "12.90")]
+    pub monthly_fee: Money,
+    #[serde(default)]
+    pub operation_fees: Vec<OperationFee>,
+    #[serde(default)]
+    pub waivers: Vec<FeeWaiver>,
+}
+
+/// Replaces the fields given. The account type a schedule applies to cannot change.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FeeScheduleUpdate {
+    pub name: Option<String>,
+    #[schema(value_type = Option<String>, example = "12.90")]
+    pub monthly_fee: Option<Money>,
+    pub operation_fees: Option<Vec<OperationFee>>,
+    pub waivers: Option<Vec<FeeWaiver>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> FeeSchedule {
+        FeeSchedule::new(&FeeScheduleCreate {
+            name: "checking".to_string(),
+            bank_account_type: Some(AccountType::Checking),
+            monthly_fee: Money::from_minor_units(1_290),
+            operation_fees: vec![OperationFee {
+                operation: TransactionOperation::Withdrawal,
+                amount: Money::from_minor_units(250),
+            }],
+            waivers: vec![
+                FeeWaiver {
+                    min_balance: Money::from_minor_units(100_000),
+                    waived_bps: 5_000,
+                },
+                FeeWaiver {
+                    min_balance: Money::from_minor_units(500_000),
+                    waived_bps: 10_000,
+                },
+            ],
+        })
+        .expect("Fee schedule creation failed")
+    }
+
+    #[test]
+    fn test_waivers_apply_the_highest_tier_reached() {
+        let schedule = schedule();
+
+        for (balance, monthly, withdrawal) in [
+            (0, 1_290, 250),
+            (99_999, 1_290, 250),
+            (100_000, 645, 125),
+            (499_999, 645, 125),
+            (500_000, 0, 0),
+        ] {
+            let balance = Money::from_minor_units(balance);
+            assert_eq!(
+                schedule.monthly_charge(balance),
+                Money::from_minor_units(monthly),
+                "{}",
+                balance
+            );
+            assert_eq!(
+                schedule.operation_charge(&TransactionOperation::Withdrawal, balance),
+                Money::from_minor_units(withdrawal),
+                "{}",
+                balance
+            );
+            assert_eq!(
+                schedule.operation_charge(&TransactionOperation::Deposit, balance),
+                Money::ZERO
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_schedules_are_rejected() {
+        let mut schedule = schedule();
+
+        for (update, expected) in [
+            (
+                FeeScheduleUpdate {
+                    name: Some(" ".to_string()),
+                    monthly_fee: None,
+                    operation_fees: None,
+                    waivers: None,
+                },
+                FeeError::InvalidName,
+            ),
+            (
+                FeeScheduleUpdate {
+                    name: None,
+                    monthly_fee: Some(Money::from_minor_units(-1)),
+                    operation_fees: None,
+                    waivers: None,
+                },
+                FeeError::NegativeFee,
+            ),
+            (
+                FeeScheduleUpdate {
+                    name: None,
+                    monthly_fee: None,
+                    operation_fees: Some(vec![OperationFee {
+                        operation: TransactionOperation::Fee,
+                        amount: Money::from_minor_units(100),
+                    }]),
+                    waivers: None,
+                },
+                FeeError::UnsupportedOperation(TransactionOperation::Fee),
+            ),
+            (
+                FeeScheduleUpdate {
+                    name: None,
+                    monthly_fee: None,
+                    operation_fees: Some(vec![
+                        OperationFee {
+                            operation: TransactionOperation::Transfer,
+                            amount: Money::from_minor_units(100),
+                        },
+                        OperationFee {
+                            operation: TransactionOperation::Transfer,
+                            amount: Money::from_minor_units(200),
+                        },
+                    ]),
+                    waivers: None,
+                },
+                FeeError::DuplicateOperation(TransactionOperation::Transfer),
+            ),
+            (
+                FeeScheduleUpdate {
+                    name: None,
+                    monthly_fee: None,
+                    operation_fees: None,
+                    waivers: Some(vec![FeeWaiver {
+                        min_balance: Money::ZERO,
+                        waived_bps: 10_001,
+                    }]),
+                },
+                FeeError::InvalidWaiver,
+            ),
+        ] {
+            let error = schedule.clone().update(&update).unwrap_err();
+            assert_eq!(error.downcast_ref::<FeeError>(), Some(&expected));
+        }
+
+        schedule
+            .update(&FeeScheduleUpdate {
+                name: Some("premium".to_string()),
+                monthly_fee: Some(Money::ZERO),
+                operation_fees: None,
+                waivers: None,
+            })
+            .unwrap();
+        assert_eq!(schedule.name, "premium");
+        assert_eq!(schedule.monthly_charge(Money::ZERO), Money::ZERO);
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    decrypt_user_key, load_master_key,
+    structs::{encrypted_field::EncryptedField, money::Money},
+    traits::encryptable::Encryptable,
+};
+
+use super::user_dto::User;
+
+/// How long a hold reserves funds when the request does not say.
+pub const DEFAULT_HOLD_DURATION: chrono::Duration = chrono::Duration::days(7);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "hold_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum HoldStatus {
+    Active,
+    Captured,
+    Released,
+    Expired,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HoldError {
+    #[error("Hold amount must be positive")]
+    InvalidAmount,
+    #[error("Hold must expire in the future")]
+    InvalidExpiry,
+    #[error("Hold cannot be captured into the account it holds")]
+    SameAccount,
+    #[error("Hold is {0:?} and can no longer change")]
+    NotActive(HoldStatus),
+    #[error("Hold has expired")]
+    Expired,
+    #[error("Capture exceeds the {0} held")]
+    ExceedsHold(Money),
+}
+
+/// Funds reserved on an account until they are captured, released or the hold expires.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Hold {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub to_account_id: Option<Uuid>,
+    pub amount: EncryptedField<Money>,
+    pub captured_amount: Option<EncryptedField<Money>>,
+    pub status: HoldStatus,
+    pub transaction_id: Option<Uuid>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl Hold {
+    pub fn new(
+        owner: &User,
+        account_id: Uuid,
+        to_account_id: Option<Uuid>,
+        amount: Money,
+        expires_at: NaiveDateTime,
+    ) -> anyhow::Result<Self> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        Ok(Self {
+            id: Uuid::now_v7(),
+            account_id,
+            to_account_id,
+            amount: amount.encrypt(&key)?,
+            captured_amount: None,
+            status: HoldStatus::Active,
+            transaction_id: None,
+            expires_at,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+        })
+    }
+
+    pub fn get_amount(&self, owner: &User) -> anyhow::Result<Money> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        Ok(Money::decrypt(&self.amount, &key)?)
+    }
+
+    pub fn get_captured_amount(&self, owner: &User) -> anyhow::Result<Option<Money>> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        match &self.captured_amount {
+            Some(captured_amount) => Ok(Some(Money::decrypt(captured_amount, &key)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Marks the hold captured for `amount`; whatever is left of it is released.
+    pub fn capture(&mut self, owner: &User, amount: Money) -> anyhow::Result<()> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+
+        self.captured_amount = Some(amount.encrypt(&key)?);
+        self.status = HoldStatus::Captured;
+        self.updated_at = Some(chrono::Utc::now().naive_utc());
+
+        Ok(())
+    }
+
+    /// Whether the hold still reserves funds at `now`, even if the expiry job has not run yet.
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.status == HoldStatus::Active && self.expires_at > now
+    }
+}
+
+/// Total reserved by `holds`, all of which belong to accounts of `owner`.
+pub fn held_amount(holds: &[Hold], owner: &User) -> anyhow::Result<Money> {
+    let mut total = Money::ZERO;
+    for hold in holds {
+        total = total
+            .checked_add(hold.get_amount(owner)?)
+            .ok_or_else(|| anyhow::anyhow!("Held amount overflow"))?;
+    }
+
+    Ok(total)
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct HoldCreate {
+    pub account_id: Uuid,
+    /// Account that receives the funds on capture; without one the capture is a payment.
+    pub to_account_id: Option<Uuid>,
+    #[schema(value_type = String, example = "49.90")]
+    pub amount: Money,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// Body of a capture; without an amount the whole hold is captured.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct HoldCapture {
+    #[schema(value_type = Option<String>, example = "39.90")]
+    pub amount: Option<Money>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HoldModel {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub to_account_id: Option<Uuid>,
+    #[schema(value_type = String, example = "49.90")]
+    pub amount: Money,
+    #[schema(value_type = Option<String>, example = "39.90")]
+    pub captured_amount: Option<Money>,
+    pub status: HoldStatus,
+    pub transaction_id: Option<Uuid>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl HoldModel {
+    pub fn from_dto(hold: &Hold, owner: &User) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: hold.id,
+            account_id: hold.account_id,
+            to_account_id: hold.to_account_id,
+            amount: hold.get_amount(owner)?,
+            captured_amount: hold.get_captured_amount(owner)?,
+            status: hold.status,
+            transaction_id: hold.transaction_id,
+            expires_at: hold.expires_at,
+            created_at: hold.created_at,
+            updated_at: hold.updated_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_keeps_both_amounts() {
+        let owner = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let now = chrono::Utc::now().naive_utc();
+        let mut hold = Hold::new(
+            &owner,
+            Uuid::now_v7(),
+            None,
+            Money::from_minor_units(5_000),
+            now + DEFAULT_HOLD_DURATION,
+        )
+        .expect("Hold creation failed");
+        assert!(hold.is_active(now));
+        assert!(!hold.is_active(hold.expires_at));
+
+        hold.capture(&owner, Money::from_minor_units(3_990))
+            .unwrap();
+
+        assert!(!hold.is_active(now));
+        assert_eq!(
+            hold.get_amount(&owner).unwrap(),
+            Money::from_minor_units(5_000)
+        );
+        assert_eq!(
+            hold.get_captured_amount(&owner).unwrap(),
+            Some(Money::from_minor_units(3_990))
+        );
+    }
+
+    #[test]
+    fn test_held_amount_sums_holds() {
+        let owner = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let expires_at = chrono::Utc::now().naive_utc() + DEFAULT_HOLD_DURATION;
+        let account_id = Uuid::now_v7();
+        let holds: Vec<Hold> = [1_000, 250]
+            .into_iter()
+            .map(|minor_units| {
+                Hold::new(
+                    &owner,
+                    account_id,
+                    None,
+                    Money::from_minor_units(minor_units),
+                    expires_at,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        assert_eq!(
+            held_amount(&holds, &owner).unwrap(),
+            Money::from_minor_units(1_250)
+        );
+        assert_eq!(held_amount(&[], &owner).unwrap(), Money::ZERO);
+    }
+}
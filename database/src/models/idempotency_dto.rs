@@ -0,0 +1,50 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Longest `Idempotency-Key` accepted, matching the column size.
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct IdempotencyKey {
+    pub user_id: Uuid,
+    pub key: String,
+    pub fingerprint: Vec<u8>,
+    pub transaction_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl IdempotencyKey {
+    pub fn new(user_id: Uuid, key: String, fingerprint: Vec<u8>, ttl: chrono::Duration) -> Self {
+        let created_at = chrono::Utc::now().naive_utc();
+
+        Self {
+            user_id,
+            key,
+            fingerprint,
+            transaction_id: None,
+            created_at,
+            expires_at: created_at + ttl,
+        }
+    }
+}
+
+/// What a request carrying an idempotency key should do.
+#[derive(Debug, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    /// The key is new, or its previous use expired: process the request.
+    New,
+    /// The key was already used for the same request: return the transaction it created.
+    Replay(Uuid),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IdempotencyError {
+    #[error("Idempotency key must be between 1 and {MAX_IDEMPOTENCY_KEY_LENGTH} characters")]
+    InvalidKey,
+    #[error("Idempotency key was already used for a different request")]
+    KeyReused,
+}
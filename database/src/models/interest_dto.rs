@@ -0,0 +1,404 @@
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    decrypt_user_key, load_master_key,
+    structs::{
+        encrypted_field::EncryptedField,
+        money::{round_half_even, Money},
+    },
+    traits::encryptable::Encryptable,
+};
+
+use super::user_dto::User;
+
+/// Accruals are kept in millionths of a minor unit, so daily interest on small
+/// balances is not lost to rounding before it is paid.
+pub const ACCRUAL_UNITS_PER_MINOR_UNIT: i64 = 1_000_000;
+
+const BASIS_POINTS: i128 = 10_000;
+
+/// How a day counts towards the year the annual rate applies to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "day_count_convention")]
+pub enum DayCountConvention {
+    #[sqlx(rename = "actual_360")]
+    #[serde(rename = "actual_360")]
+    Actual360,
+    #[sqlx(rename = "actual_365")]
+    #[serde(rename = "actual_365")]
+    Actual365,
+    #[sqlx(rename = "actual_actual")]
+    #[serde(rename = "actual_actual")]
+    ActualActual,
+    #[sqlx(rename = "thirty_360")]
+    #[serde(rename = "thirty_360")]
+    Thirty360,
+}
+
+impl DayCountConvention {
+    /// The share of a year that `day` is worth, as a numerator and a denominator.
+    pub fn day_fraction(&self, day: NaiveDate) -> (i128, i128) {
+        match self {
+            DayCountConvention::Actual360 => (1, 360),
+            DayCountConvention::Actual365 => (1, 365),
+            DayCountConvention::ActualActual => (1, if day.leap_year() { 366 } else { 365 }),
+            // every month is worth 30 days: the 31st counts for nothing and the end of
+            // February makes up for the days the month is short of
+            DayCountConvention::Thirty360 => {
+                let is_last_day = day
+                    .succ_opt()
+                    .is_some_and(|next| next.month() != day.month());
+                let days = match day.day() {
+                    31 => 0,
+                    day_of_month if is_last_day && day.month() == 2 => 31 - day_of_month as i128,
+                    _ => 1,
+                };
+                (days, 360)
+            }
+        }
+    }
+}
+
+/// How often accrued interest is paid into the account, where it starts earning interest itself.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "compounding_frequency", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CompoundingFrequency {
+    Daily,
+    Monthly,
+    Quarterly,
+    Annually,
+}
+
+impl CompoundingFrequency {
+    /// The first payout date after `day`; payouts fall on the first day of each period.
+    pub fn next_payout(&self, day: NaiveDate) -> anyhow::Result<NaiveDate> {
+        let months = match self {
+            CompoundingFrequency::Daily => {
+                return day
+                    .succ_opt()
+                    .ok_or_else(|| anyhow::anyhow!("Payout date overflow"))
+            }
+            CompoundingFrequency::Monthly => 1,
+            CompoundingFrequency::Quarterly => 3,
+            CompoundingFrequency::Annually => 12,
+        };
+        let period_start =
+            NaiveDate::from_ymd_opt(day.year(), (day.month0() / months * months) + 1, 1)
+                .ok_or_else(|| anyhow::anyhow!("Invalid payout date"))?;
+
+        period_start
+            .checked_add_months(Months::new(months))
+            .ok_or_else(|| anyhow::anyhow!("Payout date overflow"))
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum InterestError {
+    #[error("Interest rate cannot be negative")]
+    InvalidRate,
+    #[error("Interest product needs a name")]
+    InvalidName,
+    #[error("Account has no interest product")]
+    NotAssigned,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct InterestProduct {
+    pub id: Uuid,
+    pub name: String,
+    /// Nominal annual rate in basis points: 425 is 4.25% a year.
+    pub annual_rate_bps: i32,
+    pub day_count: DayCountConvention,
+    pub compounding: CompoundingFrequency,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl InterestProduct {
+    pub fn new(product: &InterestProductCreate) -> anyhow::Result<Self> {
+        if product.annual_rate_bps < 0 {
+            return Err(InterestError::InvalidRate.into());
+        }
+        if product.name.trim().is_empty() {
+            return Err(InterestError::InvalidName.into());
+        }
+
+        Ok(Self {
+            id: Uuid::now_v7(),
+            name: product.name.trim().to_string(),
+            annual_rate_bps: product.annual_rate_bps,
+            day_count: product.day_count,
+            compounding: product.compounding,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+        })
+    }
+
+    /// Interest `balance` earns over `day`, in accrual units. Only positive balances earn interest.
+    pub fn daily_accrual(&self, balance: Money, day: NaiveDate) -> i64 {
+        if !balance.is_positive() {
+            return 0;
+        }
+        let (numerator, denominator) = self.day_count.day_fraction(day);
+
+        round_half_even(
+            balance.minor_units() as i128
+                * self.annual_rate_bps as i128
+                * numerator
+                * ACCRUAL_UNITS_PER_MINOR_UNIT as i128,
+            BASIS_POINTS * denominator,
+        ) as i64
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct InterestProductCreate {
+    pub name: String,
+    #[schema(example = 425)]
+    pub annual_rate_bps: i32,
+    pub day_count: DayCountConvention,
+    pub compounding: CompoundingFrequency,
+}
+
+/// The interest product of an account and what it has earned since the last payout.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AccountInterest {
+    pub account_id: Uuid,
+    pub product_id: Uuid,
+    pub accrued: EncryptedField<i64>,
+    pub accrued_through: NaiveDate,
+    pub next_payout_on: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl AccountInterest {
+    /// Starts accruing on `today` with nothing earned yet.
+    pub fn new(
+        owner: &User,
+        account_id: Uuid,
+        product: &InterestProduct,
+        today: NaiveDate,
+    ) -> anyhow::Result<Self> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        Ok(Self {
+            account_id,
+            product_id: product.id,
+            accrued: 0_i64.encrypt(&key)?,
+            accrued_through: today
+                .pred_opt()
+                .ok_or_else(|| anyhow::anyhow!("Invalid accrual date"))?,
+            next_payout_on: product.compounding.next_payout(today)?,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+        })
+    }
+
+    pub fn get_accrued(&self, owner: &User) -> anyhow::Result<i64> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        Ok(i64::decrypt(&self.accrued, &key)?)
+    }
+
+    fn set_accrued(&mut self, owner: &User, accrued: i64) -> anyhow::Result<()> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        self.accrued = accrued.encrypt(&key)?;
+        self.updated_at = Some(chrono::Utc::now().naive_utc());
+        Ok(())
+    }
+
+    /// Accrues every full day before `today` that has not been accrued yet on `balance`.
+    ///
+    /// Days missed while the job was not running are caught up on the current balance.
+    pub fn accrue(
+        &mut self,
+        owner: &User,
+        product: &InterestProduct,
+        balance: Money,
+        today: NaiveDate,
+    ) -> anyhow::Result<()> {
+        let mut accrued = self.get_accrued(owner)?;
+        let mut day = self.accrued_through;
+        while let Some(next) = day.succ_opt().filter(|next| *next < today) {
+            accrued = accrued
+                .checked_add(product.daily_accrual(balance, next))
+                .ok_or_else(|| anyhow::anyhow!("Accrued interest overflow"))?;
+            day = next;
+        }
+
+        self.accrued_through = day;
+        self.set_accrued(owner, accrued)
+    }
+
+    /// Takes the whole minor units out of the accrual when a payout is due on `today`,
+    /// leaving the fraction to keep accruing, and moves on to the next payout date.
+    pub fn take_payout(
+        &mut self,
+        owner: &User,
+        product: &InterestProduct,
+        today: NaiveDate,
+    ) -> anyhow::Result<Option<Money>> {
+        if self.next_payout_on > today {
+            return Ok(None);
+        }
+
+        let accrued = self.get_accrued(owner)?;
+        let payout = accrued.div_euclid(ACCRUAL_UNITS_PER_MINOR_UNIT);
+        self.set_accrued(owner, accrued.rem_euclid(ACCRUAL_UNITS_PER_MINOR_UNIT))?;
+        self.next_payout_on = product.compounding.next_payout(today)?;
+
+        Ok(Some(Money::from_minor_units(payout)).filter(|payout| payout.is_positive()))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AccountInterestAssign {
+    pub product_id: Uuid,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountInterestModel {
+    pub account_id: Uuid,
+    pub product_id: Uuid,
+    /// Earned but not paid yet, rounded to the minor unit.
+    #[schema(value_type = String, example = "3.17")]
+    pub accrued: Money,
+    pub accrued_through: NaiveDate,
+    pub next_payout_on: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl AccountInterestModel {
+    pub fn from_dto(interest: &AccountInterest, owner: &User) -> anyhow::Result<Self> {
+        let accrued = round_half_even(
+            interest.get_accrued(owner)? as i128,
+            ACCRUAL_UNITS_PER_MINOR_UNIT as i128,
+        );
+
+        Ok(Self {
+            account_id: interest.account_id,
+            product_id: interest.product_id,
+            accrued: Money::from_minor_units(accrued as i64),
+            accrued_through: interest.accrued_through,
+            next_payout_on: interest.next_payout_on,
+            created_at: interest.created_at,
+            updated_at: interest.updated_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn product(
+        day_count: DayCountConvention,
+        compounding: CompoundingFrequency,
+    ) -> InterestProduct {
+        InterestProduct::new(&InterestProductCreate {
+            name: "savings".to_string(),
+            annual_rate_bps: 365,
+            day_count,
+            compounding,
+        })
+        .expect("Product creation failed")
+    }
+
+    #[test]
+    fn test_thirty_360_months_are_worth_thirty_days() {
+        for (first, last) in [
+            ("2025-01-01", "2025-01-31"),
+            ("2025-02-01", "2025-02-28"),
+            ("2024-02-01", "2024-02-29"),
+            ("2025-04-01", "2025-04-30"),
+        ] {
+            let days: i128 = date(first)
+                .iter_days()
+                .take_while(|day| *day <= date(last))
+                .map(|day| DayCountConvention::Thirty360.day_fraction(day).0)
+                .sum();
+
+            assert_eq!(days, 30, "{}", first);
+        }
+    }
+
+    #[test]
+    fn test_payouts_fall_on_the_first_day_of_the_next_period() {
+        for (compounding, expected) in [
+            (CompoundingFrequency::Daily, "2025-05-16"),
+            (CompoundingFrequency::Monthly, "2025-06-01"),
+            (CompoundingFrequency::Quarterly, "2025-07-01"),
+            (CompoundingFrequency::Annually, "2026-01-01"),
+        ] {
+            assert_eq!(
+                compounding.next_payout(date("2025-05-15")).unwrap(),
+                date(expected),
+                "{:?}",
+                compounding
+            );
+        }
+    }
+
+    #[test]
+    fn test_fractional_accruals_carry_over_to_the_next_payout() {
+        let owner = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let product = product(DayCountConvention::Actual365, CompoundingFrequency::Monthly);
+        // 3.65% a year on 10.00 is a tenth of a cent a day
+        let balance = Money::from_minor_units(1_000);
+        let mut interest =
+            AccountInterest::new(&owner, Uuid::now_v7(), &product, date("2025-01-01")).unwrap();
+
+        interest
+            .accrue(&owner, &product, balance, date("2025-02-01"))
+            .unwrap();
+        assert_eq!(interest.accrued_through, date("2025-01-31"));
+        assert_eq!(
+            interest.get_accrued(&owner).unwrap(),
+            31 * ACCRUAL_UNITS_PER_MINOR_UNIT / 10
+        );
+
+        let payout = interest
+            .take_payout(&owner, &product, date("2025-02-01"))
+            .unwrap();
+        assert_eq!(payout, Some(Money::from_minor_units(3)));
+        assert_eq!(
+            interest.get_accrued(&owner).unwrap(),
+            ACCRUAL_UNITS_PER_MINOR_UNIT / 10
+        );
+        assert_eq!(interest.next_payout_on, date("2025-03-01"));
+
+        // accruing twice on the same day adds nothing
+        interest
+            .accrue(&owner, &product, balance, date("2025-02-01"))
+            .unwrap();
+        assert_eq!(
+            interest.get_accrued(&owner).unwrap(),
+            ACCRUAL_UNITS_PER_MINOR_UNIT / 10
+        );
+        assert_eq!(
+            interest
+                .take_payout(&owner, &product, date("2025-02-01"))
+                .unwrap(),
+            None
+        );
+    }
+}
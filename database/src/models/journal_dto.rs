@@ -0,0 +1,265 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    decrypt_user_key, load_master_key,
+    structs::{encrypted_field::EncryptedField, money::Money},
+    traits::encryptable::Encryptable,
+};
+
+use super::{transaction_dto::TransactionOperation, user_dto::User};
+
+/// Bank-owned ledger accounts that are the counterpart of money entering or leaving customer accounts.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Type, ToSchema)]
+#[sqlx(type_name = "system_account", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SystemAccount {
+    Fees,
+    Interest,
+    CashIn,
+    CashOut,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "posting_direction", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PostingDirection {
+    Debit,
+    Credit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LedgerAccount {
+    Customer(Uuid),
+    System(SystemAccount),
+}
+
+impl TransactionOperation {
+    /// The ledger accounts debited and credited, in that order, when this operation moves money.
+    pub fn ledger_accounts(
+        &self,
+        from_account_id: Option<Uuid>,
+        to_account_id: Uuid,
+    ) -> anyhow::Result<(LedgerAccount, LedgerAccount)> {
+        let customer = LedgerAccount::Customer(to_account_id);
+
+        Ok(match self {
+            TransactionOperation::Deposit => {
+                (LedgerAccount::System(SystemAccount::CashIn), customer)
+            }
+            TransactionOperation::Withdrawal | TransactionOperation::Payment => {
+                (customer, LedgerAccount::System(SystemAccount::CashOut))
+            }
+            TransactionOperation::Fee => (customer, LedgerAccount::System(SystemAccount::Fees)),
+            TransactionOperation::Interest => {
+                (LedgerAccount::System(SystemAccount::Interest), customer)
+            }
+            TransactionOperation::Transfer => match from_account_id {
+                Some(from_account_id) => (LedgerAccount::Customer(from_account_id), customer),
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "Transfer operations must have a source account."
+                    ))
+                }
+            },
+            TransactionOperation::Reversal => {
+                return Err(anyhow::anyhow!(
+                    "Reversals post the inverse of the transaction they reverse."
+                ))
+            }
+        })
+    }
+}
+
+/// The key that protects a posting: the account owner's key, or the master key for system accounts.
+pub fn posting_key(owner: Option<&User>) -> anyhow::Result<Vec<u8>> {
+    let master_key = load_master_key()?;
+    match owner {
+        Some(user) => Ok(decrypt_user_key(&user.encryption_key, &master_key)?),
+        None => Ok(master_key),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+impl JournalEntry {
+    pub fn new(transaction_id: Uuid, created_at: NaiveDateTime) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            transaction_id,
+            created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Posting {
+    pub id: Uuid,
+    pub journal_entry_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub system_account: Option<SystemAccount>,
+    pub direction: PostingDirection,
+    pub amount: EncryptedField<Money>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Posting {
+    pub fn new(
+        entry: &JournalEntry,
+        ledger_account: LedgerAccount,
+        direction: PostingDirection,
+        amount: Money,
+        owner: Option<&User>,
+    ) -> anyhow::Result<Self> {
+        if amount.is_negative() {
+            return Err(anyhow::anyhow!("Posting amounts cannot be negative"));
+        }
+
+        let (account_id, system_account) = match ledger_account {
+            LedgerAccount::Customer(account_id) => (Some(account_id), None),
+            LedgerAccount::System(system_account) => (None, Some(system_account)),
+        };
+
+        Ok(Self {
+            id: Uuid::now_v7(),
+            journal_entry_id: entry.id,
+            account_id,
+            system_account,
+            direction,
+            amount: amount.encrypt(&posting_key(owner)?)?,
+            created_at: entry.created_at,
+        })
+    }
+
+    pub fn ledger_account(&self) -> anyhow::Result<LedgerAccount> {
+        match (self.account_id, self.system_account) {
+            (Some(account_id), None) => Ok(LedgerAccount::Customer(account_id)),
+            (None, Some(system_account)) => Ok(LedgerAccount::System(system_account)),
+            _ => Err(anyhow::anyhow!(
+                "Posting {} has no single ledger account",
+                self.id
+            )),
+        }
+    }
+
+    pub fn get_amount(&self, owner: Option<&User>) -> anyhow::Result<Money> {
+        Ok(Money::decrypt(&self.amount, &posting_key(owner)?)?)
+    }
+
+    /// The amount with debits positive and credits negative, so a balanced entry sums to zero.
+    pub fn get_signed_amount(&self, owner: Option<&User>) -> anyhow::Result<Money> {
+        let amount = self.get_amount(owner)?;
+        Ok(match self.direction {
+            PostingDirection::Debit => amount,
+            PostingDirection::Credit => -amount,
+        })
+    }
+}
+
+/// Result of checking that every journal entry, and so the whole ledger, sums to zero.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LedgerVerification {
+    pub entries: u64,
+    pub postings: u64,
+    pub unbalanced_entries: Vec<Uuid>,
+    #[schema(value_type = String, example = "0.00")]
+    pub total: Money,
+}
+
+impl LedgerVerification {
+    pub fn is_balanced(&self) -> bool {
+        self.unbalanced_entries.is_empty() && self.total.is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_operation_debits_and_credits_distinct_accounts() {
+        let from_account_id = Uuid::now_v7();
+        let to_account_id = Uuid::now_v7();
+        let operations = [
+            TransactionOperation::Deposit,
+            TransactionOperation::Fee,
+            TransactionOperation::Interest,
+            TransactionOperation::Payment,
+            TransactionOperation::Transfer,
+            TransactionOperation::Withdrawal,
+        ];
+
+        for operation in operations {
+            let (debit, credit) = operation
+                .ledger_accounts(Some(from_account_id), to_account_id)
+                .expect("Operation has no ledger accounts");
+
+            assert_ne!(debit, credit, "{:?} posts to a single account", operation);
+        }
+    }
+
+    #[test]
+    fn test_customer_side_of_operations() {
+        let account_id = Uuid::now_v7();
+        let customer = LedgerAccount::Customer(account_id);
+
+        let (_, credit) = TransactionOperation::Deposit
+            .ledger_accounts(None, account_id)
+            .unwrap();
+        assert_eq!(credit, customer);
+
+        let (debit, credit) = TransactionOperation::Fee
+            .ledger_accounts(None, account_id)
+            .unwrap();
+        assert_eq!(debit, customer);
+        assert_eq!(credit, LedgerAccount::System(SystemAccount::Fees));
+
+        assert!(TransactionOperation::Transfer
+            .ledger_accounts(None, account_id)
+            .is_err());
+    }
+
+    #[test]
+    fn test_balanced_entry_sums_to_zero() {
+        let user = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let entry = JournalEntry::new(Uuid::now_v7(), chrono::Utc::now().naive_utc());
+        let amount = Money::from_minor_units(12_345);
+
+        let debit = Posting::new(
+            &entry,
+            LedgerAccount::System(SystemAccount::CashIn),
+            PostingDirection::Debit,
+            amount,
+            None,
+        )
+        .expect("Posting creation failed");
+        let credit = Posting::new(
+            &entry,
+            LedgerAccount::Customer(Uuid::now_v7()),
+            PostingDirection::Credit,
+            amount,
+            Some(&user),
+        )
+        .expect("Posting creation failed");
+
+        let total =
+            debit.get_signed_amount(None).unwrap() + credit.get_signed_amount(Some(&user)).unwrap();
+
+        assert_eq!(total, Money::ZERO);
+        assert!(credit.get_amount(None).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::structs::money::Money;
+
+use super::transaction_dto::TransactionOperation;
+
+/// Daily limits count what was initiated over this many hours up to now.
+pub const LIMIT_WINDOW_HOURS: i32 = 24;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LimitError {
+    #[error("A limit applies to an account or to a user, not both")]
+    AmbiguousScope,
+    #[error("{0:?} operations cannot be limited")]
+    UnsupportedOperation(TransactionOperation),
+    #[error("A limit needs a per-operation or a daily maximum")]
+    MissingMaximum,
+    #[error("Limits must be positive")]
+    NonPositive,
+}
+
+/// A transaction that would take its initiating account over a limit.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LimitExceeded {
+    #[error("Amount exceeds the {0} per-operation limit")]
+    PerOperation(Money),
+    #[error("Amount exceeds the {limit} daily limit, {remaining} is left for the last 24 hours")]
+    Daily { limit: Money, remaining: Money },
+}
+
+/// Caps the amount of one operation that accounts may initiate.
+///
+/// A limit with an account applies to that account, one with a user to all of the user's
+/// accounts together, and one with neither to every user that has no limit of their own.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TransactionLimit {
+    pub id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub operation: TransactionOperation,
+    #[schema(value_type = Option<String>, example = "5000.00")]
+    pub per_operation: Option<Money>,
+    /// Counted over the last 24 hours; reversals do not give any of it back.
+    #[schema(value_type = Option<String>, example = "20000.00")]
+    pub daily: Option<Money>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl TransactionLimit {
+    pub fn new(limit: &TransactionLimitCreate) -> anyhow::Result<Self> {
+        if limit.account_id.is_some() && limit.user_id.is_some() {
+            return Err(LimitError::AmbiguousScope.into());
+        }
+
+        let new_limit = Self {
+            id: Uuid::now_v7(),
+            account_id: limit.account_id,
+            user_id: limit.user_id,
+            operation: limit.operation.clone(),
+            per_operation: limit.per_operation,
+            daily: limit.daily,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+        };
+        new_limit.validate()?;
+
+        Ok(new_limit)
+    }
+
+    pub fn update(&mut self, update: &TransactionLimitUpdate) -> anyhow::Result<()> {
+        self.per_operation = update.per_operation;
+        self.daily = update.daily;
+        self.validate()?;
+        self.updated_at = Some(chrono::Utc::now().naive_utc());
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), LimitError> {
+        if !matches!(
+            self.operation,
+            TransactionOperation::Deposit
+                | TransactionOperation::Payment
+                | TransactionOperation::Transfer
+                | TransactionOperation::Withdrawal
+        ) {
+            return Err(LimitError::UnsupportedOperation(self.operation.clone()));
+        }
+        if self.per_operation.is_none() && self.daily.is_none() {
+            return Err(LimitError::MissingMaximum);
+        }
+        if [self.per_operation, self.daily]
+            .into_iter()
+            .flatten()
+            .any(|maximum| !maximum.is_positive())
+        {
+            return Err(LimitError::NonPositive);
+        }
+
+        Ok(())
+    }
+
+    /// Whether an operation of `amount` fits, given the `used` total initiated within the
+    /// window, `amount` included.
+    pub fn check(&self, amount: Money, used: Money) -> Result<(), LimitExceeded> {
+        if let Some(per_operation) = self.per_operation.filter(|maximum| amount > *maximum) {
+            return Err(LimitExceeded::PerOperation(per_operation));
+        }
+        if let Some(daily) = self.daily.filter(|maximum| used > *maximum) {
+            return Err(LimitExceeded::Daily {
+                limit: daily,
+                remaining: (daily - (used - amount)).max(Money::ZERO),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Without an account or a user, the limit applies to every user.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TransactionLimitCreate {
+    pub account_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub operation: TransactionOperation,
+    #[schema(value_type = Option<String>, example = "5000.00")]
+    pub per_operation: Option<Money>,
+    #[schema(value_type = Option<String>, example = "20000.00")]
+    pub daily: Option<Money>,
+}
+
+/// Replaces both maximums; leaving one out removes it. The scope and operation cannot change.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TransactionLimitUpdate {
+    #[schema(value_type = Option<String>, example = "5000.00")]
+    pub per_operation: Option<Money>,
+    #[schema(value_type = Option<String>, example = "20000.00")]
+    pub daily: Option<Money>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(per_operation: Option<i64>, daily: Option<i64>) -> anyhow::Result<TransactionLimit> {
+        TransactionLimit::new(&TransactionLimitCreate {
+            account_id: None,
+            user_id: None,
+            operation: TransactionOperation::Withdrawal,
+            per_operation: per_operation.map(Money::from_minor_units),
+            daily: daily.map(Money::from_minor_units),
+        })
+    }
+
+    #[test]
+    fn test_limits_need_a_positive_maximum() {
+        for (per_operation, daily, expected) in [
+            (None, None, LimitError::MissingMaximum),
+            (Some(0), None, LimitError::NonPositive),
+            (Some(500_000), Some(-1), LimitError::NonPositive),
+        ] {
+            let error = limit(per_operation, daily).unwrap_err();
+            assert_eq!(error.downcast_ref::<LimitError>(), Some(&expected));
+        }
+
+        let error = TransactionLimit::new(&TransactionLimitCreate {
+            account_id: Some(Uuid::now_v7()),
+            user_id: Some(Uuid::now_v7()),
+            operation: TransactionOperation::Fee,
+            per_operation: None,
+            daily: Some(Money::from_minor_units(1)),
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<LimitError>(),
+            Some(&LimitError::AmbiguousScope)
+        );
+    }
+
+    #[test]
+    fn test_operations_are_checked_against_both_maximums() {
+        let limit = limit(Some(500_000), Some(2_000_000)).unwrap();
+        let money = Money::from_minor_units;
+
+        assert_eq!(limit.check(money(500_000), money(2_000_000)), Ok(()));
+        assert_eq!(
+            limit.check(money(500_001), money(500_001)),
+            Err(LimitExceeded::PerOperation(money(500_000)))
+        );
+        assert_eq!(
+            limit.check(money(400_000), money(2_100_000)),
+            Err(LimitExceeded::Daily {
+                limit: money(2_000_000),
+                remaining: money(300_000),
+            })
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "notification_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    ScheduleSkipped,
+    ScheduleFailed,
+}
+
+/// Something the system did on a user's behalf that they should hear about.
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: NotificationKind,
+    pub message: String,
+    pub schedule_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub read_at: Option<NaiveDateTime>,
+}
+
+impl Notification {
+    pub fn new(
+        user_id: Uuid,
+        kind: NotificationKind,
+        message: String,
+        schedule_id: Option<Uuid>,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            user_id,
+            kind,
+            message,
+            schedule_id,
+            created_at: chrono::Utc::now().naive_utc(),
+            read_at: None,
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    decrypt_user_key, load_master_key,
+    structs::{
+        encrypted_field::EncryptedField,
+        money::{round_half_even, Money},
+    },
+    traits::encryptable::Encryptable,
+};
+
+use super::{
+    interest_dto::{CompoundingFrequency, DayCountConvention, ACCRUAL_UNITS_PER_MINOR_UNIT},
+    user_dto::User,
+};
+
+const BASIS_POINTS: i128 = 10_000;
+
+/// Overdraft interest accrues on actual days over a 365-day year.
+const OVERDRAFT_DAY_COUNT: DayCountConvention = DayCountConvention::Actual365;
+
+/// Overdraft interest is charged on the first day of each month.
+const OVERDRAFT_CHARGES: CompoundingFrequency = CompoundingFrequency::Monthly;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum OverdraftError {
+    #[error("Overdraft limit cannot be negative")]
+    NegativeLimit,
+    #[error("Overdraft rate cannot be negative")]
+    InvalidRate,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct OverdraftUpdate {
+    #[schema(value_type = String, example = "500.00")]
+    pub limit: Money,
+    #[schema(example = 1290)]
+    pub annual_rate_bps: i32,
+}
+
+impl OverdraftUpdate {
+    pub fn validate(&self) -> Result<(), OverdraftError> {
+        if self.limit.is_negative() {
+            return Err(OverdraftError::NegativeLimit);
+        }
+        if self.annual_rate_bps < 0 {
+            return Err(OverdraftError::InvalidRate);
+        }
+
+        Ok(())
+    }
+}
+
+/// Interest owed on an overdrawn balance since it was last charged.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct OverdraftInterest {
+    pub account_id: Uuid,
+    pub accrued: EncryptedField<i64>,
+    pub accrued_through: NaiveDate,
+    pub next_charge_on: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl OverdraftInterest {
+    /// Starts accruing on `today` with nothing owed yet.
+    pub fn new(owner: &User, account_id: Uuid, today: NaiveDate) -> anyhow::Result<Self> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        Ok(Self {
+            account_id,
+            accrued: 0_i64.encrypt(&key)?,
+            accrued_through: today
+                .pred_opt()
+                .ok_or_else(|| anyhow::anyhow!("Invalid accrual date"))?,
+            next_charge_on: OVERDRAFT_CHARGES.next_payout(today)?,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+        })
+    }
+
+    pub fn get_accrued(&self, owner: &User) -> anyhow::Result<i64> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        Ok(i64::decrypt(&self.accrued, &key)?)
+    }
+
+    fn set_accrued(&mut self, owner: &User, accrued: i64) -> anyhow::Result<()> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        self.accrued = accrued.encrypt(&key)?;
+        self.updated_at = Some(chrono::Utc::now().naive_utc());
+        Ok(())
+    }
+
+    /// Accrues every full day before `today` that has not been accrued yet on the overdrawn
+    /// part of `balance`.
+    pub fn accrue(
+        &mut self,
+        owner: &User,
+        annual_rate_bps: i32,
+        balance: Money,
+        today: NaiveDate,
+    ) -> anyhow::Result<()> {
+        let mut accrued = self.get_accrued(owner)?;
+        let mut day = self.accrued_through;
+        while let Some(next) = day.succ_opt().filter(|next| *next < today) {
+            accrued = accrued
+                .checked_add(daily_accrual(annual_rate_bps, balance, next))
+                .ok_or_else(|| anyhow::anyhow!("Accrued interest overflow"))?;
+            day = next;
+        }
+
+        self.accrued_through = day;
+        self.set_accrued(owner, accrued)
+    }
+
+    /// The whole minor units owed when a charge is due on `today`.
+    pub fn due_charge(&self, owner: &User, today: NaiveDate) -> anyhow::Result<Option<Money>> {
+        if self.next_charge_on > today {
+            return Ok(None);
+        }
+
+        Ok(Some(Money::from_minor_units(
+            self.get_accrued(owner)?
+                .div_euclid(ACCRUAL_UNITS_PER_MINOR_UNIT),
+        )))
+    }
+
+    /// Takes `charged` out of the accrual, leaving the fraction for the next charge, and
+    /// moves on to the next charge date.
+    pub fn settle(&mut self, owner: &User, charged: Money, today: NaiveDate) -> anyhow::Result<()> {
+        let accrued =
+            self.get_accrued(owner)? - charged.minor_units() * ACCRUAL_UNITS_PER_MINOR_UNIT;
+        self.set_accrued(owner, accrued)?;
+        self.next_charge_on = OVERDRAFT_CHARGES.next_payout(today)?;
+
+        Ok(())
+    }
+}
+
+/// Interest the overdrawn part of `balance` owes over `day`, in accrual units.
+fn daily_accrual(annual_rate_bps: i32, balance: Money, day: NaiveDate) -> i64 {
+    if !balance.is_negative() {
+        return 0;
+    }
+    let (numerator, denominator) = OVERDRAFT_DAY_COUNT.day_fraction(day);
+
+    round_half_even(
+        -(balance.minor_units() as i128)
+            * annual_rate_bps as i128
+            * numerator
+            * ACCRUAL_UNITS_PER_MINOR_UNIT as i128,
+        BASIS_POINTS * denominator,
+    ) as i64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverdraftChargeOutcome {
+    NotDue,
+    Charged(Uuid),
+}
+
+/// Tally of one run of the overdraft interest job.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OverdraftRunSummary {
+    pub accounts: u64,
+    pub charged: u64,
+    pub failed: u64,
+}
+
+impl OverdraftRunSummary {
+    pub fn record(&mut self, outcome: OverdraftChargeOutcome) {
+        self.accounts += 1;
+        match outcome {
+            OverdraftChargeOutcome::NotDue => {}
+            OverdraftChargeOutcome::Charged(_) => self.charged += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_only_overdrawn_balances_accrue_until_charged() {
+        let owner = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let mut interest =
+            OverdraftInterest::new(&owner, Uuid::now_v7(), date("2025-01-10")).unwrap();
+        assert_eq!(interest.next_charge_on, date("2025-02-01"));
+
+        // 36.5% a year on 100.00 overdrawn is 10 cents a day
+        interest
+            .accrue(
+                &owner,
+                3_650,
+                Money::from_minor_units(10_000),
+                date("2025-01-20"),
+            )
+            .unwrap();
+        assert_eq!(interest.get_accrued(&owner).unwrap(), 0);
+        interest
+            .accrue(
+                &owner,
+                3_650,
+                Money::from_minor_units(-10_000),
+                date("2025-02-01"),
+            )
+            .unwrap();
+        assert_eq!(
+            interest.get_accrued(&owner).unwrap(),
+            12 * 10 * ACCRUAL_UNITS_PER_MINOR_UNIT
+        );
+
+        assert_eq!(
+            interest.due_charge(&owner, date("2025-01-31")).unwrap(),
+            None
+        );
+        let charge = interest
+            .due_charge(&owner, date("2025-02-01"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(charge, Money::from_minor_units(120));
+
+        interest.settle(&owner, charge, date("2025-02-01")).unwrap();
+        assert_eq!(interest.get_accrued(&owner).unwrap(), 0);
+        assert_eq!(interest.next_charge_on, date("2025-03-01"));
+    }
+}
@@ -0,0 +1,59 @@
+use thiserror::Error;
+
+use crate::structs::money::Money;
+
+use super::transaction_dto::TransactionCreate;
+
+/// The longest GrpHdr/MsgId pain.001 allows.
+pub const MAX_MESSAGE_ID_LENGTH: usize = 35;
+
+/// Problems with a payment file as a whole; none of its payments are made.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PaymentFileError {
+    #[error("Malformed payment file: {0}")]
+    Malformed(String),
+    #[error("Expected a pain.001 customer credit transfer initiation")]
+    UnsupportedDocument,
+    #[error("The group header needs a MsgId of 1 to {MAX_MESSAGE_ID_LENGTH} characters")]
+    InvalidMessageId,
+    #[error("Payment file {0} was already applied")]
+    AlreadyApplied(String),
+    #[error("A payment file needs at least one payment")]
+    Empty,
+    #[error("A payment file holds at most {0} payments")]
+    TooLarge(usize),
+    #[error("The group header declares {declared} payments but the file holds {found}")]
+    NumberOfTransactions { declared: String, found: usize },
+    #[error(
+        "The group header declares a control sum of {declared} but the payments add up to {found}"
+    )]
+    ControlSum { declared: String, found: Money },
+}
+
+/// Problems with a single payment of a file, reported against it.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum PaymentEntryError {
+    #[error("Unsupported payment method {0}, only TRF is accepted")]
+    UnsupportedPaymentMethod(String),
+    #[error("Unsupported currency {0}, only BRL is accepted")]
+    UnsupportedCurrency(String),
+    #[error("Invalid amount {0}")]
+    InvalidAmount(String),
+    #[error("Account {0} is not identified by an account id")]
+    InvalidAccount(String),
+}
+
+/// One credit transfer of a payment file.
+#[derive(Debug)]
+pub struct PaymentFileEntry {
+    pub payment_information_id: String,
+    pub end_to_end_id: String,
+    /// The transfer it becomes, unless the entry itself is invalid.
+    pub transaction: Result<TransactionCreate, PaymentEntryError>,
+}
+
+#[derive(Debug)]
+pub struct PaymentFile {
+    pub message_id: String,
+    pub entries: Vec<PaymentFileEntry>,
+}
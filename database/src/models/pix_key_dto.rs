@@ -0,0 +1,293 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Most keys a single account may have.
+pub const MAX_KEYS_PER_ACCOUNT: i64 = 5;
+const MAX_EMAIL_LENGTH: usize = 77;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "pix_key_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PixKeyType {
+    Email,
+    Phone,
+    /// A CPF or a CNPJ.
+    TaxId,
+    /// A key the bank generates, for customers who would rather not share their details.
+    Random,
+}
+
+impl PixKeyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PixKeyType::Email => "email",
+            PixKeyType::Phone => "phone",
+            PixKeyType::TaxId => "tax_id",
+            PixKeyType::Random => "random",
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PixKeyError {
+    #[error("Invalid key {0:?}")]
+    InvalidKey(String),
+    #[error("Not a valid {} key", .0.as_str())]
+    TypeMismatch(PixKeyType),
+    #[error("A {} key needs its value", .0.as_str())]
+    MissingKey(PixKeyType),
+    #[error("Random keys are generated by the bank and cannot be chosen")]
+    RandomKeyGiven,
+    #[error("Email keys must be the email of the account owner")]
+    EmailNotOwned,
+    #[error("Only the bank registers {} keys, once it has verified them", .0.as_str())]
+    VerificationRequired(PixKeyType),
+    #[error("The key is already registered")]
+    KeyTaken,
+    #[error("An account holds at most {0} keys")]
+    TooManyKeys(i64),
+    #[error("No account is registered under that key")]
+    KeyNotFound,
+    #[error("Give either to_account_id or to_key, not both")]
+    AmbiguousRecipient,
+    #[error("A transaction needs to_account_id or to_key")]
+    MissingRecipient,
+    #[error("Keys can only name the recipient of transfers")]
+    TransfersOnly,
+}
+
+/// An alias that names the account transfers to it go to.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PixKey {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub key_type: PixKeyType,
+    /// The key in its normalized form.
+    pub key: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl PixKey {
+    pub fn new(account_id: Uuid, key_type: PixKeyType, key: String) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            account_id,
+            key_type,
+            key,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    pub fn masked(&self) -> String {
+        mask_key(self.key_type, &self.key)
+    }
+}
+
+/// Body of a new key; random keys are left without one.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PixKeyCreate {
+    pub account_id: Uuid,
+    pub key_type: PixKeyType,
+    #[serde(default)]
+    #[schema(example = "+5511987654321")]
+    pub key: Option<String>,
+}
+
+/// Who a transfer by key reached, as much as the sender may see of it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TransactionRecipient {
+    pub name: String,
+    pub key_type: PixKeyType,
+    /// The key with the parts that identify its owner hidden.
+    #[schema(example = "***.982.247-**")]
+    pub key: String,
+}
+
+/// Works out the type of a key from its shape and normalizes it: emails in lower case, phone
+/// numbers as `+` and digits, tax IDs as digits and random keys as hyphenated UUIDs.
+pub fn parse_key(raw: &str) -> Result<(PixKeyType, String), PixKeyError> {
+    let key = raw.trim();
+    let invalid = || PixKeyError::InvalidKey(raw.to_string());
+
+    if key.contains('@') {
+        let email = key.to_lowercase();
+        let valid = match email.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.is_empty()
+                    && !domain.contains('@')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+            }
+            None => false,
+        };
+        if !valid || email.len() > MAX_EMAIL_LENGTH || email.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+
+        return Ok((PixKeyType::Email, email));
+    }
+
+    if let Some(number) = key.strip_prefix('+') {
+        let digits: String = number
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '(' | ')'))
+            .collect();
+        if !(8..=15).contains(&digits.len()) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        return Ok((PixKeyType::Phone, format!("+{}", digits)));
+    }
+
+    if let Ok(random) = Uuid::parse_str(key) {
+        return Ok((PixKeyType::Random, random.hyphenated().to_string()));
+    }
+
+    let digits: String = key
+        .chars()
+        .filter(|c| !matches!(c, '.' | '-' | '/' | ' '))
+        .collect();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) || !valid_tax_id(&digits) {
+        return Err(invalid());
+    }
+
+    Ok((PixKeyType::TaxId, digits))
+}
+
+/// Checks the two check digits of a CPF (11 digits) or a CNPJ (14 digits).
+fn valid_tax_id(digits: &str) -> bool {
+    let digits: Vec<u32> = digits.bytes().map(|b| (b - b'0') as u32).collect();
+    // every digit the same passes the check digits, but no such number is ever issued
+    if digits.windows(2).all(|pair| pair[0] == pair[1]) {
+        return false;
+    }
+
+    let check_digit = |body: &[u32], weights: &[u32]| {
+        let sum: u32 = body.iter().zip(weights).map(|(d, w)| d * w).sum();
+        match sum % 11 {
+            0 | 1 => 0,
+            rest => 11 - rest,
+        }
+    };
+
+    match digits.len() {
+        11 => {
+            let first = check_digit(&digits[..9], &[10, 9, 8, 7, 6, 5, 4, 3, 2]);
+            let second = check_digit(&digits[..10], &[11, 10, 9, 8, 7, 6, 5, 4, 3, 2]);
+            digits[9] == first && digits[10] == second
+        }
+        14 => {
+            let first = check_digit(&digits[..12], &[5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2]);
+            let second = check_digit(&digits[..13], &[6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2]);
+            digits[12] == first && digits[13] == second
+        }
+        _ => false,
+    }
+}
+
+/// Hides a normalized key the way the payer's bank shows it: enough to recognize it, not enough
+/// to learn it. A CNPJ is public and shown in full.
+pub fn mask_key(key_type: PixKeyType, key: &str) -> String {
+    match key_type {
+        PixKeyType::Email => match key.split_once('@') {
+            Some((local, domain)) => {
+                let first: String = local.chars().take(1).collect();
+                format!("{}***@{}", first, domain)
+            }
+            None => "***".to_string(),
+        },
+        PixKeyType::Phone if key.len() > 7 => format!(
+            "{}{}{}",
+            &key[..3],
+            "*".repeat(key.len() - 7),
+            &key[key.len() - 4..]
+        ),
+        PixKeyType::TaxId if key.len() == 11 => {
+            format!("***.{}.{}-**", &key[3..6], &key[6..9])
+        }
+        PixKeyType::TaxId if key.len() == 14 => format!(
+            "{}.{}.{}/{}-{}",
+            &key[..2],
+            &key[2..5],
+            &key[5..8],
+            &key[8..12],
+            &key[12..]
+        ),
+        PixKeyType::Random if key.len() == 36 => {
+            format!("{}-****-****-****-************", &key[..8])
+        }
+        _ => "***".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_are_recognized_and_normalized() {
+        assert_eq!(
+            parse_key(" Maria.Silva@Example.com ").unwrap(),
+            (PixKeyType::Email, "maria.silva@example.com".to_string())
+        );
+        assert_eq!(
+            parse_key("+55 (11) 98765-4321").unwrap(),
+            (PixKeyType::Phone, "+5511987654321".to_string())
+        );
+        assert_eq!(
+            parse_key("529.982.247-25").unwrap(),
+            (PixKeyType::TaxId, "52998224725".to_string())
+        );
+        assert_eq!(
+            parse_key("11.222.333/0001-81").unwrap(),
+            (PixKeyType::TaxId, "11222333000181".to_string())
+        );
+        assert_eq!(
+            parse_key("123E4567-E89B-12D3-A456-426614174000").unwrap(),
+            (
+                PixKeyType::Random,
+                "123e4567-e89b-12d3-a456-426614174000".to_string()
+            )
+        );
+
+        for invalid in [
+            "529.982.247-26",
+            "111.111.111-11",
+            "+55 11",
+            "maria@",
+            "@x.com",
+            "ACME",
+        ] {
+            assert_eq!(
+                parse_key(invalid),
+                Err(PixKeyError::InvalidKey(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_keys_are_masked() {
+        assert_eq!(
+            mask_key(PixKeyType::Email, "maria.silva@example.com"),
+            "m***@example.com"
+        );
+        assert_eq!(
+            mask_key(PixKeyType::Phone, "+5511987654321"),
+            "+55*******4321"
+        );
+        assert_eq!(mask_key(PixKeyType::TaxId, "52998224725"), "***.982.247-**");
+        assert_eq!(
+            mask_key(PixKeyType::TaxId, "11222333000181"),
+            "11.222.333/0001-81"
+        );
+        assert_eq!(
+            mask_key(PixKeyType::Random, "123e4567-e89b-12d3-a456-426614174000"),
+            "123e4567-****-****-****-************"
+        );
+    }
+}
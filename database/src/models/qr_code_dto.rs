@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::structs::money::Money;
+
+use super::transaction_dto::{TransactionCreate, TransactionOperation};
+
+/// Problems with a BR Code payload itself.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum BrCodeError {
+    #[error("Malformed BR Code field at offset {0}")]
+    Malformed(usize),
+    #[error("BR Code checksum {declared} does not match the payload, expected {computed}")]
+    Checksum { declared: String, computed: String },
+    #[error("BR Code without {0}")]
+    MissingField(&'static str),
+    #[error("The BR Code is not an instant payment")]
+    NotPix,
+    #[error("Dynamic BR Codes that point to a URL are not supported")]
+    UrlPayload,
+    #[error("Unsupported currency {0}, only BRL (986) is accepted")]
+    UnsupportedCurrency(String),
+    #[error("Invalid amount {0}")]
+    InvalidAmount(String),
+    #[error("Transaction ids are up to 25 letters and digits, got {0:?}")]
+    InvalidTransactionId(String),
+    #[error("{0} is too long for a BR Code")]
+    TooLong(&'static str),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QrCodeError {
+    #[error("Account {0} has no payment key to be paid through")]
+    NoKey(Uuid),
+    #[error("The key is not registered to the account")]
+    KeyNotOwned,
+    #[error("The BR Code asks for {requested}, not {given}")]
+    AmountMismatch { requested: Money, given: Money },
+    #[error("The BR Code has no amount, so the payment needs one")]
+    MissingAmount,
+    #[error("The BR Code {0} was already paid")]
+    AlreadyPaid(String),
+}
+
+/// Body of a new QR payload for an account to be paid through.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QrCodeCreate {
+    pub account_id: Uuid,
+    /// One of the account's keys; its oldest one when left out.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Left out, the payer chooses the amount.
+    #[serde(default)]
+    #[schema(value_type = Option<String>, example = "25.00")]
+    pub amount: Option<Money>,
+    /// Up to 25 letters and digits. Dynamic payloads get one when left out.
+    #[serde(default)]
+    pub transaction_id: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[schema(example = "SAO PAULO")]
+    pub city: String,
+    /// Dynamic payloads are meant for a single payment; static ones can be printed and reused.
+    #[serde(default)]
+    pub dynamic: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QrCode {
+    /// The text to render as a QR code, or to offer as "copy and paste".
+    pub payload: String,
+    pub transaction_id: Option<String>,
+    #[schema(value_type = Option<String>, example = "25.00")]
+    pub amount: Option<Money>,
+}
+
+/// Body of a payment of a scanned QR payload.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QrTransactionCreate {
+    pub from_account_id: Uuid,
+    pub payload: String,
+    /// Needed when the payload has no amount, and must match it when it has one.
+    #[serde(default)]
+    #[schema(value_type = Option<String>, example = "25.00")]
+    pub amount: Option<Money>,
+}
+
+impl QrTransactionCreate {
+    /// The transfer to the key of the payload.
+    pub fn to_transaction(
+        &self,
+        key: &str,
+        requested: Option<Money>,
+    ) -> Result<TransactionCreate, QrCodeError> {
+        let amount = match (requested, self.amount) {
+            (Some(requested), Some(given)) if requested != given => {
+                return Err(QrCodeError::AmountMismatch { requested, given })
+            }
+            (Some(amount), _) | (None, Some(amount)) => amount,
+            (None, None) => return Err(QrCodeError::MissingAmount),
+        };
+
+        Ok(TransactionCreate {
+            operation: TransactionOperation::Transfer,
+            from_account_id: Some(self.from_account_id),
+            to_account_id: Uuid::nil(),
+            to_key: Some(key.to_string()),
+            amount,
+            reverses_transaction_id: None,
+        })
+    }
+}
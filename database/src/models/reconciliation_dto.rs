@@ -0,0 +1,63 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::structs::money::Money;
+
+/// Stored balance of an account compared with the balance recomputed from its transactions.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReconciliationReport {
+    pub account_id: Uuid,
+    pub transactions: u64,
+    #[schema(value_type = String, example = "1000.00")]
+    pub expected_balance: Money,
+    #[schema(value_type = String, example = "1000.00")]
+    pub actual_balance: Money,
+    /// Actual minus expected balance; zero when the account is consistent.
+    #[schema(value_type = String, example = "0.00")]
+    pub difference: Money,
+    pub checked_at: NaiveDateTime,
+}
+
+impl ReconciliationReport {
+    pub fn new(
+        account_id: Uuid,
+        transactions: u64,
+        expected_balance: Money,
+        actual_balance: Money,
+    ) -> Self {
+        Self {
+            account_id,
+            transactions,
+            expected_balance,
+            actual_balance,
+            difference: actual_balance - expected_balance,
+            checked_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.difference.is_zero()
+    }
+}
+
+/// An account that could not be reconciled, such as one whose history does not decrypt.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReconciliationFailure {
+    pub account_id: Uuid,
+    pub error: String,
+}
+
+/// The outcome of reconciling every account; one account failing does not stop the others.
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ReconciliationRun {
+    pub reports: Vec<ReconciliationReport>,
+    pub failures: Vec<ReconciliationFailure>,
+}
+
+impl ReconciliationRun {
+    pub fn is_consistent(&self) -> bool {
+        self.failures.is_empty() && self.reports.iter().all(ReconciliationReport::is_consistent)
+    }
+}
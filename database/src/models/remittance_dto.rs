@@ -0,0 +1,166 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Return file occurrence for a payment that was made.
+pub const OCCURRENCE_PAID: &str = "00";
+/// Return file occurrence for a payment the bank accepted and scheduled.
+pub const OCCURRENCE_SCHEDULED: &str = "BD";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "remittance_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RemittanceStatus {
+    /// Sent, with no return yet.
+    Pending,
+    Scheduled,
+    Paid,
+    Rejected,
+}
+
+impl RemittanceStatus {
+    /// Any occurrence other than paid or scheduled is the reason the bank rejected it.
+    pub fn from_occurrences(occurrences: &[String]) -> Self {
+        match occurrences {
+            [] => RemittanceStatus::Pending,
+            [code] if code == OCCURRENCE_PAID => RemittanceStatus::Paid,
+            [code] if code == OCCURRENCE_SCHEDULED => RemittanceStatus::Scheduled,
+            _ => RemittanceStatus::Rejected,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RemittanceError {
+    #[error("Account {0} needs its bank, agency and account numbers for CNAB files")]
+    MissingBankDetails(Uuid),
+    #[error("Expected a return file, got a remittance file")]
+    NotAReturnFile,
+    #[error("No account matches the one in the file header")]
+    UnknownAccount,
+}
+
+/// Fixed-width layout problems in a CNAB 240 file, by line.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CnabError {
+    #[error("The file needs a file header, at least one batch and a file trailer")]
+    Truncated,
+    #[error("Line {line} has {length} characters instead of 240")]
+    LineLength { line: usize, length: usize },
+    #[error("Line {line}: expected {expected}")]
+    UnexpectedRecord { line: usize, expected: &'static str },
+    #[error("Line {line}: invalid {field} {value:?}")]
+    InvalidField {
+        line: usize,
+        field: &'static str,
+        value: String,
+    },
+    #[error("Line {line}: the trailer declares {declared} {total} but there are {found}")]
+    TotalMismatch {
+        line: usize,
+        total: &'static str,
+        declared: String,
+        found: String,
+    },
+    #[error("{field} {value:?} does not fit a CNAB 240 field")]
+    Overflow { field: &'static str, value: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Remittance {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub file_sequence: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl Remittance {
+    pub fn new(account_id: Uuid, file_sequence: i32) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            account_id,
+            file_sequence,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct RemittanceItem {
+    pub remittance_id: Uuid,
+    /// Position of the payment in its file.
+    pub sequence: i32,
+    pub transaction_id: Uuid,
+    pub status: RemittanceStatus,
+    /// The number the bank gave the payment, from its return files.
+    pub bank_reference: Option<String>,
+    /// The occurrence codes of the latest return, two characters each.
+    pub occurrences: Option<String>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// The "seu número" of a payment: the file and the position in it, which return files echo
+/// back so the payment can be found again.
+pub fn your_number(file_sequence: i32, sequence: i32) -> String {
+    format!("{:06}{:05}", file_sequence, sequence)
+}
+
+pub fn parse_your_number(your_number: &str) -> Option<(i32, i32)> {
+    let your_number = your_number.trim();
+    if your_number.len() != 11 || !your_number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((
+        your_number[..6].parse().ok()?,
+        your_number[6..].parse().ok()?,
+    ))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReturnReport {
+    pub account_id: Uuid,
+    /// The items the return file updated.
+    pub updated: Vec<RemittanceItem>,
+    /// The "seu número" of the payments in the file that match no remitted transfer.
+    pub unmatched: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_occurrences_and_your_numbers() {
+        let codes = |codes: &[&str]| {
+            codes
+                .iter()
+                .map(|code| code.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            RemittanceStatus::from_occurrences(&[]),
+            RemittanceStatus::Pending
+        );
+        assert_eq!(
+            RemittanceStatus::from_occurrences(&codes(&["00"])),
+            RemittanceStatus::Paid
+        );
+        assert_eq!(
+            RemittanceStatus::from_occurrences(&codes(&["BD"])),
+            RemittanceStatus::Scheduled
+        );
+        assert_eq!(
+            RemittanceStatus::from_occurrences(&codes(&["AG", "AM"])),
+            RemittanceStatus::Rejected
+        );
+
+        assert_eq!(your_number(12, 3), "00001200003");
+        assert_eq!(parse_your_number("00001200003   "), Some((12, 3)));
+        assert_eq!(parse_your_number("ABC"), None);
+    }
+}
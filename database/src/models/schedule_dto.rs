@@ -0,0 +1,571 @@
+use std::str::FromStr;
+
+use chrono::{Months, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    decrypt_user_key, load_master_key,
+    structs::{encrypted_field::EncryptedField, money::Money},
+    traits::encryptable::Encryptable,
+};
+
+use super::user_dto::User;
+
+/// How many runs in a row may fail before the schedule is given up on.
+pub const MAX_SCHEDULE_ATTEMPTS: i32 = 3;
+
+/// Wait before retrying a failed run, multiplied by the number of failures so far.
+pub const SCHEDULE_RETRY_DELAY: chrono::Duration = chrono::Duration::minutes(15);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "schedule_frequency", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleFrequency {
+    Once,
+    Daily,
+    Weekly,
+    Monthly,
+    Cron,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type, ToSchema)]
+#[sqlx(type_name = "schedule_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleStatus {
+    Active,
+    Paused,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error("Scheduled amount must be positive")]
+    InvalidAmount,
+    #[error("Schedule cannot transfer into the account it transfers from")]
+    SameAccount,
+    #[error("Schedule must end after it starts")]
+    InvalidEnd,
+    #[error("Cron schedules need a cron expression")]
+    MissingCronExpression,
+    #[error("Only cron schedules take a cron expression")]
+    UnexpectedCronExpression,
+    #[error("Invalid cron expression: {0}")]
+    InvalidCronExpression(String),
+    #[error("Schedule is {0:?} and can no longer change")]
+    NotEditable(ScheduleStatus),
+    #[error("Schedules can only be set to active or paused")]
+    InvalidStatus,
+}
+
+/// A transfer that runs once at a future date, or repeatedly as a standing order.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Schedule {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    pub amount: EncryptedField<Money>,
+    pub frequency: ScheduleFrequency,
+    pub cron_expression: Option<String>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: Option<NaiveDateTime>,
+    pub next_run_at: Option<NaiveDateTime>,
+    pub retry_at: Option<NaiveDateTime>,
+    pub status: ScheduleStatus,
+    pub failed_attempts: i32,
+    pub last_error: Option<String>,
+    pub last_run_at: Option<NaiveDateTime>,
+    pub last_transaction_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl Schedule {
+    pub fn new(owner: &User, schedule: &ScheduleCreate) -> anyhow::Result<Self> {
+        let now = chrono::Utc::now().naive_utc();
+
+        if !schedule.amount.is_positive() {
+            return Err(ScheduleError::InvalidAmount.into());
+        }
+        if schedule.from_account_id == schedule.to_account_id {
+            return Err(ScheduleError::SameAccount.into());
+        }
+        match (schedule.frequency, &schedule.cron_expression) {
+            (ScheduleFrequency::Cron, None) => {
+                return Err(ScheduleError::MissingCronExpression.into())
+            }
+            (ScheduleFrequency::Cron, Some(expression)) => {
+                cron::Schedule::from_str(expression)
+                    .map_err(|e| ScheduleError::InvalidCronExpression(e.to_string()))?;
+            }
+            (_, Some(_)) => return Err(ScheduleError::UnexpectedCronExpression.into()),
+            (_, None) => {}
+        }
+
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        let mut new_schedule = Self {
+            id: Uuid::now_v7(),
+            user_id: owner.id,
+            from_account_id: schedule.from_account_id,
+            to_account_id: schedule.to_account_id,
+            amount: schedule.amount.encrypt(&key)?,
+            frequency: schedule.frequency,
+            cron_expression: schedule.cron_expression.clone(),
+            starts_at: schedule.starts_at.unwrap_or(now),
+            ends_at: schedule.ends_at,
+            next_run_at: None,
+            retry_at: None,
+            status: ScheduleStatus::Active,
+            failed_attempts: 0,
+            last_error: None,
+            last_run_at: None,
+            last_transaction_id: None,
+            created_at: now,
+            updated_at: None,
+        };
+
+        // the first run may be the start itself
+        new_schedule.next_run_at =
+            new_schedule.next_occurrence(new_schedule.starts_at - chrono::Duration::seconds(1))?;
+        if new_schedule.next_run_at.is_none() {
+            return Err(ScheduleError::InvalidEnd.into());
+        }
+
+        Ok(new_schedule)
+    }
+
+    pub fn get_amount(&self, owner: &User) -> anyhow::Result<Money> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+        Ok(Money::decrypt(&self.amount, &key)?)
+    }
+
+    /// Applies an update from the owner; only active and paused schedules can change.
+    pub fn update(
+        &mut self,
+        owner: &User,
+        update: &ScheduleUpdate,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        if !matches!(self.status, ScheduleStatus::Active | ScheduleStatus::Paused) {
+            return Err(ScheduleError::NotEditable(self.status).into());
+        }
+
+        if let Some(amount) = update.amount {
+            if !amount.is_positive() {
+                return Err(ScheduleError::InvalidAmount.into());
+            }
+            let master_key = load_master_key()?;
+            let key = decrypt_user_key(&owner.encryption_key, &master_key)?;
+            self.amount = amount.encrypt(&key)?;
+        }
+
+        if let Some(ends_at) = update.ends_at {
+            if ends_at <= self.starts_at {
+                return Err(ScheduleError::InvalidEnd.into());
+            }
+            self.ends_at = Some(ends_at);
+            if self
+                .next_run_at
+                .is_some_and(|next_run_at| next_run_at > ends_at)
+            {
+                self.next_run_at = None;
+                self.retry_at = None;
+                self.status = ScheduleStatus::Completed;
+            }
+        }
+
+        match update.status {
+            Some(ScheduleStatus::Paused) if self.status == ScheduleStatus::Active => {
+                self.status = ScheduleStatus::Paused;
+            }
+            Some(ScheduleStatus::Active) if self.status == ScheduleStatus::Paused => {
+                // runs missed while paused are skipped, but a one-off still happens
+                if self.frequency != ScheduleFrequency::Once
+                    && self
+                        .next_run_at
+                        .is_some_and(|next_run_at| next_run_at <= now)
+                {
+                    self.next_run_at = self.next_occurrence(now)?;
+                    self.retry_at = None;
+                }
+                self.status = match self.next_run_at {
+                    Some(_) => ScheduleStatus::Active,
+                    None => ScheduleStatus::Completed,
+                };
+            }
+            Some(ScheduleStatus::Active) | Some(ScheduleStatus::Paused) | None => {}
+            Some(_) => return Err(ScheduleError::InvalidStatus.into()),
+        }
+
+        self.updated_at = Some(now);
+
+        Ok(())
+    }
+
+    /// The first run strictly after `after`, or `None` once the schedule has nothing left to run.
+    pub fn next_occurrence(&self, after: NaiveDateTime) -> anyhow::Result<Option<NaiveDateTime>> {
+        let start = self.starts_at;
+        let every = |period: chrono::Duration| {
+            if after < start {
+                return start;
+            }
+            let elapsed = (after - start).num_seconds() / period.num_seconds();
+            start + period * (elapsed as i32 + 1)
+        };
+
+        let next = match self.frequency {
+            ScheduleFrequency::Once => Some(start).filter(|start| *start > after),
+            ScheduleFrequency::Daily => Some(every(chrono::Duration::days(1))),
+            ScheduleFrequency::Weekly => Some(every(chrono::Duration::weeks(1))),
+            ScheduleFrequency::Monthly => {
+                // always count from the start, so a run on the 31st comes back after shorter months
+                let mut months = 0;
+                loop {
+                    let next = start
+                        .checked_add_months(Months::new(months))
+                        .ok_or_else(|| anyhow::anyhow!("Schedule date overflow"))?;
+                    if next > after {
+                        break Some(next);
+                    }
+                    months += 1;
+                }
+            }
+            ScheduleFrequency::Cron => {
+                let expression = self
+                    .cron_expression
+                    .as_deref()
+                    .ok_or(ScheduleError::MissingCronExpression)?;
+                let schedule = cron::Schedule::from_str(expression)
+                    .map_err(|e| ScheduleError::InvalidCronExpression(e.to_string()))?;
+                let after = after.max(start - chrono::Duration::seconds(1));
+
+                schedule
+                    .after(&Utc.from_utc_datetime(&after))
+                    .next()
+                    .map(|next| next.naive_utc())
+            }
+        };
+
+        Ok(next.filter(|next| self.ends_at.is_none_or(|ends_at| *next <= ends_at)))
+    }
+
+    /// Records a run at `now` that created `transaction_id`.
+    pub fn record_success(
+        &mut self,
+        transaction_id: Uuid,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        self.failed_attempts = 0;
+        self.last_error = None;
+        self.last_transaction_id = Some(transaction_id);
+
+        self.advance(now)
+    }
+
+    /// Records a run at `now` skipped because the account could not cover it.
+    ///
+    /// Standing orders carry on with their next run; a one-off transfer fails.
+    pub fn record_skip(&mut self, error: String, now: NaiveDateTime) -> anyhow::Result<()> {
+        self.failed_attempts = 0;
+        self.last_error = Some(error);
+
+        self.advance(now)?;
+        if self.frequency == ScheduleFrequency::Once {
+            self.status = ScheduleStatus::Failed;
+        }
+
+        Ok(())
+    }
+
+    /// Records a failed run at `now` and sets up a retry, returning whether the schedule
+    /// failed for good instead.
+    pub fn record_failure(&mut self, error: String, now: NaiveDateTime) -> bool {
+        self.failed_attempts += 1;
+        self.last_error = Some(error);
+        self.last_run_at = Some(now);
+        self.updated_at = Some(now);
+
+        if self.failed_attempts >= MAX_SCHEDULE_ATTEMPTS {
+            self.status = ScheduleStatus::Failed;
+            self.retry_at = None;
+            return true;
+        }
+
+        self.retry_at = Some(now + SCHEDULE_RETRY_DELAY * self.failed_attempts);
+        false
+    }
+
+    fn advance(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+        self.retry_at = None;
+        self.last_run_at = Some(now);
+        self.updated_at = Some(now);
+        self.next_run_at = self.next_occurrence(now)?;
+        if self.next_run_at.is_none() {
+            self.status = ScheduleStatus::Completed;
+        }
+
+        Ok(())
+    }
+}
+
+/// A run that broke off before it could record its outcome on the schedule.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("Scheduled transfer {schedule_id} could not run: {message}")]
+pub struct ScheduleRunError {
+    pub schedule_id: Uuid,
+    pub message: String,
+}
+
+/// What happened to a schedule the worker picked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleRunOutcome {
+    Executed(Uuid),
+    /// The account could not cover the transfer, so this run was skipped.
+    Skipped,
+    /// The run failed and will be tried again.
+    Retrying,
+    /// The run failed too many times and the schedule was given up on.
+    Failed,
+}
+
+/// Tally of one pass of the worker over the due schedules.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleRunSummary {
+    pub executed: u64,
+    pub skipped: u64,
+    pub retrying: u64,
+    pub failed: u64,
+}
+
+impl ScheduleRunSummary {
+    pub fn record(&mut self, outcome: ScheduleRunOutcome) {
+        match outcome {
+            ScheduleRunOutcome::Executed(_) => self.executed += 1,
+            ScheduleRunOutcome::Skipped => self.skipped += 1,
+            ScheduleRunOutcome::Retrying => self.retrying += 1,
+            ScheduleRunOutcome::Failed => self.failed += 1,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.executed + self.skipped + self.retrying + self.failed
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ScheduleCreate {
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    #[schema(value_type = String, example = "150.00")]
+    pub amount: Money,
+    pub frequency: ScheduleFrequency,
+    /// Required for cron schedules, in UTC: `sec min hour day-of-month month day-of-week [year]`.
+    #[schema(example = "0 0 9 * * Mon-Fri")]
+    pub cron_expression: Option<String>,
+    /// First run; defaults to now.
+    pub starts_at: Option<NaiveDateTime>,
+    pub ends_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct ScheduleUpdate {
+    #[schema(value_type = Option<String>, example = "200.00")]
+    pub amount: Option<Money>,
+    pub ends_at: Option<NaiveDateTime>,
+    /// Pause or resume the schedule.
+    pub status: Option<ScheduleStatus>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScheduleModel {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    #[schema(value_type = String, example = "150.00")]
+    pub amount: Money,
+    pub frequency: ScheduleFrequency,
+    pub cron_expression: Option<String>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: Option<NaiveDateTime>,
+    pub next_run_at: Option<NaiveDateTime>,
+    pub retry_at: Option<NaiveDateTime>,
+    pub status: ScheduleStatus,
+    pub failed_attempts: i32,
+    pub last_error: Option<String>,
+    pub last_run_at: Option<NaiveDateTime>,
+    pub last_transaction_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl ScheduleModel {
+    pub fn from_dto(schedule: &Schedule, owner: &User) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: schedule.id,
+            user_id: schedule.user_id,
+            from_account_id: schedule.from_account_id,
+            to_account_id: schedule.to_account_id,
+            amount: schedule.get_amount(owner)?,
+            frequency: schedule.frequency,
+            cron_expression: schedule.cron_expression.clone(),
+            starts_at: schedule.starts_at,
+            ends_at: schedule.ends_at,
+            next_run_at: schedule.next_run_at,
+            retry_at: schedule.retry_at,
+            status: schedule.status,
+            failed_attempts: schedule.failed_attempts,
+            last_error: schedule.last_error.clone(),
+            last_run_at: schedule.last_run_at,
+            last_transaction_id: schedule.last_transaction_id,
+            created_at: schedule.created_at,
+            updated_at: schedule.updated_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn schedule(frequency: ScheduleFrequency, cron_expression: Option<&str>) -> Schedule {
+        let owner = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+
+        Schedule::new(
+            &owner,
+            &ScheduleCreate {
+                from_account_id: Uuid::now_v7(),
+                to_account_id: Uuid::now_v7(),
+                amount: Money::from_minor_units(15_000),
+                frequency,
+                cron_expression: cron_expression.map(str::to_string),
+                starts_at: Some(at("2026-01-31 09:00:00")),
+                ends_at: None,
+            },
+        )
+        .expect("Schedule creation failed")
+    }
+
+    #[test]
+    fn test_occurrences_follow_the_frequency() {
+        let after = at("2026-02-10 12:00:00");
+
+        for (frequency, cron_expression, first, expected) in [
+            (
+                ScheduleFrequency::Once,
+                None,
+                at("2026-01-31 09:00:00"),
+                None,
+            ),
+            (
+                ScheduleFrequency::Daily,
+                None,
+                at("2026-01-31 09:00:00"),
+                Some(at("2026-02-11 09:00:00")),
+            ),
+            (
+                ScheduleFrequency::Weekly,
+                None,
+                at("2026-01-31 09:00:00"),
+                Some(at("2026-02-14 09:00:00")),
+            ),
+            (
+                ScheduleFrequency::Monthly,
+                None,
+                at("2026-01-31 09:00:00"),
+                Some(at("2026-02-28 09:00:00")),
+            ),
+            (
+                ScheduleFrequency::Cron,
+                Some("0 30 8 1 * *"),
+                at("2026-02-01 08:30:00"),
+                Some(at("2026-03-01 08:30:00")),
+            ),
+        ] {
+            let schedule = schedule(frequency, cron_expression);
+
+            assert_eq!(schedule.next_run_at, Some(first), "{:?}", frequency);
+            assert_eq!(
+                schedule.next_occurrence(after).unwrap(),
+                expected,
+                "{:?}",
+                frequency
+            );
+        }
+
+        // monthly runs go back to the start day once the month allows it
+        let monthly = schedule(ScheduleFrequency::Monthly, None);
+        assert_eq!(
+            monthly.next_occurrence(at("2026-02-28 09:00:00")).unwrap(),
+            Some(at("2026-03-31 09:00:00"))
+        );
+    }
+
+    #[test]
+    fn test_failures_retry_then_give_up() {
+        let mut schedule = schedule(ScheduleFrequency::Daily, None);
+        let now = at("2026-01-31 09:00:05");
+
+        for attempt in 1..MAX_SCHEDULE_ATTEMPTS {
+            assert!(!schedule.record_failure("boom".to_string(), now));
+            assert_eq!(schedule.status, ScheduleStatus::Active);
+            assert_eq!(
+                schedule.retry_at,
+                Some(now + SCHEDULE_RETRY_DELAY * attempt)
+            );
+        }
+
+        assert!(schedule.record_failure("boom".to_string(), now));
+        assert_eq!(schedule.status, ScheduleStatus::Failed);
+        assert_eq!(schedule.retry_at, None);
+    }
+
+    #[test]
+    fn test_cron_schedules_need_a_valid_expression() {
+        let owner = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let create = |cron_expression: Option<&str>| ScheduleCreate {
+            from_account_id: Uuid::now_v7(),
+            to_account_id: Uuid::now_v7(),
+            amount: Money::from_minor_units(100),
+            frequency: ScheduleFrequency::Cron,
+            cron_expression: cron_expression.map(str::to_string),
+            starts_at: None,
+            ends_at: None,
+        };
+
+        let error = Schedule::new(&owner, &create(None)).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ScheduleError>(),
+            Some(&ScheduleError::MissingCronExpression)
+        );
+
+        let error = Schedule::new(&owner, &create(Some("every tuesday"))).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ScheduleError>(),
+            Some(ScheduleError::InvalidCronExpression(_))
+        ));
+    }
+}
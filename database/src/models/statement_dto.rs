@@ -0,0 +1,116 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::structs::money::Money;
+
+use super::transaction_dto::TransactionOperation;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StatementError {
+    #[error("Statement period cannot end before it starts")]
+    InvalidPeriod,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    /// ISO 20022 bank to customer statement.
+    Camt053,
+    #[default]
+    Csv,
+    Ofx,
+}
+
+impl StatementFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StatementFormat::Camt053 => "application/xml",
+            StatementFormat::Csv => "text/csv; charset=utf-8",
+            StatementFormat::Ofx => "application/x-ofx",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StatementFormat::Camt053 => "xml",
+            StatementFormat::Csv => "csv",
+            StatementFormat::Ofx => "ofx",
+        }
+    }
+}
+
+/// The days a statement covers, both included, and the file format to render it in.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatementQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub format: StatementFormat,
+}
+
+impl StatementQuery {
+    pub fn validate(&self) -> Result<(), StatementError> {
+        if self.to < self.from {
+            return Err(StatementError::InvalidPeriod);
+        }
+
+        Ok(())
+    }
+
+    /// The first instant of the period and the first one after it.
+    pub fn period(&self) -> anyhow::Result<(NaiveDateTime, NaiveDateTime)> {
+        let end = self
+            .to
+            .succ_opt()
+            .ok_or_else(|| anyhow::anyhow!("Invalid statement period"))?;
+
+        Ok((
+            self.from.and_time(NaiveTime::MIN),
+            end.and_time(NaiveTime::MIN),
+        ))
+    }
+}
+
+/// One transaction as it moved the balance of the account a statement is for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementEntry {
+    pub transaction_id: Uuid,
+    pub posted_at: NaiveDateTime,
+    pub operation: TransactionOperation,
+    /// The other account of a transfer or reversal, if any.
+    pub counterparty_account_id: Option<Uuid>,
+    /// Positive when it credited the account.
+    pub amount: Money,
+    /// The balance right after it.
+    pub balance: Money,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_periods_include_both_days() {
+        let query = StatementQuery {
+            from: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+            format: StatementFormat::Csv,
+        };
+        let (start, end) = query.period().unwrap();
+
+        assert_eq!(start.to_string(), "2025-01-01 00:00:00");
+        assert_eq!(end.to_string(), "2025-02-01 00:00:00");
+        assert_eq!(
+            StatementQuery {
+                from: query.to,
+                to: query.from,
+                ..query
+            }
+            .validate(),
+            Err(StatementError::InvalidPeriod)
+        );
+    }
+}
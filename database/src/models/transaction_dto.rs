@@ -1,14 +1,22 @@
 use crate::{
-    decrypt_user_key, load_master_key, structs::encrypted_field::EncryptedField,
+    decrypt_user_key, load_master_key,
+    structs::{
+        encrypted_field::EncryptedField,
+        money::{Money, MoneyEncoding},
+    },
     traits::encryptable::Encryptable,
 };
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::prelude::{FromRow, Type};
+use thiserror::Error;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Type, ToSchema)]
+use super::pix_key_dto::TransactionRecipient;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Type, ToSchema)]
 #[sqlx(type_name = "transaction_operation", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum TransactionOperation {
@@ -18,6 +26,23 @@ pub enum TransactionOperation {
     Payment,
     Transfer,
     Withdrawal,
+    /// Moves all or part of an earlier transaction's amount back between the same accounts.
+    Reversal,
+}
+
+impl TransactionOperation {
+    /// The name the API and the database use for the operation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionOperation::Deposit => "deposit",
+            TransactionOperation::Fee => "fee",
+            TransactionOperation::Interest => "interest",
+            TransactionOperation::Payment => "payment",
+            TransactionOperation::Transfer => "transfer",
+            TransactionOperation::Withdrawal => "withdrawal",
+            TransactionOperation::Reversal => "reversal",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -26,7 +51,9 @@ pub struct Transaction {
     pub operation: TransactionOperation,
     pub from_account_id: Option<Uuid>,
     pub to_account_id: Uuid,
-    pub amount: EncryptedField<f64>,
+    pub amount: EncryptedField<Money>,
+    pub amount_encoding: MoneyEncoding,
+    pub reverses_transaction_id: Option<Uuid>,
     pub created_at: NaiveDateTime,
 }
 
@@ -35,7 +62,7 @@ impl Transaction {
         from_account_id: Option<Uuid>,
         to_account_id: Uuid,
         operation: TransactionOperation,
-        amount: f64,
+        amount: Money,
         user_key: &[u8],
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let master_key = load_master_key()?;
@@ -46,23 +73,83 @@ impl Transaction {
             from_account_id,
             to_account_id,
             amount: amount.encrypt(&key)?,
+            amount_encoding: MoneyEncoding::MinorUnits,
+            reverses_transaction_id: None,
             created_at: chrono::Utc::now().naive_utc(),
         })
     }
 
-    pub fn get_amount(&self, user_key: &[u8]) -> Result<f64, Box<dyn std::error::Error>> {
+    pub fn get_amount(&self, user_key: &[u8]) -> Result<Money, Box<dyn std::error::Error>> {
+        let master_key = load_master_key()?;
+        let key = decrypt_user_key(user_key, &master_key)?;
+        Ok(self.amount_encoding.decrypt(&self.amount, &key)?)
+    }
+
+    /// How much this transaction changed the balance of `account_id`, given its decrypted amount.
+    ///
+    /// A reversal undoes part of the transaction it reverses, which must be passed as `reversed`.
+    pub fn balance_change(
+        &self,
+        account_id: &Uuid,
+        amount: Money,
+        reversed: Option<&Transaction>,
+    ) -> anyhow::Result<Money> {
+        if let TransactionOperation::Reversal = self.operation {
+            let original = reversed
+                .filter(|original| Some(original.id) == self.reverses_transaction_id)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Reversal {} needs the transaction it reverses", self.id)
+                })?;
+
+            return Ok(-original.balance_change(account_id, amount, None)?);
+        }
+
+        if self.from_account_id.as_ref() == Some(account_id) {
+            return Ok(-amount);
+        }
+
+        if &self.to_account_id != account_id {
+            return Ok(Money::ZERO);
+        }
+
+        Ok(match self.operation {
+            TransactionOperation::Deposit
+            | TransactionOperation::Interest
+            | TransactionOperation::Transfer => amount,
+            TransactionOperation::Fee
+            | TransactionOperation::Payment
+            | TransactionOperation::Withdrawal => -amount,
+            TransactionOperation::Reversal => unreachable!("reversals are handled above"),
+        })
+    }
+
+    /// Re-encrypts an amount written under an older encoding as minor units.
+    pub fn reencrypt_amount(&mut self, user_key: &[u8]) -> anyhow::Result<()> {
         let master_key = load_master_key()?;
         let key = decrypt_user_key(user_key, &master_key)?;
-        Ok(f64::decrypt(&self.amount, &key)?)
+        let amount = self.amount_encoding.decrypt(&self.amount, &key)?;
+
+        self.amount = amount.encrypt(&key)?;
+        self.amount_encoding = MoneyEncoding::MinorUnits;
+
+        Ok(())
     }
 }
 
-#[derive(Debug, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TransactionCreate {
     pub operation: TransactionOperation,
     pub from_account_id: Option<Uuid>,
+    /// Left out when `to_key` names the recipient instead.
+    #[serde(default)]
     pub to_account_id: Uuid,
-    pub amount: f64,
+    /// A payment key of the recipient of a transfer, resolved into `to_account_id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_key: Option<String>,
+    #[schema(value_type = String, example = "100.00")]
+    pub amount: Money,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverses_transaction_id: Option<Uuid>,
 }
 
 impl TransactionCreate {
@@ -75,9 +162,145 @@ impl TransactionCreate {
             from_account_id: self.from_account_id,
             to_account_id: self.to_account_id,
             amount: self.amount.encrypt(&key)?,
+            amount_encoding: MoneyEncoding::MinorUnits,
+            reverses_transaction_id: self.reverses_transaction_id,
             created_at: chrono::Utc::now().naive_utc(),
         })
     }
+
+    /// The customer account that initiates the operation, which pays its fee and counts it
+    /// towards its limits. Operations the bank posts itself have none.
+    pub fn initiating_account_id(&self) -> Option<Uuid> {
+        match self.operation {
+            TransactionOperation::Transfer => self.from_account_id,
+            TransactionOperation::Deposit
+            | TransactionOperation::Payment
+            | TransactionOperation::Withdrawal => Some(self.to_account_id),
+            TransactionOperation::Fee
+            | TransactionOperation::Interest
+            | TransactionOperation::Reversal => None,
+        }
+    }
+
+    /// SHA-256 of the canonical JSON form, so equivalent bodies such as `"10"` and `"10.00"` match.
+    pub fn fingerprint(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(Sha256::digest(serde_json::to_vec(self)?).to_vec())
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TransactionError {
+    #[error("Amount must be positive")]
+    InvalidAmount,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ReversalError {
+    #[error("Reversals cannot be reversed")]
+    NotReversible,
+    #[error("Reversal amount must be positive")]
+    InvalidAmount,
+    #[error("Transaction has already been fully reversed")]
+    AlreadyReversed,
+    #[error("Reversal exceeds the {0} left to reverse")]
+    ExceedsRemaining(Money),
+}
+
+/// Body of a reversal; without an amount, whatever is left of the original is reversed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ReversalCreate {
+    #[schema(value_type = Option<String>, example = "25.00")]
+    pub amount: Option<Money>,
+}
+
+/// Most transactions a single batch may hold.
+pub const MAX_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BatchError {
+    #[error("A batch needs at least one transaction")]
+    Empty,
+    #[error("A batch holds at most {0} transactions")]
+    TooLarge(usize),
+}
+
+/// How a batch treats the transactions that fail.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    /// Nothing is applied unless every transaction succeeds.
+    #[default]
+    AllOrNothing,
+    /// The transactions that succeed are applied and the others reported.
+    BestEffort,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TransactionBatch {
+    #[serde(default)]
+    pub mode: BatchMode,
+    pub transactions: Vec<TransactionCreate>,
+}
+
+impl TransactionBatch {
+    pub fn validate(&self) -> Result<(), BatchError> {
+        if self.transactions.is_empty() {
+            return Err(BatchError::Empty);
+        }
+        if self.transactions.len() > MAX_BATCH_SIZE {
+            return Err(BatchError::TooLarge(MAX_BATCH_SIZE));
+        }
+
+        Ok(())
+    }
+
+    /// Every account the batch touches, for locking them all up front.
+    pub fn account_ids(&self) -> Vec<Uuid> {
+        self.transactions
+            .iter()
+            .flat_map(|transaction| [transaction.from_account_id, Some(transaction.to_account_id)])
+            .flatten()
+            .collect()
+    }
+}
+
+/// What happened to one transaction of a batch.
+#[derive(Debug)]
+pub enum BatchItemOutcome {
+    Created(Transaction),
+    /// It went through, but was undone along with the rest of an all-or-nothing batch.
+    RolledBack,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Created,
+    RolledBack,
+    Failed,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchItemResult {
+    /// Position of the transaction in the request.
+    pub index: usize,
+    /// The caller's own id for the item, such as the EndToEndId of a payment file entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    pub status: BatchItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionModel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionBatchResult {
+    pub mode: BatchMode,
+    /// False when a failure rolled back an all-or-nothing batch.
+    pub committed: bool,
+    pub items: Vec<BatchItemResult>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -86,7 +309,16 @@ pub struct TransactionModel {
     pub operation: TransactionOperation,
     pub from_account_id: Option<Uuid>,
     pub to_account_id: Uuid,
-    pub amount: f64,
+    #[schema(value_type = String, example = "100.00")]
+    pub amount: Money,
+    /// The transaction this one reverses, for reversals.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reverses_transaction_id: Option<Uuid>,
+    /// Reversals written against this transaction, oldest first.
+    pub reversed_by: Vec<Uuid>,
+    /// Who a transfer by key reached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<TransactionRecipient>,
     pub created_at: NaiveDateTime,
 }
 
@@ -99,10 +331,25 @@ impl TransactionModel {
             operation: transaction.operation.clone(),
             from_account_id: transaction.from_account_id,
             to_account_id: transaction.to_account_id,
-            amount: f64::decrypt(&transaction.amount, &key)?,
+            amount: transaction
+                .amount_encoding
+                .decrypt(&transaction.amount, &key)?,
+            reverses_transaction_id: transaction.reverses_transaction_id,
+            reversed_by: Vec::new(),
+            recipient: None,
             created_at: transaction.created_at,
         })
     }
+
+    pub fn with_reversals(mut self, reversed_by: Vec<Uuid>) -> Self {
+        self.reversed_by = reversed_by;
+        self
+    }
+
+    pub fn with_recipient(mut self, recipient: Option<TransactionRecipient>) -> Self {
+        self.recipient = recipient;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -122,7 +369,7 @@ mod tests {
             Some("test".to_string()),
         )
         .expect("User creation failed");
-        let amount = 250.0;
+        let amount = Money::from_minor_units(25_000);
 
         let transaction = Transaction::new(
             Some(from_account_id),
@@ -140,6 +387,124 @@ mod tests {
         assert_eq!(amount, decrypted_amount);
     }
 
+    #[test]
+    fn test_fingerprint_ignores_amount_formatting() {
+        let to_account_id = Uuid::new_v4();
+        let parse = |body: String| serde_json::from_str::<TransactionCreate>(&body).unwrap();
+
+        let plain = parse(format!(
+            r#"{{"operation": "deposit", "to_account_id": "{}", "amount": 10}}"#,
+            to_account_id
+        ));
+        let padded = parse(format!(
+            r#"{{"operation": "deposit", "to_account_id": "{}", "amount": "10.00"}}"#,
+            to_account_id
+        ));
+        let different = parse(format!(
+            r#"{{"operation": "deposit", "to_account_id": "{}", "amount": "10.01"}}"#,
+            to_account_id
+        ));
+
+        assert_eq!(plain.fingerprint().unwrap(), padded.fingerprint().unwrap());
+        assert_ne!(
+            plain.fingerprint().unwrap(),
+            different.fingerprint().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_balance_change_follows_operation() {
+        let user = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let from_account_id = Uuid::new_v4();
+        let to_account_id = Uuid::new_v4();
+        let amount = Money::from_minor_units(1_000);
+
+        let transfer = Transaction::new(
+            Some(from_account_id),
+            to_account_id,
+            TransactionOperation::Transfer,
+            amount,
+            &user.encryption_key,
+        )
+        .expect("Transaction creation failed");
+        assert_eq!(
+            transfer
+                .balance_change(&from_account_id, amount, None)
+                .unwrap(),
+            -amount
+        );
+        assert_eq!(
+            transfer
+                .balance_change(&to_account_id, amount, None)
+                .unwrap(),
+            amount
+        );
+        assert_eq!(
+            transfer
+                .balance_change(&Uuid::new_v4(), amount, None)
+                .unwrap(),
+            Money::ZERO
+        );
+
+        let fee = Transaction::new(
+            None,
+            to_account_id,
+            TransactionOperation::Fee,
+            amount,
+            &user.encryption_key,
+        )
+        .expect("Transaction creation failed");
+        assert_eq!(
+            fee.balance_change(&to_account_id, amount, None).unwrap(),
+            -amount
+        );
+    }
+
+    #[test]
+    fn test_reversal_undoes_its_original() {
+        let user = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let account_id = Uuid::new_v4();
+        let amount = Money::from_minor_units(250);
+
+        let fee = Transaction::new(
+            None,
+            account_id,
+            TransactionOperation::Fee,
+            Money::from_minor_units(1_000),
+            &user.encryption_key,
+        )
+        .expect("Transaction creation failed");
+        let mut reversal = Transaction::new(
+            None,
+            account_id,
+            TransactionOperation::Reversal,
+            amount,
+            &user.encryption_key,
+        )
+        .expect("Transaction creation failed");
+        reversal.reverses_transaction_id = Some(fee.id);
+
+        assert_eq!(
+            reversal
+                .balance_change(&account_id, amount, Some(&fee))
+                .unwrap(),
+            amount
+        );
+        assert!(reversal.balance_change(&account_id, amount, None).is_err());
+    }
+
     #[test]
     fn test_transaction_amount_with_wrong_key_fails() {
         let from_account_id = Uuid::new_v4();
@@ -158,7 +523,7 @@ mod tests {
             Some("test".to_string()),
         )
         .expect("User creation failed");
-        let amount = 250.0;
+        let amount = Money::from_minor_units(25_000);
 
         let transaction = Transaction::new(
             Some(from_account_id),
@@ -20,6 +20,8 @@ pub struct User {
     pub encryption_key: Vec<u8>, // User-specific encryption key
     pub created_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    /// When the user was soft deleted; they can no longer sign in.
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 impl User {
@@ -48,6 +50,7 @@ impl User {
             encryption_key,
             created_at: chrono::Utc::now().naive_utc(),
             updated_at: None,
+            deleted_at: None,
         })
     }
 }
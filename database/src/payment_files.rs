@@ -0,0 +1,31 @@
+//! Payment files and codes: those sent in by customers, read into the transactions they ask
+//! for, those exchanged with banks for payments into accounts they hold, and the QR payloads and
+//! payment slips customers are paid through.
+
+pub mod boleto;
+pub mod brcode;
+pub mod cnab240;
+pub mod pain001;
+
+/// Folds Portuguese accents into plain ASCII letters, keeping the case, and blanks out any other
+/// character outside printable ASCII.
+pub(crate) fn to_ascii(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'Á' | 'À' | 'Â' | 'Ã' | 'Ä' => 'A',
+            'é' | 'ê' | 'è' => 'e',
+            'É' | 'Ê' | 'È' => 'E',
+            'í' | 'ì' => 'i',
+            'Í' | 'Ì' => 'I',
+            'ó' | 'ô' | 'õ' | 'ò' => 'o',
+            'Ó' | 'Ô' | 'Õ' | 'Ò' => 'O',
+            'ú' | 'ü' | 'ù' => 'u',
+            'Ú' | 'Ü' | 'Ù' => 'U',
+            'ç' => 'c',
+            'Ç' => 'C',
+            c if c.is_ascii_graphic() || c == ' ' => c,
+            _ => ' ',
+        })
+        .collect()
+}
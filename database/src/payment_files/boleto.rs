@@ -0,0 +1,317 @@
+//! Boleto: the bank payment slip. Its 44-digit barcode holds the bank, the currency, a check
+//! digit, the due date as days from a base date, the amount and 25 digits laid out by the
+//! issuing bank. Payers key in the 47-digit typeable line instead, which rearranges the
+//! barcode into five fields, the first three with a check digit of their own.
+
+use chrono::NaiveDate;
+
+use crate::{models::boleto_dto::BarcodeError, structs::money::Money};
+
+pub const BARCODE_LENGTH: usize = 44;
+pub const TYPEABLE_LINE_LENGTH: usize = 47;
+const BRL: char = '9';
+/// Barcodes starting with 8 are utility and tax bills, laid out differently.
+const UTILITY_BILL: char = '8';
+const FREE_FIELD_LENGTH: usize = 25;
+const MAX_AMOUNT: i64 = 9_999_999_999;
+/// The due date factors of 1000 to 9999 ran out on 2025-02-21 and started over at 1000.
+const MIN_FACTOR: i64 = 1000;
+const MAX_FACTOR: i64 = 9999;
+const FACTOR_CYCLE: i64 = MAX_FACTOR - MIN_FACTOR + 1;
+/// A factor names the date in the cycle that falls in the window of 3000 days before and
+/// 6000 days after the day it is read.
+const DAYS_BEFORE_READING: i64 = 3000;
+/// Wallet of the slips issued here, in the free field.
+const WALLET: &str = "09";
+
+fn factor_base() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1997, 10, 7).unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Barcode {
+    pub bank_id: i32,
+    /// Slips without a due date can be paid at any time.
+    pub due_date: Option<NaiveDate>,
+    /// Left out, the payer chooses the amount.
+    pub amount: Option<Money>,
+    /// The 25 digits the issuing bank lays out as it sees fit.
+    pub free_field: String,
+}
+
+impl Barcode {
+    /// The 44 digits of the barcode.
+    pub fn digits(&self) -> Result<String, BarcodeError> {
+        if !(0..=999).contains(&self.bank_id) {
+            return Err(BarcodeError::TooLong("bank"));
+        }
+        if self.free_field.len() != FREE_FIELD_LENGTH
+            || !self.free_field.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(BarcodeError::TooLong("free field"));
+        }
+        let factor = match self.due_date {
+            Some(due_date) => due_date_factor(due_date)?,
+            None => 0,
+        };
+        let amount = self.amount.map_or(0, |amount| amount.minor_units());
+        if !(0..=MAX_AMOUNT).contains(&amount) {
+            return Err(BarcodeError::TooLong("amount"));
+        }
+
+        let body = format!(
+            "{:03}{}{:04}{:010}{}",
+            self.bank_id, BRL, factor, amount, self.free_field
+        );
+        let check_digit = mod11(&body);
+        Ok(format!("{}{}{}", &body[..4], check_digit, &body[4..]))
+    }
+
+    /// The typeable line of the barcode, punctuated the way slips print it.
+    pub fn typeable_line(&self) -> Result<String, BarcodeError> {
+        Ok(typeable_line(&self.digits()?))
+    }
+}
+
+/// The free field of the slips issued here: agency, wallet, our number, account and a zero.
+pub fn free_field(agency: i32, our_number: i64, account: i64) -> Result<String, BarcodeError> {
+    if !(0..=9_999).contains(&agency) {
+        return Err(BarcodeError::TooLong("agency"));
+    }
+    if !(0..=99_999_999_999).contains(&our_number) {
+        return Err(BarcodeError::TooLong("our number"));
+    }
+    if !(0..=9_999_999).contains(&account) {
+        return Err(BarcodeError::TooLong("account"));
+    }
+
+    Ok(format!(
+        "{:04}{}{:011}{:07}0",
+        agency, WALLET, our_number, account
+    ))
+}
+
+/// Days from the base date to `due_date`, starting over after 9999.
+fn due_date_factor(due_date: NaiveDate) -> Result<i64, BarcodeError> {
+    let days = (due_date - factor_base()).num_days();
+    if days < MIN_FACTOR {
+        return Err(BarcodeError::InvalidDueDate);
+    }
+
+    Ok((days - MIN_FACTOR) % FACTOR_CYCLE + MIN_FACTOR)
+}
+
+/// The due date a factor names when read on `today`; a factor of zero names none.
+fn due_date(factor: i64, today: NaiveDate) -> Option<NaiveDate> {
+    if factor < MIN_FACTOR {
+        return None;
+    }
+
+    let earliest = (today - factor_base()).num_days() - DAYS_BEFORE_READING;
+    let cycles = (earliest - factor + FACTOR_CYCLE - 1).div_euclid(FACTOR_CYCLE);
+    let days = factor + cycles.max(0) * FACTOR_CYCLE;
+    Some(factor_base() + chrono::Duration::days(days))
+}
+
+/// Weights the digits 2, 1, 2, ... from the right, adding up the digits of each product.
+fn mod10(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .zip([2, 1].iter().cycle())
+        .map(|(digit, weight)| {
+            let product = (digit - b'0') as u32 * weight;
+            product / 10 + product % 10
+        })
+        .sum();
+
+    (10 - sum % 10) % 10
+}
+
+/// Weights the digits 2 to 9 from the right; results of 0, 10 and 11 become 1.
+fn mod11(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .zip((2..=9).cycle())
+        .map(|(digit, weight)| (digit - b'0') as u32 * weight)
+        .sum();
+
+    match 11 - sum % 11 {
+        0 | 10 | 11 => 1,
+        check_digit => check_digit,
+    }
+}
+
+/// Rearranges the 44 digits of a valid barcode into the five fields of its typeable line.
+pub fn typeable_line(barcode: &str) -> String {
+    let with_check_digit = |field: String| {
+        let check_digit = mod10(&field);
+        format!("{}{}", field, check_digit)
+    };
+    let first = with_check_digit(format!("{}{}", &barcode[..4], &barcode[19..24]));
+    let second = with_check_digit(barcode[24..34].to_string());
+    let third = with_check_digit(barcode[34..44].to_string());
+
+    format!(
+        "{}.{} {}.{} {}.{} {} {}",
+        &first[..5],
+        &first[5..],
+        &second[..5],
+        &second[5..],
+        &third[..5],
+        &third[5..],
+        &barcode[4..5],
+        &barcode[5..19]
+    )
+}
+
+/// Reads a typeable line or a barcode, ignoring spaces and dots, and checks all of its check
+/// digits. Due dates are read as of `today`.
+pub fn parse(input: &str, today: NaiveDate) -> Result<Barcode, BarcodeError> {
+    let digits: String = input.chars().filter(|c| !matches!(c, ' ' | '.')).collect();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(BarcodeError::NotDigits);
+    }
+    if digits.starts_with(UTILITY_BILL) {
+        return Err(BarcodeError::UtilityBill);
+    }
+
+    let barcode = match digits.len() {
+        BARCODE_LENGTH => digits,
+        TYPEABLE_LINE_LENGTH => {
+            let fields = [
+                (&digits[..10], 1),
+                (&digits[10..21], 2),
+                (&digits[21..32], 3),
+            ];
+            for (field, number) in fields {
+                let (body, check_digit) = field.split_at(field.len() - 1);
+                if mod10(body).to_string() != check_digit {
+                    return Err(BarcodeError::FieldCheckDigit(number));
+                }
+            }
+
+            // bank and currency, check digit, due date and amount, then the free field
+            format!(
+                "{}{}{}{}{}",
+                &digits[..4],
+                &digits[32..47],
+                &digits[4..9],
+                &digits[10..20],
+                &digits[21..31]
+            )
+        }
+        length => return Err(BarcodeError::InvalidLength(length)),
+    };
+
+    let body = format!("{}{}", &barcode[..4], &barcode[5..]);
+    if mod11(&body).to_string() != barcode[4..5] {
+        return Err(BarcodeError::BarcodeCheckDigit);
+    }
+    let currency = barcode[3..4].chars().next().unwrap_or_default();
+    if currency != BRL {
+        return Err(BarcodeError::UnsupportedCurrency(currency));
+    }
+
+    let factor: i64 = barcode[5..9].parse().map_err(|_| BarcodeError::NotDigits)?;
+    let amount: i64 = barcode[9..19]
+        .parse()
+        .map_err(|_| BarcodeError::NotDigits)?;
+    Ok(Barcode {
+        bank_id: barcode[..3].parse().map_err(|_| BarcodeError::NotDigits)?,
+        due_date: due_date(factor, today),
+        amount: Some(Money::from_minor_units(amount)).filter(|amount| amount.is_positive()),
+        free_field: barcode[19..].to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BARCODE: &str = "00193373700000001000500940144816060680935031";
+    const TYPEABLE_LINE: &str = "00190.50095 40144.816069 06809.350314 3 37370000000100";
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_barcodes_and_typeable_lines_match_a_real_slip() {
+        let barcode = parse(TYPEABLE_LINE, date(2008, 1, 1)).unwrap();
+        assert_eq!(barcode.bank_id, 1);
+        assert_eq!(barcode.due_date, Some(date(2007, 12, 31)));
+        assert_eq!(barcode.amount, Some(Money::from_minor_units(100)));
+        assert_eq!(barcode.free_field, "0500940144816060680935031");
+        assert_eq!(barcode.digits().unwrap(), BARCODE);
+        assert_eq!(barcode.typeable_line().unwrap(), TYPEABLE_LINE);
+
+        assert_eq!(parse(BARCODE, date(2008, 1, 1)).unwrap(), barcode);
+        assert_eq!(
+            parse(&TYPEABLE_LINE.replace(['.', ' '], ""), date(2008, 1, 1)).unwrap(),
+            barcode
+        );
+    }
+
+    #[test]
+    fn test_due_dates_start_over_after_factor_9999() {
+        assert_eq!(due_date_factor(date(2000, 7, 3)).unwrap(), 1000);
+        assert_eq!(due_date_factor(date(2025, 2, 21)).unwrap(), 9999);
+        assert_eq!(due_date_factor(date(2025, 2, 22)).unwrap(), 1000);
+        assert_eq!(
+            due_date_factor(date(2000, 7, 2)),
+            Err(BarcodeError::InvalidDueDate)
+        );
+
+        let today = date(2026, 10, 18);
+        assert_eq!(due_date(1000, today), Some(date(2025, 2, 22)));
+        assert_eq!(due_date(9999, today), Some(date(2025, 2, 21)));
+        assert_eq!(due_date(0, today), None);
+
+        let slip = Barcode {
+            bank_id: 341,
+            due_date: Some(date(2026, 11, 30)),
+            amount: None,
+            free_field: free_field(1234, 42, 56789).unwrap(),
+        };
+        assert_eq!(slip.free_field, "1234090000000004200567890");
+        assert_eq!(parse(&slip.typeable_line().unwrap(), today).unwrap(), slip);
+    }
+
+    #[test]
+    fn test_lines_are_checked() {
+        let today = date(2008, 1, 1);
+        let typo = |position: usize| {
+            let mut line: Vec<char> = TYPEABLE_LINE.chars().collect();
+            line[position] = if line[position] == '1' { '2' } else { '1' };
+            line.into_iter().collect::<String>()
+        };
+
+        assert_eq!(
+            parse(&typo(2), today),
+            Err(BarcodeError::FieldCheckDigit(1))
+        );
+        assert_eq!(
+            parse(&typo(14), today),
+            Err(BarcodeError::FieldCheckDigit(2))
+        );
+        assert_eq!(
+            parse(&typo(27), today),
+            Err(BarcodeError::FieldCheckDigit(3))
+        );
+        assert_eq!(
+            parse(&typo(40), today),
+            Err(BarcodeError::BarcodeCheckDigit)
+        );
+        assert_eq!(
+            parse(&BARCODE[1..], today),
+            Err(BarcodeError::InvalidLength(43))
+        );
+        assert_eq!(parse("00190-50095", today), Err(BarcodeError::NotDigits));
+        assert_eq!(
+            parse(&format!("8{}", &BARCODE[1..]), today),
+            Err(BarcodeError::UtilityBill)
+        );
+    }
+}
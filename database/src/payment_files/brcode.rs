@@ -0,0 +1,355 @@
+//! BR Code: the EMV merchant-presented QR payload of instant payments. A payload is a run of
+//! fields, each an id of two digits, a length of two digits and the value, some of which are
+//! templates of fields themselves. It ends with a CRC16 of everything before the checksum value.
+
+use crate::{
+    models::qr_code_dto::BrCodeError,
+    structs::money::{Money, MONEY_SCALE},
+};
+
+use super::to_ascii;
+
+const PAYLOAD_FORMAT: &str = "01";
+/// Point of initiation of a payload meant for a single payment.
+const DYNAMIC: &str = "12";
+const PIX_GUI: &str = "br.gov.bcb.pix";
+const NO_CATEGORY: &str = "0000";
+const BRL: &str = "986";
+const COUNTRY: &str = "BR";
+/// Transaction id of a payload without one.
+const NO_TRANSACTION_ID: &str = "***";
+const CHECKSUM_PREFIX: &str = "6304";
+const MAX_FIELD_LENGTH: usize = 99;
+const MAX_AMOUNT_LENGTH: usize = 13;
+const MAX_NAME_LENGTH: usize = 25;
+const MAX_CITY_LENGTH: usize = 15;
+const MAX_TRANSACTION_ID_LENGTH: usize = 25;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrCode {
+    /// The payment key of the account being paid.
+    pub key: String,
+    pub description: Option<String>,
+    pub merchant_name: String,
+    pub merchant_city: String,
+    /// Left out, the payer chooses the amount.
+    pub amount: Option<Money>,
+    pub transaction_id: Option<String>,
+    /// Meant for a single payment, rather than printed and reused.
+    pub dynamic: bool,
+}
+
+/// CRC-16/CCITT-FALSE, as the EMV specification asks for.
+fn crc16(data: &str) -> u16 {
+    data.bytes().fold(0xFFFF, |crc, byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x1021,
+        })
+    })
+}
+
+fn field(id: &str, value: &str, name: &'static str) -> Result<String, BrCodeError> {
+    if value.len() > MAX_FIELD_LENGTH {
+        return Err(BrCodeError::TooLong(name));
+    }
+
+    Ok(format!("{}{:02}{}", id, value.len(), value))
+}
+
+fn truncated(text: &str, length: usize) -> String {
+    to_ascii(text.trim()).chars().take(length).collect()
+}
+
+/// Renders a payload, folding names into ASCII and cutting them to the lengths the
+/// specification allows.
+pub fn encode(code: &BrCode) -> Result<String, BrCodeError> {
+    if let Some(transaction_id) = &code.transaction_id {
+        if transaction_id.is_empty()
+            || transaction_id.len() > MAX_TRANSACTION_ID_LENGTH
+            || !transaction_id.bytes().all(|b| b.is_ascii_alphanumeric())
+        {
+            return Err(BrCodeError::InvalidTransactionId(transaction_id.clone()));
+        }
+    }
+
+    let mut account = field("00", PIX_GUI, "key")? + &field("01", &code.key, "key")?;
+    if let Some(description) = &code.description {
+        account += &field("02", &to_ascii(description.trim()), "description")?;
+    }
+
+    let mut payload = field("00", PAYLOAD_FORMAT, "payload format")?;
+    if code.dynamic {
+        payload += &field("01", DYNAMIC, "point of initiation")?;
+    }
+    payload += &field("26", &account, "key and description")?;
+    payload += &field("52", NO_CATEGORY, "merchant category")?;
+    payload += &field("53", BRL, "currency")?;
+    if let Some(amount) = code.amount {
+        let text = amount.to_string();
+        if !amount.is_positive() {
+            return Err(BrCodeError::InvalidAmount(text));
+        }
+        if text.len() > MAX_AMOUNT_LENGTH {
+            return Err(BrCodeError::TooLong("amount"));
+        }
+        payload += &field("54", &text, "amount")?;
+    }
+    payload += &field("58", COUNTRY, "country")?;
+    payload += &field(
+        "59",
+        &truncated(&code.merchant_name, MAX_NAME_LENGTH),
+        "merchant name",
+    )?;
+    payload += &field(
+        "60",
+        &truncated(&code.merchant_city, MAX_CITY_LENGTH),
+        "merchant city",
+    )?;
+    let transaction_id = code.transaction_id.as_deref().unwrap_or(NO_TRANSACTION_ID);
+    payload += &field(
+        "62",
+        &field("05", transaction_id, "transaction id")?,
+        "additional data",
+    )?;
+
+    payload += CHECKSUM_PREFIX;
+    let checksum = crc16(&payload);
+    Ok(format!("{}{:04X}", payload, checksum))
+}
+
+struct Field<'a> {
+    id: &'a str,
+    value: &'a str,
+    /// Where the value starts in the payload.
+    offset: usize,
+}
+
+/// Splits `data`, which starts at `offset` in the payload, into its fields.
+fn fields(data: &str, offset: usize) -> Result<Vec<Field<'_>>, BrCodeError> {
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while position < data.len() {
+        let malformed = BrCodeError::Malformed(offset + position);
+        let header = data.get(position..position + 4).ok_or(malformed.clone())?;
+        if !header.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(malformed);
+        }
+        let length: usize = header[2..].parse().map_err(|_| malformed.clone())?;
+        let value = data
+            .get(position + 4..position + 4 + length)
+            .ok_or(malformed)?;
+        fields.push(Field {
+            id: &header[..2],
+            value,
+            offset: offset + position + 4,
+        });
+        position += 4 + length;
+    }
+
+    Ok(fields)
+}
+
+fn find<'a>(fields: &'a [Field], id: &str) -> Option<&'a Field<'a>> {
+    fields.iter().find(|field| field.id == id)
+}
+
+fn parse_amount(value: &str) -> Result<Money, BrCodeError> {
+    let invalid = || BrCodeError::InvalidAmount(value.to_string());
+    let decimals = value
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len());
+    if decimals > MONEY_SCALE as usize || !value.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return Err(invalid());
+    }
+
+    let amount: Money = value.parse().map_err(|_| invalid())?;
+    if !amount.is_positive() {
+        return Err(invalid());
+    }
+
+    Ok(amount)
+}
+
+/// Reads a payload, checking its checksum before anything else.
+pub fn decode(payload: &str) -> Result<BrCode, BrCodeError> {
+    let payload = payload.trim();
+    if let Some((offset, _)) = payload.char_indices().find(|(_, c)| !c.is_ascii()) {
+        return Err(BrCodeError::Malformed(offset));
+    }
+
+    let fields = fields(payload, 0)?;
+    let declared = match fields.last() {
+        Some(field) if field.id == "63" && field.value.len() == 4 => field.value,
+        _ => return Err(BrCodeError::MissingField("checksum")),
+    };
+    let computed = format!("{:04X}", crc16(&payload[..payload.len() - 4]));
+    if !declared.eq_ignore_ascii_case(&computed) {
+        return Err(BrCodeError::Checksum {
+            declared: declared.to_string(),
+            computed,
+        });
+    }
+
+    match fields.first() {
+        Some(field) if field.id == "00" && field.value == PAYLOAD_FORMAT => {}
+        _ => return Err(BrCodeError::MissingField("payload format indicator")),
+    }
+
+    // the merchant account may be in any of the templates from 26 to 51
+    let mut account = None;
+    for field in fields
+        .iter()
+        .filter(|field| ("26"..="51").contains(&field.id))
+    {
+        let template = self::fields(field.value, field.offset)?;
+        if find(&template, "00").is_some_and(|gui| gui.value.eq_ignore_ascii_case(PIX_GUI)) {
+            account = Some(template);
+            break;
+        }
+    }
+    let account = account.ok_or(BrCodeError::NotPix)?;
+    let key = match (find(&account, "01"), find(&account, "25")) {
+        (Some(key), _) => key.value.to_string(),
+        (None, Some(_)) => return Err(BrCodeError::UrlPayload),
+        (None, None) => return Err(BrCodeError::MissingField("key")),
+    };
+
+    match find(&fields, "53") {
+        Some(currency) if currency.value == BRL => {}
+        Some(currency) => return Err(BrCodeError::UnsupportedCurrency(currency.value.to_string())),
+        None => return Err(BrCodeError::MissingField("currency")),
+    }
+
+    let transaction_id = match find(&fields, "62") {
+        Some(additional) => self::fields(additional.value, additional.offset)?
+            .iter()
+            .find(|field| field.id == "05")
+            .map(|field| field.value.to_string())
+            .filter(|transaction_id| transaction_id != NO_TRANSACTION_ID),
+        None => None,
+    };
+
+    Ok(BrCode {
+        key,
+        description: find(&account, "02").map(|field| field.value.to_string()),
+        merchant_name: find(&fields, "59")
+            .ok_or(BrCodeError::MissingField("merchant name"))?
+            .value
+            .to_string(),
+        merchant_city: find(&fields, "60")
+            .ok_or(BrCodeError::MissingField("merchant city"))?
+            .value
+            .to_string(),
+        amount: find(&fields, "54")
+            .map(|field| parse_amount(field.value))
+            .transpose()?,
+        transaction_id,
+        dynamic: find(&fields, "01").is_some_and(|field| field.value == DYNAMIC),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The static payload of the example in the Central Bank's BR Code manual.
+    const MANUAL_EXAMPLE: &str = concat!(
+        "00020126580014br.gov.bcb.pix0136123e4567-e12b-12d1-a456-426655440000",
+        "5204000053039865802BR5913Fulano de Tal6008BRASILIA62070503***63041D3D"
+    );
+
+    /// Recomputes the checksum of a payload that was edited.
+    fn signed(payload: &str) -> String {
+        let payload = &payload[..payload.len() - 4];
+        format!("{}{:04X}", payload, crc16(payload))
+    }
+
+    #[test]
+    fn test_manual_example_round_trips() {
+        assert_eq!(crc16("123456789"), 0x29B1);
+
+        let code = decode(MANUAL_EXAMPLE).unwrap();
+        assert_eq!(
+            code,
+            BrCode {
+                key: "123e4567-e12b-12d1-a456-426655440000".to_string(),
+                description: None,
+                merchant_name: "Fulano de Tal".to_string(),
+                merchant_city: "BRASILIA".to_string(),
+                amount: None,
+                transaction_id: None,
+                dynamic: false,
+            }
+        );
+        assert_eq!(encode(&code).unwrap(), MANUAL_EXAMPLE);
+    }
+
+    #[test]
+    fn test_dynamic_payload_round_trips() {
+        let code = BrCode {
+            key: "+5511987654321".to_string(),
+            description: Some("Pedido 42".to_string()),
+            merchant_name: "Padaria Sao Joao e Filhos Ltda".to_string(),
+            merchant_city: "SAO JOSE DOS CAMPOS".to_string(),
+            amount: Some(Money::from_minor_units(1_050)),
+            transaction_id: Some("PEDIDO42".to_string()),
+            dynamic: true,
+        };
+        let payload = encode(&code).unwrap();
+
+        assert!(payload.starts_with("000201010212"));
+        assert!(payload.contains("540510.50"));
+        assert_eq!(
+            decode(&payload).unwrap(),
+            BrCode {
+                merchant_name: "Padaria Sao Joao e Filhos".to_string(),
+                merchant_city: "SAO JOSE DOS CA".to_string(),
+                ..code
+            }
+        );
+        assert_eq!(
+            encode(&BrCode {
+                merchant_name: "Padaria São João".to_string(),
+                ..decode(&payload).unwrap()
+            })
+            .unwrap(),
+            encode(&BrCode {
+                merchant_name: "Padaria Sao Joao".to_string(),
+                ..decode(&payload).unwrap()
+            })
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_payloads_are_checked() {
+        assert!(matches!(
+            decode(&MANUAL_EXAMPLE.replace("BRASILIA", "BRASILIO")),
+            Err(BrCodeError::Checksum { declared, .. }) if declared == "1D3D"
+        ));
+        assert_eq!(
+            decode(&MANUAL_EXAMPLE[..MANUAL_EXAMPLE.len() - 6]).unwrap_err(),
+            BrCodeError::Malformed(129)
+        );
+        assert_eq!(
+            decode(&signed(
+                &MANUAL_EXAMPLE.replace("br.gov.bcb.pix", "br.gov.bcb.xyz")
+            ))
+            .unwrap_err(),
+            BrCodeError::NotPix
+        );
+        assert_eq!(
+            decode(&signed(&MANUAL_EXAMPLE.replace("5303986", "5303840"))).unwrap_err(),
+            BrCodeError::UnsupportedCurrency("840".to_string())
+        );
+        assert_eq!(
+            encode(&BrCode {
+                transaction_id: Some("PEDIDO-42".to_string()),
+                ..decode(MANUAL_EXAMPLE).unwrap()
+            })
+            .unwrap_err(),
+            BrCodeError::InvalidTransactionId("PEDIDO-42".to_string())
+        );
+    }
+}
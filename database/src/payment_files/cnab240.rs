@@ -0,0 +1,701 @@
+//! FEBRABAN CNAB 240 files for paying into accounts: the remittance files sent to the bank
+//! and the return files it answers with. Every record is a fixed-width line of 240 characters;
+//! the positions in the comments are 1-based and inclusive, as in the layout manual.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::{
+    models::{
+        account_dto::Account,
+        remittance_dto::{CnabError, RemittanceError},
+    },
+    structs::money::Money,
+};
+
+use super::to_ascii;
+
+pub const LINE_LENGTH: usize = 240;
+const FILE_LAYOUT_VERSION: &str = "089";
+const BATCH_LAYOUT_VERSION: &str = "045";
+const RECORDING_DENSITY: &str = "01600";
+/// Supplier payments.
+const SERVICE_TYPE: &str = "20";
+/// Credit into a current account at the paying bank.
+const SAME_BANK_CREDIT: &str = "01";
+/// TED to an account at another bank.
+const OTHER_BANK_TED: &str = "41";
+const DATE_FORMAT: &str = "%d%m%Y";
+const TIME_FORMAT: &str = "%H%M%S";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CnabFileKind {
+    Remittance,
+    Return,
+}
+
+impl CnabFileKind {
+    fn code(&self) -> u64 {
+        match self {
+            CnabFileKind::Remittance => 1,
+            CnabFileKind::Return => 2,
+        }
+    }
+}
+
+/// An account as the bank fields of a record hold it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CnabAccount {
+    pub bank_id: i32,
+    pub agency_number: i32,
+    pub agency_digit: Option<i32>,
+    pub account_number: i64,
+    pub account_digit: Option<i32>,
+}
+
+impl CnabAccount {
+    pub fn from_account(account: &Account) -> Result<Self, RemittanceError> {
+        match (
+            account.bank_id,
+            account.bank_agency_number,
+            account.bank_account_number,
+        ) {
+            (Some(bank_id), Some(agency_number), Some(account_number)) => Ok(Self {
+                bank_id,
+                agency_number,
+                agency_digit: account.bank_agency_digit,
+                account_number,
+                account_digit: account.bank_account_digit,
+            }),
+            _ => Err(RemittanceError::MissingBankDetails(account.id)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CnabHeader {
+    pub kind: CnabFileKind,
+    /// The account the payments are made from; its bank is the one the file goes to.
+    pub company: CnabAccount,
+    pub company_name: String,
+    pub file_sequence: i32,
+    pub generated_at: NaiveDateTime,
+}
+
+/// A segment A record: a credit into the beneficiary's account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CnabPayment {
+    pub your_number: String,
+    pub beneficiary: CnabAccount,
+    pub beneficiary_name: String,
+    pub payment_date: NaiveDate,
+    pub amount: Money,
+    /// The bank's own number for the payment, in return files.
+    pub bank_reference: Option<String>,
+    pub effective_date: Option<NaiveDate>,
+    pub effective_amount: Option<Money>,
+    /// Two-character occurrence codes, in return files.
+    pub occurrences: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CnabFile {
+    pub header: CnabHeader,
+    pub payments: Vec<CnabPayment>,
+}
+
+struct Record(String);
+
+impl Record {
+    fn new() -> Self {
+        Self(String::with_capacity(LINE_LENGTH))
+    }
+
+    fn numeric(mut self, field: &'static str, value: i64, width: usize) -> Result<Self, CnabError> {
+        let text = format!("{:0width$}", value, width = width);
+        if value < 0 || text.len() > width {
+            return Err(CnabError::Overflow {
+                field,
+                value: value.to_string(),
+            });
+        }
+        self.0.push_str(&text);
+
+        Ok(self)
+    }
+
+    fn text(mut self, value: &str, width: usize) -> Self {
+        // text fields take upper case ASCII only
+        let value: String = to_ascii(value)
+            .to_ascii_uppercase()
+            .chars()
+            .take(width)
+            .collect();
+        self.0
+            .push_str(&format!("{:<width$}", value, width = width));
+        self
+    }
+
+    fn blank(self, width: usize) -> Self {
+        self.text("", width)
+    }
+
+    fn digit(self, field: &'static str, digit: Option<i32>) -> Result<Self, CnabError> {
+        match digit {
+            Some(digit) => self.numeric(field, digit as i64, 1),
+            None => Ok(self.blank(1)),
+        }
+    }
+
+    fn date(self, field: &'static str, date: Option<NaiveDate>) -> Result<Self, CnabError> {
+        match date {
+            Some(date) => Ok(self.text(&date.format(DATE_FORMAT).to_string(), 8)),
+            None => self.numeric(field, 0, 8),
+        }
+    }
+
+    fn money(self, field: &'static str, amount: Money, width: usize) -> Result<Self, CnabError> {
+        self.numeric(field, amount.minor_units(), width)
+    }
+
+    /// Agency and account with their check digits, at the same spot in every record.
+    fn account(self, account: &CnabAccount) -> Result<Self, CnabError> {
+        self.numeric("agency", account.agency_number as i64, 5)?
+            .digit("agency digit", account.agency_digit)?
+            .numeric("account", account.account_number, 12)?
+            .digit("account digit", account.account_digit)
+            .map(|record| record.blank(1))
+    }
+
+    fn finish(self) -> String {
+        debug_assert_eq!(self.0.len(), LINE_LENGTH);
+        self.0
+    }
+}
+
+fn payment_method(company: &CnabAccount, beneficiary: &CnabAccount) -> &'static str {
+    match company.bank_id == beneficiary.bank_id {
+        true => SAME_BANK_CREDIT,
+        false => OTHER_BANK_TED,
+    }
+}
+
+/// Renders a file, one batch per payment method in the order the methods first appear.
+pub fn write(file: &CnabFile) -> Result<String, CnabError> {
+    let header = &file.header;
+    let bank_id = header.company.bank_id as i64;
+    let mut lines = Vec::new();
+
+    lines.push(
+        Record::new()
+            .numeric("bank", bank_id, 3)? // 001-003
+            .numeric("batch", 0, 4)? // 004-007
+            .numeric("record type", 0, 1)? // 008
+            .blank(9) // 009-017
+            .numeric("company document type", 0, 1)? // 018
+            .numeric("company document", 0, 14)? // 019-032
+            .blank(20) // 033-052 agreement
+            .account(&header.company)? // 053-072
+            .text(&header.company_name, 30) // 073-102
+            .blank(30) // 103-132 bank name
+            .blank(10) // 133-142
+            .numeric("file kind", header.kind.code() as i64, 1)? // 143
+            .text(&header.generated_at.format(DATE_FORMAT).to_string(), 8) // 144-151
+            .text(&header.generated_at.format(TIME_FORMAT).to_string(), 6) // 152-157
+            .numeric("file sequence", header.file_sequence as i64, 6)? // 158-163
+            .text(FILE_LAYOUT_VERSION, 3) // 164-166
+            .text(RECORDING_DENSITY, 5) // 167-171
+            .blank(69) // 172-240
+            .finish(),
+    );
+
+    let mut methods: Vec<&str> = Vec::new();
+    for payment in &file.payments {
+        let method = payment_method(&header.company, &payment.beneficiary);
+        if !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+
+    for (batch, method) in methods.iter().enumerate() {
+        let batch = batch as i64 + 1;
+        let payments: Vec<&CnabPayment> = file
+            .payments
+            .iter()
+            .filter(|payment| payment_method(&header.company, &payment.beneficiary) == *method)
+            .collect();
+
+        lines.push(
+            Record::new()
+                .numeric("bank", bank_id, 3)? // 001-003
+                .numeric("batch", batch, 4)? // 004-007
+                .numeric("record type", 1, 1)? // 008
+                .text("C", 1) // 009 operation
+                .text(SERVICE_TYPE, 2) // 010-011
+                .text(method, 2) // 012-013
+                .text(BATCH_LAYOUT_VERSION, 3) // 014-016
+                .blank(1) // 017
+                .numeric("company document type", 0, 1)? // 018
+                .numeric("company document", 0, 14)? // 019-032
+                .blank(20) // 033-052 agreement
+                .account(&header.company)? // 053-072
+                .text(&header.company_name, 30) // 073-102
+                .blank(138) // 103-240 message, address and occurrences
+                .finish(),
+        );
+
+        let mut total = Money::ZERO;
+        for (index, payment) in payments.iter().enumerate() {
+            let clearing = match *method {
+                SAME_BANK_CREDIT => "000",
+                _ => "018",
+            };
+            total = total
+                .checked_add(payment.amount)
+                .ok_or(CnabError::Overflow {
+                    field: "batch total",
+                    value: payment.amount.to_string(),
+                })?;
+
+            lines.push(
+                Record::new()
+                    .numeric("bank", bank_id, 3)? // 001-003
+                    .numeric("batch", batch, 4)? // 004-007
+                    .numeric("record type", 3, 1)? // 008
+                    .numeric("record number", index as i64 + 1, 5)? // 009-013
+                    .text("A", 1) // 014 segment
+                    .numeric("movement type", 0, 1)? // 015
+                    .numeric("movement instruction", 0, 2)? // 016-017
+                    .text(clearing, 3) // 018-020
+                    .numeric("beneficiary bank", payment.beneficiary.bank_id as i64, 3)? // 021-023
+                    .account(&payment.beneficiary)? // 024-043
+                    .text(&payment.beneficiary_name, 30) // 044-073
+                    .text(&payment.your_number, 20) // 074-093
+                    .date("payment date", Some(payment.payment_date))? // 094-101
+                    .text("BRL", 3) // 102-104
+                    .numeric("currency quantity", 0, 15)? // 105-119
+                    .money("amount", payment.amount, 15)? // 120-134
+                    .text(payment.bank_reference.as_deref().unwrap_or_default(), 20) // 135-154
+                    .date("effective date", payment.effective_date)? // 155-162
+                    .money(
+                        "effective amount",
+                        payment.effective_amount.unwrap_or(Money::ZERO),
+                        15,
+                    )? // 163-177
+                    .blank(40) // 178-217 other information
+                    .blank(12) // 218-229 purpose codes
+                    .numeric("beneficiary notice", 0, 1)? // 230
+                    .text(&payment.occurrences.concat(), 10) // 231-240
+                    .finish(),
+            );
+        }
+
+        lines.push(
+            Record::new()
+                .numeric("bank", bank_id, 3)? // 001-003
+                .numeric("batch", batch, 4)? // 004-007
+                .numeric("record type", 5, 1)? // 008
+                .blank(9) // 009-017
+                .numeric("batch records", payments.len() as i64 + 2, 6)? // 018-023
+                .money("batch total", total, 18)? // 024-041
+                .numeric("batch currency quantity", 0, 18)? // 042-059
+                .numeric("debit notice", 0, 6)? // 060-065
+                .blank(175) // 066-240
+                .finish(),
+        );
+    }
+
+    let records = lines.len() as i64 + 1;
+    lines.push(
+        Record::new()
+            .numeric("bank", bank_id, 3)? // 001-003
+            .numeric("batch", 9999, 4)? // 004-007
+            .numeric("record type", 9, 1)? // 008
+            .blank(9) // 009-017
+            .numeric("batches", methods.len() as i64, 6)? // 018-023
+            .numeric("records", records, 6)? // 024-029
+            .numeric("reconciliation accounts", 0, 6)? // 030-035
+            .blank(205) // 036-240
+            .finish(),
+    );
+
+    let mut text = lines.join("\r\n");
+    text.push_str("\r\n");
+
+    Ok(text)
+}
+
+struct Line<'a> {
+    number: usize,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    fn new(number: usize, text: &'a str) -> Result<Self, CnabError> {
+        if !text.is_ascii() {
+            return Err(CnabError::InvalidField {
+                line: number,
+                field: "characters",
+                value: text.to_string(),
+            });
+        }
+        if text.len() != LINE_LENGTH {
+            return Err(CnabError::LineLength {
+                line: number,
+                length: text.len(),
+            });
+        }
+
+        Ok(Self { number, text })
+    }
+
+    fn raw(&self, start: usize, end: usize) -> &'a str {
+        &self.text[start - 1..end]
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        self.raw(start, end).trim().to_string()
+    }
+
+    fn invalid(&self, field: &'static str, value: &str) -> CnabError {
+        CnabError::InvalidField {
+            line: self.number,
+            field,
+            value: value.to_string(),
+        }
+    }
+
+    fn numeric(&self, field: &'static str, start: usize, end: usize) -> Result<i64, CnabError> {
+        let value = self.raw(start, end);
+        if !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(self.invalid(field, value));
+        }
+
+        value.parse().map_err(|_| self.invalid(field, value))
+    }
+
+    fn digit(&self, field: &'static str, position: usize) -> Result<Option<i32>, CnabError> {
+        match self.raw(position, position) {
+            " " => Ok(None),
+            _ => Ok(Some(self.numeric(field, position, position)? as i32)),
+        }
+    }
+
+    fn date(&self, field: &'static str, start: usize) -> Result<Option<NaiveDate>, CnabError> {
+        let value = self.raw(start, start + 7);
+        if value == "00000000" || value.trim().is_empty() {
+            return Ok(None);
+        }
+
+        NaiveDate::parse_from_str(value, DATE_FORMAT)
+            .map(Some)
+            .map_err(|_| self.invalid(field, value))
+    }
+
+    fn money(&self, field: &'static str, start: usize, end: usize) -> Result<Money, CnabError> {
+        Ok(Money::from_minor_units(self.numeric(field, start, end)?))
+    }
+
+    fn record_type(&self) -> &'a str {
+        self.raw(8, 8)
+    }
+
+    fn expect(&self, record_type: &str, expected: &'static str) -> Result<(), CnabError> {
+        match self.record_type() == record_type {
+            true => Ok(()),
+            false => Err(CnabError::UnexpectedRecord {
+                line: self.number,
+                expected,
+            }),
+        }
+    }
+
+    /// The agency and account at 053-072 of headers and 024-043 of segment A.
+    fn account(&self, bank_id: i32, start: usize) -> Result<CnabAccount, CnabError> {
+        Ok(CnabAccount {
+            bank_id,
+            agency_number: self.numeric("agency", start, start + 4)? as i32,
+            agency_digit: self.digit("agency digit", start + 5)?,
+            account_number: self.numeric("account", start + 6, start + 17)?,
+            account_digit: self.digit("account digit", start + 18)?,
+        })
+    }
+}
+
+fn mismatch(
+    line: &Line,
+    total: &'static str,
+    declared: impl ToString,
+    found: impl ToString,
+) -> CnabError {
+    CnabError::TotalMismatch {
+        line: line.number,
+        total,
+        declared: declared.to_string(),
+        found: found.to_string(),
+    }
+}
+
+/// Reads a remittance or return file, checking the layout of every line and the totals of the
+/// trailers. Segments other than A are checked for their place in the batch and skipped.
+pub fn parse(text: &str) -> Result<CnabFile, CnabError> {
+    let lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| Line::new(index + 1, line))
+        .collect::<Result<Vec<Line>, CnabError>>()?;
+    let mut lines = lines.iter().peekable();
+
+    let header_line = lines.next().ok_or(CnabError::Truncated)?;
+    header_line.expect("0", "a file header")?;
+    let bank = header_line.raw(1, 3);
+    let bank_id = header_line.numeric("bank", 1, 3)? as i32;
+    let kind = match header_line.raw(143, 143) {
+        "1" => CnabFileKind::Remittance,
+        "2" => CnabFileKind::Return,
+        value => return Err(header_line.invalid("file kind", value)),
+    };
+    let generated_at = NaiveDate::parse_from_str(header_line.raw(144, 151), DATE_FORMAT)
+        .ok()
+        .and_then(|date| {
+            NaiveTime::parse_from_str(header_line.raw(152, 157), TIME_FORMAT)
+                .ok()
+                .map(|time| date.and_time(time))
+        })
+        .ok_or_else(|| header_line.invalid("generation date", header_line.raw(144, 157)))?;
+    let header = CnabHeader {
+        kind,
+        company: header_line.account(bank_id, 53)?,
+        company_name: header_line.text(73, 102),
+        file_sequence: header_line.numeric("file sequence", 158, 163)? as i32,
+        generated_at,
+    };
+
+    let mut payments = Vec::new();
+    let mut batches = 0;
+    let mut records = 1;
+    loop {
+        let line = lines.next().ok_or(CnabError::Truncated)?;
+        if line.raw(1, 3) != bank {
+            return Err(line.invalid("bank", line.raw(1, 3)));
+        }
+        records += 1;
+        if line.record_type() == "9" {
+            if batches == 0 {
+                return Err(CnabError::Truncated);
+            }
+            let declared = line.numeric("batches", 18, 23)?;
+            if declared != batches {
+                return Err(mismatch(line, "batches", declared, batches));
+            }
+            let declared = line.numeric("records", 24, 29)?;
+            if declared != records {
+                return Err(mismatch(line, "records", declared, records));
+            }
+            break;
+        }
+
+        line.expect("1", "a batch header or the file trailer")?;
+        batches += 1;
+        let batch = line.numeric("batch", 4, 7)?;
+        if batch != batches {
+            return Err(line.invalid("batch", line.raw(4, 7)));
+        }
+
+        let mut batch_records = 1;
+        let mut total = Money::ZERO;
+        loop {
+            let line = lines.next().ok_or(CnabError::Truncated)?;
+            records += 1;
+            batch_records += 1;
+            if line.numeric("batch", 4, 7)? != batch {
+                return Err(line.invalid("batch", line.raw(4, 7)));
+            }
+            if line.record_type() == "5" {
+                let declared = line.numeric("batch records", 18, 23)?;
+                if declared != batch_records {
+                    return Err(mismatch(line, "batch records", declared, batch_records));
+                }
+                let declared = line.money("batch total", 24, 41)?;
+                if declared != total {
+                    return Err(mismatch(line, "batch total", declared, total));
+                }
+                break;
+            }
+
+            line.expect("3", "a detail record or the batch trailer")?;
+            if line.numeric("record number", 9, 13)? != batch_records - 1 {
+                return Err(line.invalid("record number", line.raw(9, 13)));
+            }
+            if line.raw(14, 14) != "A" {
+                continue;
+            }
+
+            let amount = line.money("amount", 120, 134)?;
+            total = total
+                .checked_add(amount)
+                .ok_or_else(|| line.invalid("amount", line.raw(120, 134)))?;
+            let bank_reference = line.text(135, 154);
+            let effective_amount = line.money("effective amount", 163, 177)?;
+            let occurrences = line.raw(231, 240);
+            payments.push(CnabPayment {
+                your_number: line.text(74, 93),
+                beneficiary: line.account(line.numeric("beneficiary bank", 21, 23)? as i32, 24)?,
+                beneficiary_name: line.text(44, 73),
+                payment_date: line
+                    .date("payment date", 94)?
+                    .ok_or_else(|| line.invalid("payment date", line.raw(94, 101)))?,
+                amount,
+                bank_reference: Some(bank_reference).filter(|reference| !reference.is_empty()),
+                effective_date: line.date("effective date", 155)?,
+                effective_amount: Some(effective_amount).filter(|amount| !amount.is_zero()),
+                occurrences: (0..occurrences.len())
+                    .step_by(2)
+                    .map(|start| occurrences[start..start + 2].trim().to_string())
+                    .filter(|code| !code.is_empty())
+                    .collect(),
+            });
+        }
+    }
+
+    if let Some(line) = lines.next() {
+        return Err(CnabError::UnexpectedRecord {
+            line: line.number,
+            expected: "the end of the file",
+        });
+    }
+
+    Ok(CnabFile { header, payments })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(bank_id: i32, account_number: i64) -> CnabAccount {
+        CnabAccount {
+            bank_id,
+            agency_number: 1234,
+            agency_digit: Some(5),
+            account_number,
+            account_digit: None,
+        }
+    }
+
+    fn remittance() -> CnabFile {
+        let payment = |your_number: &str, beneficiary, amount| CnabPayment {
+            your_number: your_number.to_string(),
+            beneficiary,
+            beneficiary_name: "JOAO DA SILVA".to_string(),
+            payment_date: NaiveDate::from_ymd_opt(2025, 3, 10).unwrap(),
+            amount: Money::from_minor_units(amount),
+            bank_reference: None,
+            effective_date: None,
+            effective_amount: None,
+            occurrences: Vec::new(),
+        };
+
+        CnabFile {
+            header: CnabHeader {
+                kind: CnabFileKind::Remittance,
+                company: account(1, 98765),
+                company_name: "ACME LTDA".to_string(),
+                file_sequence: 7,
+                generated_at: NaiveDate::from_ymd_opt(2025, 3, 9)
+                    .unwrap()
+                    .and_hms_opt(18, 30, 5)
+                    .unwrap(),
+            },
+            // grouped by payment method, the way batches read back
+            payments: vec![
+                payment("00000700001", account(1, 111), 10_050),
+                payment("00000700003", account(1, 333), 1),
+                payment("00000700002", account(237, 222), 99_999_999),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_remittance_round_trips() {
+        let file = remittance();
+        let text = write(&file).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines.iter().all(|line| line.len() == LINE_LENGTH));
+        // file header, two batches of header, details and trailer, file trailer
+        assert_eq!(lines.len(), 1 + (2 + 2) + (1 + 2) + 1);
+        assert_eq!(&lines[0][142..143], "1");
+        assert_eq!(&lines[1][11..13], SAME_BANK_CREDIT);
+        assert_eq!(&lines[5][11..13], OTHER_BANK_TED);
+        assert_eq!(&lines[4][23..41], "000000000000010051");
+        assert_eq!(&lines[8][17..29], "000002000009");
+        assert_eq!(parse(&text).unwrap(), file);
+    }
+
+    #[test]
+    fn test_return_reports_occurrences() {
+        let mut file = remittance();
+        file.header.kind = CnabFileKind::Return;
+        file.payments[0].bank_reference = Some("NN123".to_string());
+        file.payments[0].effective_date = Some(file.payments[0].payment_date);
+        file.payments[0].effective_amount = Some(file.payments[0].amount);
+        file.payments[0].occurrences = vec!["00".to_string()];
+        file.payments[2].occurrences = vec!["AG".to_string(), "AM".to_string()];
+
+        assert_eq!(parse(&write(&file).unwrap()).unwrap(), file);
+    }
+
+    #[test]
+    fn test_layout_is_validated() {
+        let text = write(&remittance()).unwrap();
+        let lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let with_line = |index: usize, line: String| {
+            let mut lines = lines.clone();
+            lines[index] = line;
+            lines.join("\r\n")
+        };
+
+        assert_eq!(
+            parse(&with_line(2, lines[2][..239].to_string())).unwrap_err(),
+            CnabError::LineLength {
+                line: 3,
+                length: 239
+            }
+        );
+        let mut amount = lines[2].clone();
+        amount.replace_range(119..134, "0000000000001X0");
+        assert_eq!(
+            parse(&with_line(2, amount)).unwrap_err(),
+            CnabError::InvalidField {
+                line: 3,
+                field: "amount",
+                value: "0000000000001X0".to_string()
+            }
+        );
+        let mut total = lines[4].clone();
+        total.replace_range(23..41, "000000000000010050");
+        assert_eq!(
+            parse(&with_line(4, total)).unwrap_err(),
+            CnabError::TotalMismatch {
+                line: 5,
+                total: "batch total",
+                declared: "100.50".to_string(),
+                found: "100.51".to_string()
+            }
+        );
+        assert_eq!(
+            parse(&lines[..lines.len() - 1].join("\r\n")).unwrap_err(),
+            CnabError::Truncated
+        );
+        assert!(matches!(
+            write(&CnabFile {
+                header: CnabHeader {
+                    file_sequence: 1_000_000,
+                    ..remittance().header
+                },
+                payments: Vec::new(),
+            }),
+            Err(CnabError::Overflow { .. })
+        ));
+    }
+}
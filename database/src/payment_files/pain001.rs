@@ -0,0 +1,329 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        payment_file_dto::{
+            PaymentEntryError, PaymentFile, PaymentFileEntry, PaymentFileError,
+            MAX_MESSAGE_ID_LENGTH,
+        },
+        transaction_dto::{TransactionCreate, TransactionOperation, MAX_BATCH_SIZE},
+    },
+    structs::money::{Money, MONEY_SCALE},
+};
+
+/// Any version of the customer credit transfer initiation message.
+const NAMESPACE_PREFIX: &str = "urn:iso:std:iso:20022:tech:xsd:pain.001.001.";
+const CURRENCY: &str = "BRL";
+const TRANSFER_METHOD: &str = "TRF";
+
+#[derive(Debug, Deserialize)]
+struct Document {
+    #[serde(rename = "CstmrCdtTrfInitn")]
+    initiation: Initiation,
+}
+
+#[derive(Debug, Deserialize)]
+struct Initiation {
+    #[serde(rename = "GrpHdr")]
+    group_header: GroupHeader,
+    #[serde(rename = "PmtInf", default)]
+    payment_informations: Vec<PaymentInformation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroupHeader {
+    #[serde(rename = "MsgId")]
+    message_id: String,
+    #[serde(rename = "NbOfTxs")]
+    number_of_transactions: String,
+    #[serde(rename = "CtrlSum")]
+    control_sum: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentInformation {
+    #[serde(rename = "PmtInfId")]
+    id: String,
+    #[serde(rename = "PmtMtd")]
+    method: String,
+    #[serde(rename = "DbtrAcct")]
+    debtor_account: CashAccount,
+    #[serde(rename = "CdtTrfTxInf", default)]
+    transfers: Vec<CreditTransfer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreditTransfer {
+    #[serde(rename = "PmtId")]
+    payment_id: PaymentId,
+    #[serde(rename = "Amt")]
+    amount: AmountChoice,
+    #[serde(rename = "CdtrAcct")]
+    creditor_account: CashAccount,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentId {
+    #[serde(rename = "EndToEndId")]
+    end_to_end_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AmountChoice {
+    #[serde(rename = "InstdAmt")]
+    instructed: InstructedAmount,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstructedAmount {
+    #[serde(rename = "Ccy")]
+    currency: String,
+    #[serde(rename = "$value")]
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CashAccount {
+    #[serde(rename = "Id")]
+    id: AccountIdentification,
+}
+
+/// Accounts are identified by their id under `Othr`; there are no IBANs here.
+#[derive(Debug, Deserialize)]
+struct AccountIdentification {
+    #[serde(rename = "IBAN")]
+    iban: Option<String>,
+    #[serde(rename = "Othr")]
+    other: Option<GenericIdentification>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenericIdentification {
+    #[serde(rename = "Id")]
+    id: String,
+}
+
+impl CashAccount {
+    fn account_id(&self) -> Result<Uuid, PaymentEntryError> {
+        match (&self.id.other, &self.id.iban) {
+            (Some(other), _) => Uuid::parse_str(other.id.trim())
+                .map_err(|_| PaymentEntryError::InvalidAccount(other.id.clone())),
+            (None, Some(iban)) => Err(PaymentEntryError::InvalidAccount(iban.clone())),
+            (None, None) => Err(PaymentEntryError::InvalidAccount(String::new())),
+        }
+    }
+}
+
+impl InstructedAmount {
+    fn money(&self) -> Result<Money, PaymentEntryError> {
+        let invalid = || PaymentEntryError::InvalidAmount(self.value.clone());
+        let value = self.value.trim();
+        // the schema allows more decimals than the currency has; those are not rounded away
+        let decimals = value
+            .split_once('.')
+            .map_or(0, |(_, fraction)| fraction.len());
+        if decimals > MONEY_SCALE as usize || value.starts_with(['-', '+']) {
+            return Err(invalid());
+        }
+
+        let amount: Money = value.parse().map_err(|_| invalid())?;
+        if !amount.is_positive() {
+            return Err(invalid());
+        }
+
+        Ok(amount)
+    }
+}
+
+/// Reads a pain.001 file into one transfer per credit transfer transaction. Problems with the
+/// file as a whole fail it; problems with a single payment are left on its entry.
+pub fn parse(xml: &str) -> Result<PaymentFile, PaymentFileError> {
+    if !xml.contains(NAMESPACE_PREFIX) {
+        return Err(PaymentFileError::UnsupportedDocument);
+    }
+    let document: Document =
+        serde_xml_rs::from_str(xml).map_err(|e| PaymentFileError::Malformed(e.to_string()))?;
+    let initiation = document.initiation;
+
+    let mut entries = Vec::new();
+    for payment_information in &initiation.payment_informations {
+        let from_account_id = payment_information.debtor_account.account_id();
+        for transfer in &payment_information.transfers {
+            let transaction = (|| {
+                if payment_information.method != TRANSFER_METHOD {
+                    return Err(PaymentEntryError::UnsupportedPaymentMethod(
+                        payment_information.method.clone(),
+                    ));
+                }
+                let instructed = &transfer.amount.instructed;
+                if instructed.currency != CURRENCY {
+                    return Err(PaymentEntryError::UnsupportedCurrency(
+                        instructed.currency.clone(),
+                    ));
+                }
+
+                Ok(TransactionCreate {
+                    operation: TransactionOperation::Transfer,
+                    from_account_id: Some(from_account_id.clone()?),
+                    to_account_id: transfer.creditor_account.account_id()?,
+                    to_key: None,
+                    amount: instructed.money()?,
+                    reverses_transaction_id: None,
+                })
+            })();
+
+            entries.push(PaymentFileEntry {
+                payment_information_id: payment_information.id.clone(),
+                end_to_end_id: transfer.payment_id.end_to_end_id.clone(),
+                transaction,
+            });
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(PaymentFileError::Empty);
+    }
+    if entries.len() > MAX_BATCH_SIZE {
+        return Err(PaymentFileError::TooLarge(MAX_BATCH_SIZE));
+    }
+
+    let group_header = initiation.group_header;
+    let message_id = group_header.message_id.trim();
+    if message_id.is_empty() || message_id.chars().count() > MAX_MESSAGE_ID_LENGTH {
+        return Err(PaymentFileError::InvalidMessageId);
+    }
+    if group_header.number_of_transactions.trim().parse::<usize>() != Ok(entries.len()) {
+        return Err(PaymentFileError::NumberOfTransactions {
+            declared: group_header.number_of_transactions,
+            found: entries.len(),
+        });
+    }
+    // a sum over amounts that do not parse means nothing; those entries fail on their own
+    let amounts: Option<Vec<Money>> = initiation
+        .payment_informations
+        .iter()
+        .flat_map(|payment_information| &payment_information.transfers)
+        .map(|transfer| transfer.amount.instructed.money().ok())
+        .collect();
+    if let (Some(declared), Some(amounts)) = (group_header.control_sum, amounts) {
+        let found: Money = amounts.into_iter().sum();
+        if declared.trim().parse::<Money>() != Ok(found) {
+            return Err(PaymentFileError::ControlSum { declared, found });
+        }
+    }
+
+    Ok(PaymentFile {
+        message_id: message_id.to_string(),
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(number_of_transactions: &str, control_sum: &str, transfers: &[(&str, &str)]) -> String {
+        let transfers: String = transfers
+            .iter()
+            .enumerate()
+            .map(|(index, (amount, creditor))| {
+                format!(
+                    concat!(
+                        "<CdtTrfTxInf><PmtId><EndToEndId>E2E-{}</EndToEndId></PmtId>",
+                        r#"<Amt><InstdAmt Ccy="BRL">{}</InstdAmt></Amt>"#,
+                        "<CdtrAcct><Id><Othr><Id>{}</Id></Othr></Id></CdtrAcct></CdtTrfTxInf>",
+                    ),
+                    index, amount, creditor
+                )
+            })
+            .collect();
+
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pain.001.001.03">"#,
+                "<CstmrCdtTrfInitn><GrpHdr><MsgId>MSG-1</MsgId><CreDtTm>2025-01-01T10:00:00</CreDtTm>",
+                "<NbOfTxs>{}</NbOfTxs><CtrlSum>{}</CtrlSum><InitgPty><Nm>ACME</Nm></InitgPty></GrpHdr>",
+                "<PmtInf><PmtInfId>PMT-1</PmtInfId><PmtMtd>TRF</PmtMtd>",
+                "<ReqdExctnDt>2025-01-01</ReqdExctnDt><Dbtr><Nm>ACME</Nm></Dbtr>",
+                "<DbtrAcct><Id><Othr><Id>{}</Id></Othr></Id></DbtrAcct>{}</PmtInf>",
+                "</CstmrCdtTrfInitn></Document>",
+            ),
+            number_of_transactions,
+            control_sum,
+            Uuid::nil(),
+            transfers
+        )
+    }
+
+    #[test]
+    fn test_payments_become_transfers_or_entry_errors() {
+        let creditor = Uuid::new_v4().to_string();
+        let payment_file = parse(&file(
+            "4",
+            "135.50",
+            &[
+                ("10.50", &creditor),
+                ("125.00", &creditor),
+                ("1.005", &creditor),
+                ("1.00", "ACME"),
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(payment_file.message_id, "MSG-1");
+        assert_eq!(payment_file.entries.len(), 4);
+        let first = &payment_file.entries[0];
+        assert_eq!(first.payment_information_id, "PMT-1");
+        assert_eq!(first.end_to_end_id, "E2E-0");
+        let transfer = first.transaction.as_ref().unwrap();
+        assert_eq!(transfer.from_account_id, Some(Uuid::nil()));
+        assert_eq!(transfer.to_account_id.to_string(), creditor);
+        assert_eq!(transfer.amount, Money::from_minor_units(1_050));
+        assert_eq!(
+            payment_file.entries[2].transaction.as_ref().unwrap_err(),
+            &PaymentEntryError::InvalidAmount("1.005".to_string())
+        );
+        assert_eq!(
+            payment_file.entries[3].transaction.as_ref().unwrap_err(),
+            &PaymentEntryError::InvalidAccount("ACME".to_string())
+        );
+    }
+
+    #[test]
+    fn test_group_header_must_match_the_payments() {
+        let creditor = Uuid::new_v4().to_string();
+
+        assert_eq!(
+            parse(&file("2", "10.50", &[("10.50", &creditor)])).unwrap_err(),
+            PaymentFileError::NumberOfTransactions {
+                declared: "2".to_string(),
+                found: 1
+            }
+        );
+        assert_eq!(
+            parse(&file("1", "10.00", &[("10.50", &creditor)])).unwrap_err(),
+            PaymentFileError::ControlSum {
+                declared: "10.00".to_string(),
+                found: Money::from_minor_units(1_050)
+            }
+        );
+        assert_eq!(
+            parse("<Document><BkToCstmrStmt/></Document>").unwrap_err(),
+            PaymentFileError::UnsupportedDocument
+        );
+        assert_eq!(
+            parse(
+                &file("1", "10.50", &[("10.50", &creditor)])
+                    .replace("MSG-1", &"M".repeat(MAX_MESSAGE_ID_LENGTH + 1))
+            )
+            .unwrap_err(),
+            PaymentFileError::InvalidMessageId
+        );
+        assert!(matches!(
+            parse(&file("1", "10.50", &[("10.50", &creditor)]).replace("</Document>", "")),
+            Err(PaymentFileError::Malformed(_))
+        ));
+    }
+}
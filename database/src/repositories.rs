@@ -1,3 +1,20 @@
 pub mod accounts;
+pub mod audit;
+pub mod banks;
+pub mod boletos;
+pub mod employers;
+pub mod fees;
+pub mod holds;
+pub mod idempotency;
+pub mod interest;
+pub mod journal;
+pub mod limits;
+pub mod notifications;
+pub mod overdrafts;
+pub mod payment_files;
+pub mod pix_keys;
+pub mod qr_payments;
+pub mod remittances;
+pub mod schedules;
 pub mod transactions;
 pub mod users;
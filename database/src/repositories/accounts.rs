@@ -1,14 +1,18 @@
 use crate::{
     filters::account::Filter as AccountFilter,
     models::{
-        account_dto::{Account, AccountCreate},
+        account_dto::{Account, AccountCreate, AccountStatus, AccountUpdate, InsufficientFunds},
+        hold_dto::held_amount,
         transaction_dto::{TransactionCreate, TransactionOperation},
         user_dto::User,
     },
+    structs::money::{Money, MoneyEncoding},
 };
 use sqlx::{PgPool, Postgres, Row, Transaction};
 use uuid::Uuid;
 
+use super::holds::HoldRepository;
+
 #[derive(Debug, Clone)]
 pub struct AccountRepository;
 
@@ -90,9 +94,10 @@ impl AccountRepository {
                 bank_agency_number,
                 bank_agency_digit,
                 bank_account_type,
-                balance
+                balance,
+                balance_encoding
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING *
             "#,
         )
@@ -105,6 +110,7 @@ impl AccountRepository {
         .bind(account.bank_agency_digit)
         .bind(account.bank_account_type)
         .bind(&account.balance)
+        .bind(account.balance_encoding)
         .execute(&mut **executor)
         .await?;
 
@@ -115,51 +121,174 @@ impl AccountRepository {
         &self,
         executor: &mut Transaction<'_, Postgres>,
         id: &Uuid,
-        account: &AccountCreate,
+        account: &AccountUpdate,
     ) -> anyhow::Result<Account> {
         let account = sqlx::query_as::<_, Account>(
             r#"
             UPDATE accounts
             SET
-                user_id = $2,
-                bank_id = $3,
-                bank_account_number = $4,
-                bank_account_digit = $5,
-                bank_agency_number = $6,
-                bank_agency_digit = $7,
-                bank_account_type = $8,
-                balance = $9
+                bank_id = $2,
+                bank_account_number = $3,
+                bank_account_digit = $4,
+                bank_agency_number = $5,
+                bank_agency_digit = $6,
+                updated_at = $7
             WHERE id = $1
             RETURNING *
             "#,
         )
         .bind(id)
-        .bind(account.user_id)
         .bind(account.bank_id)
         .bind(account.bank_account_number)
         .bind(account.bank_account_digit)
         .bind(account.bank_agency_number)
         .bind(account.bank_agency_digit)
-        .bind(account.bank_account_type)
-        .bind(account.balance)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(account)
+    }
+
+    pub async fn set_overdraft(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        id: &Uuid,
+        limit: Money,
+        rate_bps: i32,
+    ) -> anyhow::Result<Account> {
+        let account = sqlx::query_as::<_, Account>(
+            r#"
+            UPDATE accounts
+            SET overdraft_limit = $2, overdraft_rate_bps = $3, updated_at = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(limit)
+        .bind(rate_bps)
+        .bind(chrono::Utc::now().naive_utc())
         .fetch_one(&mut **executor)
         .await?;
 
         Ok(account)
     }
 
+    /// Reads an account and locks its row until the surrounding transaction ends.
+    pub async fn find_by_id_for_update(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Account> {
+        let account =
+            sqlx::query_as::<_, Account>(r#"SELECT * FROM accounts WHERE id = $1 FOR UPDATE"#)
+                .bind(id)
+                .fetch_one(&mut **executor)
+                .await?;
+
+        Ok(account)
+    }
+
+    /// Reads an account and keeps its balance from changing until the surrounding transaction
+    /// ends, while still letting others read it.
+    pub async fn find_by_id_for_share(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Account> {
+        let account =
+            sqlx::query_as::<_, Account>(r#"SELECT * FROM accounts WHERE id = $1 FOR SHARE"#)
+                .bind(id)
+                .fetch_one(&mut **executor)
+                .await?;
+
+        Ok(account)
+    }
+
+    /// Accounts held at a bank under the given agency and account, digits included.
+    pub async fn find_by_bank_details(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        bank_id: i32,
+        agency: (i32, Option<i32>),
+        account: (i64, Option<i32>),
+    ) -> anyhow::Result<Vec<Account>> {
+        let accounts = sqlx::query_as::<_, Account>(
+            r#"
+            SELECT * FROM accounts
+            WHERE bank_id = $1
+                AND bank_agency_number = $2
+                AND bank_agency_digit IS NOT DISTINCT FROM $3
+                AND bank_account_number = $4
+                AND bank_account_digit IS NOT DISTINCT FROM $5
+            ORDER BY id
+            "#,
+        )
+        .bind(bank_id)
+        .bind(agency.0)
+        .bind(agency.1)
+        .bind(account.0)
+        .bind(account.1)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(accounts)
+    }
+
+    /// Ids of every account `user_id` owns, read through the open transaction.
+    pub async fn find_ids_by_user_id(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        user_id: &Uuid,
+    ) -> anyhow::Result<Vec<Uuid>> {
+        let account_ids: Vec<(Uuid,)> =
+            sqlx::query_as(r#"SELECT id FROM accounts WHERE user_id = $1 ORDER BY id"#)
+                .bind(user_id)
+                .fetch_all(&mut **executor)
+                .await?;
+
+        Ok(account_ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Locks several accounts until the surrounding transaction ends.
+    ///
+    /// Rows are always locked in id order, so two transactions touching the same
+    /// accounts queue up behind each other instead of deadlocking.
+    pub async fn lock_by_ids(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        ids: &[Uuid],
+    ) -> anyhow::Result<Vec<Account>> {
+        let mut ids = ids.to_vec();
+        ids.sort();
+        ids.dedup();
+
+        let accounts = sqlx::query_as::<_, Account>(
+            r#"SELECT * FROM accounts WHERE id = ANY($1) ORDER BY id FOR UPDATE"#,
+        )
+        .bind(&ids)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        if accounts.len() != ids.len() {
+            return Err(anyhow::anyhow!("Account not found"));
+        }
+
+        Ok(accounts)
+    }
+
     pub async fn update_balance(
         &self,
-        db_pool: &PgPool,
         executor: &mut Transaction<'_, Postgres>,
         transaction: &TransactionCreate,
-        amount: f64,
+        amount: Money,
         acting_user_id: &Uuid,
     ) -> anyhow::Result<Account> {
         match &transaction.operation {
             TransactionOperation::Transfer => {
-                let mut from_account = match transaction.from_account_id {
-                    Some(from_account_id) => self.find_by_id(db_pool, &from_account_id).await?,
+                let from_account_id = match transaction.from_account_id {
+                    Some(from_account_id) => from_account_id,
                     None => {
                         return Err(anyhow::anyhow!(
                             "Transfer operations must have a source account."
@@ -167,14 +296,28 @@ impl AccountRepository {
                     }
                 };
 
+                if from_account_id == transaction.to_account_id {
+                    return Err(anyhow::anyhow!(
+                        "Transfer operations must have different source and destination accounts."
+                    ));
+                }
+
+                let mut accounts = self
+                    .lock_by_ids(executor, &[from_account_id, transaction.to_account_id])
+                    .await?
+                    .into_iter();
+                let (mut from_account, mut to_account) = match (accounts.next(), accounts.next()) {
+                    (Some(first), Some(second)) if first.id == from_account_id => (first, second),
+                    (Some(first), Some(second)) => (second, first),
+                    _ => return Err(anyhow::anyhow!("Account not found")),
+                };
+
                 if &from_account.user_id != acting_user_id {
                     return Err(anyhow::anyhow!(
                         "You are not allowed to perform this operation"
                     ));
                 }
 
-                let mut to_account = self.find_by_id(db_pool, &transaction.to_account_id).await?;
-
                 let from_user = sqlx::query_as::<_, User>(r#"SELECT * FROM users WHERE id = $1"#)
                     .bind(from_account.user_id)
                     .fetch_one(&mut **executor)
@@ -187,36 +330,46 @@ impl AccountRepository {
                 let from_balance = from_account.get_balance(&from_user)?;
                 let to_balance = to_account.get_balance(&to_user)?;
 
+                if amount
+                    > self
+                        .available_balance(executor, &from_account, &from_user)
+                        .await?
+                {
+                    return Err(InsufficientFunds.into());
+                }
+
                 let new_from_balance = from_balance - amount;
                 let new_to_balance = to_balance + amount;
 
                 from_account.update_balance(&from_user, new_from_balance)?;
                 to_account.update_balance(&to_user, new_to_balance)?;
 
-                sqlx::query(r#"UPDATE accounts SET balance = $2 WHERE id = $1"#)
-                    .bind(from_account.id)
-                    .bind(from_account.balance)
-                    .execute(&mut **executor)
-                    .await?;
+                self.save_balance(executor, &from_account).await?;
 
-                let to_account = sqlx::query_as::<_, Account>(
-                    r#"UPDATE accounts SET balance = $2 WHERE id = $1 RETURNING *"#,
-                )
-                .bind(to_account.id)
-                .bind(to_account.balance)
-                .fetch_one(&mut **executor)
-                .await?;
-
-                Ok(to_account)
+                self.save_balance(executor, &to_account).await
             }
             _ => {
-                let mut to_account = self.find_by_id(db_pool, &transaction.to_account_id).await?;
+                let mut to_account = self
+                    .find_by_id_for_update(executor, &transaction.to_account_id)
+                    .await?;
                 let user = sqlx::query_as::<_, User>(r#"SELECT * FROM users WHERE id = $1"#)
                     .bind(to_account.user_id)
                     .fetch_one(&mut **executor)
                     .await?;
 
                 let balance = to_account.get_balance(&user)?;
+
+                // every debit leaves what active holds reserve in place
+                if matches!(
+                    transaction.operation,
+                    TransactionOperation::Fee
+                        | TransactionOperation::Payment
+                        | TransactionOperation::Withdrawal
+                ) && amount > self.available_balance(executor, &to_account, &user).await?
+                {
+                    return Err(InsufficientFunds.into());
+                }
+
                 let new_balance = match &transaction.operation {
                     TransactionOperation::Deposit => balance + amount,
                     TransactionOperation::Fee => balance - amount,
@@ -228,30 +381,173 @@ impl AccountRepository {
 
                 to_account.update_balance(&user, new_balance)?;
 
-                let account = sqlx::query_as::<_, Account>(
-                    r#"UPDATE accounts SET balance = $2 WHERE id = $1 RETURNING *"#,
-                )
-                .bind(to_account.id)
-                .bind(to_account.balance)
+                self.save_balance(executor, &to_account).await
+            }
+        }
+    }
+
+    /// The balance minus what active holds reserve on it, plus the overdraft limit.
+    ///
+    /// Call it with the account row locked, so no hold can be added in between.
+    pub async fn available_balance(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        account: &Account,
+        user: &User,
+    ) -> anyhow::Result<Money> {
+        let holds = HoldRepository::new()
+            .find_active_by_account_id(&mut **executor, &account.id)
+            .await?;
+
+        Ok(account.get_balance(user)? - held_amount(&holds, user)? + account.overdraft_limit)
+    }
+
+    /// Adds each signed amount to its account's balance, locking the accounts first.
+    ///
+    /// Fails with `InsufficientFunds` if a debit is more than its account's available balance,
+    /// so active holds stay covered.
+    pub async fn adjust_balances(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        adjustments: &[(Uuid, Money)],
+    ) -> anyhow::Result<Vec<Account>> {
+        let ids: Vec<Uuid> = adjustments.iter().map(|(id, _)| *id).collect();
+        let accounts = self.lock_by_ids(executor, &ids).await?;
+        let mut adjusted = Vec::with_capacity(accounts.len());
+
+        for mut account in accounts {
+            let change: Money = adjustments
+                .iter()
+                .filter(|(id, _)| *id == account.id)
+                .map(|(_, amount)| *amount)
+                .sum();
+            let user = sqlx::query_as::<_, User>(r#"SELECT * FROM users WHERE id = $1"#)
+                .bind(account.user_id)
                 .fetch_one(&mut **executor)
                 .await?;
 
-                Ok(account)
+            if change.is_negative()
+                && -change > self.available_balance(executor, &account, &user).await?
+            {
+                return Err(InsufficientFunds.into());
             }
+
+            let balance = account.get_balance(&user)?;
+            account.update_balance(&user, balance + change)?;
+
+            adjusted.push(self.save_balance(executor, &account).await?);
         }
+
+        Ok(adjusted)
+    }
+
+    /// Debits `amount` from an account, locking it first, with no regard for its overdraft
+    /// limit or holds.
+    pub async fn overdraw(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        account_id: &Uuid,
+        amount: Money,
+    ) -> anyhow::Result<Account> {
+        let mut account = self.find_by_id_for_update(executor, account_id).await?;
+        let user = sqlx::query_as::<_, User>(r#"SELECT * FROM users WHERE id = $1"#)
+            .bind(account.user_id)
+            .fetch_one(&mut **executor)
+            .await?;
+        account.overdraw(&user, amount)?;
+
+        self.save_balance(executor, &account).await
+    }
+
+    pub async fn save_balance(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        account: &Account,
+    ) -> anyhow::Result<Account> {
+        let account = sqlx::query_as::<_, Account>(
+            r#"UPDATE accounts SET balance = $2, balance_encoding = $3 WHERE id = $1 RETURNING *"#,
+        )
+        .bind(account.id)
+        .bind(&account.balance)
+        .bind(account.balance_encoding)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(account)
+    }
+
+    pub async fn find_by_balance_encoding(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        encoding: MoneyEncoding,
+    ) -> anyhow::Result<Vec<Account>> {
+        let accounts = sqlx::query_as::<_, Account>(
+            r#"SELECT * FROM accounts WHERE balance_encoding = $1 ORDER BY id FOR UPDATE"#,
+        )
+        .bind(encoding)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(accounts)
     }
 
-    pub async fn delete(&self, executor: &mut Transaction<'_, Postgres>, id: &Uuid) -> bool {
-        sqlx::query!(
+    /// Moves an account to `status`, stamping when it was closed.
+    pub async fn set_status(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        id: &Uuid,
+        status: AccountStatus,
+    ) -> anyhow::Result<Account> {
+        let now = chrono::Utc::now().naive_utc();
+        let account = sqlx::query_as::<_, Account>(
             r#"
-            DELETE FROM accounts
+            UPDATE accounts
+            SET status = $2, closed_at = $3, updated_at = $4
             WHERE id = $1
+            RETURNING *
             "#,
-            id
         )
-        .execute(&mut **executor)
-        .await
-        .is_ok()
+        .bind(id)
+        .bind(status)
+        .bind((status == AccountStatus::Closed).then_some(now))
+        .bind(now)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(account)
+    }
+
+    /// Hides an account from listings; it and its transactions stay in place.
+    pub async fn soft_delete(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Account> {
+        let account = sqlx::query_as::<_, Account>(
+            r#"UPDATE accounts SET deleted_at = $2, updated_at = $2 WHERE id = $1 RETURNING *"#,
+        )
+        .bind(id)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(account)
+    }
+
+    pub async fn restore(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Account> {
+        let account = sqlx::query_as::<_, Account>(
+            r#"UPDATE accounts SET deleted_at = NULL, updated_at = $2 WHERE id = $1 RETURNING *"#,
+        )
+        .bind(id)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(account)
     }
 
     pub async fn get_total(
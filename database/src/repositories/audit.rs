@@ -0,0 +1,173 @@
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Row, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    filters::audit::Filter as AuditFilter,
+    models::audit_dto::{AuditFailure, AuditRecord, AuditTarget},
+};
+
+#[derive(Debug, Clone)]
+pub struct AuditRepository;
+
+impl Default for AuditRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuditRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_all(
+        &self,
+        db_pool: &PgPool,
+        filters: &AuditFilter,
+    ) -> anyhow::Result<Vec<AuditRecord>> {
+        let args = filters.get_arguments();
+        let query = r#"SELECT * FROM audit_log "#.to_owned() + &filters.query();
+
+        let records = sqlx::query_as_with::<_, AuditRecord, _>(&query, args)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(records)
+    }
+
+    pub async fn get_total(&self, db_pool: &PgPool, filters: &AuditFilter) -> anyhow::Result<u64> {
+        let args = filters.get_arguments();
+        let query = r#"SELECT COUNT(*) as total FROM audit_log "#.to_owned() + &filters.total();
+        let result = sqlx::query_with(&query, args).fetch_one(db_pool).await?;
+
+        Ok(result.get::<i64, &str>("total") as u64)
+    }
+
+    /// Reads up to `limit` records following `sequence`, oldest first.
+    pub async fn find_after(
+        &self,
+        db_pool: &PgPool,
+        sequence: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<AuditRecord>> {
+        let records = sqlx::query_as::<_, AuditRecord>(
+            r#"SELECT * FROM audit_log WHERE sequence > $1 ORDER BY sequence LIMIT $2"#,
+        )
+        .bind(sequence)
+        .bind(limit)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Holds off other writers until the surrounding transaction ends, so records are chained
+    /// one at a time. Readers are not blocked.
+    pub async fn lock(&self, executor: &mut SqlxTransaction<'_, Postgres>) -> anyhow::Result<()> {
+        sqlx::query(r#"LOCK TABLE audit_log IN EXCLUSIVE MODE"#)
+            .execute(&mut **executor)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_last(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+    ) -> anyhow::Result<Option<AuditRecord>> {
+        let record = sqlx::query_as::<_, AuditRecord>(
+            r#"SELECT * FROM audit_log ORDER BY sequence DESC LIMIT 1"#,
+        )
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(record)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        record: &AuditRecord,
+    ) -> anyhow::Result<AuditRecord> {
+        let record = sqlx::query_as::<_, AuditRecord>(
+            r#"
+            INSERT INTO audit_log (
+                sequence, actor_id, scopes, action, target_type, target_id, status, before, after,
+                ip, request_id, created_at, previous_hash, hash
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING *
+            "#,
+        )
+        .bind(record.sequence)
+        .bind(record.actor_id)
+        .bind(&record.scopes)
+        .bind(&record.action)
+        .bind(&record.target_type)
+        .bind(record.target_id)
+        .bind(record.status)
+        .bind(&record.before)
+        .bind(&record.after)
+        .bind(&record.ip)
+        .bind(&record.request_id)
+        .bind(record.created_at)
+        .bind(&record.previous_hash)
+        .bind(&record.hash)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(record)
+    }
+
+    pub async fn create_failure(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        failure: &AuditFailure,
+    ) -> anyhow::Result<AuditFailure> {
+        let failure = sqlx::query_as::<_, AuditFailure>(
+            r#"
+            INSERT INTO audit_failures (
+                id, actor_id, scopes, action, target_type, target_id, status, before, after, ip,
+                request_id, error, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            RETURNING *
+            "#,
+        )
+        .bind(failure.id)
+        .bind(failure.actor_id)
+        .bind(&failure.scopes)
+        .bind(&failure.action)
+        .bind(&failure.target_type)
+        .bind(failure.target_id)
+        .bind(failure.status)
+        .bind(&failure.before)
+        .bind(&failure.after)
+        .bind(&failure.ip)
+        .bind(&failure.request_id)
+        .bind(&failure.error)
+        .bind(failure.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(failure)
+    }
+
+    /// Reads a row of the target's table as JSON, or `None` when it does not exist.
+    pub async fn find_snapshot(
+        &self,
+        db_pool: &PgPool,
+        target: AuditTarget,
+        id: &Uuid,
+    ) -> anyhow::Result<Option<Value>> {
+        // the table name comes from a fixed list, never from the request
+        let query = format!("SELECT to_jsonb(t) FROM {} t WHERE id = $1", target.table());
+        let snapshot = sqlx::query_scalar::<_, Value>(&query)
+            .bind(id)
+            .fetch_optional(db_pool)
+            .await?;
+
+        Ok(snapshot)
+    }
+}
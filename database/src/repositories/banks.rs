@@ -0,0 +1,111 @@
+use sqlx::{PgExecutor, PgPool, Postgres, Row, Transaction as SqlxTransaction};
+
+use crate::{
+    filters::bank::Filter as BankFilter,
+    models::bank_dto::{Bank, BankEntry},
+};
+
+#[derive(Debug, Clone)]
+pub struct BankRepository;
+
+impl Default for BankRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_by_id<'e>(
+        &self,
+        executor: impl PgExecutor<'e>,
+        id: i32,
+    ) -> anyhow::Result<Option<Bank>> {
+        let bank = sqlx::query_as::<_, Bank>(r#"SELECT * FROM banks WHERE id = $1"#)
+            .bind(id)
+            .fetch_optional(executor)
+            .await?;
+
+        Ok(bank)
+    }
+
+    pub async fn find_all(
+        &self,
+        db_pool: &PgPool,
+        filters: &BankFilter,
+    ) -> anyhow::Result<Vec<Bank>> {
+        let args = filters.get_arguments();
+        let query = r#"SELECT * FROM banks "#.to_owned() + &filters.query();
+
+        let banks = sqlx::query_as_with::<_, Bank, _>(&query, args)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(banks)
+    }
+
+    pub async fn get_total(&self, db_pool: &PgPool, filters: &BankFilter) -> anyhow::Result<u64> {
+        let args = filters.get_arguments();
+        let query = r#"SELECT COUNT(*) as total FROM banks "#.to_owned() + &filters.total();
+        let result = sqlx::query_with(&query, args).fetch_one(db_pool).await?;
+
+        Ok(result.get::<i64, &str>("total") as u64)
+    }
+
+    /// Writes a bank as listed in the registry, telling whether it was added (`Some(true)`),
+    /// changed (`Some(false)`) or already up to date (`None`).
+    pub async fn upsert(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        entry: &BankEntry,
+    ) -> anyhow::Result<Option<bool>> {
+        let inserted = sqlx::query_scalar::<_, bool>(
+            r#"
+            INSERT INTO banks (id, ispb, short_name, name, active)
+            VALUES ($1, $2, $3, $4, TRUE)
+            ON CONFLICT (id) DO UPDATE
+            SET ispb = EXCLUDED.ispb,
+                short_name = EXCLUDED.short_name,
+                name = EXCLUDED.name,
+                active = TRUE
+            WHERE (banks.ispb, banks.short_name, banks.name, banks.active)
+                IS DISTINCT FROM (EXCLUDED.ispb, EXCLUDED.short_name, EXCLUDED.name, TRUE)
+            RETURNING xmax = 0
+            "#,
+        )
+        .bind(entry.id)
+        .bind(&entry.ispb)
+        .bind(&entry.short_name)
+        .bind(&entry.name)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(inserted)
+    }
+
+    /// Deactivates the banks missing from `ids`, handing the ISPBs in `ispbs` over to the
+    /// codes now listed under them. Returns how many banks were deactivated.
+    pub async fn deactivate_missing(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        ids: &[i32],
+        ispbs: &[String],
+    ) -> anyhow::Result<u64> {
+        sqlx::query(r#"UPDATE banks SET ispb = NULL WHERE id <> ALL($1) AND ispb = ANY($2)"#)
+            .bind(ids)
+            .bind(ispbs)
+            .execute(&mut **executor)
+            .await?;
+
+        let result =
+            sqlx::query(r#"UPDATE banks SET active = FALSE WHERE active AND id <> ALL($1)"#)
+                .bind(ids)
+                .execute(&mut **executor)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,191 @@
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::boleto_dto::{Boleto, BoletoPayment};
+
+#[derive(Debug, Clone)]
+pub struct BoletoRepository;
+
+impl Default for BoletoRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoletoRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_by_id(&self, db_pool: &PgPool, id: &Uuid) -> anyhow::Result<Boleto> {
+        let boleto = sqlx::query_as::<_, Boleto>(r#"SELECT * FROM boletos WHERE id = $1"#)
+            .bind(id)
+            .fetch_one(db_pool)
+            .await?;
+
+        Ok(boleto)
+    }
+
+    /// Slips issued by an account, newest first.
+    pub async fn find_by_account_id(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Vec<Boleto>> {
+        let boletos = sqlx::query_as::<_, Boleto>(
+            r#"SELECT * FROM boletos WHERE account_id = $1 ORDER BY our_number DESC"#,
+        )
+        .bind(account_id)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(boletos)
+    }
+
+    /// The slip issued here under `barcode`, if any.
+    pub async fn find_by_barcode<'e>(
+        &self,
+        executor: impl PgExecutor<'e>,
+        barcode: &str,
+    ) -> anyhow::Result<Option<Boleto>> {
+        let boleto = sqlx::query_as::<_, Boleto>(r#"SELECT * FROM boletos WHERE barcode = $1"#)
+            .bind(barcode)
+            .fetch_optional(executor)
+            .await?;
+
+        Ok(boleto)
+    }
+
+    /// Reads a slip issued here and locks its row until the surrounding transaction ends.
+    pub async fn find_by_barcode_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        barcode: &str,
+    ) -> anyhow::Result<Option<Boleto>> {
+        let boleto =
+            sqlx::query_as::<_, Boleto>(r#"SELECT * FROM boletos WHERE barcode = $1 FOR UPDATE"#)
+                .bind(barcode)
+                .fetch_optional(&mut **executor)
+                .await?;
+
+        Ok(boleto)
+    }
+
+    pub async fn next_our_number(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<i64> {
+        let (our_number,): (i64,) = sqlx::query_as(
+            r#"SELECT COALESCE(MAX(our_number), 0) + 1 FROM boletos WHERE account_id = $1"#,
+        )
+        .bind(account_id)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(our_number)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        boleto: &Boleto,
+    ) -> anyhow::Result<Boleto> {
+        let boleto = sqlx::query_as::<_, Boleto>(
+            r#"
+            INSERT INTO boletos (
+                id, account_id, our_number, barcode, amount, due_date, fine_bps, interest_bps,
+                status, transaction_id, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING *
+            "#,
+        )
+        .bind(boleto.id)
+        .bind(boleto.account_id)
+        .bind(boleto.our_number)
+        .bind(&boleto.barcode)
+        .bind(boleto.amount)
+        .bind(boleto.due_date)
+        .bind(boleto.fine_bps)
+        .bind(boleto.interest_bps)
+        .bind(boleto.status)
+        .bind(boleto.transaction_id)
+        .bind(boleto.created_at)
+        .bind(boleto.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(boleto)
+    }
+
+    pub async fn save(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        boleto: &Boleto,
+    ) -> anyhow::Result<Boleto> {
+        let boleto = sqlx::query_as::<_, Boleto>(
+            r#"
+            UPDATE boletos
+            SET status = $2, transaction_id = $3, updated_at = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(boleto.id)
+        .bind(boleto.status)
+        .bind(boleto.transaction_id)
+        .bind(boleto.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(boleto)
+    }
+
+    pub async fn create_payment(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        payment: &BoletoPayment,
+    ) -> anyhow::Result<BoletoPayment> {
+        let payment = sqlx::query_as::<_, BoletoPayment>(
+            r#"
+            INSERT INTO boleto_payments (
+                transaction_id, from_account_id, boleto_id, barcode, payee, due_date, amount,
+                fine, interest, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING *
+            "#,
+        )
+        .bind(payment.transaction_id)
+        .bind(payment.from_account_id)
+        .bind(payment.boleto_id)
+        .bind(&payment.barcode)
+        .bind(&payment.payee)
+        .bind(payment.due_date)
+        .bind(payment.amount)
+        .bind(payment.fine)
+        .bind(payment.interest)
+        .bind(payment.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(payment)
+    }
+
+    /// Slips paid from an account, newest first.
+    pub async fn find_payments_by_account_id(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Vec<BoletoPayment>> {
+        let payments = sqlx::query_as::<_, BoletoPayment>(
+            r#"SELECT * FROM boleto_payments WHERE from_account_id = $1 ORDER BY created_at DESC"#,
+        )
+        .bind(account_id)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(payments)
+    }
+}
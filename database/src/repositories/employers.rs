@@ -0,0 +1,96 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::employer_dto::Employer;
+
+#[derive(Debug, Clone)]
+pub struct EmployerRepository;
+
+impl Default for EmployerRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmployerRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_by_account_id(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Vec<Employer>> {
+        let employers = sqlx::query_as::<_, Employer>(
+            r#"SELECT * FROM account_employers WHERE account_id = $1 ORDER BY created_at"#,
+        )
+        .bind(account_id)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(employers)
+    }
+
+    pub async fn exists(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        employer_account_id: &Uuid,
+    ) -> anyhow::Result<bool> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM account_employers
+                WHERE account_id = $1 AND employer_account_id = $2
+            )
+            "#,
+        )
+        .bind(account_id)
+        .bind(employer_account_id)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Registers the employer, returning `None` when it already was.
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        employer: &Employer,
+    ) -> anyhow::Result<Option<Employer>> {
+        let employer = sqlx::query_as::<_, Employer>(
+            r#"
+            INSERT INTO account_employers (account_id, employer_account_id, created_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(employer.account_id)
+        .bind(employer.employer_account_id)
+        .bind(employer.created_at)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(employer)
+    }
+
+    pub async fn delete(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        employer_account_id: &Uuid,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"DELETE FROM account_employers WHERE account_id = $1 AND employer_account_id = $2"#,
+        )
+        .bind(account_id)
+        .bind(employer_account_id)
+        .execute(&mut **executor)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,210 @@
+use chrono::NaiveDate;
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::{account_dto::AccountType, fee_dto::FeeSchedule};
+
+#[derive(Debug, Clone)]
+pub struct FeeRepository;
+
+impl Default for FeeRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FeeRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_all(&self, db_pool: &PgPool) -> anyhow::Result<Vec<FeeSchedule>> {
+        let schedules = sqlx::query_as::<_, FeeSchedule>(
+            r#"SELECT * FROM fee_schedules ORDER BY bank_account_type NULLS FIRST, id"#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    pub async fn find_by_id(&self, db_pool: &PgPool, id: &Uuid) -> anyhow::Result<FeeSchedule> {
+        let schedule =
+            sqlx::query_as::<_, FeeSchedule>(r#"SELECT * FROM fee_schedules WHERE id = $1"#)
+                .bind(id)
+                .fetch_one(db_pool)
+                .await?;
+
+        Ok(schedule)
+    }
+
+    /// Reads a fee schedule and locks it until the surrounding transaction ends.
+    pub async fn find_by_id_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<FeeSchedule> {
+        let schedule = sqlx::query_as::<_, FeeSchedule>(
+            r#"SELECT * FROM fee_schedules WHERE id = $1 FOR UPDATE"#,
+        )
+        .bind(id)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    /// The schedule for `bank_account_type`, falling back to the default schedule.
+    pub async fn find_for_account_type(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        bank_account_type: Option<AccountType>,
+    ) -> anyhow::Result<Option<FeeSchedule>> {
+        let schedule = sqlx::query_as::<_, FeeSchedule>(
+            r#"
+            SELECT * FROM fee_schedules
+            WHERE bank_account_type = $1 OR bank_account_type IS NULL
+            ORDER BY bank_account_type NULLS LAST
+            LIMIT 1
+            "#,
+        )
+        .bind(bank_account_type)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        schedule: &FeeSchedule,
+    ) -> anyhow::Result<FeeSchedule> {
+        let schedule = sqlx::query_as::<_, FeeSchedule>(
+            r#"
+            INSERT INTO fee_schedules (id, name, bank_account_type, monthly_fee, operation_fees, waivers, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(&schedule.name)
+        .bind(schedule.bank_account_type)
+        .bind(schedule.monthly_fee)
+        .bind(&schedule.operation_fees)
+        .bind(&schedule.waivers)
+        .bind(schedule.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn save(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        schedule: &FeeSchedule,
+    ) -> anyhow::Result<FeeSchedule> {
+        let schedule = sqlx::query_as::<_, FeeSchedule>(
+            r#"
+            UPDATE fee_schedules
+            SET name = $2, monthly_fee = $3, operation_fees = $4, waivers = $5, updated_at = $6
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(&schedule.name)
+        .bind(schedule.monthly_fee)
+        .bind(&schedule.operation_fees)
+        .bind(&schedule.waivers)
+        .bind(schedule.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn delete(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(r#"DELETE FROM fee_schedules WHERE id = $1"#)
+            .bind(id)
+            .execute(&mut **executor)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Accounts whose schedule has a maintenance fee that was not settled for `period` yet.
+    pub async fn find_uncharged_account_ids(
+        &self,
+        db_pool: &PgPool,
+        period: NaiveDate,
+    ) -> anyhow::Result<Vec<Uuid>> {
+        let account_ids: Vec<(Uuid,)> = sqlx::query_as(
+            r#"
+            SELECT a.id FROM accounts a
+            JOIN fee_schedules f ON f.id = (
+                SELECT id FROM fee_schedules
+                WHERE bank_account_type = a.bank_account_type OR bank_account_type IS NULL
+                ORDER BY bank_account_type NULLS LAST
+                LIMIT 1
+            )
+            WHERE f.monthly_fee > 0
+                AND a.status <> 'closed'
+                AND NOT EXISTS (
+                    SELECT 1 FROM fee_charges c WHERE c.account_id = a.id AND c.period = $1
+                )
+            ORDER BY a.id
+            "#,
+        )
+        .bind(period)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(account_ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    pub async fn has_charge(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        period: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        let charged: Option<(Uuid,)> = sqlx::query_as(
+            r#"SELECT account_id FROM fee_charges WHERE account_id = $1 AND period = $2"#,
+        )
+        .bind(account_id)
+        .bind(period)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(charged.is_some())
+    }
+
+    /// Marks the maintenance fee of `period` as settled, by `transaction_id` unless it was waived.
+    pub async fn record_charge(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        period: NaiveDate,
+        transaction_id: Option<Uuid>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO fee_charges (account_id, period, transaction_id)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(account_id)
+        .bind(period)
+        .bind(transaction_id)
+        .execute(&mut **executor)
+        .await?;
+
+        Ok(())
+    }
+}
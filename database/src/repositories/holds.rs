@@ -0,0 +1,137 @@
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::hold_dto::Hold;
+
+#[derive(Debug, Clone)]
+pub struct HoldRepository;
+
+impl Default for HoldRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HoldRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_by_id(&self, db_pool: &PgPool, id: &Uuid) -> anyhow::Result<Hold> {
+        let hold = sqlx::query_as::<_, Hold>(r#"SELECT * FROM holds WHERE id = $1"#)
+            .bind(id)
+            .fetch_one(db_pool)
+            .await?;
+
+        Ok(hold)
+    }
+
+    /// Reads a hold and locks its row until the surrounding transaction ends.
+    pub async fn find_by_id_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Hold> {
+        let hold = sqlx::query_as::<_, Hold>(r#"SELECT * FROM holds WHERE id = $1 FOR UPDATE"#)
+            .bind(id)
+            .fetch_one(&mut **executor)
+            .await?;
+
+        Ok(hold)
+    }
+
+    pub async fn find_by_account_id(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Vec<Hold>> {
+        let holds = sqlx::query_as::<_, Hold>(
+            r#"SELECT * FROM holds WHERE account_id = $1 ORDER BY created_at DESC, id DESC"#,
+        )
+        .bind(account_id)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(holds)
+    }
+
+    /// Holds that still reserve funds on the account, including ones the expiry job has not reached yet.
+    pub async fn find_active_by_account_id<'e>(
+        &self,
+        executor: impl PgExecutor<'e>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Vec<Hold>> {
+        let holds = sqlx::query_as::<_, Hold>(
+            r#"SELECT * FROM holds WHERE account_id = $1 AND status = 'active' AND expires_at > $2"#,
+        )
+        .bind(account_id)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_all(executor)
+        .await?;
+
+        Ok(holds)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        hold: &Hold,
+    ) -> anyhow::Result<Hold> {
+        let hold = sqlx::query_as::<_, Hold>(
+            r#"
+            INSERT INTO holds (id, account_id, to_account_id, amount, status, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(hold.id)
+        .bind(hold.account_id)
+        .bind(hold.to_account_id)
+        .bind(&hold.amount)
+        .bind(hold.status)
+        .bind(hold.expires_at)
+        .bind(hold.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(hold)
+    }
+
+    /// Writes the parts of a hold that change once it leaves the active state.
+    pub async fn save(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        hold: &Hold,
+    ) -> anyhow::Result<Hold> {
+        let hold = sqlx::query_as::<_, Hold>(
+            r#"
+            UPDATE holds
+            SET captured_amount = $2, status = $3, transaction_id = $4, updated_at = $5
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(hold.id)
+        .bind(&hold.captured_amount)
+        .bind(hold.status)
+        .bind(hold.transaction_id)
+        .bind(hold.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(hold)
+    }
+
+    /// Moves every active hold past its expiry to `expired`, returning how many there were.
+    pub async fn expire(&self, db_pool: &PgPool) -> anyhow::Result<u64> {
+        let now = chrono::Utc::now().naive_utc();
+        let result = sqlx::query(
+            r#"UPDATE holds SET status = 'expired', updated_at = $1 WHERE status = 'active' AND expires_at <= $1"#,
+        )
+        .bind(now)
+        .execute(db_pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,98 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::idempotency_dto::IdempotencyKey;
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyRepository;
+
+impl Default for IdempotencyRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdempotencyRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Inserts `key`, or takes over an expired row with the same key.
+    ///
+    /// Returns `None` when a live row already exists. A concurrent claim of the
+    /// same key waits on the primary key until the other transaction ends, so
+    /// only one request can ever hold it.
+    pub async fn claim(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        key: &IdempotencyKey,
+    ) -> anyhow::Result<Option<IdempotencyKey>> {
+        let claimed = sqlx::query_as::<_, IdempotencyKey>(
+            r#"
+            INSERT INTO idempotency_keys (user_id, key, fingerprint, transaction_id, created_at, expires_at)
+            VALUES ($1, $2, $3, NULL, $4, $5)
+            ON CONFLICT (user_id, key) DO UPDATE SET
+                fingerprint = EXCLUDED.fingerprint,
+                transaction_id = NULL,
+                created_at = EXCLUDED.created_at,
+                expires_at = EXCLUDED.expires_at
+            WHERE idempotency_keys.expires_at <= EXCLUDED.created_at
+            RETURNING *
+            "#,
+        )
+        .bind(key.user_id)
+        .bind(&key.key)
+        .bind(&key.fingerprint)
+        .bind(key.created_at)
+        .bind(key.expires_at)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(claimed)
+    }
+
+    pub async fn find(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        user_id: &Uuid,
+        key: &str,
+    ) -> anyhow::Result<IdempotencyKey> {
+        let key = sqlx::query_as::<_, IdempotencyKey>(
+            r#"SELECT * FROM idempotency_keys WHERE user_id = $1 AND key = $2"#,
+        )
+        .bind(user_id)
+        .bind(key)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(key)
+    }
+
+    pub async fn set_transaction_id(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        user_id: &Uuid,
+        key: &str,
+        transaction_id: &Uuid,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"UPDATE idempotency_keys SET transaction_id = $3 WHERE user_id = $1 AND key = $2"#,
+        )
+        .bind(user_id)
+        .bind(key)
+        .bind(transaction_id)
+        .execute(&mut **executor)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_expired(&self, db_pool: &PgPool) -> anyhow::Result<u64> {
+        let result = sqlx::query(r#"DELETE FROM idempotency_keys WHERE expires_at <= $1"#)
+            .bind(chrono::Utc::now().naive_utc())
+            .execute(db_pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,164 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::interest_dto::{AccountInterest, InterestProduct};
+
+#[derive(Debug, Clone)]
+pub struct InterestRepository;
+
+impl Default for InterestRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InterestRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_all_products(
+        &self,
+        db_pool: &PgPool,
+    ) -> anyhow::Result<Vec<InterestProduct>> {
+        let products = sqlx::query_as::<_, InterestProduct>(
+            r#"SELECT * FROM interest_products ORDER BY name, id"#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(products)
+    }
+
+    pub async fn find_product_by_id(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<InterestProduct> {
+        let product = sqlx::query_as::<_, InterestProduct>(
+            r#"SELECT * FROM interest_products WHERE id = $1"#,
+        )
+        .bind(id)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(product)
+    }
+
+    pub async fn create_product(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        product: &InterestProduct,
+    ) -> anyhow::Result<InterestProduct> {
+        let product = sqlx::query_as::<_, InterestProduct>(
+            r#"
+            INSERT INTO interest_products (id, name, annual_rate_bps, day_count, compounding, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(product.id)
+        .bind(&product.name)
+        .bind(product.annual_rate_bps)
+        .bind(product.day_count)
+        .bind(product.compounding)
+        .bind(product.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(product)
+    }
+
+    pub async fn find_by_account_id(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> anyhow::Result<AccountInterest> {
+        let interest = sqlx::query_as::<_, AccountInterest>(
+            r#"SELECT * FROM account_interest WHERE account_id = $1"#,
+        )
+        .bind(account_id)
+        .fetch_one(db_pool)
+        .await?;
+
+        Ok(interest)
+    }
+
+    /// Reads the interest of an account and locks it until the surrounding transaction ends.
+    pub async fn find_by_account_id_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Option<AccountInterest>> {
+        let interest = sqlx::query_as::<_, AccountInterest>(
+            r#"SELECT * FROM account_interest WHERE account_id = $1 FOR UPDATE"#,
+        )
+        .bind(account_id)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(interest)
+    }
+
+    pub async fn find_all_account_ids(&self, db_pool: &PgPool) -> anyhow::Result<Vec<Uuid>> {
+        let account_ids: Vec<(Uuid,)> =
+            sqlx::query_as(r#"SELECT account_id FROM account_interest ORDER BY account_id"#)
+                .fetch_all(db_pool)
+                .await?;
+
+        Ok(account_ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Inserts the interest of an account, or moves an existing one to another product
+    /// and its payout dates without touching what it has accrued so far.
+    pub async fn assign(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        interest: &AccountInterest,
+    ) -> anyhow::Result<AccountInterest> {
+        let interest = sqlx::query_as::<_, AccountInterest>(
+            r#"
+            INSERT INTO account_interest (account_id, product_id, accrued, accrued_through, next_payout_on, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (account_id) DO UPDATE
+            SET product_id = EXCLUDED.product_id, next_payout_on = EXCLUDED.next_payout_on,
+                updated_at = EXCLUDED.created_at
+            RETURNING *
+            "#,
+        )
+        .bind(interest.account_id)
+        .bind(interest.product_id)
+        .bind(&interest.accrued)
+        .bind(interest.accrued_through)
+        .bind(interest.next_payout_on)
+        .bind(interest.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(interest)
+    }
+
+    pub async fn save(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        interest: &AccountInterest,
+    ) -> anyhow::Result<AccountInterest> {
+        let interest = sqlx::query_as::<_, AccountInterest>(
+            r#"
+            UPDATE account_interest
+            SET accrued = $2, accrued_through = $3, next_payout_on = $4, updated_at = $5
+            WHERE account_id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(interest.account_id)
+        .bind(&interest.accrued)
+        .bind(interest.accrued_through)
+        .bind(interest.next_payout_on)
+        .bind(interest.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(interest)
+    }
+}
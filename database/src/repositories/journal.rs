@@ -0,0 +1,181 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        journal_dto::{JournalEntry, LedgerAccount, Posting, PostingDirection},
+        transaction_dto::Transaction,
+        user_dto::User,
+    },
+    structs::money::Money,
+};
+
+#[derive(Debug, Clone)]
+pub struct JournalRepository;
+
+impl Default for JournalRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JournalRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Writes the balanced journal entry behind `transaction`: one debit and one credit of `amount`.
+    ///
+    /// A reversal swaps the debit and credit accounts of the transaction it reverses.
+    pub async fn record(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &Transaction,
+        amount: Money,
+    ) -> anyhow::Result<(JournalEntry, Vec<Posting>)> {
+        let (debit, credit) = match transaction.reverses_transaction_id {
+            Some(original_id) => {
+                let original =
+                    sqlx::query_as::<_, Transaction>(r#"SELECT * FROM transactions WHERE id = $1"#)
+                        .bind(original_id)
+                        .fetch_one(&mut **executor)
+                        .await?;
+                let (debit, credit) = original
+                    .operation
+                    .ledger_accounts(original.from_account_id, original.to_account_id)?;
+
+                (credit, debit)
+            }
+            None => transaction
+                .operation
+                .ledger_accounts(transaction.from_account_id, transaction.to_account_id)?,
+        };
+
+        let entry = JournalEntry::new(transaction.id, transaction.created_at);
+        let entry = sqlx::query_as::<_, JournalEntry>(
+            r#"INSERT INTO journal_entries (id, transaction_id, created_at) VALUES ($1, $2, $3) RETURNING *"#,
+        )
+        .bind(entry.id)
+        .bind(entry.transaction_id)
+        .bind(entry.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        let mut postings = Vec::with_capacity(2);
+        for (ledger_account, direction) in [
+            (debit, PostingDirection::Debit),
+            (credit, PostingDirection::Credit),
+        ] {
+            let owner = self.find_owner(executor, &ledger_account).await?;
+            let posting = Posting::new(&entry, ledger_account, direction, amount, owner.as_ref())?;
+
+            postings.push(self.create_posting(executor, &posting).await?);
+        }
+
+        Ok((entry, postings))
+    }
+
+    async fn create_posting(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        posting: &Posting,
+    ) -> anyhow::Result<Posting> {
+        let posting = sqlx::query_as::<_, Posting>(
+            r#"
+            INSERT INTO journal_postings (
+                id,
+                journal_entry_id,
+                account_id,
+                system_account,
+                direction,
+                amount,
+                created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(posting.id)
+        .bind(posting.journal_entry_id)
+        .bind(posting.account_id)
+        .bind(posting.system_account)
+        .bind(posting.direction)
+        .bind(&posting.amount)
+        .bind(posting.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(posting)
+    }
+
+    /// The user whose key protects postings on `ledger_account`; system accounts have none.
+    pub async fn find_owner(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        ledger_account: &LedgerAccount,
+    ) -> anyhow::Result<Option<User>> {
+        match ledger_account {
+            LedgerAccount::Customer(account_id) => {
+                let user = sqlx::query_as::<_, User>(
+                    r#"SELECT users.* FROM users JOIN accounts ON accounts.user_id = users.id WHERE accounts.id = $1"#,
+                )
+                .bind(account_id)
+                .fetch_one(&mut **executor)
+                .await?;
+
+                Ok(Some(user))
+            }
+            LedgerAccount::System(_) => Ok(None),
+        }
+    }
+
+    pub async fn find_postings_by_transaction_id(
+        &self,
+        db_pool: &PgPool,
+        transaction_id: &Uuid,
+    ) -> anyhow::Result<Vec<Posting>> {
+        let postings = sqlx::query_as::<_, Posting>(
+            r#"
+            SELECT journal_postings.* FROM journal_postings
+            JOIN journal_entries ON journal_entries.id = journal_postings.journal_entry_id
+            WHERE journal_entries.transaction_id = $1
+            ORDER BY journal_postings.id
+            "#,
+        )
+        .bind(transaction_id)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(postings)
+    }
+
+    pub async fn find_all_postings(&self, db_pool: &PgPool) -> anyhow::Result<Vec<Posting>> {
+        let postings = sqlx::query_as::<_, Posting>(
+            r#"SELECT * FROM journal_postings ORDER BY journal_entry_id, id"#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(postings)
+    }
+
+    /// Transactions written before the ledger existed, which have no journal entry yet.
+    pub async fn find_unjournaled_transactions(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT transactions.* FROM transactions
+            LEFT JOIN journal_entries ON journal_entries.transaction_id = transactions.id
+            WHERE journal_entries.id IS NULL
+            ORDER BY transactions.created_at, transactions.id
+            FOR UPDATE OF transactions
+            "#,
+        )
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(transactions)
+    }
+}
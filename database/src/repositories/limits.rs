@@ -0,0 +1,148 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::{limit_dto::TransactionLimit, transaction_dto::TransactionOperation};
+
+#[derive(Debug, Clone)]
+pub struct LimitRepository;
+
+impl Default for LimitRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LimitRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_all(&self, db_pool: &PgPool) -> anyhow::Result<Vec<TransactionLimit>> {
+        let limits = sqlx::query_as::<_, TransactionLimit>(
+            r#"SELECT * FROM transaction_limits ORDER BY account_id NULLS FIRST, user_id NULLS FIRST, operation"#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(limits)
+    }
+
+    pub async fn find_by_id(
+        &self,
+        db_pool: &PgPool,
+        id: &Uuid,
+    ) -> anyhow::Result<TransactionLimit> {
+        let limit = sqlx::query_as::<_, TransactionLimit>(
+            r#"SELECT * FROM transaction_limits WHERE id = $1"#,
+        )
+        .bind(id)
+        .fetch_one(db_pool)
+        .await?;
+
+        Ok(limit)
+    }
+
+    /// Reads a limit and locks it until the surrounding transaction ends.
+    pub async fn find_by_id_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<TransactionLimit> {
+        let limit = sqlx::query_as::<_, TransactionLimit>(
+            r#"SELECT * FROM transaction_limits WHERE id = $1 FOR UPDATE"#,
+        )
+        .bind(id)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(limit)
+    }
+
+    /// The limit on `operation` for an account of `user_id`: the account's own, else the
+    /// user's, else the one for every user.
+    pub async fn find_applicable(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        user_id: &Uuid,
+        operation: &TransactionOperation,
+    ) -> anyhow::Result<Option<TransactionLimit>> {
+        let limit = sqlx::query_as::<_, TransactionLimit>(
+            r#"
+            SELECT * FROM transaction_limits
+            WHERE operation = $3
+                AND (account_id = $1 OR user_id = $2 OR (account_id IS NULL AND user_id IS NULL))
+            ORDER BY account_id IS NULL, user_id IS NULL
+            LIMIT 1
+            "#,
+        )
+        .bind(account_id)
+        .bind(user_id)
+        .bind(operation)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(limit)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        limit: &TransactionLimit,
+    ) -> anyhow::Result<TransactionLimit> {
+        let limit = sqlx::query_as::<_, TransactionLimit>(
+            r#"
+            INSERT INTO transaction_limits (id, account_id, user_id, operation, per_operation, daily, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(limit.id)
+        .bind(limit.account_id)
+        .bind(limit.user_id)
+        .bind(&limit.operation)
+        .bind(limit.per_operation)
+        .bind(limit.daily)
+        .bind(limit.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(limit)
+    }
+
+    pub async fn save(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        limit: &TransactionLimit,
+    ) -> anyhow::Result<TransactionLimit> {
+        let limit = sqlx::query_as::<_, TransactionLimit>(
+            r#"
+            UPDATE transaction_limits
+            SET per_operation = $2, daily = $3, updated_at = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(limit.id)
+        .bind(limit.per_operation)
+        .bind(limit.daily)
+        .bind(limit.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(limit)
+    }
+
+    pub async fn delete(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(r#"DELETE FROM transaction_limits WHERE id = $1"#)
+            .bind(id)
+            .execute(&mut **executor)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
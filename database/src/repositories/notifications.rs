@@ -0,0 +1,58 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::notification_dto::Notification;
+
+#[derive(Debug, Clone)]
+pub struct NotificationRepository;
+
+impl Default for NotificationRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotificationRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_by_user_id(
+        &self,
+        db_pool: &PgPool,
+        user_id: &Uuid,
+    ) -> anyhow::Result<Vec<Notification>> {
+        let notifications = sqlx::query_as::<_, Notification>(
+            r#"SELECT * FROM notifications WHERE user_id = $1 ORDER BY created_at DESC, id DESC"#,
+        )
+        .bind(user_id)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        notification: &Notification,
+    ) -> anyhow::Result<Notification> {
+        let notification = sqlx::query_as::<_, Notification>(
+            r#"
+            INSERT INTO notifications (id, user_id, kind, message, schedule_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(notification.id)
+        .bind(notification.user_id)
+        .bind(notification.kind)
+        .bind(&notification.message)
+        .bind(notification.schedule_id)
+        .bind(notification.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(notification)
+    }
+}
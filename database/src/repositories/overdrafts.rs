@@ -0,0 +1,99 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::overdraft_dto::OverdraftInterest;
+
+#[derive(Debug, Clone)]
+pub struct OverdraftRepository;
+
+impl Default for OverdraftRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OverdraftRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Accounts that can be overdrawn, or still owe interest from when they could.
+    pub async fn find_account_ids(&self, db_pool: &PgPool) -> anyhow::Result<Vec<Uuid>> {
+        let account_ids: Vec<(Uuid,)> = sqlx::query_as(
+            r#"
+            SELECT id FROM accounts WHERE overdraft_limit > 0
+            UNION
+            SELECT account_id FROM overdraft_interest
+            ORDER BY 1
+            "#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(account_ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Reads the overdraft interest of an account and locks it until the surrounding
+    /// transaction ends.
+    pub async fn find_by_account_id_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Option<OverdraftInterest>> {
+        let interest = sqlx::query_as::<_, OverdraftInterest>(
+            r#"SELECT * FROM overdraft_interest WHERE account_id = $1 FOR UPDATE"#,
+        )
+        .bind(account_id)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(interest)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        interest: &OverdraftInterest,
+    ) -> anyhow::Result<OverdraftInterest> {
+        let interest = sqlx::query_as::<_, OverdraftInterest>(
+            r#"
+            INSERT INTO overdraft_interest (account_id, accrued, accrued_through, next_charge_on, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(interest.account_id)
+        .bind(&interest.accrued)
+        .bind(interest.accrued_through)
+        .bind(interest.next_charge_on)
+        .bind(interest.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(interest)
+    }
+
+    pub async fn save(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        interest: &OverdraftInterest,
+    ) -> anyhow::Result<OverdraftInterest> {
+        let interest = sqlx::query_as::<_, OverdraftInterest>(
+            r#"
+            UPDATE overdraft_interest
+            SET accrued = $2, accrued_through = $3, next_charge_on = $4, updated_at = $5
+            WHERE account_id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(interest.account_id)
+        .bind(&interest.accrued)
+        .bind(interest.accrued_through)
+        .bind(interest.next_charge_on)
+        .bind(interest.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(interest)
+    }
+}
@@ -0,0 +1,42 @@
+use sqlx::{Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct PaymentFileRepository;
+
+impl Default for PaymentFileRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PaymentFileRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Records that the user applied the file `message_id`, returning `false` when it already
+    /// was. A concurrent claim of the same file waits on the primary key until the other
+    /// transaction ends.
+    pub async fn claim(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        user_id: &Uuid,
+        message_id: &str,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO payment_files (user_id, message_id, created_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(message_id)
+        .bind(chrono::Utc::now().naive_utc())
+        .execute(&mut **executor)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
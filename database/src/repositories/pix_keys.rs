@@ -0,0 +1,129 @@
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::pix_key_dto::PixKey;
+
+#[derive(Debug, Clone)]
+pub struct PixKeyRepository;
+
+impl Default for PixKeyRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PixKeyRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_by_id(&self, db_pool: &PgPool, id: &Uuid) -> anyhow::Result<PixKey> {
+        let pix_key = sqlx::query_as::<_, PixKey>(r#"SELECT * FROM pix_keys WHERE id = $1"#)
+            .bind(id)
+            .fetch_one(db_pool)
+            .await?;
+
+        Ok(pix_key)
+    }
+
+    /// The key registered under a normalized `key`, if any.
+    pub async fn find_by_key<'e>(
+        &self,
+        executor: impl PgExecutor<'e>,
+        key: &str,
+    ) -> anyhow::Result<Option<PixKey>> {
+        let pix_key = sqlx::query_as::<_, PixKey>(r#"SELECT * FROM pix_keys WHERE key = $1"#)
+            .bind(key)
+            .fetch_optional(executor)
+            .await?;
+
+        Ok(pix_key)
+    }
+
+    /// Keys of an account, oldest first.
+    pub async fn find_by_account_id(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Vec<PixKey>> {
+        let pix_keys = sqlx::query_as::<_, PixKey>(
+            r#"SELECT * FROM pix_keys WHERE account_id = $1 ORDER BY created_at, id"#,
+        )
+        .bind(account_id)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(pix_keys)
+    }
+
+    /// Keys of every account `user_id` owns.
+    pub async fn find_by_user_id(
+        &self,
+        db_pool: &PgPool,
+        user_id: &Uuid,
+    ) -> anyhow::Result<Vec<PixKey>> {
+        let pix_keys = sqlx::query_as::<_, PixKey>(
+            r#"
+            SELECT pix_keys.* FROM pix_keys
+            JOIN accounts ON accounts.id = pix_keys.account_id
+            WHERE accounts.user_id = $1
+            ORDER BY pix_keys.created_at, pix_keys.id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(pix_keys)
+    }
+
+    pub async fn count_by_account_id(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<i64> {
+        let (count,): (i64,) =
+            sqlx::query_as(r#"SELECT COUNT(*) FROM pix_keys WHERE account_id = $1"#)
+                .bind(account_id)
+                .fetch_one(&mut **executor)
+                .await?;
+
+        Ok(count)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        pix_key: &PixKey,
+    ) -> anyhow::Result<PixKey> {
+        let pix_key = sqlx::query_as::<_, PixKey>(
+            r#"
+            INSERT INTO pix_keys (id, account_id, key_type, key, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(pix_key.id)
+        .bind(pix_key.account_id)
+        .bind(pix_key.key_type)
+        .bind(&pix_key.key)
+        .bind(pix_key.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(pix_key)
+    }
+
+    pub async fn delete(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(r#"DELETE FROM pix_keys WHERE id = $1"#)
+            .bind(id)
+            .execute(&mut **executor)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
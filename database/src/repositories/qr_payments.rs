@@ -0,0 +1,44 @@
+use sqlx::{Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct QrPaymentRepository;
+
+impl Default for QrPaymentRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QrPaymentRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Records `transaction_id` as the payment of the dynamic code `qr_transaction_id` issued
+    /// for the account, returning `false` when the code was already paid. A concurrent payment
+    /// of the same code waits on the primary key until the other transaction ends.
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        qr_transaction_id: &str,
+        transaction_id: &Uuid,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO qr_payments (account_id, qr_transaction_id, transaction_id, created_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(account_id)
+        .bind(qr_transaction_id)
+        .bind(transaction_id)
+        .bind(chrono::Utc::now().naive_utc())
+        .execute(&mut **executor)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
@@ -0,0 +1,169 @@
+use sqlx::{Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::models::{
+    remittance_dto::{Remittance, RemittanceItem},
+    transaction_dto::Transaction,
+};
+
+#[derive(Debug, Clone)]
+pub struct RemittanceRepository;
+
+impl Default for RemittanceRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RemittanceRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Transfers out of `account_id` into accounts held at a bank that no remittance has
+    /// carried yet and that were not reversed, oldest first.
+    pub async fn find_unremitted_transfers(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT transactions.* FROM transactions
+            JOIN accounts ON accounts.id = transactions.to_account_id
+            WHERE transactions.operation = 'transfer'
+                AND transactions.from_account_id = $1
+                AND transactions.reverses_transaction_id IS NULL
+                AND accounts.bank_id IS NOT NULL
+                AND accounts.bank_agency_number IS NOT NULL
+                AND accounts.bank_account_number IS NOT NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM remittance_items WHERE remittance_items.transaction_id = transactions.id
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM transactions reversals WHERE reversals.reverses_transaction_id = transactions.id
+                )
+            ORDER BY transactions.created_at, transactions.id
+            "#,
+        )
+        .bind(account_id)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(transactions)
+    }
+
+    /// The sequence number of the next file of an account; the caller holds its row lock.
+    pub async fn next_file_sequence(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<i32> {
+        let (sequence,): (i32,) = sqlx::query_as(
+            r#"SELECT COALESCE(MAX(file_sequence), 0) + 1 FROM remittances WHERE account_id = $1"#,
+        )
+        .bind(account_id)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(sequence)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        remittance: &Remittance,
+    ) -> anyhow::Result<Remittance> {
+        let remittance = sqlx::query_as::<_, Remittance>(
+            r#"
+            INSERT INTO remittances (id, account_id, file_sequence, created_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(remittance.id)
+        .bind(remittance.account_id)
+        .bind(remittance.file_sequence)
+        .bind(remittance.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(remittance)
+    }
+
+    pub async fn create_item(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        item: &RemittanceItem,
+    ) -> anyhow::Result<RemittanceItem> {
+        let item = sqlx::query_as::<_, RemittanceItem>(
+            r#"
+            INSERT INTO remittance_items (remittance_id, sequence, transaction_id, status)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(item.remittance_id)
+        .bind(item.sequence)
+        .bind(item.transaction_id)
+        .bind(item.status)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(item)
+    }
+
+    /// Reads the item at `sequence` of file `file_sequence` of an account and locks its row
+    /// until the surrounding transaction ends.
+    pub async fn find_item_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        file_sequence: i32,
+        sequence: i32,
+    ) -> anyhow::Result<Option<RemittanceItem>> {
+        let item = sqlx::query_as::<_, RemittanceItem>(
+            r#"
+            SELECT remittance_items.* FROM remittance_items
+            JOIN remittances ON remittances.id = remittance_items.remittance_id
+            WHERE remittances.account_id = $1
+                AND remittances.file_sequence = $2
+                AND remittance_items.sequence = $3
+            FOR UPDATE OF remittance_items
+            "#,
+        )
+        .bind(account_id)
+        .bind(file_sequence)
+        .bind(sequence)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(item)
+    }
+
+    /// Writes what a return file reported about an item.
+    pub async fn save_item(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        item: &RemittanceItem,
+    ) -> anyhow::Result<RemittanceItem> {
+        let item = sqlx::query_as::<_, RemittanceItem>(
+            r#"
+            UPDATE remittance_items
+            SET status = $3, bank_reference = $4, occurrences = $5, updated_at = $6
+            WHERE remittance_id = $1 AND sequence = $2
+            RETURNING *
+            "#,
+        )
+        .bind(item.remittance_id)
+        .bind(item.sequence)
+        .bind(item.status)
+        .bind(&item.bank_reference)
+        .bind(&item.occurrences)
+        .bind(item.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(item)
+    }
+}
@@ -0,0 +1,174 @@
+use sqlx::{PgPool, Postgres, Row, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{filters::schedule::Filter as ScheduleFilter, models::schedule_dto::Schedule};
+
+#[derive(Debug, Clone)]
+pub struct ScheduleRepository;
+
+impl Default for ScheduleRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScheduleRepository {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn find_all(
+        &self,
+        db_pool: &PgPool,
+        filters: &ScheduleFilter,
+    ) -> anyhow::Result<Vec<Schedule>> {
+        let args = filters.get_arguments();
+        let query = r#"SELECT * FROM schedules "#.to_owned() + &filters.query();
+
+        let schedules = sqlx::query_as_with::<_, Schedule, _>(&query, args)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(schedules)
+    }
+
+    pub async fn get_total(
+        &self,
+        db_pool: &PgPool,
+        filters: &ScheduleFilter,
+    ) -> anyhow::Result<u64> {
+        let args = filters.get_arguments();
+        let query = r#"SELECT COUNT(*) as total FROM schedules "#.to_owned() + &filters.total();
+        let result = sqlx::query_with(&query, args).fetch_one(db_pool).await?;
+
+        Ok(result.get::<i64, &str>("total") as u64)
+    }
+
+    pub async fn find_by_id(&self, db_pool: &PgPool, id: &Uuid) -> anyhow::Result<Schedule> {
+        let schedule = sqlx::query_as::<_, Schedule>(r#"SELECT * FROM schedules WHERE id = $1"#)
+            .bind(id)
+            .fetch_one(db_pool)
+            .await?;
+
+        Ok(schedule)
+    }
+
+    /// Reads a schedule and locks its row until the surrounding transaction ends.
+    pub async fn find_by_id_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Schedule> {
+        let schedule =
+            sqlx::query_as::<_, Schedule>(r#"SELECT * FROM schedules WHERE id = $1 FOR UPDATE"#)
+                .bind(id)
+                .fetch_one(&mut **executor)
+                .await?;
+
+        Ok(schedule)
+    }
+
+    /// Locks the longest-waiting schedule due at `now`.
+    ///
+    /// Rows another worker already holds are skipped, so several workers can drain
+    /// the queue side by side without running a schedule twice.
+    pub async fn find_next_due_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        now: chrono::NaiveDateTime,
+    ) -> anyhow::Result<Option<Schedule>> {
+        let schedule = sqlx::query_as::<_, Schedule>(
+            r#"
+            SELECT * FROM schedules
+            WHERE status = 'active' AND COALESCE(retry_at, next_run_at) <= $1
+            ORDER BY COALESCE(retry_at, next_run_at), id
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(now)
+        .fetch_optional(&mut **executor)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn create(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        schedule: &Schedule,
+    ) -> anyhow::Result<Schedule> {
+        let schedule = sqlx::query_as::<_, Schedule>(
+            r#"
+            INSERT INTO schedules (
+                id, user_id, from_account_id, to_account_id, amount, frequency, cron_expression,
+                starts_at, ends_at, next_run_at, status, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING *
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(schedule.user_id)
+        .bind(schedule.from_account_id)
+        .bind(schedule.to_account_id)
+        .bind(&schedule.amount)
+        .bind(schedule.frequency)
+        .bind(&schedule.cron_expression)
+        .bind(schedule.starts_at)
+        .bind(schedule.ends_at)
+        .bind(schedule.next_run_at)
+        .bind(schedule.status)
+        .bind(schedule.created_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    /// Writes everything about a schedule that updates and runs can change.
+    pub async fn save(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        schedule: &Schedule,
+    ) -> anyhow::Result<Schedule> {
+        let schedule = sqlx::query_as::<_, Schedule>(
+            r#"
+            UPDATE schedules
+            SET amount = $2, ends_at = $3, next_run_at = $4, retry_at = $5, status = $6,
+                failed_attempts = $7, last_error = $8, last_run_at = $9, last_transaction_id = $10,
+                updated_at = $11
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(&schedule.amount)
+        .bind(schedule.ends_at)
+        .bind(schedule.next_run_at)
+        .bind(schedule.retry_at)
+        .bind(schedule.status)
+        .bind(schedule.failed_attempts)
+        .bind(&schedule.last_error)
+        .bind(schedule.last_run_at)
+        .bind(schedule.last_transaction_id)
+        .bind(schedule.updated_at)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn delete(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(r#"DELETE FROM schedules WHERE id = $1"#)
+            .bind(id)
+            .execute(&mut **executor)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
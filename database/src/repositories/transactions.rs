@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use sqlx::{PgPool, Postgres, Row, Transaction as SqlxTransaction};
 use uuid::Uuid;
 
@@ -5,8 +6,9 @@ use crate::{
     filters::transaction::Filter as TransactionFilter,
     models::{
         account_dto::Account,
-        transaction_dto::{Transaction, TransactionCreate},
+        transaction_dto::{Transaction, TransactionCreate, TransactionOperation},
     },
+    structs::money::MoneyEncoding,
 };
 
 use super::users::UserRepository;
@@ -65,6 +67,178 @@ impl TransactionRepository {
         Ok(transaction)
     }
 
+    /// Reads a transaction within the surrounding transaction, without locking it.
+    pub async fn find_by_id_within(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Transaction> {
+        let transaction =
+            sqlx::query_as::<_, Transaction>(r#"SELECT * from transactions WHERE id = $1"#)
+                .bind(id)
+                .fetch_one(&mut **executor)
+                .await?;
+
+        Ok(transaction)
+    }
+
+    /// Reads a transaction and locks its row until the surrounding transaction ends.
+    pub async fn find_by_id_for_update(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Transaction> {
+        let transaction = sqlx::query_as::<_, Transaction>(
+            r#"SELECT * FROM transactions WHERE id = $1 FOR UPDATE"#,
+        )
+        .bind(id)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(transaction)
+    }
+
+    pub async fn find_reversals(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"SELECT * FROM transactions WHERE reverses_transaction_id = $1 ORDER BY created_at, id"#,
+        )
+        .bind(id)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(transactions)
+    }
+
+    /// Every transaction into or out of the account, oldest first, read in the open transaction.
+    pub async fn find_by_account_id(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM transactions
+            WHERE from_account_id = $1 OR to_account_id = $1
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(account_id)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(transactions)
+    }
+
+    /// Ids of the reversals written against each of `ids`, oldest first.
+    pub async fn find_reversal_ids(
+        &self,
+        db_pool: &PgPool,
+        ids: &[Uuid],
+    ) -> anyhow::Result<Vec<(Uuid, Uuid)>> {
+        let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT reverses_transaction_id, id FROM transactions
+            WHERE reverses_transaction_id = ANY($1)
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(ids)
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Transactions of `operation` that any of `account_ids` initiated after `since`,
+    /// counting the ones written by the open transaction.
+    pub async fn find_initiated_since(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        operation: &TransactionOperation,
+        account_ids: &[Uuid],
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM transactions
+            WHERE operation = $1
+                AND (CASE WHEN operation = 'transfer' THEN from_account_id ELSE to_account_id END) = ANY($2)
+                AND created_at > $3
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(operation)
+        .bind(account_ids)
+        .bind(since)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(transactions)
+    }
+
+    /// How many `operation`s the account initiated from `since` on.
+    pub async fn count_initiated_since(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        operation: &TransactionOperation,
+        account_id: &Uuid,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM transactions
+            WHERE operation = $1
+                AND (CASE WHEN operation = 'transfer' THEN from_account_id ELSE to_account_id END) = $2
+                AND created_at >= $3
+            "#,
+        )
+        .bind(operation)
+        .bind(account_id)
+        .bind(since)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Up to `limit` transactions of an account created in `[since, until)`, oldest first,
+    /// resuming after the `(created_at, id)` of the last one read.
+    pub async fn find_account_page(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        since: Option<NaiveDateTime>,
+        until: NaiveDateTime,
+        after: Option<(NaiveDateTime, Uuid)>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM transactions
+            WHERE (from_account_id = $1 OR to_account_id = $1)
+                AND ($2::timestamp IS NULL OR created_at >= $2)
+                AND created_at < $3
+                AND ($4::timestamp IS NULL OR (created_at, id) > ($4, $5::uuid))
+            ORDER BY created_at, id
+            LIMIT $6
+            "#,
+        )
+        .bind(account_id)
+        .bind(since)
+        .bind(until)
+        .bind(after.map(|(created_at, _)| created_at))
+        .bind(after.map(|(_, id)| id))
+        .bind(limit)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(transactions)
+    }
+
     pub async fn create(
         &self,
         db_pool: &PgPool,
@@ -82,19 +256,53 @@ impl TransactionRepository {
         let transaction = transaction_create.to_transaction(&user.encryption_key)?;
 
         let created_transaction = sqlx::query_as::<_, Transaction>(
-            r#"INSERT INTO transactions (id, operation, from_account_id, to_account_id, amount) VALUES ($1, $2, $3, $4, $5) RETURNING *"#,
+            r#"INSERT INTO transactions (id, operation, from_account_id, to_account_id, amount, amount_encoding, reverses_transaction_id, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *"#,
         )
         .bind(transaction_id)
         .bind(&transaction.operation)
         .bind(transaction.from_account_id)
         .bind(transaction.to_account_id)
         .bind(&transaction.amount)
+        .bind(transaction.amount_encoding)
+        .bind(transaction.reverses_transaction_id)
+        .bind(chrono::Utc::now().naive_utc())
         .fetch_one(&mut **executor)
         .await?;
 
         Ok(created_transaction)
     }
 
+    pub async fn find_by_amount_encoding(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        encoding: MoneyEncoding,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"SELECT * FROM transactions WHERE amount_encoding = $1 ORDER BY id FOR UPDATE"#,
+        )
+        .bind(encoding)
+        .fetch_all(&mut **executor)
+        .await?;
+
+        Ok(transactions)
+    }
+
+    /// Rewrites the amount ciphertext only; every other column is immutable.
+    pub async fn save_amount(
+        &self,
+        executor: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &Transaction,
+    ) -> anyhow::Result<()> {
+        sqlx::query(r#"UPDATE transactions SET amount = $2, amount_encoding = $3 WHERE id = $1"#)
+            .bind(transaction.id)
+            .bind(&transaction.amount)
+            .bind(transaction.amount_encoding)
+            .execute(&mut **executor)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn update(&self) -> anyhow::Result<Transaction> {
         Err(anyhow::anyhow!("Transaction alterations are not allowed"))
     }
@@ -114,17 +322,4 @@ impl TransactionRepository {
 
         Ok(result.get::<i64, &str>("total") as u64)
     }
-
-    pub async fn delete_by_account_id(
-        &self,
-        executor: &mut SqlxTransaction<'_, Postgres>,
-        account_id: &Uuid,
-    ) -> anyhow::Result<()> {
-        sqlx::query(r#"DELETE FROM transactions WHERE to_account_id = $1"#)
-            .bind(account_id)
-            .execute(&mut **executor)
-            .await?;
-
-        Ok(())
-    }
 }
@@ -48,6 +48,36 @@ impl UserRepository {
         Ok(user)
     }
 
+    /// The owner of an account, read through the open transaction.
+    pub async fn find_by_account_id(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<User> {
+        let user = sqlx::query_as::<_, User>(
+            r#"SELECT users.* FROM users JOIN accounts ON accounts.user_id = users.id WHERE accounts.id = $1"#,
+        )
+        .bind(account_id)
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(user)
+    }
+
+    /// Reads a user and locks its row until the surrounding transaction ends.
+    pub async fn find_by_id_for_update(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<User> {
+        let user = sqlx::query_as::<_, User>(r#"SELECT * FROM users WHERE id = $1 FOR UPDATE"#)
+            .bind(id)
+            .fetch_one(&mut **executor)
+            .await?;
+
+        Ok(user)
+    }
+
     pub async fn find_one_by_filter(
         &self,
         executor: &PgPool,
@@ -112,17 +142,32 @@ impl UserRepository {
         Ok(user)
     }
 
+    /// Soft deletes a user, failing when there is no user left to delete.
     pub async fn delete(&self, executor: &mut Transaction<'_, Postgres>, id: &Uuid) -> bool {
-        sqlx::query!(
-            r#"
-            DELETE FROM users
-            WHERE id = $1
-            "#,
-            id
+        sqlx::query(
+            r#"UPDATE users SET deleted_at = $2, updated_at = $2 WHERE id = $1 AND deleted_at IS NULL"#,
         )
+        .bind(id)
+        .bind(chrono::Utc::now().naive_utc())
         .execute(&mut **executor)
         .await
-        .is_ok()
+        .is_ok_and(|result| result.rows_affected() > 0)
+    }
+
+    pub async fn restore(
+        &self,
+        executor: &mut Transaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<User> {
+        let user = sqlx::query_as::<_, User>(
+            r#"UPDATE users SET deleted_at = NULL, updated_at = $2 WHERE id = $1 RETURNING *"#,
+        )
+        .bind(id)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_one(&mut **executor)
+        .await?;
+
+        Ok(user)
     }
 
     pub async fn get_total(&self, executor: &PgPool, filters: &UserFilter) -> anyhow::Result<u64> {
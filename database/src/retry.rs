@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use rand::Rng;
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+
+/// How many times an operation is attempted before its last error is returned.
+pub const MAX_TRANSACTION_ATTEMPTS: u32 = 5;
+
+const SERIALIZATION_FAILURE: &str = "40001";
+const DEADLOCK_DETECTED: &str = "40P01";
+
+/// Whether `error` was caused by Postgres aborting the transaction because of a
+/// serialization failure or a deadlock, in which case it is safe to run it again.
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|cause| match cause.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::Database(db_error)) => matches!(
+                db_error.code().as_deref(),
+                Some(SERIALIZATION_FAILURE) | Some(DEADLOCK_DETECTED)
+            ),
+            _ => false,
+        })
+}
+
+/// Runs `operation` inside a fresh database transaction and commits it.
+///
+/// When the operation or the commit fails with a retryable error the transaction
+/// is rolled back and the operation runs again from scratch, with a jittered
+/// exponential backoff, up to [`MAX_TRANSACTION_ATTEMPTS`] times. Any other error
+/// rolls back and is returned immediately.
+pub async fn with_transaction_retry<T, F>(db_pool: &PgPool, mut operation: F) -> anyhow::Result<T>
+where
+    F: for<'t> FnMut(
+        &'t mut SqlxTransaction<'static, Postgres>,
+    ) -> BoxFuture<'t, anyhow::Result<T>>,
+{
+    let mut attempt = 1;
+
+    loop {
+        let mut tx = db_pool.begin().await?;
+
+        let result = match operation(&mut tx).await {
+            Ok(value) => tx
+                .commit()
+                .await
+                .map(|_| value)
+                .map_err(anyhow::Error::from),
+            Err(e) => {
+                tx.rollback().await.ok();
+                Err(e)
+            }
+        };
+
+        match result {
+            Err(e) if attempt < MAX_TRANSACTION_ATTEMPTS && is_retryable(&e) => {
+                tokio::time::sleep(backoff(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let base = 10 * 2_u64.pow(attempt - 1);
+    Duration::from_millis(base + rand::thread_rng().gen_range(0..=base))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_application_errors_are_not_retryable() {
+        let error = anyhow::anyhow!("Not enough funds");
+
+        assert!(!is_retryable(&error));
+    }
+
+    #[test]
+    fn test_non_database_sqlx_errors_are_not_retryable() {
+        let error = anyhow::Error::from(sqlx::Error::RowNotFound);
+
+        assert!(!is_retryable(&error));
+    }
+
+    #[test]
+    fn test_backoff_grows_with_attempts() {
+        for attempt in 1..MAX_TRANSACTION_ATTEMPTS {
+            let base = 10 * 2_u64.pow(attempt - 1);
+            let delay = backoff(attempt).as_millis() as u64;
+
+            assert!((base..=base * 2).contains(&delay));
+        }
+    }
+}
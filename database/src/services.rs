@@ -1,3 +1,20 @@
 pub mod account;
+pub mod audit;
+pub mod bank;
+pub mod boleto;
+pub mod employer;
+pub mod fee;
+pub mod hold;
+pub mod idempotency;
+pub mod interest;
+pub mod journal;
+pub mod limit;
+pub mod overdraft;
+pub mod pix_key;
+pub mod qr_code;
+pub mod reconciliation;
+pub mod remittance;
+pub mod schedule;
+pub mod statement;
 pub mod transaction;
 pub mod user;
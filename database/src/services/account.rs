@@ -4,19 +4,29 @@ use uuid::Uuid;
 use crate::{
     filters::{account::Filter as AccountFilter, user::Filter as UserFilter},
     models::{
-        account_dto::{Account, AccountCreate},
+        account_dto::{
+            Account, AccountClose, AccountCreate, AccountStatus, AccountStatusError, AccountUpdate,
+        },
         transaction_dto::{TransactionCreate, TransactionOperation},
     },
     repositories::{
-        accounts::AccountRepository, transactions::TransactionRepository, users::UserRepository,
+        accounts::AccountRepository, banks::BankRepository, holds::HoldRepository,
+        journal::JournalRepository, transactions::TransactionRepository, users::UserRepository,
     },
+    services::transaction::Service as TransactionService,
+    structs::money::MoneyEncoding,
+    validation::{self, BankDetails, FieldError, InvalidBankDetails},
 };
 
 #[derive(Debug)]
 pub struct Service {
     transaction_repository: TransactionRepository,
     account_repository: AccountRepository,
+    bank_repository: BankRepository,
+    hold_repository: HoldRepository,
+    journal_repository: JournalRepository,
     user_repository: UserRepository,
+    transaction_service: TransactionService,
 }
 
 impl Default for Service {
@@ -29,8 +39,12 @@ impl Service {
     pub fn new() -> Self {
         Self {
             account_repository: AccountRepository::new(),
+            bank_repository: BankRepository::new(),
+            hold_repository: HoldRepository::new(),
+            journal_repository: JournalRepository::new(),
             transaction_repository: TransactionRepository::new(),
             user_repository: UserRepository::new(),
+            transaction_service: TransactionService::new(),
         }
     }
 
@@ -89,9 +103,11 @@ impl Service {
         user_id: &Uuid,
         account: AccountCreate,
     ) -> anyhow::Result<Account> {
-        if account.balance < 0.0 {
+        if account.balance.is_negative() {
             return Err(anyhow::anyhow!("Balance cannot be negative"));
         }
+        self.validate_bank_details(tx, &account.bank_details())
+            .await?;
 
         let initial_balance = account.balance;
 
@@ -102,29 +118,465 @@ impl Service {
         let transaction = TransactionCreate {
             from_account_id: None,
             to_account_id: account.id,
+            to_key: None,
             amount: initial_balance,
             operation: TransactionOperation::Deposit,
+            reverses_transaction_id: None,
         };
 
-        if initial_balance > 0.0 {
-            self.transaction_repository
+        if initial_balance.is_positive() {
+            let transaction = self
+                .transaction_repository
                 .create(db_pool, tx, &account, &transaction)
                 .await?;
+            self.journal_repository
+                .record(tx, &transaction, initial_balance)
+                .await?;
         }
 
         Ok(account)
     }
 
-    pub async fn delete(&self, tx: &mut SqlxTransaction<'_, Postgres>, id: &Uuid) -> bool {
-        match self
-            .transaction_repository
-            .delete_by_account_id(tx, id)
+    /// Checks the bank is registered and operating, and the check digits of the agency and
+    /// account, reporting every field at fault.
+    async fn validate_bank_details(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        details: &BankDetails,
+    ) -> anyhow::Result<()> {
+        let mut errors = match validation::validate(details) {
+            Ok(()) => Vec::new(),
+            Err(InvalidBankDetails(errors)) => errors,
+        };
+        if let Some(bank_id) = details.bank_id {
+            let message = match self.bank_repository.find_by_id(&mut **tx, bank_id).await? {
+                None => Some(format!("No bank is registered under code {:03}", bank_id)),
+                Some(bank) if !bank.active => {
+                    Some(format!("{} is no longer in operation", bank.short_name))
+                }
+                Some(_) => None,
+            };
+            if let Some(message) = message {
+                errors.insert(
+                    0,
+                    FieldError {
+                        field: "bank_id",
+                        message,
+                    },
+                );
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(InvalidBankDetails(errors).into()),
+        }
+    }
+
+    /// Moves an account to another bank, agency or account number, checking their digits.
+    pub async fn update(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+        account: &AccountUpdate,
+    ) -> anyhow::Result<Account> {
+        self.validate_bank_details(tx, &account.bank_details())
+            .await?;
+
+        self.account_repository.update(tx, id, account).await
+    }
+
+    /// Re-encrypts every balance still stored as a legacy `f64` payload.
+    pub async fn upgrade_legacy_balances(
+        &self,
+        db_pool: &PgPool,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+    ) -> anyhow::Result<u64> {
+        let accounts = self
+            .account_repository
+            .find_by_balance_encoding(tx, MoneyEncoding::LegacyFloat)
+            .await?;
+        let total = accounts.len() as u64;
+
+        for mut account in accounts {
+            let user = self
+                .user_repository
+                .find_by_id(db_pool, &account.user_id)
+                .await?;
+            account.reencrypt_balance(&user)?;
+            self.account_repository.save_balance(tx, &account).await?;
+        }
+
+        Ok(total)
+    }
+
+    /// Stops anything from being taken out of an account until it is unfrozen.
+    pub async fn freeze(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Account> {
+        self.transition(tx, id, AccountStatus::Frozen).await
+    }
+
+    pub async fn unfreeze(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Account> {
+        self.transition(tx, id, AccountStatus::Active).await
+    }
+
+    /// Closes an account, paying its balance out to another account first; its transactions
+    /// are kept.
+    ///
+    /// An account with funds still held on it is left closing, taking nothing new until the
+    /// holds are settled and it is closed again.
+    pub async fn close(
+        &self,
+        db_pool: &PgPool,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+        close: &AccountClose,
+    ) -> anyhow::Result<Account> {
+        let account = self.transition(tx, id, AccountStatus::Closing).await?;
+        if !self
+            .hold_repository
+            .find_active_by_account_id(&mut **tx, id)
+            .await?
+            .is_empty()
+        {
+            return Ok(account);
+        }
+
+        let owner = self.user_repository.find_by_account_id(tx, id).await?;
+        let balance = account.get_balance(&owner)?;
+        if balance.is_negative() {
+            return Err(AccountStatusError::Overdrawn.into());
+        }
+        if balance.is_positive() {
+            let payout_account_id = close
+                .payout_account_id
+                .ok_or(AccountStatusError::BalanceNotPaidOut)?;
+            if payout_account_id == *id {
+                return Err(AccountStatusError::SamePayoutAccount.into());
+            }
+            self.transaction_service
+                .pay_out(db_pool, tx, id, &payout_account_id, balance)
+                .await?;
+        }
+
+        self.account_repository
+            .set_status(tx, id, AccountStatus::Closed)
             .await
+    }
+
+    /// Closes an account with nothing left in it and hides it from listings, keeping it and
+    /// its history in place.
+    pub async fn delete(
+        &self,
+        db_pool: &PgPool,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Account> {
+        let account = self
+            .account_repository
+            .find_by_id_for_update(tx, id)
+            .await?;
+        if account.status != AccountStatus::Closed
+            && self
+                .close(db_pool, tx, id, &AccountClose::default())
+                .await?
+                .status
+                != AccountStatus::Closed
         {
-            Ok(_) => {}
-            Err(_) => return false,
+            return Err(AccountStatusError::FundsHeld.into());
         }
 
-        self.account_repository.delete(tx, id).await
+        self.account_repository.soft_delete(tx, id).await
+    }
+
+    pub async fn restore(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Account> {
+        self.account_repository.restore(tx, id).await
+    }
+
+    async fn transition(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+        status: AccountStatus,
+    ) -> anyhow::Result<Account> {
+        let account = self
+            .account_repository
+            .find_by_id_for_update(tx, id)
+            .await?;
+        if !account.status.can_become(status) {
+            return Err(AccountStatusError::InvalidTransition {
+                from: account.status,
+                to: status,
+            }
+            .into());
+        }
+
+        self.account_repository.set_status(tx, id, status).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        filters::transaction::Filter as TransactionFilter,
+        models::{hold_dto::HoldCreate, user_dto::User},
+        services::{hold::Service as HoldService, user::Service as UserService},
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    async fn transfer(
+        db_pool: &PgPool,
+        from: &Account,
+        to: &Account,
+        amount: i64,
+    ) -> Result<(), AccountStatusError> {
+        let mut tx = db_pool.begin().await.unwrap();
+        let result = TransactionService::new()
+            .create(
+                db_pool,
+                &mut tx,
+                &TransactionCreate {
+                    operation: TransactionOperation::Transfer,
+                    from_account_id: Some(from.id),
+                    to_account_id: to.id,
+                    to_key: None,
+                    amount: Money::from_minor_units(amount),
+                    reverses_transaction_id: None,
+                },
+                &from.user_id,
+            )
+            .await;
+        match result {
+            Ok(_) => {
+                tx.commit().await.unwrap();
+                Ok(())
+            }
+            Err(e) => Err(e.downcast::<AccountStatusError>().unwrap()),
+        }
+    }
+
+    async fn status(db_pool: &PgPool, owner: &User, account: &Account) -> (AccountStatus, Money) {
+        let account = Service::new()
+            .get_one_by_id(db_pool, &account.id)
+            .await
+            .unwrap();
+
+        (account.status, account.get_balance(owner).unwrap())
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_accounts_are_frozen_and_closed_keeping_their_history(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(10_000), Money::from_minor_units(0)],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        let (payee, payee_account) = &accounts[1];
+        transfer(&db_pool, account, payee_account, 1_000)
+            .await
+            .unwrap();
+
+        // frozen accounts are credited but not debited
+        let mut tx = db_pool.begin().await.unwrap();
+        Service::new().freeze(&mut tx, &account.id).await.unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(
+            transfer(&db_pool, account, payee_account, 1_000).await,
+            Err(AccountStatusError::Frozen)
+        );
+        transfer(&db_pool, payee_account, account, 500)
+            .await
+            .unwrap();
+        let mut tx = db_pool.begin().await.unwrap();
+        Service::new().unfreeze(&mut tx, &account.id).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let error = Service::new()
+            .close(&db_pool, &mut tx, &account.id, &AccountClose::default())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<AccountStatusError>(),
+            Some(&AccountStatusError::BalanceNotPaidOut)
+        );
+        tx.rollback().await.unwrap();
+
+        // held funds leave the account closing, taking nothing new but their capture
+        let mut tx = db_pool.begin().await.unwrap();
+        let hold = HoldService::new()
+            .create(
+                &mut tx,
+                &HoldCreate {
+                    account_id: account.id,
+                    to_account_id: Some(payee_account.id),
+                    amount: Money::from_minor_units(2_000),
+                    expires_at: None,
+                },
+            )
+            .await
+            .unwrap();
+        let close = AccountClose {
+            payout_account_id: Some(payee_account.id),
+        };
+        let closing = Service::new()
+            .close(&db_pool, &mut tx, &account.id, &close)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(closing.status, AccountStatus::Closing);
+        assert_eq!(
+            transfer(&db_pool, account, payee_account, 1_000).await,
+            Err(AccountStatusError::NotOpen(AccountStatus::Closing))
+        );
+        assert_eq!(
+            transfer(&db_pool, payee_account, account, 100).await,
+            Err(AccountStatusError::NotOpen(AccountStatus::Closing))
+        );
+
+        let mut tx = db_pool.begin().await.unwrap();
+        HoldService::new()
+            .capture(&db_pool, &mut tx, &hold.id, None)
+            .await
+            .unwrap();
+        let closed = Service::new()
+            .close(&db_pool, &mut tx, &account.id, &close)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert!(closed.closed_at.is_some());
+        assert_eq!(
+            status(&db_pool, owner, account).await,
+            (AccountStatus::Closed, Money::ZERO)
+        );
+        assert_eq!(
+            status(&db_pool, payee, payee_account).await,
+            (AccountStatus::Active, Money::from_minor_units(10_000))
+        );
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let error = Service::new()
+            .close(&db_pool, &mut tx, &account.id, &close)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<AccountStatusError>(),
+            Some(&AccountStatusError::InvalidTransition {
+                from: AccountStatus::Closed,
+                to: AccountStatus::Closing,
+            })
+        );
+        tx.rollback().await.unwrap();
+        assert_eq!(
+            transfer(&db_pool, payee_account, account, 100).await,
+            Err(AccountStatusError::NotOpen(AccountStatus::Closed))
+        );
+
+        // the opening deposit, both transfers, the capture and the payout
+        let (transactions, _) = TransactionService::new()
+            .get_all(
+                &db_pool,
+                &TransactionFilter {
+                    account_id: Some(account.id),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert_eq!(transactions.len(), 5);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_deleted_users_and_accounts_are_listed_only_on_request(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(1_000), Money::from_minor_units(0)],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        let (_, empty_account) = &accounts[1];
+        let listed = |include_deleted: Option<bool>| {
+            let db_pool = db_pool.clone();
+            let user_id = empty_account.user_id;
+            async move {
+                let (accounts, total) = Service::new()
+                    .get_all(
+                        &db_pool,
+                        &AccountFilter {
+                            user_id: Some(user_id),
+                            include_deleted,
+                            ..Default::default()
+                        },
+                    )
+                    .await;
+                assert_eq!(accounts.len() as u64, total);
+                accounts.len()
+            }
+        };
+
+        // money is never left behind in a deleted account
+        let mut tx = db_pool.begin().await.unwrap();
+        let error = Service::new()
+            .delete(&db_pool, &mut tx, &account.id)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<AccountStatusError>(),
+            Some(&AccountStatusError::BalanceNotPaidOut)
+        );
+        tx.rollback().await.unwrap();
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let deleted = Service::new()
+            .delete(&db_pool, &mut tx, &empty_account.id)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(deleted.status, AccountStatus::Closed);
+        assert!(deleted.deleted_at.is_some());
+
+        assert_eq!(listed(None).await, 0);
+        assert_eq!(listed(Some(true)).await, 1);
+
+        let mut tx = db_pool.begin().await.unwrap();
+        assert!(UserService::new().delete(&mut tx, &owner.id).await);
+        assert!(!UserService::new().delete(&mut tx, &owner.id).await);
+        tx.commit().await.unwrap();
+        assert!(UserService::new()
+            .get_one_by_email(&db_pool, &owner.email)
+            .await
+            .is_none());
+
+        let mut tx = db_pool.begin().await.unwrap();
+        UserService::new()
+            .restore(&mut tx, &owner.id)
+            .await
+            .unwrap();
+        let restored = Service::new()
+            .restore(&mut tx, &empty_account.id)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(restored.status, AccountStatus::Closed);
+        assert!(UserService::new()
+            .get_one_by_email(&db_pool, &owner.email)
+            .await
+            .is_some());
+        assert_eq!(listed(None).await, 1);
     }
 }
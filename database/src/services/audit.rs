@@ -0,0 +1,280 @@
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    filters::audit::Filter as AuditFilter,
+    models::audit_dto::{AuditEntry, AuditFailure, AuditRecord, AuditTarget, AuditVerification},
+    repositories::audit::AuditRepository,
+};
+
+/// How many records verification reads at a time.
+const VERIFY_BATCH_SIZE: i64 = 1_000;
+
+#[derive(Debug)]
+pub struct Service {
+    audit_repository: AuditRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            audit_repository: AuditRepository::new(),
+        }
+    }
+
+    pub async fn get_all(
+        &self,
+        db_pool: &PgPool,
+        filters: &AuditFilter,
+    ) -> (Vec<AuditRecord>, u64) {
+        // if we had a logging system, we would log the error here
+        let records = (self.audit_repository.find_all(db_pool, filters).await).unwrap_or_default();
+        let total = (self.audit_repository.get_total(db_pool, filters).await).unwrap_or(0);
+
+        (records, total)
+    }
+
+    pub async fn get_snapshot(
+        &self,
+        db_pool: &PgPool,
+        target: AuditTarget,
+        id: &Uuid,
+    ) -> Option<Value> {
+        // if we had a logging system, we would log the error here
+        (self
+            .audit_repository
+            .find_snapshot(db_pool, target, id)
+            .await)
+            .ok()
+            .flatten()
+    }
+
+    /// Appends `entry` to the end of the chain, encrypting its sensitive fields with `master_key`.
+    pub async fn record(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        entry: &AuditEntry,
+        master_key: &[u8],
+    ) -> anyhow::Result<AuditRecord> {
+        self.audit_repository.lock(db_tx).await?;
+        let previous = self.audit_repository.find_last(db_tx).await?;
+        let record = AuditRecord::new(entry, previous.as_ref(), master_key)?;
+
+        self.audit_repository.create(db_tx, &record).await
+    }
+
+    /// Keeps `entry` aside, with the `error` that kept it out of the chain, sealing its
+    /// sensitive fields with `master_key` like [`record`](Self::record) does.
+    pub async fn record_failure(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        entry: &AuditEntry,
+        error: &str,
+        master_key: &[u8],
+    ) -> anyhow::Result<AuditFailure> {
+        let failure = AuditFailure::new(entry, error, master_key)?;
+
+        self.audit_repository.create_failure(db_tx, &failure).await
+    }
+
+    /// Walks the chain from the start, checking that each record follows the one before it
+    /// and still hashes to what was recorded.
+    pub async fn verify(&self, db_pool: &PgPool) -> anyhow::Result<AuditVerification> {
+        let mut verification = AuditVerification {
+            records: 0,
+            broken_at: None,
+            last_hash: None,
+        };
+        let mut last_sequence = 0;
+
+        loop {
+            let records = self
+                .audit_repository
+                .find_after(db_pool, last_sequence, VERIFY_BATCH_SIZE)
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+
+            for record in records {
+                let follows = record.sequence == last_sequence + 1
+                    && record.previous_hash == verification.last_hash;
+                if verification.broken_at.is_none()
+                    && (!follows || record.compute_hash()? != record.hash)
+                {
+                    verification.broken_at = Some(record.sequence);
+                }
+
+                verification.records += 1;
+                last_sequence = record.sequence;
+                verification.last_hash = Some(record.hash);
+            }
+        }
+
+        Ok(verification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{structs::money::Money, test_helpers::create_accounts};
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_tampering_breaks_the_chain(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::ZERO]).await;
+        let (owner, account) = &accounts[0];
+        let service = Service::new();
+        let key = [0u8; 32];
+
+        let before = service
+            .get_snapshot(&db_pool, AuditTarget::User, &owner.id)
+            .await
+            .unwrap();
+        assert_eq!(before["id"], owner.id.to_string());
+        for (action, target, id) in [
+            ("PUT /users/:id", AuditTarget::User, owner.id),
+            (
+                "POST /accounts/:id/freeze",
+                AuditTarget::Account,
+                account.id,
+            ),
+            ("DELETE /accounts/:id", AuditTarget::Account, account.id),
+        ] {
+            let mut tx = db_pool.begin().await.unwrap();
+            service
+                .record(
+                    &mut tx,
+                    &AuditEntry {
+                        actor_id: Some(owner.id),
+                        scopes: vec![],
+                        action: action.to_string(),
+                        target: Some(target),
+                        target_id: Some(id),
+                        status: 200,
+                        before: Some(before.clone()),
+                        after: service.get_snapshot(&db_pool, target, &id).await,
+                        ip: Some("127.0.0.1".to_string()),
+                        request_id: Uuid::now_v7().to_string(),
+                    },
+                    &key,
+                )
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+        }
+
+        let verification = service.verify(&db_pool).await.unwrap();
+        assert!(verification.is_intact());
+        assert_eq!(verification.records, 3);
+
+        let (records, total) = service
+            .get_all(
+                &db_pool,
+                &AuditFilter {
+                    target_id: Some(account.id),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert_eq!(total, 2);
+        assert_eq!(records[0].action, "DELETE /accounts/:id");
+        assert_eq!(
+            records[0].after.as_ref().unwrap()["id"],
+            account.id.to_string()
+        );
+        let (records, _) = service.get_all(&db_pool, &AuditFilter::default()).await;
+        assert_eq!(records[2].before.as_ref().unwrap()["name"], before["name"]);
+        assert_ne!(
+            records[2].before.as_ref().unwrap()["email"],
+            before["email"]
+        );
+
+        // the log refuses changes, so tampering has to get around the trigger first
+        assert!(sqlx::query("UPDATE audit_log SET status = 500")
+            .execute(&db_pool)
+            .await
+            .is_err());
+        assert!(sqlx::query("DELETE FROM audit_log")
+            .execute(&db_pool)
+            .await
+            .is_err());
+        sqlx::query("ALTER TABLE audit_log DISABLE TRIGGER reject_audit_log_changes")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE audit_log SET status = 500 WHERE sequence = 2")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(service.verify(&db_pool).await.unwrap().broken_at, Some(2));
+
+        sqlx::query("UPDATE audit_log SET status = 200 WHERE sequence = 2")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM audit_log WHERE sequence = 1")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let verification = service.verify(&db_pool).await.unwrap();
+        assert_eq!(verification.broken_at, Some(2));
+        assert_eq!(verification.records, 2);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_failed_entries_are_kept_aside(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::ZERO]).await;
+        let (owner, _) = &accounts[0];
+        let service = Service::new();
+        let after = service
+            .get_snapshot(&db_pool, AuditTarget::User, &owner.id)
+            .await;
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let failure = service
+            .record_failure(
+                &mut tx,
+                &AuditEntry {
+                    actor_id: Some(owner.id),
+                    scopes: vec![],
+                    action: "PUT /users/:id".to_string(),
+                    target: Some(AuditTarget::User),
+                    target_id: Some(owner.id),
+                    status: 200,
+                    before: None,
+                    after: after.clone(),
+                    ip: None,
+                    request_id: "request".to_string(),
+                },
+                "audit_log is append-only",
+                &[0u8; 32],
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let (request_id, error, sealed): (String, String, Value) =
+            sqlx::query_as("SELECT request_id, error, after FROM audit_failures WHERE id = $1")
+                .bind(failure.id)
+                .fetch_one(&db_pool)
+                .await
+                .unwrap();
+        assert_eq!(request_id, "request");
+        assert_eq!(error, "audit_log is append-only");
+        let after = after.unwrap();
+        assert_eq!(sealed["name"], after["name"]);
+        assert_ne!(sealed["email"], after["email"]);
+        // the chain itself is left alone
+        assert_eq!(service.verify(&db_pool).await.unwrap().records, 0);
+    }
+}
@@ -0,0 +1,153 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+
+use crate::{
+    filters::bank::Filter as BankFilter,
+    models::bank_dto::{parse_registry, Bank, BankImportReport},
+    repositories::banks::BankRepository,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    bank_repository: BankRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            bank_repository: BankRepository::new(),
+        }
+    }
+
+    pub async fn get_one_by_id(&self, db_pool: &PgPool, id: i32) -> Option<Bank> {
+        // if we had a logging system, we would log the error here
+        (self.bank_repository.find_by_id(db_pool, id).await)
+            .ok()
+            .flatten()
+    }
+
+    pub async fn get_all(&self, db_pool: &PgPool, filters: &BankFilter) -> (Vec<Bank>, u64) {
+        // if we had a logging system, we would log the error here
+        let banks = (self.bank_repository.find_all(db_pool, filters).await).unwrap_or_default();
+        let total = (self.bank_repository.get_total(db_pool, filters).await).unwrap_or(0);
+
+        (banks, total)
+    }
+
+    /// Refreshes the registry from the participant list of the central bank. Banks missing
+    /// from the list are deactivated rather than removed, since accounts may still name them.
+    pub async fn import(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        csv: &str,
+    ) -> anyhow::Result<BankImportReport> {
+        let entries = parse_registry(csv)?;
+        let ids: Vec<i32> = entries.iter().map(|entry| entry.id).collect();
+        let ispbs: Vec<String> = entries.iter().map(|entry| entry.ispb.clone()).collect();
+
+        let mut report = BankImportReport {
+            deactivated: self
+                .bank_repository
+                .deactivate_missing(tx, &ids, &ispbs)
+                .await?,
+            ..Default::default()
+        };
+        for entry in &entries {
+            match self.bank_repository.upsert(tx, entry).await? {
+                Some(true) => report.added += 1,
+                Some(false) => report.updated += 1,
+                None => report.unchanged += 1,
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::account_dto::AccountUpdate,
+        services::account::Service as AccountService,
+        structs::money::Money,
+        test_helpers::create_accounts,
+        validation::{FieldError, InvalidBankDetails},
+    };
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_import_refreshes_the_registry_accounts_are_checked_against(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::ZERO]).await;
+        let (_, account) = &accounts[0];
+        let csv = "ISPB,Nome_Reduzido,Número_Código,Participa_da_Compe,Acesso_Principal,Nome_Extenso,Início_da_Operação\n\
+            00000000,BCO DO BRASIL S.A.,001,Sim,RSFN,Banco do Brasil S.A.,22/04/2002\n\
+            60746948,BCO BRADESCO S.A.,237,Sim,RSFN,Banco Bradesco S.A. (renamed),22/04/2002\n\
+            99999999,BCO NOVO S.A.,999,Sim,RSFN,Banco Novo S.A.,01/10/2026\n";
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let report = Service::new().import(&mut tx, csv).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let (seeded, _) = Service::new()
+            .get_all(&db_pool, &BankFilter::default())
+            .await;
+        assert_eq!(
+            report,
+            BankImportReport {
+                added: 1,
+                updated: 1,
+                unchanged: 1,
+                deactivated: seeded.len() as u64 - 3,
+            }
+        );
+        let filter = BankFilter {
+            search: Some("renamed".to_string()),
+            active: Some(true),
+            ..Default::default()
+        };
+        let (found, total) = Service::new().get_all(&db_pool, &filter).await;
+        assert_eq!((found[0].id, total), (237, 1));
+
+        let update = |bank_id| {
+            let db_pool = db_pool.clone();
+            let update = AccountUpdate {
+                bank_id: Some(bank_id),
+                bank_account_number: None,
+                bank_account_digit: None,
+                bank_agency_number: None,
+                bank_agency_digit: None,
+            };
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let result = AccountService::new()
+                    .update(&mut tx, &account.id, &update)
+                    .await;
+                tx.commit().await.unwrap();
+                result
+            }
+        };
+        assert_eq!(update(999).await.unwrap().bank_id, Some(999));
+        for (bank_id, message) in [
+            (341, "ITAÚ UNIBANCO S.A. is no longer in operation"),
+            (998, "No bank is registered under code 998"),
+        ] {
+            assert_eq!(
+                update(bank_id)
+                    .await
+                    .unwrap_err()
+                    .downcast::<InvalidBankDetails>()
+                    .unwrap(),
+                InvalidBankDetails(vec![FieldError {
+                    field: "bank_id",
+                    message: message.to_string(),
+                }])
+            );
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use chrono::NaiveDate;
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::AccountTypeError,
+        boleto_dto::{
+            Boleto, BoletoCreate, BoletoError, BoletoPayment, BoletoPaymentCreate, BoletoQuote,
+            BoletoStatus,
+        },
+        transaction_dto::{TransactionCreate, TransactionOperation},
+    },
+    payment_files::boleto::{self, Barcode},
+    repositories::{accounts::AccountRepository, boletos::BoletoRepository, users::UserRepository},
+    services::transaction::Service as TransactionService,
+    structs::money::Money,
+};
+
+const MAX_PAYEE_LENGTH: usize = 100;
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    boleto_repository: BoletoRepository,
+    transaction_service: TransactionService,
+    user_repository: UserRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            boleto_repository: BoletoRepository::new(),
+            transaction_service: TransactionService::new(),
+            user_repository: UserRepository::new(),
+        }
+    }
+
+    pub async fn get_one_by_id(&self, db_pool: &PgPool, id: &Uuid) -> Option<Boleto> {
+        // if we had a logging system, we would log the error here
+        (self.boleto_repository.find_by_id(db_pool, id).await).ok()
+    }
+
+    pub async fn get_all_by_account_id(&self, db_pool: &PgPool, account_id: &Uuid) -> Vec<Boleto> {
+        // if we had a logging system, we would log the error here
+        (self
+            .boleto_repository
+            .find_by_account_id(db_pool, account_id)
+            .await)
+            .unwrap_or_default()
+    }
+
+    pub async fn get_payments_by_account_id(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> Vec<BoletoPayment> {
+        // if we had a logging system, we would log the error here
+        (self
+            .boleto_repository
+            .find_payments_by_account_id(db_pool, account_id)
+            .await)
+            .unwrap_or_default()
+    }
+
+    /// Issues a slip for receiving into `account_id`, under the account's bank and agency and
+    /// numbered after its last slip.
+    pub async fn issue(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        boleto: &BoletoCreate,
+        today: NaiveDate,
+    ) -> anyhow::Result<Boleto> {
+        boleto.validate(today)?;
+
+        // the lock keeps two slips of the account from taking the same number
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, account_id)
+            .await?;
+        let (Some(bank_id), Some(agency), Some(account_number)) = (
+            account.bank_id,
+            account.bank_agency_number,
+            account.bank_account_number,
+        ) else {
+            return Err(BoletoError::MissingBankDetails.into());
+        };
+
+        let our_number = self
+            .boleto_repository
+            .next_our_number(db_tx, account_id)
+            .await?;
+        let barcode = Barcode {
+            bank_id,
+            due_date: Some(boleto.due_date),
+            amount: Some(boleto.amount),
+            free_field: boleto::free_field(agency, our_number, account_number)?,
+        }
+        .digits()?;
+
+        self.boleto_repository
+            .create(
+                db_tx,
+                &Boleto::new(*account_id, our_number, barcode, boleto),
+            )
+            .await
+    }
+
+    /// Reads a typeable line or barcode and works out what paying it on `today` takes. Slips
+    /// issued here name their payee and charge their own fine and interest when late.
+    pub async fn quote(
+        &self,
+        db_pool: &PgPool,
+        line: &str,
+        today: NaiveDate,
+    ) -> anyhow::Result<BoletoQuote> {
+        let barcode = boleto::parse(line, today)?;
+        let digits = barcode.digits()?;
+        let mut quote = BoletoQuote {
+            typeable_line: barcode.typeable_line()?,
+            barcode: digits.clone(),
+            bank_id: barcode.bank_id,
+            due_date: barcode.due_date,
+            amount: barcode.amount,
+            boleto_id: None,
+            payee: None,
+            fine: Money::ZERO,
+            interest: Money::ZERO,
+        };
+
+        if let Some(issued) = self
+            .boleto_repository
+            .find_by_barcode(db_pool, &digits)
+            .await?
+        {
+            if issued.status == BoletoStatus::Paid {
+                return Err(BoletoError::AlreadyPaid.into());
+            }
+            let account = self
+                .account_repository
+                .find_by_id(db_pool, &issued.account_id)
+                .await?;
+            let owner = self
+                .user_repository
+                .find_by_id(db_pool, &account.user_id)
+                .await?;
+
+            (quote.fine, quote.interest) = issued.charges(today);
+            quote.boleto_id = Some(issued.id);
+            quote.payee = Some(owner.name);
+        }
+
+        Ok(quote)
+    }
+
+    /// Pays a slip from `payment.from_account_id`. A slip issued here is paid as a transfer
+    /// to the issuing account, any other as a payment out of the bank.
+    pub async fn pay(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        payment: &BoletoPaymentCreate,
+        current_user_id: &Uuid,
+        today: NaiveDate,
+    ) -> anyhow::Result<BoletoPayment> {
+        let barcode = boleto::parse(&payment.line, today)?;
+        let digits = barcode.digits()?;
+
+        let amount = match (barcode.amount, payment.amount) {
+            (Some(requested), Some(given)) if requested != given => {
+                return Err(BoletoError::AmountMismatch { requested, given }.into())
+            }
+            (Some(amount), _) | (None, Some(amount)) => amount,
+            (None, None) => return Err(BoletoError::MissingAmount.into()),
+        };
+        if !amount.is_positive() {
+            return Err(BoletoError::InvalidAmount.into());
+        }
+        let charges = [payment.fine, payment.interest];
+        if charges.iter().flatten().any(|charge| charge.is_negative()) {
+            return Err(BoletoError::NegativeCharges.into());
+        }
+
+        // slips issued here are paid as transfers, which the transaction rules cannot tell apart
+        let payer = self
+            .account_repository
+            .find_by_id_for_update(db_tx, &payment.from_account_id)
+            .await?;
+        if let Some(account_type) = payer.bank_account_type {
+            if !account_type.rules().can_pay_boletos {
+                return Err(AccountTypeError::PaymentsNotAllowed(account_type).into());
+            }
+        }
+
+        let issued = self
+            .boleto_repository
+            .find_by_barcode_for_update(db_tx, &digits)
+            .await?;
+        let (payee, fine, interest, transaction) = match &issued {
+            Some(issued) => {
+                if issued.status == BoletoStatus::Paid {
+                    return Err(BoletoError::AlreadyPaid.into());
+                }
+                if issued.account_id == payment.from_account_id {
+                    return Err(BoletoError::SameAccount.into());
+                }
+                if charges.iter().any(Option::is_some) {
+                    return Err(BoletoError::ChargesGiven.into());
+                }
+
+                let owner = self
+                    .user_repository
+                    .find_by_account_id(db_tx, &issued.account_id)
+                    .await?;
+                let (fine, interest) = issued.charges(today);
+                let transaction = TransactionCreate {
+                    operation: TransactionOperation::Transfer,
+                    from_account_id: Some(payment.from_account_id),
+                    to_account_id: issued.account_id,
+                    to_key: None,
+                    amount: amount + fine + interest,
+                    reverses_transaction_id: None,
+                };
+                (owner.name, fine, interest, transaction)
+            }
+            None => {
+                let payee = match payment.payee.as_deref().map(str::trim) {
+                    Some(payee) if !payee.is_empty() => payee,
+                    _ => return Err(BoletoError::MissingPayee.into()),
+                };
+                let fine = payment.fine.unwrap_or(Money::ZERO);
+                let interest = payment.interest.unwrap_or(Money::ZERO);
+                let transaction = TransactionCreate {
+                    operation: TransactionOperation::Payment,
+                    from_account_id: None,
+                    to_account_id: payment.from_account_id,
+                    to_key: None,
+                    amount: amount + fine + interest,
+                    reverses_transaction_id: None,
+                };
+                (payee.to_string(), fine, interest, transaction)
+            }
+        };
+
+        let created = self
+            .transaction_service
+            .create(db_pool, db_tx, &transaction, current_user_id)
+            .await?;
+
+        let issued_id = issued.as_ref().map(|issued| issued.id);
+        if let Some(mut issued) = issued {
+            issued.status = BoletoStatus::Paid;
+            issued.transaction_id = Some(created.id);
+            issued.updated_at = Some(chrono::Utc::now().naive_utc());
+            self.boleto_repository.save(db_tx, &issued).await?;
+        }
+
+        self.boleto_repository
+            .create_payment(
+                db_tx,
+                &BoletoPayment {
+                    transaction_id: created.id,
+                    from_account_id: payment.from_account_id,
+                    boleto_id: issued_id,
+                    barcode: digits,
+                    payee: payee.chars().take(MAX_PAYEE_LENGTH).collect(),
+                    due_date: barcode.due_date,
+                    amount,
+                    fine,
+                    interest,
+                    created_at: created.created_at,
+                },
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::user_dto::User, test_helpers::create_accounts};
+
+    async fn balance(db_pool: &PgPool, owner: &User, account_id: &Uuid) -> Money {
+        AccountRepository::new()
+            .find_by_id(db_pool, account_id)
+            .await
+            .unwrap()
+            .get_balance(owner)
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_slips_issued_here_credit_their_account(db_pool: PgPool) {
+        let accounts =
+            create_accounts(&db_pool, &[Money::ZERO, Money::from_minor_units(50_000)]).await;
+        let (issuer, issuing_account) = &accounts[0];
+        let (payer, paying_account) = &accounts[1];
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let slip = BoletoCreate {
+            amount: Money::from_minor_units(10_000),
+            due_date: NaiveDate::from_ymd_opt(2026, 10, 30).unwrap(),
+            fine_bps: 200,
+            interest_bps: 100,
+        };
+        let issue = |account_id: Uuid| {
+            let db_pool = db_pool.clone();
+            let slip = slip.clone();
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let result = Service::new()
+                    .issue(&mut tx, &account_id, &slip, today)
+                    .await;
+                tx.commit().await.unwrap();
+                result
+            }
+        };
+        let pay = |payment: BoletoPaymentCreate, paid_on: NaiveDate| {
+            let db_pool = db_pool.clone();
+            let payer_id = payer.id;
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let result = Service::new()
+                    .pay(&db_pool, &mut tx, &payment, &payer_id, paid_on)
+                    .await;
+                tx.commit().await.unwrap();
+                result
+            }
+        };
+        let error = |result: anyhow::Result<BoletoPayment>| {
+            result.unwrap_err().downcast::<BoletoError>().unwrap()
+        };
+
+        assert_eq!(
+            issue(issuing_account.id)
+                .await
+                .unwrap_err()
+                .downcast::<BoletoError>()
+                .unwrap(),
+            BoletoError::MissingBankDetails
+        );
+        sqlx::query(
+            r#"UPDATE accounts SET bank_id = 1, bank_agency_number = 1234, bank_account_number = 56789 WHERE id = $1"#,
+        )
+        .bind(issuing_account.id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let on_time = issue(issuing_account.id).await.unwrap();
+        let late = issue(issuing_account.id).await.unwrap();
+        assert_eq!((on_time.our_number, late.our_number), (1, 2));
+        let line = |boleto: &Boleto| boleto::typeable_line(&boleto.barcode);
+        let payment = |line: String| BoletoPaymentCreate {
+            from_account_id: paying_account.id,
+            line,
+            amount: None,
+            payee: None,
+            fine: None,
+            interest: None,
+        };
+
+        let quote = Service::new()
+            .quote(&db_pool, &line(&on_time), today)
+            .await
+            .unwrap();
+        assert_eq!(quote.boleto_id, Some(on_time.id));
+        assert_eq!(quote.payee.as_deref(), Some(issuer.name.as_str()));
+        assert_eq!(quote.due_date, Some(slip.due_date));
+
+        let paid = pay(payment(line(&on_time)), today).await.unwrap();
+        assert_eq!(paid.boleto_id, Some(on_time.id));
+        assert_eq!(paid.total(), Money::from_minor_units(10_000));
+        assert_eq!(
+            balance(&db_pool, issuer, &issuing_account.id).await,
+            Money::from_minor_units(10_000)
+        );
+        assert_eq!(
+            error(pay(payment(line(&on_time)), today).await),
+            BoletoError::AlreadyPaid
+        );
+        assert_eq!(
+            Service::new()
+                .get_one_by_id(&db_pool, &on_time.id)
+                .await
+                .unwrap()
+                .transaction_id,
+            Some(paid.transaction_id)
+        );
+
+        let thirty_days_late = slip.due_date + chrono::Duration::days(30);
+        assert_eq!(
+            error(
+                pay(
+                    BoletoPaymentCreate {
+                        fine: Some(Money::ZERO),
+                        ..payment(line(&late))
+                    },
+                    thirty_days_late
+                )
+                .await
+            ),
+            BoletoError::ChargesGiven
+        );
+        let paid_late = pay(payment(line(&late)), thirty_days_late).await.unwrap();
+        assert_eq!(paid_late.fine, Money::from_minor_units(200));
+        assert_eq!(paid_late.interest, Money::from_minor_units(100));
+        assert_eq!(
+            balance(&db_pool, payer, &paying_account.id).await,
+            Money::from_minor_units(50_000 - 10_000 - 10_300)
+        );
+
+        // a slip of another bank is paid out of the bank, to the payee it names
+        let elsewhere = "00190.50095 40144.816069 06809.350314 3 37370000000100";
+        let paid_on = NaiveDate::from_ymd_opt(2008, 1, 2).unwrap();
+        assert_eq!(
+            error(pay(payment(elsewhere.to_string()), paid_on).await),
+            BoletoError::MissingPayee
+        );
+        let paid_elsewhere = pay(
+            BoletoPaymentCreate {
+                payee: Some("ACME Ltda".to_string()),
+                interest: Some(Money::from_minor_units(5)),
+                ..payment(elsewhere.to_string())
+            },
+            paid_on,
+        )
+        .await
+        .unwrap();
+        assert_eq!(paid_elsewhere.boleto_id, None);
+        assert_eq!(paid_elsewhere.total(), Money::from_minor_units(105));
+        assert_eq!(
+            balance(&db_pool, payer, &paying_account.id).await,
+            Money::from_minor_units(50_000 - 10_000 - 10_300 - 105)
+        );
+        assert_eq!(
+            Service::new()
+                .get_payments_by_account_id(&db_pool, &paying_account.id)
+                .await
+                .len(),
+            3
+        );
+    }
+}
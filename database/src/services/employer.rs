@@ -0,0 +1,90 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::AccountType,
+        employer_dto::{Employer, EmployerCreate, EmployerError},
+    },
+    repositories::{accounts::AccountRepository, employers::EmployerRepository},
+};
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    employer_repository: EmployerRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            employer_repository: EmployerRepository::new(),
+        }
+    }
+
+    pub async fn get_all_by_account_id(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> Vec<Employer> {
+        // if we had a logging system, we would log the error here
+        (self
+            .employer_repository
+            .find_by_account_id(db_pool, account_id)
+            .await)
+            .unwrap_or_default()
+    }
+
+    /// Lets `employer.employer_account_id` pay into the salary account `account_id`.
+    pub async fn register(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        employer: &EmployerCreate,
+    ) -> anyhow::Result<Employer> {
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, account_id)
+            .await?;
+        if account.bank_account_type != Some(AccountType::Salary) {
+            return Err(EmployerError::NotSalaryAccount.into());
+        }
+        if employer.employer_account_id == account.id {
+            return Err(EmployerError::SameAccount.into());
+        }
+        self.account_repository
+            .find_by_id_for_update(db_tx, &employer.employer_account_id)
+            .await?;
+
+        let employer = Employer {
+            account_id: account.id,
+            employer_account_id: employer.employer_account_id,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        self.employer_repository
+            .create(db_tx, &employer)
+            .await?
+            .ok_or_else(|| EmployerError::AlreadyRegistered.into())
+    }
+
+    pub async fn remove(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        employer_account_id: &Uuid,
+    ) -> bool {
+        // if we had a logging system, we would log the error here
+        (self
+            .employer_repository
+            .delete(db_tx, account_id, employer_account_id)
+            .await)
+            .unwrap_or(false)
+    }
+}
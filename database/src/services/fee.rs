@@ -0,0 +1,329 @@
+use chrono::NaiveDate;
+use sqlx::{Acquire, PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::InsufficientFunds,
+        fee_dto::{
+            fee_period, FeeChargeOutcome, FeeRunSummary, FeeSchedule, FeeScheduleCreate,
+            FeeScheduleUpdate,
+        },
+        transaction_dto::{TransactionCreate, TransactionOperation},
+    },
+    repositories::{accounts::AccountRepository, fees::FeeRepository, users::UserRepository},
+    retry::{is_retryable, with_transaction_retry},
+    services::transaction::Service as TransactionService,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    fee_repository: FeeRepository,
+    user_repository: UserRepository,
+    transaction_service: TransactionService,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            fee_repository: FeeRepository::new(),
+            user_repository: UserRepository::new(),
+            transaction_service: TransactionService::new(),
+        }
+    }
+
+    pub async fn get_all(&self, db_pool: &PgPool) -> Vec<FeeSchedule> {
+        // if we had a logging system, we would log the error here
+        (self.fee_repository.find_all(db_pool).await).unwrap_or_default()
+    }
+
+    pub async fn get_one_by_id(&self, db_pool: &PgPool, id: &Uuid) -> Option<FeeSchedule> {
+        // if we had a logging system, we would log the error here
+        (self.fee_repository.find_by_id(db_pool, id).await).ok()
+    }
+
+    pub async fn create(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        schedule: &FeeScheduleCreate,
+    ) -> anyhow::Result<FeeSchedule> {
+        let new_schedule = FeeSchedule::new(schedule)?;
+
+        self.fee_repository.create(db_tx, &new_schedule).await
+    }
+
+    pub async fn update(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+        update: &FeeScheduleUpdate,
+    ) -> anyhow::Result<FeeSchedule> {
+        let mut schedule = self.fee_repository.find_by_id_for_update(db_tx, id).await?;
+
+        schedule.update(update)?;
+
+        self.fee_repository.save(db_tx, &schedule).await
+    }
+
+    pub async fn delete(&self, db_tx: &mut SqlxTransaction<'_, Postgres>, id: &Uuid) -> bool {
+        // if we had a logging system, we would log the error here
+        (self.fee_repository.delete(db_tx, id).await).unwrap_or(false)
+    }
+
+    /// Charges the maintenance fee of every account that has not paid this month's yet,
+    /// each in its own database transaction.
+    pub async fn charge_monthly_fees(
+        &self,
+        db_pool: &PgPool,
+        today: NaiveDate,
+    ) -> anyhow::Result<FeeRunSummary> {
+        let mut summary = FeeRunSummary::default();
+
+        for account_id in self
+            .fee_repository
+            .find_uncharged_account_ids(db_pool, fee_period(today))
+            .await?
+        {
+            match with_transaction_retry(db_pool, |tx| {
+                let db_pool = db_pool.clone();
+                Box::pin(async move {
+                    Service::new()
+                        .charge_monthly_fee(&db_pool, tx, &account_id, today)
+                        .await
+                })
+            })
+            .await
+            {
+                Ok(Some(outcome)) => summary.record(outcome),
+                Ok(None) => {}
+                // if we had a logging system, we would log the error here
+                Err(_) => summary.failed += 1,
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Charges the maintenance fee of the month `today` falls in, unless it was settled already.
+    ///
+    /// An account that cannot pay is skipped without settling the month, so the next run
+    /// tries again.
+    pub async fn charge_monthly_fee(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        today: NaiveDate,
+    ) -> anyhow::Result<Option<FeeChargeOutcome>> {
+        let period = fee_period(today);
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, account_id)
+            .await?;
+        if self
+            .fee_repository
+            .has_charge(db_tx, account_id, period)
+            .await?
+        {
+            return Ok(None);
+        }
+        let schedule = match self
+            .fee_repository
+            .find_for_account_type(db_tx, account.bank_account_type)
+            .await?
+        {
+            Some(schedule) if schedule.monthly_fee.is_positive() => schedule,
+            _ => return Ok(None),
+        };
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, account_id)
+            .await?;
+
+        let fee = schedule.monthly_charge(account.get_balance(&owner)?);
+        if !fee.is_positive() {
+            self.fee_repository
+                .record_charge(db_tx, account_id, period, None)
+                .await?;
+            return Ok(Some(FeeChargeOutcome::Waived));
+        }
+
+        let mut savepoint = db_tx.begin().await?;
+        let transaction = match self
+            .transaction_service
+            .create(
+                db_pool,
+                &mut savepoint,
+                &TransactionCreate {
+                    operation: TransactionOperation::Fee,
+                    from_account_id: None,
+                    to_account_id: account.id,
+                    to_key: None,
+                    amount: fee,
+                    reverses_transaction_id: None,
+                },
+                &owner.id,
+            )
+            .await
+        {
+            Ok(transaction) => {
+                savepoint.commit().await?;
+                transaction
+            }
+            // a conflict with another transaction is retried as a whole instead
+            Err(e) if is_retryable(&e) => return Err(e),
+            Err(e) if e.downcast_ref::<InsufficientFunds>().is_some() => {
+                savepoint.rollback().await?;
+                return Ok(Some(FeeChargeOutcome::Skipped));
+            }
+            Err(e) => return Err(e),
+        };
+
+        self.fee_repository
+            .record_charge(db_tx, account_id, period, Some(transaction.id))
+            .await?;
+
+        Ok(Some(FeeChargeOutcome::Charged(transaction.id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::fee_dto::{FeeWaiver, OperationFee},
+        services::journal::Service as JournalService,
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_fees_are_charged_with_operations_and_monthly(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[
+                Money::from_minor_units(10_000),
+                Money::from_minor_units(1_000_000),
+                Money::from_minor_units(500),
+            ],
+        )
+        .await;
+        let balance = |index: usize| {
+            let db_pool = db_pool.clone();
+            let (owner, account) = &accounts[index];
+            async move {
+                AccountRepository::new()
+                    .find_by_id(&db_pool, &account.id)
+                    .await
+                    .unwrap()
+                    .get_balance(owner)
+                    .unwrap()
+            }
+        };
+
+        let mut tx = db_pool.begin().await.unwrap();
+        Service::new()
+            .create(
+                &mut tx,
+                &FeeScheduleCreate {
+                    name: "default".to_string(),
+                    bank_account_type: None,
+                    monthly_fee: Money::from_minor_units(1_000),
+                    operation_fees: vec![OperationFee {
+                        operation: TransactionOperation::Withdrawal,
+                        amount: Money::from_minor_units(200),
+                    }],
+                    waivers: vec![FeeWaiver {
+                        min_balance: Money::from_minor_units(500_000),
+                        waived_bps: 10_000,
+                    }],
+                },
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        // the withdrawal and its fee go through together...
+        let (owner, account) = &accounts[0];
+        let withdrawal = TransactionCreate {
+            operation: TransactionOperation::Withdrawal,
+            from_account_id: None,
+            to_account_id: account.id,
+            to_key: None,
+            amount: Money::from_minor_units(1_000),
+            reverses_transaction_id: None,
+        };
+        let mut tx = db_pool.begin().await.unwrap();
+        TransactionService::new()
+            .create(&db_pool, &mut tx, &withdrawal, &owner.id)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(balance(0).await, Money::from_minor_units(8_800));
+
+        // ...or not at all
+        let (owner, account) = &accounts[2];
+        let mut tx = db_pool.begin().await.unwrap();
+        let error = TransactionService::new()
+            .create(
+                &db_pool,
+                &mut tx,
+                &TransactionCreate {
+                    to_account_id: account.id,
+                    to_key: None,
+                    amount: Money::from_minor_units(400),
+                    ..withdrawal.clone()
+                },
+                &owner.id,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.downcast_ref(), Some(&InsufficientFunds));
+        tx.rollback().await.unwrap();
+        assert_eq!(balance(2).await, Money::from_minor_units(500));
+
+        // the rich account has its fee waived and the poor one cannot pay it yet
+        for expected in [
+            FeeRunSummary {
+                charged: 1,
+                waived: 1,
+                skipped: 1,
+                failed: 0,
+            },
+            FeeRunSummary {
+                skipped: 1,
+                ..Default::default()
+            },
+        ] {
+            assert_eq!(
+                Service::new()
+                    .charge_monthly_fees(&db_pool, date("2025-03-15"))
+                    .await
+                    .unwrap(),
+                expected
+            );
+        }
+        assert_eq!(balance(0).await, Money::from_minor_units(7_800));
+        assert_eq!(balance(1).await, Money::from_minor_units(1_000_000));
+        assert_eq!(balance(2).await, Money::from_minor_units(500));
+
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+}
@@ -0,0 +1,444 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::{Account, AccountStatus, AccountStatusError, InsufficientFunds},
+        hold_dto::{held_amount, Hold, HoldCreate, HoldError, HoldStatus, DEFAULT_HOLD_DURATION},
+        transaction_dto::{TransactionCreate, TransactionOperation},
+        user_dto::User,
+    },
+    repositories::{accounts::AccountRepository, holds::HoldRepository, users::UserRepository},
+    services::transaction::Service as TransactionService,
+    structs::money::Money,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    hold_repository: HoldRepository,
+    user_repository: UserRepository,
+    transaction_service: TransactionService,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            hold_repository: HoldRepository::new(),
+            user_repository: UserRepository::new(),
+            transaction_service: TransactionService::new(),
+        }
+    }
+
+    pub async fn get_one_by_id(&self, db_pool: &PgPool, id: &Uuid) -> Option<Hold> {
+        // if we had a logging system, we would log the error here
+        (self.hold_repository.find_by_id(db_pool, id).await).ok()
+    }
+
+    pub async fn get_all_by_account_id(&self, db_pool: &PgPool, account_id: &Uuid) -> Vec<Hold> {
+        // if we had a logging system, we would log the error here
+        (self
+            .hold_repository
+            .find_by_account_id(db_pool, account_id)
+            .await)
+            .unwrap_or_default()
+    }
+
+    /// What active holds reserve on `account`, owned by `owner`.
+    pub async fn get_held_amount(
+        &self,
+        db_pool: &PgPool,
+        account: &Account,
+        owner: &User,
+    ) -> anyhow::Result<Money> {
+        let holds = self
+            .hold_repository
+            .find_active_by_account_id(db_pool, &account.id)
+            .await?;
+
+        held_amount(&holds, owner)
+    }
+
+    /// Reserves funds on an account, failing with `InsufficientFunds` when its
+    /// available balance does not cover them.
+    pub async fn create(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        hold: &HoldCreate,
+    ) -> anyhow::Result<Hold> {
+        let now = chrono::Utc::now().naive_utc();
+        let expires_at = hold.expires_at.unwrap_or(now + DEFAULT_HOLD_DURATION);
+
+        if !hold.amount.is_positive() {
+            return Err(HoldError::InvalidAmount.into());
+        }
+        if expires_at <= now {
+            return Err(HoldError::InvalidExpiry.into());
+        }
+        if hold.to_account_id == Some(hold.account_id) {
+            return Err(HoldError::SameAccount.into());
+        }
+
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, &hold.account_id)
+            .await?;
+        match account.status {
+            AccountStatus::Active => {}
+            AccountStatus::Frozen => return Err(AccountStatusError::Frozen.into()),
+            status => return Err(AccountStatusError::NotOpen(status).into()),
+        }
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, &account.id)
+            .await?;
+
+        if hold.amount
+            > self
+                .account_repository
+                .available_balance(db_tx, &account, &owner)
+                .await?
+        {
+            return Err(InsufficientFunds.into());
+        }
+
+        let new_hold = Hold::new(
+            &owner,
+            account.id,
+            hold.to_account_id,
+            hold.amount,
+            expires_at,
+        )?;
+
+        self.hold_repository.create(db_tx, &new_hold).await
+    }
+
+    /// Captures `amount` of a hold, or all of it, and releases the rest.
+    ///
+    /// The hold stops reserving funds before the capture is posted, so the capture
+    /// itself is checked against the balance the hold was protecting.
+    pub async fn capture(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+        amount: Option<Money>,
+    ) -> anyhow::Result<Hold> {
+        let mut hold = self.lock_active(db_tx, id).await?;
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, &hold.account_id)
+            .await?;
+
+        let held = hold.get_amount(&owner)?;
+        let amount = amount.unwrap_or(held);
+        if !amount.is_positive() {
+            return Err(HoldError::InvalidAmount.into());
+        }
+        if amount > held {
+            return Err(HoldError::ExceedsHold(held).into());
+        }
+
+        hold.capture(&owner, amount)?;
+        self.hold_repository.save(db_tx, &hold).await?;
+
+        let transaction = match hold.to_account_id {
+            Some(to_account_id) => TransactionCreate {
+                operation: TransactionOperation::Transfer,
+                from_account_id: Some(hold.account_id),
+                to_account_id,
+                to_key: None,
+                amount,
+                reverses_transaction_id: None,
+            },
+            None => TransactionCreate {
+                operation: TransactionOperation::Payment,
+                from_account_id: None,
+                to_account_id: hold.account_id,
+                to_key: None,
+                amount,
+                reverses_transaction_id: None,
+            },
+        };
+        let transaction = self
+            .transaction_service
+            .settle(db_pool, db_tx, &transaction, &owner.id)
+            .await?;
+
+        hold.transaction_id = Some(transaction.id);
+        self.hold_repository.save(db_tx, &hold).await
+    }
+
+    /// Gives the reserved funds back to the account without moving any money.
+    pub async fn release(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Hold> {
+        let mut hold = self.lock_active(db_tx, id).await?;
+
+        hold.status = HoldStatus::Released;
+        hold.updated_at = Some(chrono::Utc::now().naive_utc());
+
+        self.hold_repository.save(db_tx, &hold).await
+    }
+
+    /// Marks every hold past its expiry as expired.
+    pub async fn expire(&self, db_pool: &PgPool) -> anyhow::Result<u64> {
+        self.hold_repository.expire(db_pool).await
+    }
+
+    async fn lock_active(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<Hold> {
+        let hold = self
+            .hold_repository
+            .find_by_id_for_update(db_tx, id)
+            .await?;
+
+        if hold.status != HoldStatus::Active {
+            return Err(HoldError::NotActive(hold.status).into());
+        }
+        if !hold.is_active(chrono::Utc::now().naive_utc()) {
+            return Err(HoldError::Expired.into());
+        }
+
+        Ok(hold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{services::journal::Service as JournalService, test_helpers::create_accounts};
+
+    async fn balance(db_pool: &PgPool, owner: &User, account: &Account) -> (Money, Money) {
+        let account = AccountRepository::new()
+            .find_by_id(db_pool, &account.id)
+            .await
+            .unwrap();
+        let ledger_balance = account.get_balance(owner).unwrap();
+        let held = Service::new()
+            .get_held_amount(db_pool, &account, owner)
+            .await
+            .unwrap();
+
+        (ledger_balance, ledger_balance - held)
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_holds_reserve_funds_until_captured(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(10_000), Money::from_minor_units(0)],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        let (merchant, merchant_account) = &accounts[1];
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let hold = Service::new()
+            .create(
+                &mut tx,
+                &HoldCreate {
+                    account_id: account.id,
+                    to_account_id: Some(merchant_account.id),
+                    amount: Money::from_minor_units(6_000),
+                    expires_at: None,
+                },
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(
+            balance(&db_pool, owner, account).await,
+            (
+                Money::from_minor_units(10_000),
+                Money::from_minor_units(4_000)
+            )
+        );
+
+        // the held funds can neither be withdrawn nor held twice
+        let mut tx = db_pool.begin().await.unwrap();
+        let withdrawal = TransactionService::new()
+            .create(
+                &db_pool,
+                &mut tx,
+                &TransactionCreate {
+                    operation: TransactionOperation::Withdrawal,
+                    from_account_id: None,
+                    to_account_id: account.id,
+                    to_key: None,
+                    amount: Money::from_minor_units(4_001),
+                    reverses_transaction_id: None,
+                },
+                &owner.id,
+            )
+            .await;
+        assert_eq!(withdrawal.unwrap_err().to_string(), "Not enough funds");
+        // nor taken by a fee or by reversing the money that came in
+        let fee = TransactionService::new()
+            .create(
+                &db_pool,
+                &mut tx,
+                &TransactionCreate {
+                    operation: TransactionOperation::Fee,
+                    from_account_id: None,
+                    to_account_id: account.id,
+                    to_key: None,
+                    amount: Money::from_minor_units(4_001),
+                    reverses_transaction_id: None,
+                },
+                &owner.id,
+            )
+            .await;
+        assert_eq!(fee.unwrap_err().to_string(), "Not enough funds");
+        let deposit_id: Uuid =
+            sqlx::query_scalar("SELECT id FROM transactions WHERE to_account_id = $1")
+                .bind(account.id)
+                .fetch_one(&mut *tx)
+                .await
+                .unwrap();
+        let reversal = TransactionService::new()
+            .reverse(
+                &db_pool,
+                &mut tx,
+                &deposit_id,
+                Some(Money::from_minor_units(4_001)),
+            )
+            .await;
+        assert_eq!(reversal.unwrap_err().to_string(), "Not enough funds");
+        let second_hold = Service::new()
+            .create(
+                &mut tx,
+                &HoldCreate {
+                    account_id: account.id,
+                    to_account_id: None,
+                    amount: Money::from_minor_units(4_001),
+                    expires_at: None,
+                },
+            )
+            .await;
+        assert_eq!(second_hold.unwrap_err().to_string(), "Not enough funds");
+        tx.rollback().await.unwrap();
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let error = Service::new()
+            .capture(
+                &db_pool,
+                &mut tx,
+                &hold.id,
+                Some(Money::from_minor_units(6_001)),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<HoldError>(),
+            Some(&HoldError::ExceedsHold(Money::from_minor_units(6_000)))
+        );
+        tx.rollback().await.unwrap();
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let captured = Service::new()
+            .capture(
+                &db_pool,
+                &mut tx,
+                &hold.id,
+                Some(Money::from_minor_units(5_500)),
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(captured.status, HoldStatus::Captured);
+        assert!(captured.transaction_id.is_some());
+
+        assert_eq!(
+            balance(&db_pool, owner, account).await,
+            (
+                Money::from_minor_units(4_500),
+                Money::from_minor_units(4_500)
+            )
+        );
+        assert_eq!(
+            balance(&db_pool, merchant, merchant_account).await,
+            (
+                Money::from_minor_units(5_500),
+                Money::from_minor_units(5_500)
+            )
+        );
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let error = Service::new().release(&mut tx, &hold.id).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<HoldError>(),
+            Some(&HoldError::NotActive(HoldStatus::Captured))
+        );
+        tx.rollback().await.unwrap();
+
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_expired_holds_free_their_funds(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::from_minor_units(1_000)]).await;
+        let (owner, account) = &accounts[0];
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let hold = Service::new()
+            .create(
+                &mut tx,
+                &HoldCreate {
+                    account_id: account.id,
+                    to_account_id: None,
+                    amount: Money::from_minor_units(1_000),
+                    expires_at: None,
+                },
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(balance(&db_pool, owner, account).await.1, Money::ZERO);
+
+        sqlx::query("UPDATE holds SET expires_at = created_at")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        // funds are available again even before the job runs
+        assert_eq!(
+            balance(&db_pool, owner, account).await.1,
+            Money::from_minor_units(1_000)
+        );
+
+        assert_eq!(Service::new().expire(&db_pool).await.unwrap(), 1);
+        let hold = Service::new()
+            .get_one_by_id(&db_pool, &hold.id)
+            .await
+            .unwrap();
+        assert_eq!(hold.status, HoldStatus::Expired);
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let error = Service::new()
+            .capture(&db_pool, &mut tx, &hold.id, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<HoldError>(),
+            Some(&HoldError::NotActive(HoldStatus::Expired))
+        );
+    }
+}
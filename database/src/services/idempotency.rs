@@ -0,0 +1,233 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::idempotency_dto::{
+        IdempotencyClaim, IdempotencyError, IdempotencyKey, MAX_IDEMPOTENCY_KEY_LENGTH,
+    },
+    repositories::idempotency::IdempotencyRepository,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    idempotency_repository: IdempotencyRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            idempotency_repository: IdempotencyRepository::new(),
+        }
+    }
+
+    /// Claims `key` for the request identified by `fingerprint`, inside `db_tx`.
+    ///
+    /// The claim is only kept if `db_tx` commits, so a failed request frees the key
+    /// for its retry. Reusing a live key for a different request fails with
+    /// [`IdempotencyError::KeyReused`].
+    pub async fn claim(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        user_id: &Uuid,
+        key: &str,
+        fingerprint: Vec<u8>,
+        ttl: chrono::Duration,
+    ) -> anyhow::Result<IdempotencyClaim> {
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+            return Err(IdempotencyError::InvalidKey.into());
+        }
+
+        let idempotency_key = IdempotencyKey::new(*user_id, key.to_string(), fingerprint, ttl);
+        if self
+            .idempotency_repository
+            .claim(db_tx, &idempotency_key)
+            .await?
+            .is_some()
+        {
+            return Ok(IdempotencyClaim::New);
+        }
+
+        let existing = self
+            .idempotency_repository
+            .find(db_tx, user_id, key)
+            .await?;
+        if existing.fingerprint != idempotency_key.fingerprint {
+            return Err(IdempotencyError::KeyReused.into());
+        }
+
+        // a key is claimed and completed in the same transaction, so a visible row has a response
+        match existing.transaction_id {
+            Some(transaction_id) => Ok(IdempotencyClaim::Replay(transaction_id)),
+            None => Err(anyhow::anyhow!("Idempotency key has no response")),
+        }
+    }
+
+    /// Records the transaction created for a claimed key as its response.
+    pub async fn complete(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        user_id: &Uuid,
+        key: &str,
+        transaction_id: &Uuid,
+    ) -> anyhow::Result<()> {
+        self.idempotency_repository
+            .set_transaction_id(db_tx, user_id, key, transaction_id)
+            .await
+    }
+
+    pub async fn purge_expired(&self, db_pool: &PgPool) -> anyhow::Result<u64> {
+        self.idempotency_repository.delete_expired(db_pool).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        models::transaction_dto::{TransactionCreate, TransactionOperation},
+        retry::with_transaction_retry,
+        services::transaction::Service as TransactionService,
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    /// Claims `key` and, when it is new, creates `transaction`; returns the id that the request answers with.
+    async fn create_once(
+        db_pool: &PgPool,
+        user_id: Uuid,
+        key: &str,
+        transaction: &TransactionCreate,
+    ) -> anyhow::Result<Uuid> {
+        with_transaction_retry(db_pool, |tx| {
+            let db_pool = db_pool.clone();
+            let key = key.to_string();
+            let transaction = transaction.clone();
+            Box::pin(async move {
+                let service = Service::new();
+                let claim = service
+                    .claim(
+                        tx,
+                        &user_id,
+                        &key,
+                        transaction.fingerprint()?,
+                        chrono::Duration::hours(1),
+                    )
+                    .await?;
+
+                match claim {
+                    IdempotencyClaim::Replay(transaction_id) => Ok(transaction_id),
+                    IdempotencyClaim::New => {
+                        let created = TransactionService::new()
+                            .create(&db_pool, tx, &transaction, &user_id)
+                            .await?;
+                        service.complete(tx, &user_id, &key, &created.id).await?;
+                        Ok(created.id)
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_concurrent_duplicates_create_one_transaction(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(10_000), Money::from_minor_units(0)],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        let (_, other_account) = &accounts[1];
+        let transfer = Arc::new(TransactionCreate {
+            operation: TransactionOperation::Transfer,
+            from_account_id: Some(account.id),
+            to_account_id: other_account.id,
+            to_key: None,
+            amount: Money::from_minor_units(1_000),
+            reverses_transaction_id: None,
+        });
+
+        // each attempt holds a connection while creating, and creating reads the owner through
+        // another, so leave one free in the test pool of five
+        let mut handles = Vec::new();
+        for _ in 0..4 {
+            let db_pool = db_pool.clone();
+            let transfer = transfer.clone();
+            let user_id = owner.id;
+            handles.push(tokio::spawn(async move {
+                create_once(&db_pool, user_id, "retry-me", &transfer).await
+            }));
+        }
+
+        let mut transaction_ids = Vec::new();
+        for handle in handles {
+            transaction_ids.push(handle.await.unwrap().unwrap());
+        }
+        transaction_ids.sort();
+        transaction_ids.dedup();
+        assert_eq!(transaction_ids.len(), 1);
+
+        let account = crate::repositories::accounts::AccountRepository::new()
+            .find_by_id(&db_pool, &account.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            account.get_balance(owner).unwrap(),
+            Money::from_minor_units(9_000)
+        );
+
+        let changed = TransactionCreate {
+            amount: Money::from_minor_units(2_000),
+            ..(*transfer).clone()
+        };
+        let error = create_once(&db_pool, owner.id, "retry-me", &changed)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<IdempotencyError>(),
+            Some(&IdempotencyError::KeyReused)
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_expired_keys_can_be_reused(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::from_minor_units(0)]).await;
+        let (owner, account) = &accounts[0];
+        let deposit = TransactionCreate {
+            operation: TransactionOperation::Deposit,
+            from_account_id: None,
+            to_account_id: account.id,
+            to_key: None,
+            amount: Money::from_minor_units(500),
+            reverses_transaction_id: None,
+        };
+
+        let first = create_once(&db_pool, owner.id, "monthly", &deposit)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE idempotency_keys SET expires_at = created_at")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let second = create_once(&db_pool, owner.id, "monthly", &deposit)
+            .await
+            .unwrap();
+        assert_ne!(first, second);
+
+        sqlx::query("UPDATE idempotency_keys SET expires_at = created_at")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(Service::new().purge_expired(&db_pool).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,292 @@
+use chrono::NaiveDate;
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        interest_dto::{AccountInterest, InterestError, InterestProduct, InterestProductCreate},
+        transaction_dto::{Transaction, TransactionCreate, TransactionOperation},
+    },
+    repositories::{
+        accounts::AccountRepository, interest::InterestRepository, users::UserRepository,
+    },
+    retry::with_transaction_retry,
+    services::transaction::Service as TransactionService,
+};
+
+/// Tally of one accrual pass over every account with an interest product.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InterestRunSummary {
+    pub accounts: u64,
+    pub payouts: u64,
+    pub failed: u64,
+}
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    interest_repository: InterestRepository,
+    user_repository: UserRepository,
+    transaction_service: TransactionService,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            interest_repository: InterestRepository::new(),
+            user_repository: UserRepository::new(),
+            transaction_service: TransactionService::new(),
+        }
+    }
+
+    pub async fn get_all_products(&self, db_pool: &PgPool) -> Vec<InterestProduct> {
+        // if we had a logging system, we would log the error here
+        (self.interest_repository.find_all_products(db_pool).await).unwrap_or_default()
+    }
+
+    pub async fn get_account_interest(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> Option<AccountInterest> {
+        // if we had a logging system, we would log the error here
+        (self
+            .interest_repository
+            .find_by_account_id(db_pool, account_id)
+            .await)
+            .ok()
+    }
+
+    pub async fn create_product(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        product: &InterestProductCreate,
+    ) -> anyhow::Result<InterestProduct> {
+        let new_product = InterestProduct::new(product)?;
+
+        self.interest_repository
+            .create_product(db_tx, &new_product)
+            .await
+    }
+
+    /// Puts an account on an interest product from `today` on.
+    ///
+    /// An account switching products first accrues everything it earned under the old one.
+    pub async fn assign(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        product_id: &Uuid,
+        today: NaiveDate,
+    ) -> anyhow::Result<AccountInterest> {
+        let product = self
+            .interest_repository
+            .find_product_by_id(db_tx, product_id)
+            .await?;
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, account_id)
+            .await?;
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, account_id)
+            .await?;
+
+        if let Some(mut current) = self
+            .interest_repository
+            .find_by_account_id_for_update(db_tx, account_id)
+            .await?
+        {
+            let current_product = self
+                .interest_repository
+                .find_product_by_id(db_tx, &current.product_id)
+                .await?;
+            current.accrue(
+                &owner,
+                &current_product,
+                account.get_balance(&owner)?,
+                today,
+            )?;
+            self.interest_repository.save(db_tx, &current).await?;
+        }
+
+        let interest = AccountInterest::new(&owner, account.id, &product, today)?;
+        self.interest_repository.assign(db_tx, &interest).await
+    }
+
+    /// Accrues the interest of one account up to `today`, and pays it out if a payout is due.
+    pub async fn accrue_account(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        today: NaiveDate,
+    ) -> anyhow::Result<Option<Transaction>> {
+        let mut interest = self
+            .interest_repository
+            .find_by_account_id_for_update(db_tx, account_id)
+            .await?
+            .ok_or(InterestError::NotAssigned)?;
+        let product = self
+            .interest_repository
+            .find_product_by_id(db_tx, &interest.product_id)
+            .await?;
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, account_id)
+            .await?;
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, account_id)
+            .await?;
+
+        interest.accrue(&owner, &product, account.get_balance(&owner)?, today)?;
+        let payout = interest.take_payout(&owner, &product, today)?;
+        self.interest_repository.save(db_tx, &interest).await?;
+
+        match payout {
+            Some(amount) => Ok(Some(
+                self.transaction_service
+                    .create(
+                        db_pool,
+                        db_tx,
+                        &TransactionCreate {
+                            operation: TransactionOperation::Interest,
+                            from_account_id: None,
+                            to_account_id: account.id,
+                            to_key: None,
+                            amount,
+                            reverses_transaction_id: None,
+                        },
+                        &owner.id,
+                    )
+                    .await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Accrues every account with an interest product, each in its own database transaction.
+    ///
+    /// An account that fails is counted and left for the next run, which catches up on
+    /// the days it missed.
+    pub async fn accrue_all(
+        &self,
+        db_pool: &PgPool,
+        today: NaiveDate,
+    ) -> anyhow::Result<InterestRunSummary> {
+        let mut summary = InterestRunSummary::default();
+
+        for account_id in self
+            .interest_repository
+            .find_all_account_ids(db_pool)
+            .await?
+        {
+            let result = with_transaction_retry(db_pool, |tx| {
+                let db_pool = db_pool.clone();
+                Box::pin(async move {
+                    Service::new()
+                        .accrue_account(&db_pool, tx, &account_id, today)
+                        .await
+                })
+            })
+            .await;
+
+            summary.accounts += 1;
+            match result {
+                Ok(Some(_)) => summary.payouts += 1,
+                Ok(None) => {}
+                // if we had a logging system, we would log the error here
+                Err(_) => summary.failed += 1,
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::interest_dto::{AccountInterestModel, CompoundingFrequency, DayCountConvention},
+        services::journal::Service as JournalService,
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_interest_compounds_on_each_payout(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::from_minor_units(1_000_000)]).await;
+        let (owner, account) = &accounts[0];
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let product = Service::new()
+            .create_product(
+                &mut tx,
+                &InterestProductCreate {
+                    name: "daily saver".to_string(),
+                    annual_rate_bps: 365,
+                    day_count: DayCountConvention::Actual365,
+                    compounding: CompoundingFrequency::Daily,
+                },
+            )
+            .await
+            .unwrap();
+        Service::new()
+            .assign(&mut tx, &account.id, &product.id, date("2025-01-01"))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        // 3.65% a year on 10,000.00 is 1.00 a day, then a little more as it compounds
+        for (today, expected) in [
+            ("2025-01-02", 1_000_100),
+            ("2025-01-03", 1_000_200),
+            // a second run on the same day pays nothing more
+            ("2025-01-03", 1_000_200),
+        ] {
+            let summary = Service::new()
+                .accrue_all(&db_pool, date(today))
+                .await
+                .unwrap();
+            assert_eq!(summary.failed, 0);
+
+            let balance = AccountRepository::new()
+                .find_by_id(&db_pool, &account.id)
+                .await
+                .unwrap()
+                .get_balance(owner)
+                .unwrap();
+            assert_eq!(balance, Money::from_minor_units(expected), "{}", today);
+        }
+
+        // the cent earned on the interest itself is still accruing
+        let interest = Service::new()
+            .get_account_interest(&db_pool, &account.id)
+            .await
+            .unwrap();
+        assert_eq!(interest.get_accrued(owner).unwrap(), 10_000);
+        let model = AccountInterestModel::from_dto(&interest, owner).unwrap();
+        assert_eq!(model.accrued, Money::ZERO);
+        assert_eq!(model.next_payout_on, date("2025-01-04"));
+
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        journal_dto::{LedgerAccount, LedgerVerification},
+        user_dto::User,
+    },
+    repositories::{
+        accounts::AccountRepository, journal::JournalRepository, users::UserRepository,
+    },
+    structs::money::Money,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    journal_repository: JournalRepository,
+    user_repository: UserRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            journal_repository: JournalRepository::new(),
+            user_repository: UserRepository::new(),
+        }
+    }
+
+    /// Decrypts every posting and checks that each journal entry sums to zero.
+    pub async fn verify(&self, db_pool: &PgPool) -> anyhow::Result<LedgerVerification> {
+        let postings = self.journal_repository.find_all_postings(db_pool).await?;
+        let mut owners: HashMap<Uuid, User> = HashMap::new();
+        let mut entries: Vec<(Uuid, Money)> = Vec::new();
+
+        for posting in &postings {
+            let owner = match posting.ledger_account()? {
+                LedgerAccount::Customer(account_id) => {
+                    if let Entry::Vacant(vacant) = owners.entry(account_id) {
+                        let account = self
+                            .account_repository
+                            .find_by_id(db_pool, &account_id)
+                            .await?;
+                        let user = self
+                            .user_repository
+                            .find_by_id(db_pool, &account.user_id)
+                            .await?;
+                        vacant.insert(user);
+                    }
+                    owners.get(&account_id)
+                }
+                LedgerAccount::System(_) => None,
+            };
+            let amount = posting.get_signed_amount(owner)?;
+
+            match entries.last_mut() {
+                Some((entry_id, sum)) if *entry_id == posting.journal_entry_id => *sum += amount,
+                _ => entries.push((posting.journal_entry_id, amount)),
+            }
+        }
+
+        Ok(LedgerVerification {
+            entries: entries.len() as u64,
+            postings: postings.len() as u64,
+            unbalanced_entries: entries
+                .iter()
+                .filter(|(_, sum)| !sum.is_zero())
+                .map(|(entry_id, _)| *entry_id)
+                .collect(),
+            total: entries.iter().map(|(_, sum)| *sum).sum(),
+        })
+    }
+
+    /// Writes journal entries for transactions created before the ledger existed.
+    pub async fn backfill(
+        &self,
+        db_pool: &PgPool,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+    ) -> anyhow::Result<u64> {
+        let transactions = self
+            .journal_repository
+            .find_unjournaled_transactions(tx)
+            .await?;
+        let total = transactions.len() as u64;
+
+        for transaction in transactions {
+            let account = self
+                .account_repository
+                .find_by_id(db_pool, &transaction.to_account_id)
+                .await?;
+            let user = self
+                .user_repository
+                .find_by_id(db_pool, &account.user_id)
+                .await?;
+            let amount = transaction
+                .get_amount(&user.encryption_key)
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+            self.journal_repository
+                .record(tx, &transaction, amount)
+                .await?;
+        }
+
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            journal_dto::{PostingDirection, SystemAccount},
+            transaction_dto::{TransactionCreate, TransactionOperation},
+        },
+        services::transaction::Service as TransactionService,
+        test_helpers::create_accounts,
+    };
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_every_operation_posts_a_balanced_entry(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[
+                Money::from_minor_units(50_000),
+                Money::from_minor_units(10_000),
+            ],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        let (_, other_account) = &accounts[1];
+        let operations = [
+            (TransactionOperation::Deposit, None, 2_500),
+            (TransactionOperation::Withdrawal, None, 1_000),
+            (TransactionOperation::Payment, None, 750),
+            (TransactionOperation::Fee, None, 199),
+            (TransactionOperation::Interest, None, 42),
+            (TransactionOperation::Transfer, Some(account.id), 5_000),
+        ];
+
+        for (operation, from_account_id, minor_units) in operations {
+            let to_account_id = match from_account_id {
+                Some(_) => other_account.id,
+                None => account.id,
+            };
+            let mut tx = db_pool.begin().await.unwrap();
+            TransactionService::new()
+                .create(
+                    &db_pool,
+                    &mut tx,
+                    &TransactionCreate {
+                        operation,
+                        from_account_id,
+                        to_account_id,
+                        to_key: None,
+                        amount: Money::from_minor_units(minor_units),
+                        reverses_transaction_id: None,
+                    },
+                    &owner.id,
+                )
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+        }
+
+        let ledger = Service::new().verify(&db_pool).await.unwrap();
+        assert!(ledger.is_balanced(), "{:?}", ledger);
+        assert_eq!(ledger.entries, 8);
+        assert_eq!(ledger.postings, 16);
+
+        // the customer's credits minus debits must match the balance stored on the account
+        let postings = JournalRepository::new()
+            .find_all_postings(&db_pool)
+            .await
+            .unwrap();
+        let ledger_balance: Money = postings
+            .iter()
+            .filter(|posting| posting.account_id == Some(account.id))
+            .map(|posting| -posting.get_signed_amount(Some(owner)).unwrap())
+            .sum();
+        let account = AccountRepository::new()
+            .find_by_id(&db_pool, &account.id)
+            .await
+            .unwrap();
+        assert_eq!(ledger_balance, account.get_balance(owner).unwrap());
+
+        let fees: Vec<_> = postings
+            .iter()
+            .filter(|posting| posting.system_account == Some(SystemAccount::Fees))
+            .collect();
+        assert_eq!(fees.len(), 1);
+        assert_eq!(fees[0].direction, PostingDirection::Credit);
+        assert_eq!(
+            fees[0].get_amount(None).unwrap(),
+            Money::from_minor_units(199)
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_backfill_journals_earlier_transactions(db_pool: PgPool) {
+        create_accounts(&db_pool, &[Money::from_minor_units(1_000)]).await;
+        sqlx::query("DELETE FROM journal_entries")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let journaled = Service::new().backfill(&db_pool, &mut tx).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let ledger = Service::new().verify(&db_pool).await.unwrap();
+        assert_eq!(journaled, 1);
+        assert_eq!(ledger.entries, 1);
+        assert!(ledger.is_balanced());
+    }
+}
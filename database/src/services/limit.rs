@@ -0,0 +1,320 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::limit_dto::{TransactionLimit, TransactionLimitCreate, TransactionLimitUpdate},
+    repositories::limits::LimitRepository,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    limit_repository: LimitRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            limit_repository: LimitRepository::new(),
+        }
+    }
+
+    pub async fn get_all(&self, db_pool: &PgPool) -> Vec<TransactionLimit> {
+        // if we had a logging system, we would log the error here
+        (self.limit_repository.find_all(db_pool).await).unwrap_or_default()
+    }
+
+    pub async fn get_one_by_id(&self, db_pool: &PgPool, id: &Uuid) -> Option<TransactionLimit> {
+        // if we had a logging system, we would log the error here
+        (self.limit_repository.find_by_id(db_pool, id).await).ok()
+    }
+
+    pub async fn create(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        limit: &TransactionLimitCreate,
+    ) -> anyhow::Result<TransactionLimit> {
+        let new_limit = TransactionLimit::new(limit)?;
+
+        self.limit_repository.create(db_tx, &new_limit).await
+    }
+
+    pub async fn update(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+        update: &TransactionLimitUpdate,
+    ) -> anyhow::Result<TransactionLimit> {
+        let mut limit = self
+            .limit_repository
+            .find_by_id_for_update(db_tx, id)
+            .await?;
+
+        limit.update(update)?;
+
+        self.limit_repository.save(db_tx, &limit).await
+    }
+
+    pub async fn delete(&self, db_tx: &mut SqlxTransaction<'_, Postgres>, id: &Uuid) -> bool {
+        // if we had a logging system, we would log the error here
+        (self.limit_repository.delete(db_tx, id).await).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::{
+        postgres::{PgConnectOptions, PgPoolOptions},
+        Executor,
+    };
+
+    use super::*;
+    use crate::{
+        models::{
+            limit_dto::LimitExceeded,
+            transaction_dto::{TransactionCreate, TransactionError, TransactionOperation},
+        },
+        services::{
+            journal::Service as JournalService, transaction::Service as TransactionService,
+        },
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_most_specific_limit_caps_rolling_totals(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::from_minor_units(100_000); 2]).await;
+        let (owner, account) = &accounts[0];
+        let (other_owner, other_account) = &accounts[1];
+        let withdraw = |owner_id: Uuid, account_id: Uuid, amount: i64| {
+            let db_pool = db_pool.clone();
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let result = TransactionService::new()
+                    .create(
+                        &db_pool,
+                        &mut tx,
+                        &TransactionCreate {
+                            operation: TransactionOperation::Withdrawal,
+                            from_account_id: None,
+                            to_account_id: account_id,
+                            to_key: None,
+                            amount: Money::from_minor_units(amount),
+                            reverses_transaction_id: None,
+                        },
+                        &owner_id,
+                    )
+                    .await;
+                match result {
+                    Ok(_) => {
+                        tx.commit().await.unwrap();
+                        Ok(())
+                    }
+                    Err(e) => Err(e.downcast::<LimitExceeded>().unwrap()),
+                }
+            }
+        };
+        let set_limit = |user_id: Option<Uuid>, per_operation: i64, daily: i64| {
+            let db_pool = db_pool.clone();
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                Service::new()
+                    .create(
+                        &mut tx,
+                        &TransactionLimitCreate {
+                            account_id: None,
+                            user_id,
+                            operation: TransactionOperation::Withdrawal,
+                            per_operation: Some(Money::from_minor_units(per_operation)),
+                            daily: Some(Money::from_minor_units(daily)),
+                        },
+                    )
+                    .await
+                    .unwrap();
+                tx.commit().await.unwrap();
+            }
+        };
+
+        set_limit(None, 5_000, 8_000).await;
+        assert_eq!(
+            withdraw(owner.id, account.id, 5_001).await,
+            Err(LimitExceeded::PerOperation(Money::from_minor_units(5_000)))
+        );
+        withdraw(owner.id, account.id, 5_000).await.unwrap();
+        withdraw(owner.id, account.id, 3_000).await.unwrap();
+        assert_eq!(
+            withdraw(owner.id, account.id, 1).await,
+            Err(LimitExceeded::Daily {
+                limit: Money::from_minor_units(8_000),
+                remaining: Money::ZERO,
+            })
+        );
+        // the global limit is counted per user
+        withdraw(other_owner.id, other_account.id, 5_000)
+            .await
+            .unwrap();
+
+        set_limit(Some(owner.id), 5_000, 10_000).await;
+        withdraw(owner.id, account.id, 2_000).await.unwrap();
+        assert_eq!(
+            withdraw(owner.id, account.id, 1).await,
+            Err(LimitExceeded::Daily {
+                limit: Money::from_minor_units(10_000),
+                remaining: Money::ZERO,
+            })
+        );
+
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_negative_operations_free_no_headroom(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::from_minor_units(100_000)]).await;
+        let (owner, account) = &accounts[0];
+        let withdraw = |amount: i64| {
+            let db_pool = db_pool.clone();
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                TransactionService::new()
+                    .create(
+                        &db_pool,
+                        &mut tx,
+                        &TransactionCreate {
+                            operation: TransactionOperation::Withdrawal,
+                            from_account_id: None,
+                            to_account_id: account.id,
+                            to_key: None,
+                            amount: Money::from_minor_units(amount),
+                            reverses_transaction_id: None,
+                        },
+                        &owner.id,
+                    )
+                    .await?;
+                tx.commit().await?;
+                anyhow::Ok(())
+            }
+        };
+
+        let mut tx = db_pool.begin().await.unwrap();
+        Service::new()
+            .create(
+                &mut tx,
+                &TransactionLimitCreate {
+                    account_id: Some(account.id),
+                    user_id: None,
+                    operation: TransactionOperation::Withdrawal,
+                    per_operation: Some(Money::from_minor_units(5_000)),
+                    daily: Some(Money::from_minor_units(8_000)),
+                },
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        withdraw(5_000).await.unwrap();
+        withdraw(3_000).await.unwrap();
+        // taking the used amount back down would reopen the daily limit
+        assert_eq!(
+            withdraw(-5_000)
+                .await
+                .unwrap_err()
+                .downcast_ref::<TransactionError>(),
+            Some(&TransactionError::InvalidAmount)
+        );
+        assert_eq!(
+            withdraw(1)
+                .await
+                .unwrap_err()
+                .downcast_ref::<LimitExceeded>(),
+            Some(&LimitExceeded::Daily {
+                limit: Money::from_minor_units(8_000),
+                remaining: Money::ZERO,
+            })
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_limit_window_ignores_the_session_time_zone(
+        pool_options: PgPoolOptions,
+        connect_options: PgConnectOptions,
+    ) {
+        // ten hours behind UTC, which would stretch a window measured in local time to 34 hours
+        let db_pool = pool_options
+            .after_connect(|connection, _| {
+                Box::pin(async move {
+                    connection
+                        .execute("SET TimeZone = 'Pacific/Honolulu'")
+                        .await?;
+                    Ok(())
+                })
+            })
+            .connect_with(connect_options)
+            .await
+            .unwrap();
+        let accounts = create_accounts(&db_pool, &[Money::from_minor_units(100_000)]).await;
+        let (owner, account) = &accounts[0];
+        let withdraw = || {
+            let db_pool = db_pool.clone();
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                TransactionService::new()
+                    .create(
+                        &db_pool,
+                        &mut tx,
+                        &TransactionCreate {
+                            operation: TransactionOperation::Withdrawal,
+                            from_account_id: None,
+                            to_account_id: account.id,
+                            to_key: None,
+                            amount: Money::from_minor_units(5_000),
+                            reverses_transaction_id: None,
+                        },
+                        &owner.id,
+                    )
+                    .await?;
+                tx.commit().await?;
+                anyhow::Ok(())
+            }
+        };
+
+        let mut tx = db_pool.begin().await.unwrap();
+        Service::new()
+            .create(
+                &mut tx,
+                &TransactionLimitCreate {
+                    account_id: Some(account.id),
+                    user_id: None,
+                    operation: TransactionOperation::Withdrawal,
+                    per_operation: None,
+                    daily: Some(Money::from_minor_units(8_000)),
+                },
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        withdraw().await.unwrap();
+        sqlx::query("UPDATE transactions SET created_at = created_at - INTERVAL '30 hours'")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        withdraw().await.unwrap();
+        assert!(withdraw()
+            .await
+            .unwrap_err()
+            .downcast_ref::<LimitExceeded>()
+            .is_some());
+    }
+}
@@ -0,0 +1,361 @@
+use chrono::NaiveDate;
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::Account,
+        overdraft_dto::{
+            OverdraftChargeOutcome, OverdraftInterest, OverdraftRunSummary, OverdraftUpdate,
+        },
+    },
+    repositories::{
+        accounts::AccountRepository, overdrafts::OverdraftRepository, users::UserRepository,
+    },
+    retry::with_transaction_retry,
+    services::transaction::Service as TransactionService,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    overdraft_repository: OverdraftRepository,
+    user_repository: UserRepository,
+    transaction_service: TransactionService,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            overdraft_repository: OverdraftRepository::new(),
+            user_repository: UserRepository::new(),
+            transaction_service: TransactionService::new(),
+        }
+    }
+
+    /// Sets the overdraft limit and rate of an account from `today` on.
+    ///
+    /// Interest owed so far is accrued at the old rate first. A limit below what the account
+    /// already uses only stops further debits.
+    pub async fn update(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        overdraft: &OverdraftUpdate,
+        today: NaiveDate,
+    ) -> anyhow::Result<Account> {
+        overdraft.validate()?;
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, account_id)
+            .await?;
+
+        if let Some(mut interest) = self
+            .overdraft_repository
+            .find_by_account_id_for_update(db_tx, account_id)
+            .await?
+        {
+            let owner = self
+                .user_repository
+                .find_by_account_id(db_tx, account_id)
+                .await?;
+            interest.accrue(
+                &owner,
+                account.overdraft_rate_bps,
+                account.get_balance(&owner)?,
+                today,
+            )?;
+            self.overdraft_repository.save(db_tx, &interest).await?;
+        }
+
+        self.account_repository
+            .set_overdraft(
+                db_tx,
+                account_id,
+                overdraft.limit,
+                overdraft.annual_rate_bps,
+            )
+            .await
+    }
+
+    /// Accrues overdraft interest on every account that can be overdrawn and charges what
+    /// is due, each account in its own database transaction.
+    pub async fn charge_all(
+        &self,
+        db_pool: &PgPool,
+        today: NaiveDate,
+    ) -> anyhow::Result<OverdraftRunSummary> {
+        let mut summary = OverdraftRunSummary::default();
+
+        for account_id in self.overdraft_repository.find_account_ids(db_pool).await? {
+            match with_transaction_retry(db_pool, |tx| {
+                let db_pool = db_pool.clone();
+                Box::pin(async move {
+                    Service::new()
+                        .charge_interest(&db_pool, tx, &account_id, today)
+                        .await
+                })
+            })
+            .await
+            {
+                Ok(outcome) => summary.record(outcome),
+                // if we had a logging system, we would log the error here
+                Err(_) => summary.failed += 1,
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Accrues the overdraft interest of one account up to `today`, and charges it as a fee
+    /// when a charge is due, even when that takes the account past its limit.
+    pub async fn charge_interest(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        today: NaiveDate,
+    ) -> anyhow::Result<OverdraftChargeOutcome> {
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, account_id)
+            .await?;
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, account_id)
+            .await?;
+        let mut interest = match self
+            .overdraft_repository
+            .find_by_account_id_for_update(db_tx, account_id)
+            .await?
+        {
+            Some(interest) => interest,
+            None => {
+                self.overdraft_repository
+                    .create(db_tx, &OverdraftInterest::new(&owner, account.id, today)?)
+                    .await?
+            }
+        };
+
+        interest.accrue(
+            &owner,
+            account.overdraft_rate_bps,
+            account.get_balance(&owner)?,
+            today,
+        )?;
+
+        let outcome = match interest.due_charge(&owner, today)? {
+            None => OverdraftChargeOutcome::NotDue,
+            Some(charge) if !charge.is_positive() => {
+                interest.settle(&owner, charge, today)?;
+                OverdraftChargeOutcome::NotDue
+            }
+            Some(charge) => {
+                let transaction = self
+                    .transaction_service
+                    .charge_overdraft_interest(db_pool, db_tx, &account.id, charge)
+                    .await?;
+                interest.settle(&owner, charge, today)?;
+                OverdraftChargeOutcome::Charged(transaction.id)
+            }
+        };
+
+        self.overdraft_repository.save(db_tx, &interest).await?;
+
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::transaction_dto::{TransactionCreate, TransactionOperation},
+        services::journal::Service as JournalService,
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_overdrafts_are_bounded_and_charged_interest(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::from_minor_units(10_000)]).await;
+        let (owner, account) = &accounts[0];
+        let withdraw = |amount: i64| {
+            let db_pool = db_pool.clone();
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let result = TransactionService::new()
+                    .create(
+                        &db_pool,
+                        &mut tx,
+                        &TransactionCreate {
+                            operation: TransactionOperation::Withdrawal,
+                            from_account_id: None,
+                            to_account_id: account.id,
+                            to_key: None,
+                            amount: Money::from_minor_units(amount),
+                            reverses_transaction_id: None,
+                        },
+                        &owner.id,
+                    )
+                    .await;
+                if result.is_ok() {
+                    tx.commit().await.unwrap();
+                }
+                result.map(|_| ())
+            }
+        };
+        let balance = || {
+            let db_pool = db_pool.clone();
+            async move {
+                AccountRepository::new()
+                    .find_by_id(&db_pool, &account.id)
+                    .await
+                    .unwrap()
+                    .get_balance(owner)
+                    .unwrap()
+            }
+        };
+
+        assert!(withdraw(10_001).await.is_err());
+
+        let mut tx = db_pool.begin().await.unwrap();
+        Service::new()
+            .update(
+                &mut tx,
+                &account.id,
+                &OverdraftUpdate {
+                    limit: Money::from_minor_units(50_000),
+                    annual_rate_bps: 3_650,
+                },
+                date("2025-01-01"),
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        withdraw(20_000).await.unwrap();
+        assert!(withdraw(40_001).await.is_err());
+        assert_eq!(balance().await, Money::from_minor_units(-10_000));
+
+        // 36.5% a year on 100.00 overdrawn is 10 cents a day, charged on the first of the month
+        for (today, expected) in [
+            (
+                "2025-01-01",
+                OverdraftRunSummary {
+                    accounts: 1,
+                    ..Default::default()
+                },
+            ),
+            (
+                "2025-02-01",
+                OverdraftRunSummary {
+                    accounts: 1,
+                    charged: 1,
+                    ..Default::default()
+                },
+            ),
+        ] {
+            assert_eq!(
+                Service::new()
+                    .charge_all(&db_pool, date(today))
+                    .await
+                    .unwrap(),
+                expected,
+                "{}",
+                today
+            );
+        }
+        assert_eq!(balance().await, Money::from_minor_units(-10_310));
+
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_interest_is_charged_past_the_limit(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::from_minor_units(10_000)]).await;
+        let (owner, account) = &accounts[0];
+        let withdraw = |amount: i64| {
+            let db_pool = db_pool.clone();
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let result = TransactionService::new()
+                    .create(
+                        &db_pool,
+                        &mut tx,
+                        &TransactionCreate {
+                            operation: TransactionOperation::Withdrawal,
+                            from_account_id: None,
+                            to_account_id: account.id,
+                            to_key: None,
+                            amount: Money::from_minor_units(amount),
+                            reverses_transaction_id: None,
+                        },
+                        &owner.id,
+                    )
+                    .await;
+                if result.is_ok() {
+                    tx.commit().await.unwrap();
+                }
+                result.map(|_| ())
+            }
+        };
+
+        let mut tx = db_pool.begin().await.unwrap();
+        Service::new()
+            .update(
+                &mut tx,
+                &account.id,
+                &OverdraftUpdate {
+                    limit: Money::from_minor_units(10_000),
+                    annual_rate_bps: 3_650,
+                },
+                date("2025-01-01"),
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        // the account sits exactly at its limit
+        withdraw(20_000).await.unwrap();
+        for today in ["2025-01-01", "2025-02-01"] {
+            Service::new()
+                .charge_all(&db_pool, date(today))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            AccountRepository::new()
+                .find_by_id(&db_pool, &account.id)
+                .await
+                .unwrap()
+                .get_balance(owner)
+                .unwrap(),
+            Money::from_minor_units(-10_310)
+        );
+        assert!(withdraw(1).await.is_err());
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+}
@@ -0,0 +1,325 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        pix_key_dto::{
+            parse_key, PixKey, PixKeyCreate, PixKeyError, PixKeyType, TransactionRecipient,
+            MAX_KEYS_PER_ACCOUNT,
+        },
+        transaction_dto::{TransactionCreate, TransactionOperation},
+    },
+    repositories::{
+        accounts::AccountRepository, pix_keys::PixKeyRepository, users::UserRepository,
+    },
+};
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    pix_key_repository: PixKeyRepository,
+    user_repository: UserRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            pix_key_repository: PixKeyRepository::new(),
+            user_repository: UserRepository::new(),
+        }
+    }
+
+    pub async fn get_one_by_id(&self, db_pool: &PgPool, id: &Uuid) -> Option<PixKey> {
+        // if we had a logging system, we would log the error here
+        (self.pix_key_repository.find_by_id(db_pool, id).await).ok()
+    }
+
+    pub async fn get_all_by_user_id(&self, db_pool: &PgPool, user_id: &Uuid) -> Vec<PixKey> {
+        // if we had a logging system, we would log the error here
+        (self
+            .pix_key_repository
+            .find_by_user_id(db_pool, user_id)
+            .await)
+            .unwrap_or_default()
+    }
+
+    /// Registers a key for an account. Email keys must be the owner's own email, random keys
+    /// are generated here, and no key may point to more than one account. Tax id and phone
+    /// keys cannot be checked against the owner here, so only an admin, having verified them,
+    /// may register them.
+    pub async fn create(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        pix_key: &PixKeyCreate,
+        is_admin: bool,
+    ) -> anyhow::Result<PixKey> {
+        // the lock keeps two requests from both taking the last free key of the account
+        self.account_repository
+            .find_by_id_for_update(db_tx, &pix_key.account_id)
+            .await?;
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, &pix_key.account_id)
+            .await?;
+
+        let key = match (pix_key.key_type, &pix_key.key) {
+            (PixKeyType::Random, Some(_)) => return Err(PixKeyError::RandomKeyGiven.into()),
+            (PixKeyType::Random, None) => Uuid::new_v4().hyphenated().to_string(),
+            (key_type, None) => return Err(PixKeyError::MissingKey(key_type).into()),
+            (key_type, Some(raw)) => {
+                let (parsed_type, key) = parse_key(raw)?;
+                if parsed_type != key_type {
+                    return Err(PixKeyError::TypeMismatch(key_type).into());
+                }
+                match key_type {
+                    PixKeyType::Email if key != owner.email.trim().to_lowercase() => {
+                        return Err(PixKeyError::EmailNotOwned.into())
+                    }
+                    PixKeyType::TaxId | PixKeyType::Phone if !is_admin => {
+                        return Err(PixKeyError::VerificationRequired(key_type).into())
+                    }
+                    _ => {}
+                }
+                key
+            }
+        };
+
+        if self
+            .pix_key_repository
+            .count_by_account_id(db_tx, &pix_key.account_id)
+            .await?
+            >= MAX_KEYS_PER_ACCOUNT
+        {
+            return Err(PixKeyError::TooManyKeys(MAX_KEYS_PER_ACCOUNT).into());
+        }
+        if self
+            .pix_key_repository
+            .find_by_key(&mut **db_tx, &key)
+            .await?
+            .is_some()
+        {
+            return Err(PixKeyError::KeyTaken.into());
+        }
+
+        self.pix_key_repository
+            .create(
+                db_tx,
+                &PixKey::new(pix_key.account_id, pix_key.key_type, key),
+            )
+            .await
+    }
+
+    pub async fn delete(&self, db_tx: &mut SqlxTransaction<'_, Postgres>, id: &Uuid) -> bool {
+        // if we had a logging system, we would log the error here
+        (self.pix_key_repository.delete(db_tx, id).await).unwrap_or(false)
+    }
+
+    /// Points a transaction given by `to_key` at the account registered under the key, and
+    /// returns who that reaches. Transactions given by account are left as they are.
+    pub async fn resolve_recipient(
+        &self,
+        db_pool: &PgPool,
+        transaction: &mut TransactionCreate,
+    ) -> anyhow::Result<Option<TransactionRecipient>> {
+        let Some(raw) = &transaction.to_key else {
+            if transaction.to_account_id.is_nil() {
+                return Err(PixKeyError::MissingRecipient.into());
+            }
+            return Ok(None);
+        };
+        if !transaction.to_account_id.is_nil() {
+            return Err(PixKeyError::AmbiguousRecipient.into());
+        }
+        if transaction.operation != TransactionOperation::Transfer {
+            return Err(PixKeyError::TransfersOnly.into());
+        }
+
+        let (_, key) = parse_key(raw)?;
+        let pix_key = self
+            .pix_key_repository
+            .find_by_key(db_pool, &key)
+            .await?
+            .ok_or(PixKeyError::KeyNotFound)?;
+        let account = self
+            .account_repository
+            .find_by_id(db_pool, &pix_key.account_id)
+            .await?;
+        let owner = self
+            .user_repository
+            .find_by_id(db_pool, &account.user_id)
+            .await?;
+
+        transaction.to_account_id = account.id;
+        Ok(Some(TransactionRecipient {
+            name: owner.name,
+            key_type: pix_key.key_type,
+            key: pix_key.masked(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{structs::money::Money, test_helpers::create_accounts};
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_keys_are_owned_unique_and_resolve_to_their_account(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::ZERO, Money::ZERO]).await;
+        let (owner, account) = &accounts[0];
+        let (other_owner, other_account) = &accounts[1];
+        let create = |account_id: Uuid, key_type, key: Option<&str>, is_admin: bool| {
+            let db_pool = db_pool.clone();
+            let key = PixKeyCreate {
+                account_id,
+                key_type,
+                key: key.map(str::to_string),
+            };
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let result = Service::new().create(&mut tx, &key, is_admin).await;
+                tx.commit().await.unwrap();
+                result
+            }
+        };
+
+        let email = create(
+            account.id,
+            PixKeyType::Email,
+            Some(&owner.email.to_uppercase()),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(email.key, owner.email);
+        let random = create(account.id, PixKeyType::Random, None, false)
+            .await
+            .unwrap();
+        assert!(Uuid::parse_str(&random.key).is_ok());
+        create(account.id, PixKeyType::TaxId, Some("529.982.247-25"), true)
+            .await
+            .unwrap();
+
+        let error =
+            |result: anyhow::Result<PixKey>| result.unwrap_err().downcast::<PixKeyError>().unwrap();
+        assert_eq!(
+            error(
+                create(
+                    other_account.id,
+                    PixKeyType::Email,
+                    Some(&owner.email),
+                    false
+                )
+                .await
+            ),
+            PixKeyError::EmailNotOwned
+        );
+        for (key_type, key) in [
+            (PixKeyType::TaxId, "111.444.777-35"),
+            (PixKeyType::Phone, "+5511912345678"),
+        ] {
+            assert_eq!(
+                error(create(account.id, key_type, Some(key), false).await),
+                PixKeyError::VerificationRequired(key_type)
+            );
+        }
+        assert_eq!(
+            error(
+                create(
+                    other_account.id,
+                    PixKeyType::TaxId,
+                    Some("52998224725"),
+                    true
+                )
+                .await
+            ),
+            PixKeyError::KeyTaken
+        );
+        assert_eq!(
+            error(
+                create(
+                    other_account.id,
+                    PixKeyType::Phone,
+                    Some("529.982.247-25"),
+                    true
+                )
+                .await
+            ),
+            PixKeyError::TypeMismatch(PixKeyType::Phone)
+        );
+        assert_eq!(
+            error(create(account.id, PixKeyType::Random, Some("x"), false).await),
+            PixKeyError::RandomKeyGiven
+        );
+        create(account.id, PixKeyType::Random, None, false)
+            .await
+            .unwrap();
+        create(account.id, PixKeyType::Phone, Some("+5511987654321"), true)
+            .await
+            .unwrap();
+        assert_eq!(
+            error(create(account.id, PixKeyType::Random, None, false).await),
+            PixKeyError::TooManyKeys(MAX_KEYS_PER_ACCOUNT)
+        );
+        assert_eq!(
+            Service::new()
+                .get_all_by_user_id(&db_pool, &owner.id)
+                .await
+                .len(),
+            MAX_KEYS_PER_ACCOUNT as usize
+        );
+        assert!(Service::new()
+            .get_all_by_user_id(&db_pool, &other_owner.id)
+            .await
+            .is_empty());
+
+        let mut transfer = TransactionCreate {
+            operation: TransactionOperation::Transfer,
+            from_account_id: Some(other_account.id),
+            to_account_id: Uuid::nil(),
+            to_key: Some("52998224725".to_string()),
+            amount: Money::from_minor_units(100),
+            reverses_transaction_id: None,
+        };
+        let recipient = Service::new()
+            .resolve_recipient(&db_pool, &mut transfer)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(transfer.to_account_id, account.id);
+        assert_eq!(recipient.name, owner.name);
+        assert_eq!(recipient.key, "***.982.247-**");
+        assert_eq!(
+            Service::new()
+                .resolve_recipient(&db_pool, &mut transfer)
+                .await
+                .unwrap_err()
+                .downcast::<PixKeyError>()
+                .unwrap(),
+            PixKeyError::AmbiguousRecipient
+        );
+
+        let mut tx = db_pool.begin().await.unwrap();
+        assert!(Service::new().delete(&mut tx, &random.id).await);
+        tx.commit().await.unwrap();
+        transfer.to_account_id = Uuid::nil();
+        transfer.to_key = Some(random.key.to_uppercase());
+        assert_eq!(
+            Service::new()
+                .resolve_recipient(&db_pool, &mut transfer)
+                .await
+                .unwrap_err()
+                .downcast::<PixKeyError>()
+                .unwrap(),
+            PixKeyError::KeyNotFound
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        pix_key_dto::parse_key,
+        qr_code_dto::{QrCode, QrCodeCreate, QrCodeError},
+    },
+    payment_files::brcode::{self, BrCode},
+    repositories::{
+        accounts::AccountRepository, pix_keys::PixKeyRepository, qr_payments::QrPaymentRepository,
+        users::UserRepository,
+    },
+};
+
+const GENERATED_TRANSACTION_ID_LENGTH: usize = 25;
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    pix_key_repository: PixKeyRepository,
+    qr_payment_repository: QrPaymentRepository,
+    user_repository: UserRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            pix_key_repository: PixKeyRepository::new(),
+            qr_payment_repository: QrPaymentRepository::new(),
+            user_repository: UserRepository::new(),
+        }
+    }
+
+    /// Renders the payload payers scan to pay an account, naming the account owner as the
+    /// merchant. Dynamic payloads without a transaction id get a fresh one.
+    pub async fn create(&self, db_pool: &PgPool, qr_code: &QrCodeCreate) -> anyhow::Result<QrCode> {
+        let account = self
+            .account_repository
+            .find_by_id(db_pool, &qr_code.account_id)
+            .await?;
+        let owner = self
+            .user_repository
+            .find_by_id(db_pool, &account.user_id)
+            .await?;
+        let keys = self
+            .pix_key_repository
+            .find_by_account_id(db_pool, &account.id)
+            .await?;
+
+        let key = match &qr_code.key {
+            Some(raw) => {
+                let (_, key) = parse_key(raw)?;
+                keys.into_iter()
+                    .find(|pix_key| pix_key.key == key)
+                    .ok_or(QrCodeError::KeyNotOwned)?
+            }
+            None => keys
+                .into_iter()
+                .next()
+                .ok_or(QrCodeError::NoKey(account.id))?,
+        };
+
+        let transaction_id = match &qr_code.transaction_id {
+            Some(transaction_id) => Some(transaction_id.clone()),
+            None if qr_code.dynamic => Some(
+                Uuid::new_v4().simple().to_string()[..GENERATED_TRANSACTION_ID_LENGTH].to_string(),
+            ),
+            None => None,
+        };
+
+        let payload = brcode::encode(&BrCode {
+            key: key.key,
+            description: qr_code.description.clone(),
+            merchant_name: owner.name,
+            merchant_city: qr_code.city.clone(),
+            amount: qr_code.amount,
+            transaction_id: transaction_id.clone(),
+            dynamic: qr_code.dynamic,
+        })?;
+
+        Ok(QrCode {
+            payload,
+            transaction_id,
+            amount: qr_code.amount,
+        })
+    }
+
+    /// Records the payment into `account_id` of the dynamic code `qr_transaction_id`, which
+    /// fails with [`QrCodeError::AlreadyPaid`] once the code has been paid.
+    pub async fn record_payment(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        qr_transaction_id: &str,
+        transaction_id: &Uuid,
+    ) -> anyhow::Result<()> {
+        if !self
+            .qr_payment_repository
+            .create(db_tx, account_id, qr_transaction_id, transaction_id)
+            .await?
+        {
+            return Err(QrCodeError::AlreadyPaid(qr_transaction_id.to_string()).into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            pix_key_dto::{PixKeyCreate, PixKeyType},
+            transaction_dto::{TransactionCreate, TransactionOperation},
+        },
+        services::{pix_key, transaction::Service as TransactionService},
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_payloads_name_a_key_of_the_account(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[Money::ZERO, Money::ZERO]).await;
+        let (owner, account) = &accounts[0];
+        let (_, other_account) = &accounts[1];
+        let qr_code = |account_id: Uuid, key: Option<&str>, dynamic: bool| QrCodeCreate {
+            account_id,
+            key: key.map(str::to_string),
+            amount: Some(Money::from_minor_units(2500)),
+            transaction_id: None,
+            description: Some("Pedido 42".to_string()),
+            city: "São Paulo".to_string(),
+            dynamic,
+        };
+        let error =
+            |result: anyhow::Result<QrCode>| result.unwrap_err().downcast::<QrCodeError>().unwrap();
+
+        assert_eq!(
+            error(
+                Service::new()
+                    .create(&db_pool, &qr_code(account.id, None, false))
+                    .await
+            ),
+            QrCodeError::NoKey(account.id)
+        );
+
+        let mut tx = db_pool.begin().await.unwrap();
+        for (key_type, key) in [
+            (PixKeyType::Email, Some(owner.email.clone())),
+            (PixKeyType::TaxId, Some("52998224725".to_string())),
+        ] {
+            pix_key::Service::new()
+                .create(
+                    &mut tx,
+                    &PixKeyCreate {
+                        account_id: account.id,
+                        key_type,
+                        key,
+                    },
+                    true,
+                )
+                .await
+                .unwrap();
+        }
+        tx.commit().await.unwrap();
+
+        let static_code = Service::new()
+            .create(&db_pool, &qr_code(account.id, None, false))
+            .await
+            .unwrap();
+        assert_eq!(static_code.transaction_id, None);
+        let decoded = brcode::decode(&static_code.payload).unwrap();
+        assert_eq!(decoded.key, owner.email);
+        assert_eq!(decoded.merchant_name, owner.name);
+        assert_eq!(decoded.merchant_city, "Sao Paulo");
+        assert_eq!(decoded.amount, Some(Money::from_minor_units(2500)));
+        assert!(!decoded.dynamic);
+
+        let dynamic_code = Service::new()
+            .create(&db_pool, &qr_code(account.id, Some("529.982.247-25"), true))
+            .await
+            .unwrap();
+        let decoded = brcode::decode(&dynamic_code.payload).unwrap();
+        assert_eq!(decoded.key, "52998224725");
+        assert!(decoded.dynamic);
+        assert_eq!(decoded.transaction_id, dynamic_code.transaction_id);
+        assert_eq!(decoded.transaction_id.unwrap().len(), 25);
+
+        assert_eq!(
+            error(
+                Service::new()
+                    .create(
+                        &db_pool,
+                        &qr_code(other_account.id, Some(&owner.email), false)
+                    )
+                    .await
+            ),
+            QrCodeError::KeyNotOwned
+        );
+
+        let qr_transaction_id = dynamic_code.transaction_id.unwrap();
+        let mut tx = db_pool.begin().await.unwrap();
+        let payment = TransactionService::new()
+            .create(
+                &db_pool,
+                &mut tx,
+                &TransactionCreate {
+                    operation: TransactionOperation::Deposit,
+                    from_account_id: None,
+                    to_account_id: account.id,
+                    to_key: None,
+                    amount: Money::from_minor_units(2500),
+                    reverses_transaction_id: None,
+                },
+                &owner.id,
+            )
+            .await
+            .unwrap();
+        Service::new()
+            .record_payment(&mut tx, &account.id, &qr_transaction_id, &payment.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            Service::new()
+                .record_payment(&mut tx, &account.id, &qr_transaction_id, &payment.id)
+                .await
+                .unwrap_err()
+                .downcast::<QrCodeError>()
+                .unwrap(),
+            QrCodeError::AlreadyPaid(qr_transaction_id)
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    filters::account::Filter as AccountFilter,
+    models::{
+        reconciliation_dto::{ReconciliationFailure, ReconciliationReport, ReconciliationRun},
+        transaction_dto::Transaction,
+        user_dto::User,
+    },
+    repositories::{
+        accounts::AccountRepository, transactions::TransactionRepository, users::UserRepository,
+    },
+    structs::money::Money,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    transaction_repository: TransactionRepository,
+    user_repository: UserRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            transaction_repository: TransactionRepository::new(),
+            user_repository: UserRepository::new(),
+        }
+    }
+
+    /// Recomputes the balance of one account from its full transaction history.
+    pub async fn reconcile_account(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+    ) -> anyhow::Result<ReconciliationReport> {
+        let mut owners = HashMap::new();
+
+        self.reconcile(db_pool, account_id, &mut owners).await
+    }
+
+    /// Reconciles every account, closed and deleted ones included, sharing decrypted owners
+    /// between them. Accounts that fail are recorded alongside the reports instead of stopping
+    /// the run.
+    pub async fn reconcile_all(&self, db_pool: &PgPool) -> anyhow::Result<ReconciliationRun> {
+        let accounts = self
+            .account_repository
+            .find_all(
+                db_pool,
+                &AccountFilter {
+                    include_deleted: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let mut owners = HashMap::new();
+        let mut run = ReconciliationRun::default();
+
+        for account in &accounts {
+            match self.reconcile(db_pool, &account.id, &mut owners).await {
+                Ok(report) => run.reports.push(report),
+                Err(e) => run.failures.push(ReconciliationFailure {
+                    account_id: account.id,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        Ok(run)
+    }
+
+    /// Compares the balance with the history while the account is locked against postings, so
+    /// a transaction committed halfway through cannot show up as a discrepancy. Everything is
+    /// read under that lock, in the same transaction.
+    async fn reconcile(
+        &self,
+        db_pool: &PgPool,
+        account_id: &Uuid,
+        owners: &mut HashMap<Uuid, User>,
+    ) -> anyhow::Result<ReconciliationReport> {
+        let mut tx = db_pool.begin().await?;
+        let account = self
+            .account_repository
+            .find_by_id_for_share(&mut tx, account_id)
+            .await?;
+        let transactions = self
+            .transaction_repository
+            .find_by_account_id(&mut tx, &account.id)
+            .await?;
+
+        let by_id: HashMap<Uuid, &Transaction> = transactions
+            .iter()
+            .map(|transaction| (transaction.id, transaction))
+            .collect();
+
+        let mut expected_balance = Money::ZERO;
+        for transaction in &transactions {
+            // amounts are encrypted with the key of the receiving account's owner
+            let owner = self
+                .find_owner(&mut tx, owners, &transaction.to_account_id)
+                .await?;
+            let amount = transaction
+                .get_amount(&owner.encryption_key)
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+            expected_balance = expected_balance
+                .checked_add(
+                    transaction.balance_change(
+                        &account.id,
+                        amount,
+                        transaction
+                            .reverses_transaction_id
+                            .and_then(|id| by_id.get(&id).copied()),
+                    )?,
+                )
+                .ok_or_else(|| anyhow::anyhow!("Balance overflow on account {}", account.id))?;
+        }
+
+        let owner = self.find_owner(&mut tx, owners, &account.id).await?;
+        let actual_balance = account.get_balance(owner)?;
+
+        let report = ReconciliationReport::new(
+            account.id,
+            transactions.len() as u64,
+            expected_balance,
+            actual_balance,
+        );
+        tx.commit().await?;
+
+        Ok(report)
+    }
+
+    async fn find_owner<'a>(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        owners: &'a mut HashMap<Uuid, User>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<&'a User> {
+        if let Entry::Vacant(vacant) = owners.entry(*account_id) {
+            let user = self
+                .user_repository
+                .find_by_account_id(db_tx, account_id)
+                .await?;
+            vacant.insert(user);
+        }
+
+        Ok(&owners[account_id])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::transaction_dto::{TransactionCreate, TransactionOperation},
+        services::transaction::Service as TransactionService,
+        structs::encrypted_field::EncryptedField,
+        test_helpers::create_accounts,
+    };
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_reconciliation_reports_drifted_balances(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[
+                Money::from_minor_units(10_000),
+                Money::from_minor_units(2_500),
+            ],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        let (_, other_account) = &accounts[1];
+
+        for (operation, from_account_id, to_account_id, minor_units) in [
+            (
+                TransactionOperation::Transfer,
+                Some(account.id),
+                other_account.id,
+                3_000,
+            ),
+            (TransactionOperation::Fee, None, account.id, 150),
+            (TransactionOperation::Deposit, None, account.id, 725),
+        ] {
+            let mut tx = db_pool.begin().await.unwrap();
+            TransactionService::new()
+                .create(
+                    &db_pool,
+                    &mut tx,
+                    &TransactionCreate {
+                        operation,
+                        from_account_id,
+                        to_account_id,
+                        to_key: None,
+                        amount: Money::from_minor_units(minor_units),
+                        reverses_transaction_id: None,
+                    },
+                    &owner.id,
+                )
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+        }
+
+        // a deleted account keeps its history, so it is reconciled all the same
+        sqlx::query("UPDATE accounts SET deleted_at = now() WHERE id = $1")
+            .bind(other_account.id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let run = Service::new().reconcile_all(&db_pool).await.unwrap();
+        assert_eq!(run.reports.len(), 2);
+        assert!(run.is_consistent());
+
+        // write a balance that no transaction accounts for
+        let mut tx = db_pool.begin().await.unwrap();
+        let mut drifted = AccountRepository::new()
+            .find_by_id_for_update(&mut tx, &account.id)
+            .await
+            .unwrap();
+        drifted
+            .update_balance(owner, Money::from_minor_units(99_999))
+            .unwrap();
+        AccountRepository::new()
+            .save_balance(&mut tx, &drifted)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let report = Service::new()
+            .reconcile_account(&db_pool, &account.id)
+            .await
+            .unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(report.transactions, 4);
+        assert_eq!(report.expected_balance, Money::from_minor_units(7_575));
+        assert_eq!(report.difference, Money::from_minor_units(92_424));
+
+        // an account that cannot be read does not stop the others from being reconciled
+        let undecryptable = EncryptedField::<Money>::new(vec![0; 12], vec![0; 16]);
+        sqlx::query("UPDATE accounts SET balance = $2 WHERE id = $1")
+            .bind(other_account.id)
+            .bind(bincode::serialize(&undecryptable).unwrap())
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let run = Service::new().reconcile_all(&db_pool).await.unwrap();
+        assert_eq!(run.reports.len(), 1);
+        assert_eq!(run.reports[0].account_id, account.id);
+        assert_eq!(run.failures.len(), 1);
+        assert_eq!(run.failures[0].account_id, other_account.id);
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::Account,
+        remittance_dto::{
+            parse_your_number, your_number, Remittance, RemittanceError, RemittanceItem,
+            RemittanceStatus, ReturnReport,
+        },
+        transaction_dto::Transaction,
+        user_dto::User,
+    },
+    payment_files::cnab240::{self, CnabAccount, CnabFile, CnabFileKind, CnabHeader, CnabPayment},
+    repositories::{
+        accounts::AccountRepository, remittances::RemittanceRepository, users::UserRepository,
+    },
+    structs::money::Money,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    account_repository: AccountRepository,
+    remittance_repository: RemittanceRepository,
+    user_repository: UserRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            account_repository: AccountRepository::new(),
+            remittance_repository: RemittanceRepository::new(),
+            user_repository: UserRepository::new(),
+        }
+    }
+
+    /// Puts every transfer out of an account that is still to be paid at another bank account
+    /// into a new CNAB 240 remittance file, or returns `None` when there are none.
+    pub async fn generate(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+    ) -> anyhow::Result<Option<(Remittance, String)>> {
+        // the lock keeps two files of the same account from taking the same sequence number
+        let account = self
+            .account_repository
+            .find_by_id_for_update(db_tx, account_id)
+            .await?;
+        let company = CnabAccount::from_account(&account)?;
+
+        let transfers = self
+            .remittance_repository
+            .find_unremitted_transfers(db_tx, account_id)
+            .await?;
+        if transfers.is_empty() {
+            return Ok(None);
+        }
+
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, account_id)
+            .await?;
+        let file_sequence = self
+            .remittance_repository
+            .next_file_sequence(db_tx, account_id)
+            .await?;
+        let remittance = self
+            .remittance_repository
+            .create(db_tx, &Remittance::new(*account_id, file_sequence))
+            .await?;
+
+        let mut beneficiaries: HashMap<Uuid, (Account, User)> = HashMap::new();
+        let mut payments = Vec::new();
+        for (index, transaction) in transfers.iter().enumerate() {
+            let sequence = index as i32 + 1;
+            if let Entry::Vacant(vacant) = beneficiaries.entry(transaction.to_account_id) {
+                let account = self
+                    .account_repository
+                    .find_by_id(db_pool, &transaction.to_account_id)
+                    .await?;
+                let user = self
+                    .user_repository
+                    .find_by_id(db_pool, &account.user_id)
+                    .await?;
+                vacant.insert((account, user));
+            }
+            let (beneficiary, beneficiary_owner) = &beneficiaries[&transaction.to_account_id];
+
+            self.remittance_repository
+                .create_item(
+                    db_tx,
+                    &RemittanceItem {
+                        remittance_id: remittance.id,
+                        sequence,
+                        transaction_id: transaction.id,
+                        status: RemittanceStatus::Pending,
+                        bank_reference: None,
+                        occurrences: None,
+                        updated_at: None,
+                    },
+                )
+                .await?;
+
+            payments.push(CnabPayment {
+                your_number: your_number(file_sequence, sequence),
+                beneficiary: CnabAccount::from_account(beneficiary)?,
+                beneficiary_name: beneficiary_owner.name.clone(),
+                payment_date: transaction.created_at.date(),
+                amount: amount(transaction, beneficiary_owner, &owner)?,
+                bank_reference: None,
+                effective_date: None,
+                effective_amount: None,
+                occurrences: Vec::new(),
+            });
+        }
+
+        let file = cnab240::write(&CnabFile {
+            header: CnabHeader {
+                kind: CnabFileKind::Remittance,
+                company,
+                company_name: owner.name,
+                file_sequence,
+                generated_at: remittance.created_at,
+            },
+            payments,
+        })?;
+
+        Ok(Some((remittance, file)))
+    }
+
+    /// Records what a return file says about the payments of earlier remittances. Payments
+    /// whose "seu número" matches none of them are reported back as unmatched.
+    pub async fn process_return(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        file: &CnabFile,
+    ) -> anyhow::Result<ReturnReport> {
+        if file.header.kind != CnabFileKind::Return {
+            return Err(RemittanceError::NotAReturnFile.into());
+        }
+
+        let company = &file.header.company;
+        let accounts = self
+            .account_repository
+            .find_by_bank_details(
+                db_tx,
+                company.bank_id,
+                (company.agency_number, company.agency_digit),
+                (company.account_number, company.account_digit),
+            )
+            .await?;
+        let [account] = accounts.as_slice() else {
+            return Err(RemittanceError::UnknownAccount.into());
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        let mut report = ReturnReport {
+            account_id: account.id,
+            updated: Vec::new(),
+            unmatched: Vec::new(),
+        };
+        for payment in &file.payments {
+            let item = match parse_your_number(&payment.your_number) {
+                Some((file_sequence, sequence)) => {
+                    self.remittance_repository
+                        .find_item_for_update(db_tx, &account.id, file_sequence, sequence)
+                        .await?
+                }
+                None => None,
+            };
+            let Some(mut item) = item else {
+                report.unmatched.push(payment.your_number.clone());
+                continue;
+            };
+
+            item.status = RemittanceStatus::from_occurrences(&payment.occurrences);
+            item.bank_reference = payment.bank_reference.clone().or(item.bank_reference);
+            item.occurrences = Some(payment.occurrences.concat());
+            item.updated_at = Some(now);
+            report
+                .updated
+                .push(self.remittance_repository.save_item(db_tx, &item).await?);
+        }
+
+        Ok(report)
+    }
+}
+
+/// Decrypts an amount with the key of the receiving account's owner, falling back to the
+/// sending one's like the transaction listing does.
+fn amount(transaction: &Transaction, receiver: &User, sender: &User) -> anyhow::Result<Money> {
+    transaction
+        .get_amount(&receiver.encryption_key)
+        .or_else(|_| transaction.get_amount(&sender.encryption_key))
+        .map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::transaction_dto::{TransactionCreate, TransactionOperation},
+        services::{
+            journal::Service as JournalService, transaction::Service as TransactionService,
+        },
+        test_helpers::create_accounts,
+    };
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_remittance_and_return_follow_transfers(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[
+                Money::from_minor_units(10_000),
+                Money::from_minor_units(0),
+                Money::from_minor_units(0),
+            ],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        // the last account is not held at a bank, so its transfer stays out of the file
+        for (number, (_, account)) in accounts[..2].iter().enumerate() {
+            sqlx::query(
+                r#"UPDATE accounts SET bank_id = 1, bank_agency_number = 1234, bank_account_number = $2 WHERE id = $1"#,
+            )
+            .bind(account.id)
+            .bind(number as i32 + 1)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        }
+        let mut tx = db_pool.begin().await.unwrap();
+        for (to_account_id, amount) in [(accounts[1].1.id, 2_500), (accounts[2].1.id, 500)] {
+            TransactionService::new()
+                .create(
+                    &db_pool,
+                    &mut tx,
+                    &TransactionCreate {
+                        operation: TransactionOperation::Transfer,
+                        from_account_id: Some(account.id),
+                        to_account_id,
+                        to_key: None,
+                        amount: Money::from_minor_units(amount),
+                        reverses_transaction_id: None,
+                    },
+                    &owner.id,
+                )
+                .await
+                .unwrap();
+        }
+        tx.commit().await.unwrap();
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let (remittance, text) = Service::new()
+            .generate(&db_pool, &mut tx, &account.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(Service::new()
+            .generate(&db_pool, &mut tx, &account.id)
+            .await
+            .unwrap()
+            .is_none());
+        tx.commit().await.unwrap();
+
+        let mut file = cnab240::parse(&text).unwrap();
+        assert_eq!(remittance.file_sequence, 1);
+        assert_eq!(file.header.company_name, "USER 0");
+        assert_eq!(file.payments.len(), 1);
+        assert_eq!(file.payments[0].your_number, "00000100001");
+        assert_eq!(file.payments[0].beneficiary.account_number, 2);
+        assert_eq!(file.payments[0].amount, Money::from_minor_units(2_500));
+
+        file.header.kind = CnabFileKind::Return;
+        file.payments[0].bank_reference = Some("NN1".to_string());
+        file.payments[0].occurrences = vec!["00".to_string()];
+        let mut unknown = file.payments[0].clone();
+        unknown.your_number = "00009900001".to_string();
+        file.payments.push(unknown);
+        let returned = cnab240::parse(&cnab240::write(&file).unwrap()).unwrap();
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let report = Service::new()
+            .process_return(&mut tx, &returned)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        assert_eq!(report.account_id, account.id);
+        assert_eq!(report.updated.len(), 1);
+        assert_eq!(report.updated[0].status, RemittanceStatus::Paid);
+        assert_eq!(report.updated[0].bank_reference.as_deref(), Some("NN1"));
+        assert_eq!(report.unmatched, vec!["00009900001".to_string()]);
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+}
@@ -0,0 +1,460 @@
+use sqlx::{Acquire, PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    filters::schedule::Filter as ScheduleFilter,
+    models::{
+        account_dto::InsufficientFunds,
+        notification_dto::{Notification, NotificationKind},
+        schedule_dto::{
+            Schedule, ScheduleCreate, ScheduleRunError, ScheduleRunOutcome, ScheduleRunSummary,
+            ScheduleUpdate,
+        },
+        transaction_dto::{TransactionCreate, TransactionOperation},
+        user_dto::User,
+    },
+    repositories::{
+        notifications::NotificationRepository, schedules::ScheduleRepository, users::UserRepository,
+    },
+    retry::{is_retryable, with_transaction_retry},
+    services::transaction::Service as TransactionService,
+};
+
+#[derive(Debug)]
+pub struct Service {
+    notification_repository: NotificationRepository,
+    schedule_repository: ScheduleRepository,
+    user_repository: UserRepository,
+    transaction_service: TransactionService,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            notification_repository: NotificationRepository::new(),
+            schedule_repository: ScheduleRepository::new(),
+            user_repository: UserRepository::new(),
+            transaction_service: TransactionService::new(),
+        }
+    }
+
+    pub async fn get_all(
+        &self,
+        db_pool: &PgPool,
+        filters: &ScheduleFilter,
+    ) -> (Vec<Schedule>, u64) {
+        // if we had a logging system, we would log the error here
+        let schedules =
+            (self.schedule_repository.find_all(db_pool, filters).await).unwrap_or_default();
+        let total = (self.schedule_repository.get_total(db_pool, filters).await).unwrap_or(0);
+
+        (schedules, total)
+    }
+
+    pub async fn get_one_by_id(&self, db_pool: &PgPool, id: &Uuid) -> Option<Schedule> {
+        // if we had a logging system, we would log the error here
+        (self.schedule_repository.find_by_id(db_pool, id).await).ok()
+    }
+
+    /// Creates a schedule owned by `owner`, who must own its origin account.
+    pub async fn create(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        owner: &User,
+        schedule: &ScheduleCreate,
+    ) -> anyhow::Result<Schedule> {
+        let new_schedule = Schedule::new(owner, schedule)?;
+
+        self.schedule_repository.create(db_tx, &new_schedule).await
+    }
+
+    pub async fn update(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+        owner: &User,
+        update: &ScheduleUpdate,
+    ) -> anyhow::Result<Schedule> {
+        let mut schedule = self
+            .schedule_repository
+            .find_by_id_for_update(db_tx, id)
+            .await?;
+
+        schedule.update(owner, update, chrono::Utc::now().naive_utc())?;
+
+        self.schedule_repository.save(db_tx, &schedule).await
+    }
+
+    pub async fn delete(&self, db_tx: &mut SqlxTransaction<'_, Postgres>, id: &Uuid) -> bool {
+        // if we had a logging system, we would log the error here
+        (self.schedule_repository.delete(db_tx, id).await).unwrap_or(false)
+    }
+
+    /// Runs every schedule that is due, each in its own database transaction.
+    ///
+    /// A schedule whose run breaks off is recorded as failed in a transaction of its own, so
+    /// it waits for its retry instead of holding up the schedules behind it.
+    pub async fn run_due(&self, db_pool: &PgPool) -> anyhow::Result<ScheduleRunSummary> {
+        let mut summary = ScheduleRunSummary::default();
+
+        loop {
+            let result = with_transaction_retry(db_pool, |tx| {
+                let db_pool = db_pool.clone();
+                Box::pin(async move {
+                    Service::new()
+                        .run_next(&db_pool, tx, chrono::Utc::now().naive_utc())
+                        .await
+                })
+            })
+            .await;
+
+            let outcome = match result {
+                Ok(Some(outcome)) => outcome,
+                Ok(None) => break,
+                Err(e) => {
+                    let error = e.downcast::<ScheduleRunError>()?;
+                    with_transaction_retry(db_pool, |tx| {
+                        let error = error.clone();
+                        Box::pin(async move {
+                            Service::new()
+                                .record_run_error(tx, &error, chrono::Utc::now().naive_utc())
+                                .await
+                        })
+                    })
+                    .await?
+                }
+            };
+            summary.record(outcome);
+        }
+
+        Ok(summary)
+    }
+
+    /// Runs the next schedule due at `now`, if there is one.
+    ///
+    /// The transfer goes through a savepoint, so a failed run still records its
+    /// outcome on the schedule in `db_tx`. A run that breaks off anywhere else fails with
+    /// [`ScheduleRunError`], which names the schedule.
+    pub async fn run_next(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        now: chrono::NaiveDateTime,
+    ) -> anyhow::Result<Option<ScheduleRunOutcome>> {
+        let mut schedule = match self
+            .schedule_repository
+            .find_next_due_for_update(db_tx, now)
+            .await?
+        {
+            Some(schedule) => schedule,
+            None => return Ok(None),
+        };
+
+        match self.run(db_pool, db_tx, &mut schedule, now).await {
+            Ok(outcome) => Ok(Some(outcome)),
+            Err(e) if is_retryable(&e) => Err(e),
+            Err(e) => Err(ScheduleRunError {
+                schedule_id: schedule.id,
+                message: e.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// Runs the transfer of `schedule`, locked in `db_tx`, and records its outcome there.
+    async fn run(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        schedule: &mut Schedule,
+        now: chrono::NaiveDateTime,
+    ) -> anyhow::Result<ScheduleRunOutcome> {
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, &schedule.from_account_id)
+            .await?;
+        let transfer = TransactionCreate {
+            operation: TransactionOperation::Transfer,
+            from_account_id: Some(schedule.from_account_id),
+            to_account_id: schedule.to_account_id,
+            to_key: None,
+            amount: schedule.get_amount(&owner)?,
+            reverses_transaction_id: None,
+        };
+
+        let mut savepoint = db_tx.begin().await?;
+        let outcome = match self
+            .transaction_service
+            .create(db_pool, &mut savepoint, &transfer, &owner.id)
+            .await
+        {
+            Ok(transaction) => {
+                savepoint.commit().await?;
+                schedule.record_success(transaction.id, now)?;
+                ScheduleRunOutcome::Executed(transaction.id)
+            }
+            // let the whole run start over instead of counting it as a failure
+            Err(e) if is_retryable(&e) => return Err(e),
+            Err(e) if e.downcast_ref::<InsufficientFunds>().is_some() => {
+                savepoint.rollback().await?;
+                schedule.record_skip(e.to_string(), now)?;
+                self.notify(
+                    db_tx,
+                    schedule,
+                    NotificationKind::ScheduleSkipped,
+                    format!(
+                        "Scheduled transfer {} was skipped: not enough funds",
+                        schedule.id
+                    ),
+                )
+                .await?;
+                ScheduleRunOutcome::Skipped
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                if schedule.record_failure(e.to_string(), now) {
+                    self.notify(
+                        db_tx,
+                        schedule,
+                        NotificationKind::ScheduleFailed,
+                        format!("Scheduled transfer {} failed: {}", schedule.id, e),
+                    )
+                    .await?;
+                    ScheduleRunOutcome::Failed
+                } else {
+                    ScheduleRunOutcome::Retrying
+                }
+            }
+        };
+
+        self.schedule_repository.save(db_tx, schedule).await?;
+
+        Ok(outcome)
+    }
+
+    /// Records a run that broke off as a failed attempt, which sets up its retry or gives up
+    /// on the schedule.
+    pub async fn record_run_error(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        error: &ScheduleRunError,
+        now: chrono::NaiveDateTime,
+    ) -> anyhow::Result<ScheduleRunOutcome> {
+        let mut schedule = self
+            .schedule_repository
+            .find_by_id_for_update(db_tx, &error.schedule_id)
+            .await?;
+
+        let outcome = if schedule.record_failure(error.message.clone(), now) {
+            self.notify(
+                db_tx,
+                &schedule,
+                NotificationKind::ScheduleFailed,
+                format!(
+                    "Scheduled transfer {} failed: {}",
+                    schedule.id, error.message
+                ),
+            )
+            .await?;
+            ScheduleRunOutcome::Failed
+        } else {
+            ScheduleRunOutcome::Retrying
+        };
+        self.schedule_repository.save(db_tx, &schedule).await?;
+
+        Ok(outcome)
+    }
+
+    async fn notify(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        schedule: &Schedule,
+        kind: NotificationKind,
+        message: String,
+    ) -> anyhow::Result<Notification> {
+        self.notification_repository
+            .create(
+                db_tx,
+                &Notification::new(schedule.user_id, kind, message, Some(schedule.id)),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::schedule_dto::{ScheduleFrequency, ScheduleStatus},
+        repositories::accounts::AccountRepository,
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_due_schedules_run_and_skip_when_short(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(2_500), Money::from_minor_units(0)],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        let (payee, payee_account) = &accounts[1];
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let schedule = Service::new()
+            .create(
+                &mut tx,
+                owner,
+                &ScheduleCreate {
+                    from_account_id: account.id,
+                    to_account_id: payee_account.id,
+                    amount: Money::from_minor_units(1_000),
+                    frequency: ScheduleFrequency::Daily,
+                    cron_expression: None,
+                    starts_at: Some(chrono::Utc::now().naive_utc() - chrono::Duration::days(1)),
+                    ends_at: None,
+                },
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        // one run per pass: the next one is only due tomorrow
+        for expected in [
+            ScheduleRunSummary {
+                executed: 1,
+                ..Default::default()
+            },
+            ScheduleRunSummary::default(),
+        ] {
+            assert_eq!(Service::new().run_due(&db_pool).await.unwrap(), expected);
+        }
+
+        let payee_balance = AccountRepository::new()
+            .find_by_id(&db_pool, &payee_account.id)
+            .await
+            .unwrap()
+            .get_balance(payee)
+            .unwrap();
+        assert_eq!(payee_balance, Money::from_minor_units(1_000));
+
+        // pretend two more days went by, with only enough money for one run
+        let mut runs = Vec::new();
+        for days in [1, 2] {
+            sqlx::query("UPDATE schedules SET next_run_at = now() - interval '1 second'")
+                .execute(&db_pool)
+                .await
+                .unwrap();
+            let mut tx = db_pool.begin().await.unwrap();
+            let now = chrono::Utc::now().naive_utc() + chrono::Duration::days(days);
+            runs.push(
+                Service::new()
+                    .run_next(&db_pool, &mut tx, now)
+                    .await
+                    .unwrap(),
+            );
+            tx.commit().await.unwrap();
+        }
+        assert!(matches!(runs[0], Some(ScheduleRunOutcome::Executed(_))));
+        assert_eq!(runs[1], Some(ScheduleRunOutcome::Skipped));
+
+        let schedule = Service::new()
+            .get_one_by_id(&db_pool, &schedule.id)
+            .await
+            .unwrap();
+        assert_eq!(schedule.status, ScheduleStatus::Active);
+        assert_eq!(schedule.last_error.as_deref(), Some("Not enough funds"));
+        assert!(schedule.next_run_at.unwrap() > chrono::Utc::now().naive_utc());
+
+        let notifications = NotificationRepository::new()
+            .find_by_user_id(&db_pool, &owner.id)
+            .await
+            .unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].kind, NotificationKind::ScheduleSkipped);
+        assert_eq!(notifications[0].schedule_id, Some(schedule.id));
+    }
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_a_broken_schedule_does_not_hold_up_the_rest(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[
+                Money::from_minor_units(10_000),
+                Money::from_minor_units(10_000),
+                Money::from_minor_units(0),
+            ],
+        )
+        .await;
+        let (payee, payee_account) = &accounts[2];
+        let mut schedules = Vec::new();
+        for (days, (owner, account)) in [2, 1].into_iter().zip(&accounts) {
+            let mut tx = db_pool.begin().await.unwrap();
+            schedules.push(
+                Service::new()
+                    .create(
+                        &mut tx,
+                        owner,
+                        &ScheduleCreate {
+                            from_account_id: account.id,
+                            to_account_id: payee_account.id,
+                            amount: Money::from_minor_units(1_000),
+                            frequency: ScheduleFrequency::Daily,
+                            cron_expression: None,
+                            starts_at: Some(
+                                chrono::Utc::now().naive_utc() - chrono::Duration::days(days),
+                            ),
+                            ends_at: None,
+                        },
+                    )
+                    .await
+                    .unwrap(),
+            );
+            tx.commit().await.unwrap();
+        }
+        // an amount sealed with another user's key cannot be read back, so the run breaks off
+        // before it reaches the transfer
+        sqlx::query(
+            "UPDATE schedules SET amount = (SELECT amount FROM schedules WHERE id = $2) WHERE id = $1",
+        )
+        .bind(schedules[0].id)
+        .bind(schedules[1].id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            Service::new().run_due(&db_pool).await.unwrap(),
+            ScheduleRunSummary {
+                executed: 1,
+                retrying: 1,
+                ..Default::default()
+            }
+        );
+
+        let broken = Service::new()
+            .get_one_by_id(&db_pool, &schedules[0].id)
+            .await
+            .unwrap();
+        assert_eq!(broken.status, ScheduleStatus::Active);
+        assert_eq!(broken.failed_attempts, 1);
+        assert!(broken.last_error.is_some());
+        assert!(broken.retry_at.unwrap() > chrono::Utc::now().naive_utc());
+
+        let payee_balance = AccountRepository::new()
+            .find_by_id(&db_pool, &payee_account.id)
+            .await
+            .unwrap()
+            .get_balance(payee)
+            .unwrap();
+        assert_eq!(payee_balance, Money::from_minor_units(1_000));
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use chrono::NaiveDateTime;
+use futures::{stream, Stream, StreamExt};
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::Account,
+        statement_dto::{StatementEntry, StatementQuery},
+        transaction_dto::Transaction,
+        user_dto::User,
+    },
+    repositories::{transactions::TransactionRepository, users::UserRepository},
+    statements::{self, StatementWriter},
+    structs::money::Money,
+};
+
+/// How many transactions are read from the database at a time.
+const PAGE_SIZE: i64 = 500;
+
+#[derive(Debug)]
+pub struct Service {
+    transaction_repository: TransactionRepository,
+    user_repository: UserRepository,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            transaction_repository: TransactionRepository::new(),
+            user_repository: UserRepository::new(),
+        }
+    }
+
+    /// Renders the statement of `account` for the period of `query` as a stream of file
+    /// chunks. The opening balance is worked out before the first chunk; the entries are read
+    /// a page at a time while the stream is consumed.
+    ///
+    /// Every pass reads the same snapshot of the database, so the balances always agree with
+    /// the entries, whatever is posted while the statement streams.
+    pub async fn render(
+        &self,
+        db_pool: &PgPool,
+        account: &Account,
+        query: &StatementQuery,
+    ) -> anyhow::Result<impl Stream<Item = anyhow::Result<Vec<u8>>> + Send + 'static> {
+        query.validate()?;
+        let (start, end) = query.period()?;
+
+        let mut db_tx = db_pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *db_tx)
+            .await?;
+        let mut history = self.history(db_tx, account.id, None, start);
+        let opening_balance = history.total(Money::ZERO).await?;
+        // the decrypted owners carry over between the passes
+        history.rewind(Some(start), end);
+
+        let mut writer = statements::writer(query.format);
+        let closing_balance = match writer.needs_closing_balance() {
+            true => {
+                let closing_balance = history.total(opening_balance).await?;
+                history.rewind(Some(start), end);
+                Some(closing_balance)
+            }
+            false => None,
+        };
+        let opening = writer.opening(account, query, opening_balance, closing_balance)?;
+        let state = Rendering {
+            history,
+            writer,
+            balance: opening_balance,
+            closed: false,
+        };
+
+        Ok(stream::once(async move { Ok(opening) })
+            .chain(stream::try_unfold(state, Rendering::next_chunk)))
+    }
+
+    fn history(
+        &self,
+        db_tx: SqlxTransaction<'static, Postgres>,
+        account_id: Uuid,
+        since: Option<NaiveDateTime>,
+        until: NaiveDateTime,
+    ) -> History {
+        History {
+            db_tx,
+            transaction_repository: self.transaction_repository.clone(),
+            user_repository: self.user_repository.clone(),
+            account_id,
+            since,
+            until,
+            after: None,
+            page: VecDeque::new(),
+            exhausted: false,
+            owners: HashMap::new(),
+        }
+    }
+}
+
+/// What is left of a statement while it streams.
+struct Rendering {
+    history: History,
+    writer: Box<dyn StatementWriter>,
+    balance: Money,
+    closed: bool,
+}
+
+impl Rendering {
+    async fn next_chunk(mut self) -> anyhow::Result<Option<(Vec<u8>, Self)>> {
+        if self.closed {
+            return Ok(None);
+        }
+
+        let chunk = match self.history.next().await? {
+            Some(mut entry) => {
+                self.balance = self.balance.checked_add(entry.amount).ok_or_else(|| {
+                    anyhow::anyhow!("Balance overflow on account {}", self.history.account_id)
+                })?;
+                entry.balance = self.balance;
+                self.writer.entry(&entry)?
+            }
+            None => {
+                self.closed = true;
+                self.writer.closing(self.balance)?
+            }
+        };
+
+        Ok(Some((chunk, self)))
+    }
+}
+
+/// Walks the transactions of an account in order, decrypting each one. The balance of the
+/// entries it yields is left at zero for the caller to fill in.
+struct History {
+    /// The read-only transaction every page is read in, rolled back once dropped.
+    db_tx: SqlxTransaction<'static, Postgres>,
+    transaction_repository: TransactionRepository,
+    user_repository: UserRepository,
+    account_id: Uuid,
+    since: Option<NaiveDateTime>,
+    until: NaiveDateTime,
+    after: Option<(NaiveDateTime, Uuid)>,
+    page: VecDeque<Transaction>,
+    exhausted: bool,
+    /// Decrypted owners by the id of their account.
+    owners: HashMap<Uuid, User>,
+}
+
+impl History {
+    /// Starts over on another period.
+    fn rewind(&mut self, since: Option<NaiveDateTime>, until: NaiveDateTime) {
+        self.since = since;
+        self.until = until;
+        self.after = None;
+        self.page.clear();
+        self.exhausted = false;
+    }
+
+    /// Adds every remaining entry to `balance`.
+    async fn total(&mut self, mut balance: Money) -> anyhow::Result<Money> {
+        while let Some(entry) = self.next().await? {
+            balance = balance.checked_add(entry.amount).ok_or_else(|| {
+                anyhow::anyhow!("Balance overflow on account {}", self.account_id)
+            })?;
+        }
+
+        Ok(balance)
+    }
+
+    async fn next(&mut self) -> anyhow::Result<Option<StatementEntry>> {
+        if self.page.is_empty() && !self.exhausted {
+            let page = self
+                .transaction_repository
+                .find_account_page(
+                    &mut self.db_tx,
+                    &self.account_id,
+                    self.since,
+                    self.until,
+                    self.after,
+                    PAGE_SIZE,
+                )
+                .await?;
+            self.exhausted = (page.len() as i64) < PAGE_SIZE;
+            self.after = page
+                .last()
+                .map(|transaction| (transaction.created_at, transaction.id));
+            self.page = page.into();
+        }
+
+        let Some(transaction) = self.page.pop_front() else {
+            return Ok(None);
+        };
+
+        let amount = self.amount(&transaction).await?;
+        let reversed = match transaction.reverses_transaction_id {
+            Some(id) => Some(
+                self.transaction_repository
+                    .find_by_id_within(&mut self.db_tx, &id)
+                    .await?,
+            ),
+            None => None,
+        };
+        let change = transaction.balance_change(&self.account_id, amount, reversed.as_ref())?;
+        let counterparty_account_id = if transaction.from_account_id == Some(self.account_id) {
+            Some(transaction.to_account_id)
+        } else {
+            transaction.from_account_id
+        };
+
+        Ok(Some(StatementEntry {
+            transaction_id: transaction.id,
+            posted_at: transaction.created_at,
+            operation: transaction.operation,
+            counterparty_account_id,
+            amount: change,
+            balance: Money::ZERO,
+        }))
+    }
+
+    /// Decrypts an amount with the key of the receiving account's owner, falling back to
+    /// the sending one's like the transaction listing does.
+    async fn amount(&mut self, transaction: &Transaction) -> anyhow::Result<Money> {
+        let owner = self.owner(&transaction.to_account_id).await?;
+        let amount = transaction
+            .get_amount(&owner.encryption_key)
+            .map_err(|e| anyhow::anyhow!(e.to_string()));
+
+        match (amount, transaction.from_account_id) {
+            (Err(_), Some(from_account_id)) => {
+                let owner = self.owner(&from_account_id).await?;
+                transaction
+                    .get_amount(&owner.encryption_key)
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))
+            }
+            (amount, _) => amount,
+        }
+    }
+
+    async fn owner(&mut self, account_id: &Uuid) -> anyhow::Result<&User> {
+        if let Entry::Vacant(vacant) = self.owners.entry(*account_id) {
+            let user = self
+                .user_repository
+                .find_by_account_id(&mut self.db_tx, account_id)
+                .await?;
+            vacant.insert(user);
+        }
+
+        Ok(&self.owners[account_id])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{
+        models::{
+            statement_dto::StatementFormat,
+            transaction_dto::{TransactionCreate, TransactionOperation},
+        },
+        repositories::accounts::AccountRepository,
+        services::{
+            journal::Service as JournalService, transaction::Service as TransactionService,
+        },
+        test_helpers::create_accounts,
+    };
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_statement_runs_from_opening_to_closing_balance(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(10_000), Money::from_minor_units(0)],
+        )
+        .await;
+        let (owner, account) = &accounts[0];
+        let (_, other_account) = &accounts[1];
+        let mut tx = db_pool.begin().await.unwrap();
+        let transfer = TransactionService::new()
+            .create(
+                &db_pool,
+                &mut tx,
+                &TransactionCreate {
+                    operation: TransactionOperation::Transfer,
+                    from_account_id: Some(account.id),
+                    to_account_id: other_account.id,
+                    to_key: None,
+                    amount: Money::from_minor_units(2_500),
+                    reverses_transaction_id: None,
+                },
+                &owner.id,
+            )
+            .await
+            .unwrap();
+        let reversal = TransactionService::new()
+            .reverse(
+                &db_pool,
+                &mut tx,
+                &transfer.id,
+                Some(Money::from_minor_units(1_000)),
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let today = chrono::Utc::now().date_naive();
+        let render = |from, to| {
+            let db_pool = db_pool.clone();
+            async move {
+                let account = AccountRepository::new()
+                    .find_by_id(&db_pool, &account.id)
+                    .await
+                    .unwrap();
+                let chunks: Vec<Vec<u8>> = Service::new()
+                    .render(
+                        &db_pool,
+                        &account,
+                        &StatementQuery {
+                            from,
+                            to,
+                            format: StatementFormat::Csv,
+                        },
+                    )
+                    .await
+                    .unwrap()
+                    .try_collect()
+                    .await
+                    .unwrap();
+                String::from_utf8(chunks.concat()).unwrap()
+            }
+        };
+
+        let statement = render(today, today).await;
+        let lines: Vec<&str> = statement.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1].ends_with(",opening_balance,,,0.00"));
+        assert!(lines[2].ends_with(",deposit,,100.00,100.00"));
+        assert!(lines[3].contains(&format!(
+            "{},transfer,{},-25.00,75.00",
+            transfer.id, other_account.id
+        )));
+        assert!(lines[4].contains(&format!(
+            "{},reversal,{},10.00,85.00",
+            reversal.id, other_account.id
+        )));
+        assert!(lines[5].ends_with(",closing_balance,,,85.00"));
+
+        // a later period opens with everything before it
+        let tomorrow = today.succ_opt().unwrap();
+        let statement = render(tomorrow, tomorrow).await;
+        let lines: Vec<&str> = statement.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with(",opening_balance,,,85.00"));
+        assert!(lines[2].ends_with(",closing_balance,,,85.00"));
+
+        // what is posted while a statement streams stays out of it
+        let mut chunks = Box::pin(
+            Service::new()
+                .render(
+                    &db_pool,
+                    account,
+                    &StatementQuery {
+                        from: today,
+                        to: today,
+                        format: StatementFormat::Csv,
+                    },
+                )
+                .await
+                .unwrap(),
+        );
+        let opening = chunks.try_next().await.unwrap().unwrap();
+        let mut tx = db_pool.begin().await.unwrap();
+        TransactionService::new()
+            .create(
+                &db_pool,
+                &mut tx,
+                &TransactionCreate {
+                    operation: TransactionOperation::Deposit,
+                    from_account_id: None,
+                    to_account_id: account.id,
+                    to_key: None,
+                    amount: Money::from_minor_units(1_000),
+                    reverses_transaction_id: None,
+                },
+                &owner.id,
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        let rest: Vec<Vec<u8>> = chunks.try_collect().await.unwrap();
+        let statement = String::from_utf8([opening, rest.concat()].concat()).unwrap();
+        let lines: Vec<&str> = statement.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[5].ends_with(",closing_balance,,,85.00"));
+
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+}
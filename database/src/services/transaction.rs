@@ -1,16 +1,41 @@
-use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
+use std::collections::{hash_map::Entry, HashMap};
+
+use chrono::{Duration, Utc};
+use sqlx::{Acquire, PgPool, Postgres, Transaction as SqlxTransaction};
 use uuid::Uuid;
 
 use crate::{
     filters::transaction::Filter as TransactionFilter,
-    models::transaction_dto::{Transaction, TransactionCreate},
-    repositories::{accounts::AccountRepository, transactions::TransactionRepository},
+    models::{
+        account_dto::{AccountStatus, AccountStatusError, AccountTypeError},
+        fee_dto::fee_period,
+        journal_dto::LedgerAccount,
+        limit_dto::LIMIT_WINDOW_HOURS,
+        payment_file_dto::PaymentFileError,
+        transaction_dto::{
+            BatchItemOutcome, BatchMode, ReversalError, Transaction, TransactionBatch,
+            TransactionCreate, TransactionError, TransactionOperation,
+        },
+    },
+    repositories::{
+        accounts::AccountRepository, employers::EmployerRepository, fees::FeeRepository,
+        journal::JournalRepository, limits::LimitRepository, payment_files::PaymentFileRepository,
+        transactions::TransactionRepository, users::UserRepository,
+    },
+    retry::is_retryable,
+    structs::money::{Money, MoneyEncoding},
 };
 
 #[derive(Debug)]
 pub struct Service {
     account_repository: AccountRepository,
+    employer_repository: EmployerRepository,
+    fee_repository: FeeRepository,
+    journal_repository: JournalRepository,
+    limit_repository: LimitRepository,
+    payment_file_repository: PaymentFileRepository,
     transaction_repository: TransactionRepository,
+    user_repository: UserRepository,
 }
 
 impl Default for Service {
@@ -23,7 +48,13 @@ impl Service {
     pub fn new() -> Self {
         Self {
             account_repository: AccountRepository::new(),
+            employer_repository: EmployerRepository::new(),
+            fee_repository: FeeRepository::new(),
+            journal_repository: JournalRepository::new(),
+            limit_repository: LimitRepository::new(),
+            payment_file_repository: PaymentFileRepository::new(),
             transaction_repository: TransactionRepository::new(),
+            user_repository: UserRepository::new(),
         }
     }
 
@@ -49,6 +80,13 @@ impl Service {
         (transactions, total)
     }
 
+    /// Posts `transaction` along with the fee its paying account's fee schedule charges for it.
+    ///
+    /// Fails with [`TransactionError::InvalidAmount`] unless the amount is positive, with
+    /// [`AccountStatusError`] when the status of an account involved does not allow it, with
+    /// [`AccountTypeError`] when its type does not, and with
+    /// [`LimitExceeded`](crate::models::limit_dto::LimitExceeded) when it takes its initiating
+    /// account over a limit.
     pub async fn create(
         &self,
         db_pool: &PgPool,
@@ -56,26 +94,1163 @@ impl Service {
         transaction: &TransactionCreate,
         current_user_id: &Uuid,
     ) -> anyhow::Result<Transaction> {
+        self.create_checked(db_pool, db_tx, transaction, current_user_id, false)
+            .await
+    }
+
+    /// Posts `transaction` like [`create`](Self::create), as the settlement of funds held
+    /// earlier, which a closing account still honours.
+    pub async fn settle(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &TransactionCreate,
+        current_user_id: &Uuid,
+    ) -> anyhow::Result<Transaction> {
+        self.create_checked(db_pool, db_tx, transaction, current_user_id, true)
+            .await
+    }
+
+    async fn create_checked(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &TransactionCreate,
+        current_user_id: &Uuid,
+        settling: bool,
+    ) -> anyhow::Result<Transaction> {
+        // a negative amount would move money the other way, out of the account credited
+        if !transaction.amount.is_positive() {
+            return Err(TransactionError::InvalidAmount.into());
+        }
+
+        let created_transaction = self
+            .post(db_pool, db_tx, transaction, current_user_id)
+            .await?;
+        self.check_account_states(db_tx, transaction, settling)
+            .await?;
+        self.check_account_types(db_tx, transaction).await?;
+        self.check_limits(db_tx, transaction).await?;
+        self.charge_operation_fee(db_pool, db_tx, transaction, current_user_id)
+            .await?;
+
+        Ok(created_transaction)
+    }
+
+    /// Posts every transaction of `batch`, each in its own savepoint, and reports what
+    /// happened to each of them in order.
+    ///
+    /// All of the accounts involved are locked up front in id order, so batches sharing
+    /// accounts queue up behind each other instead of deadlocking. When an item of an
+    /// all-or-nothing batch fails, everything the batch posted is rolled back.
+    pub async fn create_batch(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        batch: &TransactionBatch,
+        current_user_id: &Uuid,
+    ) -> anyhow::Result<Vec<BatchItemOutcome>> {
+        batch.validate()?;
         self.account_repository
-            .update_balance(
-                db_pool,
-                db_tx,
-                transaction,
-                transaction.amount,
-                current_user_id,
-            )
+            .lock_by_ids(db_tx, &batch.account_ids())
+            .await?;
+
+        let mut batch_tx = db_tx.begin().await?;
+        let mut outcomes = Vec::with_capacity(batch.transactions.len());
+        for transaction in &batch.transactions {
+            let mut savepoint = batch_tx.begin().await?;
+            match self
+                .create(db_pool, &mut savepoint, transaction, current_user_id)
+                .await
+            {
+                Ok(created) => {
+                    savepoint.commit().await?;
+                    outcomes.push(BatchItemOutcome::Created(created));
+                }
+                // a conflict with another transaction is retried as a whole instead
+                Err(e) if is_retryable(&e) => return Err(e),
+                Err(e) => {
+                    savepoint.rollback().await?;
+                    outcomes.push(BatchItemOutcome::Failed(e.to_string()));
+                }
+            }
+        }
+
+        let failed = outcomes
+            .iter()
+            .any(|outcome| matches!(outcome, BatchItemOutcome::Failed(_)));
+        if failed && batch.mode == BatchMode::AllOrNothing {
+            batch_tx.rollback().await?;
+            for outcome in &mut outcomes {
+                if let BatchItemOutcome::Created(_) = outcome {
+                    *outcome = BatchItemOutcome::RolledBack;
+                }
+            }
+        } else {
+            batch_tx.commit().await?;
+        }
+
+        Ok(outcomes)
+    }
+
+    /// Posts the batch read from the payment file `message_id`, which each user can only have
+    /// applied once. A file none of whose payments were made can be sent again.
+    pub async fn create_payment_file_batch(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        message_id: &str,
+        batch: &TransactionBatch,
+        current_user_id: &Uuid,
+    ) -> anyhow::Result<Vec<BatchItemOutcome>> {
+        let mut file_tx = db_tx.begin().await?;
+        if !self
+            .payment_file_repository
+            .claim(&mut file_tx, current_user_id, message_id)
+            .await?
+        {
+            return Err(PaymentFileError::AlreadyApplied(message_id.to_string()).into());
+        }
+
+        let outcomes = self
+            .create_batch(db_pool, &mut file_tx, batch, current_user_id)
             .await?;
+        match outcomes
+            .iter()
+            .any(|outcome| matches!(outcome, BatchItemOutcome::Created(_)))
+        {
+            true => file_tx.commit().await?,
+            false => file_tx.rollback().await?,
+        }
 
+        Ok(outcomes)
+    }
+
+    async fn post(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &TransactionCreate,
+        current_user_id: &Uuid,
+    ) -> anyhow::Result<Transaction> {
         let account = self
             .account_repository
-            .find_by_id(db_pool, &transaction.to_account_id)
+            .update_balance(db_tx, transaction, transaction.amount, current_user_id)
             .await?;
 
-        let transaction = self
+        let created_transaction = self
             .transaction_repository
             .create(db_pool, db_tx, &account, transaction)
             .await?;
 
-        Ok(transaction)
+        self.journal_repository
+            .record(db_tx, &created_transaction, transaction.amount)
+            .await?;
+
+        Ok(created_transaction)
+    }
+
+    /// Moves the whole balance of a closing account to `to_account_id`, free of fees and
+    /// limits. Nothing is posted when the balance is zero.
+    pub async fn pay_out(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        from_account_id: &Uuid,
+        to_account_id: &Uuid,
+        amount: Money,
+    ) -> anyhow::Result<Option<Transaction>> {
+        if !amount.is_positive() {
+            return Ok(None);
+        }
+
+        let transaction = TransactionCreate {
+            operation: TransactionOperation::Transfer,
+            from_account_id: Some(*from_account_id),
+            to_account_id: *to_account_id,
+            to_key: None,
+            amount,
+            reverses_transaction_id: None,
+        };
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, from_account_id)
+            .await?;
+        let created_transaction = self.post(db_pool, db_tx, &transaction, &owner.id).await?;
+        self.check_account_states(db_tx, &transaction, true).await?;
+        self.check_account_types(db_tx, &transaction).await?;
+
+        Ok(Some(created_transaction))
+    }
+
+    /// Posts `amount` of overdraft interest as a fee on `account_id`, free of fees and limits.
+    ///
+    /// Interest on an overdraft is owed however far the account is overdrawn, so the fee may
+    /// take the balance past the overdraft limit.
+    pub async fn charge_overdraft_interest(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        account_id: &Uuid,
+        amount: Money,
+    ) -> anyhow::Result<Transaction> {
+        if !amount.is_positive() {
+            return Err(TransactionError::InvalidAmount.into());
+        }
+
+        let transaction = TransactionCreate {
+            operation: TransactionOperation::Fee,
+            from_account_id: None,
+            to_account_id: *account_id,
+            to_key: None,
+            amount,
+            reverses_transaction_id: None,
+        };
+        let account = self
+            .account_repository
+            .overdraw(db_tx, account_id, amount)
+            .await?;
+        let created_transaction = self
+            .transaction_repository
+            .create(db_pool, db_tx, &account, &transaction)
+            .await?;
+        self.journal_repository
+            .record(db_tx, &created_transaction, amount)
+            .await?;
+
+        Ok(created_transaction)
+    }
+
+    /// Checks the accounts of `transaction`, already posted, can still take it: frozen
+    /// accounts are not debited, and closing or closed ones take nothing new besides the
+    /// settlement of what is held on a closing account.
+    async fn check_account_states(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &TransactionCreate,
+        settling: bool,
+    ) -> anyhow::Result<()> {
+        let (debited, credited) = match transaction.operation {
+            TransactionOperation::Transfer => {
+                (transaction.from_account_id, Some(transaction.to_account_id))
+            }
+            TransactionOperation::Deposit => (None, Some(transaction.to_account_id)),
+            TransactionOperation::Withdrawal | TransactionOperation::Payment => {
+                (Some(transaction.to_account_id), None)
+            }
+            _ => (None, None),
+        };
+
+        if let Some(account_id) = debited {
+            match self
+                .account_repository
+                .find_by_id_for_update(db_tx, &account_id)
+                .await?
+                .status
+            {
+                AccountStatus::Active => {}
+                AccountStatus::Closing if settling => {}
+                AccountStatus::Frozen => return Err(AccountStatusError::Frozen.into()),
+                status => return Err(AccountStatusError::NotOpen(status).into()),
+            }
+        }
+        if let Some(account_id) = credited {
+            match self
+                .account_repository
+                .find_by_id_for_update(db_tx, &account_id)
+                .await?
+                .status
+            {
+                AccountStatus::Active | AccountStatus::Frozen => {}
+                status => return Err(AccountStatusError::NotOpen(status).into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks `transaction`, already posted, against the rules of the types of its accounts:
+    /// savings accounts make no payments, salary accounts receive only from their employers,
+    /// and withdrawals count towards the monthly allowance of the account.
+    async fn check_account_types(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &TransactionCreate,
+    ) -> anyhow::Result<()> {
+        if let TransactionOperation::Deposit | TransactionOperation::Transfer =
+            transaction.operation
+        {
+            let receiver = self
+                .account_repository
+                .find_by_id_for_update(db_tx, &transaction.to_account_id)
+                .await?;
+            if receiver
+                .bank_account_type
+                .is_some_and(|account_type| account_type.rules().employers_only)
+            {
+                let from_employer = match &transaction.from_account_id {
+                    Some(from_account_id) => {
+                        self.employer_repository
+                            .exists(db_tx, &receiver.id, from_account_id)
+                            .await?
+                    }
+                    None => false,
+                };
+                if !from_employer {
+                    return Err(AccountTypeError::NotAnEmployer.into());
+                }
+            }
+        }
+
+        let Some(account_id) = transaction.initiating_account_id() else {
+            return Ok(());
+        };
+        let Some(account_type) = self
+            .account_repository
+            .find_by_id_for_update(db_tx, &account_id)
+            .await?
+            .bank_account_type
+        else {
+            return Ok(());
+        };
+        let rules = account_type.rules();
+        match (&transaction.operation, rules.monthly_withdrawals) {
+            (TransactionOperation::Payment, _) if !rules.can_pay_boletos => {
+                Err(AccountTypeError::PaymentsNotAllowed(account_type).into())
+            }
+            (TransactionOperation::Withdrawal, Some(allowance)) => {
+                let withdrawals = self
+                    .transaction_repository
+                    .count_initiated_since(
+                        db_tx,
+                        &transaction.operation,
+                        &account_id,
+                        // created_at is written in UTC, so the month starts at midnight UTC
+                        fee_period(Utc::now().date_naive()).into(),
+                    )
+                    .await?;
+                match withdrawals > allowance {
+                    true => Err(AccountTypeError::WithdrawalAllowanceExceeded {
+                        account_type,
+                        allowance,
+                    }
+                    .into()),
+                    false => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks `transaction`, already posted, against the most specific limit on its operation.
+    ///
+    /// Posting first leaves the accounts locked, so concurrent operations of one account are
+    /// counted one after the other; the owner is locked too when all of their accounts count.
+    async fn check_limits(
+        &self,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &TransactionCreate,
+    ) -> anyhow::Result<()> {
+        let account_id = match transaction.initiating_account_id() {
+            Some(account_id) => account_id,
+            None => return Ok(()),
+        };
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, &account_id)
+            .await?;
+        let limit = match self
+            .limit_repository
+            .find_applicable(db_tx, &account_id, &owner.id, &transaction.operation)
+            .await?
+        {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        if limit.daily.is_none() {
+            return Ok(limit.check(transaction.amount, transaction.amount)?);
+        }
+
+        let account_ids = match limit.account_id {
+            Some(account_id) => vec![account_id],
+            None => {
+                self.user_repository
+                    .find_by_id_for_update(db_tx, &owner.id)
+                    .await?;
+                self.account_repository
+                    .find_ids_by_user_id(db_tx, &owner.id)
+                    .await?
+            }
+        };
+
+        // amounts are encrypted with the key of their destination account's owner
+        let mut keys: HashMap<Uuid, Vec<u8>> = HashMap::new();
+        let mut used = Money::ZERO;
+        for initiated in self
+            .transaction_repository
+            .find_initiated_since(
+                db_tx,
+                &transaction.operation,
+                &account_ids,
+                // created_at is written in UTC, whatever the session's time zone
+                Utc::now().naive_utc() - Duration::hours(LIMIT_WINDOW_HOURS.into()),
+            )
+            .await?
+        {
+            let key = match keys.entry(initiated.to_account_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(
+                    self.user_repository
+                        .find_by_account_id(db_tx, &initiated.to_account_id)
+                        .await?
+                        .encryption_key,
+                ),
+            };
+            used += initiated
+                .get_amount(key)
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        }
+
+        Ok(limit.check(transaction.amount, used)?)
+    }
+
+    /// Posts the per-operation fee for `transaction`, if its paying account's schedule has one.
+    ///
+    /// The fee goes into the same database transaction, so an operation whose fee cannot be
+    /// paid fails as a whole.
+    async fn charge_operation_fee(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        transaction: &TransactionCreate,
+        current_user_id: &Uuid,
+    ) -> anyhow::Result<Option<Transaction>> {
+        let payer = match transaction.initiating_account_id() {
+            Some(payer_id) => {
+                self.account_repository
+                    .find_by_id_for_update(db_tx, &payer_id)
+                    .await?
+            }
+            None => return Ok(None),
+        };
+        let schedule = match self
+            .fee_repository
+            .find_for_account_type(db_tx, payer.bank_account_type)
+            .await?
+        {
+            Some(schedule) => schedule,
+            None => return Ok(None),
+        };
+        let owner = self
+            .user_repository
+            .find_by_account_id(db_tx, &payer.id)
+            .await?;
+
+        let fee = schedule.operation_charge(&transaction.operation, payer.get_balance(&owner)?);
+        if !fee.is_positive() {
+            return Ok(None);
+        }
+
+        self.post(
+            db_pool,
+            db_tx,
+            &TransactionCreate {
+                operation: TransactionOperation::Fee,
+                from_account_id: None,
+                to_account_id: payer.id,
+                to_key: None,
+                amount: fee,
+                reverses_transaction_id: None,
+            },
+            current_user_id,
+        )
+        .await
+        .map(Some)
+    }
+
+    /// Moves `amount` of the original transaction back between its accounts, or whatever
+    /// is left of it when no amount is given.
+    ///
+    /// The original row stays locked until `db_tx` ends, so concurrent reversals can
+    /// never add up to more than the original amount.
+    pub async fn reverse(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+        original_id: &Uuid,
+        amount: Option<Money>,
+    ) -> anyhow::Result<Transaction> {
+        let original = self
+            .transaction_repository
+            .find_by_id_for_update(db_tx, original_id)
+            .await?;
+        if let TransactionOperation::Reversal = original.operation {
+            return Err(ReversalError::NotReversible.into());
+        }
+
+        // the original and all of its reversals are encrypted with the destination owner's key
+        let owner = self
+            .journal_repository
+            .find_owner(db_tx, &LedgerAccount::Customer(original.to_account_id))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Account not found"))?;
+        let decrypt = |transaction: &Transaction| {
+            transaction
+                .get_amount(&owner.encryption_key)
+                .map_err(|e| anyhow::anyhow!(e.to_string()))
+        };
+
+        let mut remaining = decrypt(&original)?;
+        for reversal in self
+            .transaction_repository
+            .find_reversals(db_tx, original_id)
+            .await?
+        {
+            remaining -= decrypt(&reversal)?;
+        }
+
+        let amount = match amount {
+            None if remaining.is_zero() => return Err(ReversalError::AlreadyReversed.into()),
+            None => remaining,
+            Some(amount) if !amount.is_positive() => {
+                return Err(ReversalError::InvalidAmount.into())
+            }
+            Some(_) if remaining.is_zero() => return Err(ReversalError::AlreadyReversed.into()),
+            Some(amount) if amount > remaining => {
+                return Err(ReversalError::ExceedsRemaining(remaining).into())
+            }
+            Some(amount) => amount,
+        };
+
+        let mut adjustments = Vec::with_capacity(2);
+        for account_id in [Some(original.to_account_id), original.from_account_id]
+            .into_iter()
+            .flatten()
+        {
+            adjustments.push((
+                account_id,
+                -original.balance_change(&account_id, amount, None)?,
+            ));
+        }
+        let to_account = self
+            .account_repository
+            .adjust_balances(db_tx, &adjustments)
+            .await?
+            .into_iter()
+            .find(|account| account.id == original.to_account_id)
+            .ok_or_else(|| anyhow::anyhow!("Account not found"))?;
+
+        let reversal = self
+            .transaction_repository
+            .create(
+                db_pool,
+                db_tx,
+                &to_account,
+                &TransactionCreate {
+                    operation: TransactionOperation::Reversal,
+                    from_account_id: original.from_account_id,
+                    to_account_id: original.to_account_id,
+                    to_key: None,
+                    amount,
+                    reverses_transaction_id: Some(original.id),
+                },
+            )
+            .await?;
+
+        self.journal_repository
+            .record(db_tx, &reversal, amount)
+            .await?;
+
+        Ok(reversal)
+    }
+
+    /// The reversals of each of `ids`, for linking transactions to them.
+    pub async fn get_reversal_ids(
+        &self,
+        db_pool: &PgPool,
+        ids: &[Uuid],
+    ) -> HashMap<Uuid, Vec<Uuid>> {
+        let mut reversals: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        // if we had a logging system, we would log the error here
+        for (original_id, reversal_id) in (self
+            .transaction_repository
+            .find_reversal_ids(db_pool, ids)
+            .await)
+            .unwrap_or_default()
+        {
+            reversals.entry(original_id).or_default().push(reversal_id);
+        }
+
+        reversals
+    }
+
+    /// Re-encrypts every amount still stored as a legacy `f64` payload.
+    ///
+    /// Amounts are encrypted with the key of the destination account's owner.
+    pub async fn upgrade_legacy_amounts(
+        &self,
+        db_pool: &PgPool,
+        db_tx: &mut SqlxTransaction<'_, Postgres>,
+    ) -> anyhow::Result<u64> {
+        let transactions = self
+            .transaction_repository
+            .find_by_amount_encoding(db_tx, MoneyEncoding::LegacyFloat)
+            .await?;
+        let total = transactions.len() as u64;
+
+        for mut transaction in transactions {
+            let account = self
+                .account_repository
+                .find_by_id(db_pool, &transaction.to_account_id)
+                .await?;
+            let user = self
+                .user_repository
+                .find_by_id(db_pool, &account.user_id)
+                .await?;
+            transaction.reencrypt_amount(&user.encryption_key)?;
+            self.transaction_repository
+                .save_amount(db_tx, &transaction)
+                .await?;
+        }
+
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join_all;
+    use rand::{seq::SliceRandom, Rng};
+
+    use super::*;
+    use crate::{
+        models::{
+            account_dto::{AccountType, AccountUpdate},
+            employer_dto::EmployerCreate,
+            transaction_dto::BatchError,
+        },
+        retry::with_transaction_retry,
+        services::{
+            account::Service as AccountService, employer::Service as EmployerService,
+            journal::Service as JournalService, reconciliation::Service as ReconciliationService,
+        },
+        structs::money::Money,
+        test_helpers::create_accounts,
+    };
+
+    const ACCOUNTS: usize = 6;
+    const TRANSFERS: usize = 300;
+    const INITIAL_BALANCE: Money = Money::from_minor_units(100_000);
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_concurrent_transfers_conserve_money(db_pool: PgPool) {
+        let accounts = create_accounts(&db_pool, &[INITIAL_BALANCE; ACCOUNTS]).await;
+        let mut rng = rand::thread_rng();
+
+        let transfers = (0..TRANSFERS).map(|_| {
+            let mut pair = accounts.choose_multiple(&mut rng, 2);
+            let (from_user, from_account) = pair.next().unwrap();
+            let (_, to_account) = pair.next().unwrap();
+            let transaction = TransactionCreate {
+                operation: TransactionOperation::Transfer,
+                from_account_id: Some(from_account.id),
+                to_account_id: to_account.id,
+                to_key: None,
+                amount: Money::from_minor_units(rng.gen_range(1..=40_000)),
+                reverses_transaction_id: None,
+            };
+            let from_user_id = from_user.id;
+            let db_pool = db_pool.clone();
+
+            tokio::spawn(async move {
+                with_transaction_retry(&db_pool.clone(), |tx| {
+                    let db_pool = db_pool.clone();
+                    let transaction = transaction.clone();
+                    Box::pin(async move {
+                        Service::new()
+                            .create(&db_pool, tx, &transaction, &from_user_id)
+                            .await
+                    })
+                })
+                .await
+            })
+        });
+
+        let results = join_all(transfers).await;
+        let succeeded = results
+            .into_iter()
+            .filter(|result| matches!(result, Ok(Ok(_))))
+            .count();
+
+        let mut total = Money::ZERO;
+        for (user, account) in &accounts {
+            let account = AccountService::new()
+                .get_one_by_id(&db_pool, &account.id)
+                .await
+                .unwrap();
+            let balance = account.get_balance(user).unwrap();
+
+            assert!(!balance.is_negative(), "Account {} overdrawn", account.id);
+            total += balance;
+        }
+
+        let (_, recorded) = Service::new()
+            .get_all(&db_pool, &TransactionFilter::default())
+            .await;
+
+        assert!(succeeded > 0, "No transfer succeeded");
+        assert_eq!(total, Money::from_minor_units(100_000 * ACCOUNTS as i64));
+        assert_eq!(recorded as usize, succeeded + ACCOUNTS);
+
+        let ledger = JournalService::new().verify(&db_pool).await.unwrap();
+        assert!(ledger.is_balanced());
+        assert_eq!(ledger.entries, recorded);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_amounts_must_be_positive(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[
+                Money::from_minor_units(10_000),
+                Money::from_minor_units(10_000),
+            ],
+        )
+        .await;
+        let (user, account) = &accounts[0];
+        let (_, other_account) = &accounts[1];
+
+        // a negative deposit would drain the account, a negative transfer its recipient
+        for (operation, from_account_id, amount) in [
+            (TransactionOperation::Deposit, None, -5_000),
+            (TransactionOperation::Transfer, Some(account.id), -5_000),
+            (TransactionOperation::Transfer, Some(account.id), 0),
+        ] {
+            let mut tx = db_pool.begin().await.unwrap();
+            let error = Service::new()
+                .create(
+                    &db_pool,
+                    &mut tx,
+                    &TransactionCreate {
+                        operation,
+                        from_account_id,
+                        to_account_id: other_account.id,
+                        to_key: None,
+                        amount: Money::from_minor_units(amount),
+                        reverses_transaction_id: None,
+                    },
+                    &user.id,
+                )
+                .await
+                .unwrap_err();
+            tx.commit().await.unwrap();
+            assert_eq!(
+                error.downcast_ref::<TransactionError>(),
+                Some(&TransactionError::InvalidAmount)
+            );
+        }
+
+        let account_repository = AccountRepository::new();
+        for (owner, account) in &accounts {
+            let account = account_repository
+                .find_by_id(&db_pool, &account.id)
+                .await
+                .unwrap();
+            assert_eq!(
+                account.get_balance(owner).unwrap(),
+                Money::from_minor_units(10_000)
+            );
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_reversals_never_exceed_the_original(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(10_000), Money::from_minor_units(0)],
+        )
+        .await;
+        let (from_user, from_account) = &accounts[0];
+        let (to_user, to_account) = &accounts[1];
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let transfer = Service::new()
+            .create(
+                &db_pool,
+                &mut tx,
+                &TransactionCreate {
+                    operation: TransactionOperation::Transfer,
+                    from_account_id: Some(from_account.id),
+                    to_account_id: to_account.id,
+                    to_key: None,
+                    amount: Money::from_minor_units(3_000),
+                    reverses_transaction_id: None,
+                },
+                &from_user.id,
+            )
+            .await
+            .unwrap();
+
+        let partial = Service::new()
+            .reverse(
+                &db_pool,
+                &mut tx,
+                &transfer.id,
+                Some(Money::from_minor_units(1_000)),
+            )
+            .await
+            .unwrap();
+        let error = Service::new()
+            .reverse(
+                &db_pool,
+                &mut tx,
+                &transfer.id,
+                Some(Money::from_minor_units(2_001)),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ReversalError>(),
+            Some(&ReversalError::ExceedsRemaining(Money::from_minor_units(
+                2_000
+            )))
+        );
+
+        let rest = Service::new()
+            .reverse(&db_pool, &mut tx, &transfer.id, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            rest.get_amount(&to_user.encryption_key).unwrap(),
+            Money::from_minor_units(2_000)
+        );
+
+        let error = Service::new()
+            .reverse(&db_pool, &mut tx, &transfer.id, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ReversalError>(),
+            Some(&ReversalError::AlreadyReversed)
+        );
+        let error = Service::new()
+            .reverse(&db_pool, &mut tx, &partial.id, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ReversalError>(),
+            Some(&ReversalError::NotReversible)
+        );
+        tx.commit().await.unwrap();
+
+        for (user, account, expected) in [
+            (from_user, from_account, Money::from_minor_units(10_000)),
+            (to_user, to_account, Money::ZERO),
+        ] {
+            let account = AccountService::new()
+                .get_one_by_id(&db_pool, &account.id)
+                .await
+                .unwrap();
+            assert_eq!(account.get_balance(user).unwrap(), expected);
+        }
+
+        let reversals = Service::new()
+            .get_reversal_ids(&db_pool, &[transfer.id])
+            .await;
+        assert_eq!(reversals[&transfer.id], vec![partial.id, rest.id]);
+
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+        assert!(ReconciliationService::new()
+            .reconcile_all(&db_pool)
+            .await
+            .unwrap()
+            .is_consistent());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_batches_apply_all_or_what_succeeds(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(10_000), Money::from_minor_units(0)],
+        )
+        .await;
+        let (payer, payer_account) = &accounts[0];
+        let (payee, payee_account) = &accounts[1];
+        let transfer = |amount: i64| TransactionCreate {
+            operation: TransactionOperation::Transfer,
+            from_account_id: Some(payer_account.id),
+            to_account_id: payee_account.id,
+            to_key: None,
+            amount: Money::from_minor_units(amount),
+            reverses_transaction_id: None,
+        };
+        let balances = || async {
+            let mut balances = Vec::new();
+            for (user, account) in &accounts {
+                let account = AccountService::new()
+                    .get_one_by_id(&db_pool, &account.id)
+                    .await
+                    .unwrap();
+                balances.push(account.get_balance(user).unwrap().minor_units());
+            }
+            balances
+        };
+
+        for (mode, expected) in [
+            (BatchMode::AllOrNothing, vec![10_000, 0]),
+            (BatchMode::BestEffort, vec![4_000, 6_000]),
+        ] {
+            let batch = TransactionBatch {
+                mode,
+                transactions: vec![transfer(6_000), transfer(6_000)],
+            };
+            let mut tx = db_pool.begin().await.unwrap();
+            let outcomes = Service::new()
+                .create_batch(&db_pool, &mut tx, &batch, &payer.id)
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            match (mode, &outcomes[..]) {
+                (
+                    BatchMode::AllOrNothing,
+                    [BatchItemOutcome::RolledBack, BatchItemOutcome::Failed(error)],
+                )
+                | (
+                    BatchMode::BestEffort,
+                    [BatchItemOutcome::Created(_), BatchItemOutcome::Failed(error)],
+                ) => {
+                    assert_eq!(error, "Not enough funds")
+                }
+                _ => panic!("Unexpected {:?} outcomes: {:?}", mode, outcomes),
+            }
+            assert_eq!(balances().await, expected, "{:?}", mode);
+        }
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let error = Service::new()
+            .create_batch(
+                &db_pool,
+                &mut tx,
+                &TransactionBatch {
+                    mode: BatchMode::BestEffort,
+                    transactions: Vec::new(),
+                },
+                &payee.id,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.downcast_ref::<BatchError>(), Some(&BatchError::Empty));
+
+        assert!(JournalService::new()
+            .verify(&db_pool)
+            .await
+            .unwrap()
+            .is_balanced());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_payment_files_are_applied_once(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[Money::from_minor_units(10_000), Money::from_minor_units(0)],
+        )
+        .await;
+        let (payer, payer_account) = &accounts[0];
+        let (_, payee_account) = &accounts[1];
+        let apply = |message_id: &'static str, amount: i64| {
+            let db_pool = db_pool.clone();
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let outcomes = Service::new()
+                    .create_payment_file_batch(
+                        &db_pool,
+                        &mut tx,
+                        message_id,
+                        &TransactionBatch {
+                            mode: BatchMode::AllOrNothing,
+                            transactions: vec![TransactionCreate {
+                                operation: TransactionOperation::Transfer,
+                                from_account_id: Some(payer_account.id),
+                                to_account_id: payee_account.id,
+                                to_key: None,
+                                amount: Money::from_minor_units(amount),
+                                reverses_transaction_id: None,
+                            }],
+                        },
+                        &payer.id,
+                    )
+                    .await?;
+                tx.commit().await?;
+                anyhow::Ok(outcomes)
+            }
+        };
+
+        // a file that paid nothing can be sent again
+        assert!(matches!(
+            &apply("MSG-1", 20_000).await.unwrap()[..],
+            [BatchItemOutcome::Failed(_)]
+        ));
+        assert!(matches!(
+            &apply("MSG-1", 1_000).await.unwrap()[..],
+            [BatchItemOutcome::Created(_)]
+        ));
+        assert_eq!(
+            apply("MSG-1", 1_000)
+                .await
+                .unwrap_err()
+                .downcast_ref::<PaymentFileError>(),
+            Some(&PaymentFileError::AlreadyApplied("MSG-1".to_string()))
+        );
+        apply("MSG-2", 1_000).await.unwrap();
+
+        let payer_account = AccountService::new()
+            .get_one_by_id(&db_pool, &payer_account.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            payer_account.get_balance(payer).unwrap(),
+            Money::from_minor_units(8_000)
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_account_types_restrict_operations(db_pool: PgPool) {
+        let accounts = create_accounts(
+            &db_pool,
+            &[
+                Money::from_minor_units(100_000),
+                Money::from_minor_units(100_000),
+                Money::from_minor_units(100_000),
+            ],
+        )
+        .await;
+        let (employer_user, employer_account) = &accounts[0];
+        let (stranger_user, stranger_account) = &accounts[1];
+        let (user, account) = &accounts[2];
+        let set_type = |account_type: &'static str| {
+            let db_pool = db_pool.clone();
+            let account_id = account.id;
+            async move {
+                sqlx::query(
+                    "UPDATE accounts SET bank_account_type = $2::account_type WHERE id = $1",
+                )
+                .bind(account_id)
+                .bind(account_type)
+                .execute(&db_pool)
+                .await
+                .unwrap();
+            }
+        };
+        let create = |operation: TransactionOperation, from_account_id: Option<Uuid>| {
+            let db_pool = db_pool.clone();
+            let transaction = TransactionCreate {
+                operation,
+                from_account_id,
+                to_account_id: account.id,
+                to_key: None,
+                amount: Money::from_minor_units(100),
+                reverses_transaction_id: None,
+            };
+            let user_id = match from_account_id {
+                Some(id) if id == employer_account.id => employer_user.id,
+                Some(_) => stranger_user.id,
+                None => user.id,
+            };
+            async move {
+                let mut tx = db_pool.begin().await.unwrap();
+                let result = Service::new()
+                    .create(&db_pool, &mut tx, &transaction, &user_id)
+                    .await;
+                tx.commit().await.unwrap();
+                result.map_err(|e| e.downcast::<AccountTypeError>().unwrap())
+            }
+        };
+
+        set_type("salary").await;
+        // changing the bank details leaves the type, and its rules, as they are
+        let mut tx = db_pool.begin().await.unwrap();
+        let updated = AccountService::new()
+            .update(
+                &mut tx,
+                &account.id,
+                &AccountUpdate {
+                    bank_id: None,
+                    bank_account_number: None,
+                    bank_account_digit: None,
+                    bank_agency_number: None,
+                    bank_agency_digit: None,
+                },
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(updated.bank_account_type, Some(AccountType::Salary));
+
+        let mut tx = db_pool.begin().await.unwrap();
+        EmployerService::new()
+            .register(
+                &mut tx,
+                &account.id,
+                &EmployerCreate {
+                    employer_account_id: employer_account.id,
+                },
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        assert!(
+            create(TransactionOperation::Transfer, Some(employer_account.id))
+                .await
+                .is_ok()
+        );
+        assert_eq!(
+            create(TransactionOperation::Transfer, Some(stranger_account.id))
+                .await
+                .unwrap_err(),
+            AccountTypeError::NotAnEmployer
+        );
+        assert_eq!(
+            create(TransactionOperation::Deposit, None)
+                .await
+                .unwrap_err(),
+            AccountTypeError::NotAnEmployer
+        );
+
+        set_type("savings").await;
+        assert_eq!(
+            create(TransactionOperation::Payment, None)
+                .await
+                .unwrap_err(),
+            AccountTypeError::PaymentsNotAllowed(AccountType::Savings)
+        );
+        let allowance = AccountType::Savings.rules().monthly_withdrawals.unwrap();
+        for _ in 0..allowance {
+            assert!(create(TransactionOperation::Withdrawal, None).await.is_ok());
+        }
+        assert_eq!(
+            create(TransactionOperation::Withdrawal, None)
+                .await
+                .unwrap_err(),
+            AccountTypeError::WithdrawalAllowanceExceeded {
+                account_type: AccountType::Savings,
+                allowance,
+            }
+        );
+
+        set_type("checking").await;
+        assert!(create(TransactionOperation::Withdrawal, None).await.is_ok());
+        assert!(create(TransactionOperation::Payment, None).await.is_ok());
     }
 }
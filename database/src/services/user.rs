@@ -82,7 +82,16 @@ impl Service {
         }
     }
 
+    /// Soft deletes a user; their accounts are left as they are.
     pub async fn delete(&self, tx: &mut SqlxTransaction<'_, Postgres>, id: &Uuid) -> bool {
         self.user_repository.delete(tx, id).await
     }
+
+    pub async fn restore(
+        &self,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+        id: &Uuid,
+    ) -> anyhow::Result<User> {
+        self.user_repository.restore(tx, id).await
+    }
 }
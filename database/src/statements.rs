@@ -0,0 +1,140 @@
+pub mod camt053;
+pub mod csv;
+pub mod ofx;
+
+use crate::{
+    models::{
+        account_dto::Account,
+        statement_dto::{StatementEntry, StatementFormat, StatementQuery},
+    },
+    structs::money::Money,
+};
+
+/// Renders a statement piece by piece, so a long history can be sent while it is still read.
+pub trait StatementWriter: Send {
+    /// Whether [`opening`](Self::opening) needs the closing balance, which takes an extra pass
+    /// over the period before the first chunk.
+    fn needs_closing_balance(&self) -> bool {
+        false
+    }
+
+    /// Everything that comes before the first entry. The closing balance is only worked out
+    /// for the writers that ask for it.
+    fn opening(
+        &mut self,
+        account: &Account,
+        query: &StatementQuery,
+        opening_balance: Money,
+        closing_balance: Option<Money>,
+    ) -> anyhow::Result<Vec<u8>>;
+
+    fn entry(&mut self, entry: &StatementEntry) -> anyhow::Result<Vec<u8>>;
+
+    /// Everything that comes after the last entry.
+    fn closing(&mut self, closing_balance: Money) -> anyhow::Result<Vec<u8>>;
+}
+
+pub fn writer(format: StatementFormat) -> Box<dyn StatementWriter> {
+    match format {
+        StatementFormat::Camt053 => Box::new(self::camt053::Camt053Writer::new()),
+        StatementFormat::Csv => Box::new(self::csv::CsvWriter::new()),
+        StatementFormat::Ofx => Box::new(self::ofx::OfxWriter::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::{transaction_dto::TransactionOperation, user_dto::User};
+
+    #[test]
+    fn test_writers_carry_balances_through() {
+        let user = User::new(
+            "test".to_string(),
+            "test".to_string(),
+            Some(true),
+            Some("test".to_string()),
+        )
+        .expect("User creation failed");
+        let account = Account::new(
+            &user,
+            Money::ZERO,
+            Some(1),
+            Some(12345),
+            Some(6),
+            Some(1),
+            Some(9),
+            None,
+        )
+        .expect("Account creation failed");
+        let query = StatementQuery {
+            from: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+            format: StatementFormat::Csv,
+        };
+        let entry = StatementEntry {
+            transaction_id: Uuid::new_v4(),
+            posted_at: query.from.and_hms_opt(10, 30, 0).unwrap(),
+            operation: TransactionOperation::Withdrawal,
+            counterparty_account_id: None,
+            amount: Money::from_minor_units(-2_500),
+            balance: Money::from_minor_units(7_500),
+        };
+        let render = |format| {
+            let mut writer = writer(format);
+            let mut file = writer
+                .opening(
+                    &account,
+                    &query,
+                    Money::from_minor_units(10_000),
+                    Some(entry.balance),
+                )
+                .unwrap();
+            file.extend(writer.entry(&entry).unwrap());
+            file.extend(writer.closing(entry.balance).unwrap());
+            String::from_utf8(file).unwrap()
+        };
+
+        assert_eq!(
+            render(StatementFormat::Csv),
+            format!(
+                concat!(
+                    "date,transaction_id,operation,counterparty_account_id,amount,balance\n",
+                    "2025-01-01,,opening_balance,,,100.00\n",
+                    "2025-01-01 10:30:00,{},withdrawal,,-25.00,75.00\n",
+                    "2025-01-31,,closing_balance,,,75.00\n",
+                ),
+                entry.transaction_id
+            )
+        );
+
+        let ofx = render(StatementFormat::Ofx);
+        assert!(
+            ofx.contains("<BANKID>001</BANKID><BRANCHID>1-9</BRANCHID><ACCTID>12345-6</ACCTID>")
+        );
+        assert!(ofx.contains(&format!(
+            "<STMTTRN><TRNTYPE>CASH</TRNTYPE><DTPOSTED>20250101103000</DTPOSTED><TRNAMT>-25.00</TRNAMT><FITID>{}</FITID>",
+            entry.transaction_id
+        )));
+        assert!(ofx.contains("<LEDGERBAL><BALAMT>75.00</BALAMT><DTASOF>20250131235959</DTASOF>"));
+        assert!(ofx.contains("<VALUE>100.00</VALUE><DTASOF>20250101000000</DTASOF>"));
+        assert!(ofx.ends_with("</OFX>\n"));
+
+        let camt = render(StatementFormat::Camt053);
+        let opening = camt.find("<Cd>OPBD</Cd>").unwrap();
+        let closing = camt.find("<Cd>CLBD</Cd>").unwrap();
+        let first_entry = camt.find("<Ntry>").unwrap();
+        assert!(opening < closing && closing < first_entry);
+        assert!(camt.contains(
+            r#"<Cd>CLBD</Cd></CdOrPrtry></Tp><Amt Ccy="BRL">75.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>"#
+        ));
+        assert!(camt.contains(&format!(
+            r#"<Ntry><NtryRef>{}</NtryRef><Amt Ccy="BRL">25.00</Amt><CdtDbtInd>DBIT</CdtDbtInd>"#,
+            entry.transaction_id
+        )));
+        assert!(camt.ends_with("</Document>\n"));
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::Account,
+        statement_dto::{StatementEntry, StatementQuery},
+    },
+    structs::money::Money,
+};
+
+use super::StatementWriter;
+
+const CURRENCY: &str = "BRL";
+const NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:camt.053.001.02";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// ISO 20022 camt.053.001.02 bank to customer statement. The schema lists both balances
+/// before the entries, so this writer asks for the closing balance up front.
+pub struct Camt053Writer;
+
+impl Default for Camt053Writer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camt053Writer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn date_time(date_time: NaiveDateTime) -> String {
+    date_time.format(DATE_TIME_FORMAT).to_string()
+}
+
+/// ISO 20022 amounts are unsigned; the sign goes in a credit/debit indicator.
+fn amount(amount: Money) -> String {
+    let indicator = match amount.is_negative() {
+        true => "DBIT",
+        false => "CRDT",
+    };
+
+    format!(
+        r#"<Amt Ccy="{}">{}</Amt><CdtDbtInd>{}</CdtDbtInd>"#,
+        CURRENCY,
+        amount.abs(),
+        indicator
+    )
+}
+
+fn balance(code: &str, value: Money, date: NaiveDate) -> String {
+    format!(
+        "<Bal><Tp><CdOrPrtry><Cd>{}</Cd></CdOrPrtry></Tp>{}<Dt><Dt>{}</Dt></Dt></Bal>\n",
+        code,
+        amount(value),
+        date
+    )
+}
+
+fn account_id(account_id: &Uuid) -> String {
+    format!("<Id><Othr><Id>{}</Id></Othr></Id>", account_id)
+}
+
+impl StatementWriter for Camt053Writer {
+    fn needs_closing_balance(&self) -> bool {
+        true
+    }
+
+    fn opening(
+        &mut self,
+        account: &Account,
+        query: &StatementQuery,
+        opening_balance: Money,
+        closing_balance: Option<Money>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let closing_balance = closing_balance
+            .ok_or_else(|| anyhow::anyhow!("camt.053 statements need the closing balance"))?;
+        let now = date_time(chrono::Utc::now().naive_utc());
+        let end = query
+            .to
+            .and_time(NaiveTime::from_hms_opt(23, 59, 59).expect("valid time"));
+
+        Ok(format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                "\n",
+                r#"<Document xmlns="{namespace}">"#,
+                "\n<BkToCstmrStmt>\n",
+                "<GrpHdr><MsgId>{message_id}</MsgId><CreDtTm>{now}</CreDtTm></GrpHdr>\n",
+                "<Stmt><Id>{account_id}-{from}-{to}</Id><CreDtTm>{now}</CreDtTm>",
+                "<FrToDt><FrDtTm>{start}</FrDtTm><ToDtTm>{end}</ToDtTm></FrToDt>\n",
+                "<Acct>{account}<Ccy>{currency}</Ccy></Acct>\n",
+                "{opening}{closing}",
+            ),
+            namespace = NAMESPACE,
+            message_id = Uuid::now_v7().simple(),
+            now = now,
+            account_id = account.id.simple(),
+            from = query.from.format("%Y%m%d"),
+            to = query.to.format("%Y%m%d"),
+            start = date_time(query.from.and_time(NaiveTime::MIN)),
+            end = date_time(end),
+            account = account_id(&account.id),
+            currency = CURRENCY,
+            opening = balance("OPBD", opening_balance, query.from),
+            closing = balance("CLBD", closing_balance, query.to),
+        )
+        .into_bytes())
+    }
+
+    fn entry(&mut self, entry: &StatementEntry) -> anyhow::Result<Vec<u8>> {
+        // the counterparty is the creditor of a debit and the debtor of a credit
+        let related_parties = match (entry.counterparty_account_id, entry.amount.is_negative()) {
+            (Some(account_id), true) => format!(
+                "<RltdPties><CdtrAcct>{}</CdtrAcct></RltdPties>",
+                self::account_id(&account_id)
+            ),
+            (Some(account_id), false) => format!(
+                "<RltdPties><DbtrAcct>{}</DbtrAcct></RltdPties>",
+                self::account_id(&account_id)
+            ),
+            (None, _) => String::new(),
+        };
+
+        Ok(format!(
+            concat!(
+                "<Ntry><NtryRef>{id}</NtryRef>{amount}<Sts>BOOK</Sts>",
+                "<BookgDt><DtTm>{posted_at}</DtTm></BookgDt><ValDt><DtTm>{posted_at}</DtTm></ValDt>",
+                "<AcctSvcrRef>{id}</AcctSvcrRef>",
+                "<BkTxCd><Prtry><Cd>{operation}</Cd></Prtry></BkTxCd>",
+                "<NtryDtls><TxDtls><Refs><TxId>{id}</TxId></Refs>{related_parties}</TxDtls></NtryDtls>",
+                "</Ntry>\n",
+            ),
+            id = entry.transaction_id,
+            amount = amount(entry.amount),
+            posted_at = date_time(entry.posted_at),
+            operation = entry.operation.as_str(),
+            related_parties = related_parties,
+        )
+        .into_bytes())
+    }
+
+    fn closing(&mut self, _closing_balance: Money) -> anyhow::Result<Vec<u8>> {
+        Ok(b"</Stmt>\n</BkToCstmrStmt>\n</Document>\n".to_vec())
+    }
+}
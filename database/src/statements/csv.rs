@@ -0,0 +1,102 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        account_dto::Account,
+        statement_dto::{StatementEntry, StatementQuery},
+    },
+    structs::money::Money,
+};
+
+use super::StatementWriter;
+
+/// One line of the file; the first and the last carry the opening and closing balances.
+#[derive(Debug, Serialize)]
+struct Row {
+    date: String,
+    transaction_id: Option<Uuid>,
+    operation: &'static str,
+    counterparty_account_id: Option<Uuid>,
+    amount: Option<Money>,
+    balance: Money,
+}
+
+/// Comma separated values with a header line, amounts and balances in major units.
+pub struct CsvWriter {
+    header_written: bool,
+    to: Option<NaiveDate>,
+}
+
+impl Default for CsvWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvWriter {
+    pub fn new() -> Self {
+        Self {
+            header_written: false,
+            to: None,
+        }
+    }
+
+    fn write(&mut self, row: &Row) -> anyhow::Result<Vec<u8>> {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(!self.header_written)
+            .from_writer(Vec::new());
+        writer.serialize(row)?;
+        self.header_written = true;
+
+        writer
+            .into_inner()
+            .map_err(|e| anyhow::anyhow!(e.to_string()))
+    }
+}
+
+impl StatementWriter for CsvWriter {
+    fn opening(
+        &mut self,
+        _account: &Account,
+        query: &StatementQuery,
+        opening_balance: Money,
+        _closing_balance: Option<Money>,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.to = Some(query.to);
+        self.write(&Row {
+            date: query.from.to_string(),
+            transaction_id: None,
+            operation: "opening_balance",
+            counterparty_account_id: None,
+            amount: None,
+            balance: opening_balance,
+        })
+    }
+
+    fn entry(&mut self, entry: &StatementEntry) -> anyhow::Result<Vec<u8>> {
+        self.write(&Row {
+            date: entry.posted_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            transaction_id: Some(entry.transaction_id),
+            operation: entry.operation.as_str(),
+            counterparty_account_id: entry.counterparty_account_id,
+            amount: Some(entry.amount),
+            balance: entry.balance,
+        })
+    }
+
+    fn closing(&mut self, closing_balance: Money) -> anyhow::Result<Vec<u8>> {
+        let to = self
+            .to
+            .ok_or_else(|| anyhow::anyhow!("Statement was closed before it was opened"))?;
+        self.write(&Row {
+            date: to.to_string(),
+            transaction_id: None,
+            operation: "closing_balance",
+            counterparty_account_id: None,
+            amount: None,
+            balance: closing_balance,
+        })
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::{
+    models::{
+        account_dto::Account,
+        statement_dto::{StatementEntry, StatementQuery},
+        transaction_dto::TransactionOperation,
+    },
+    structs::money::Money,
+};
+
+use super::StatementWriter;
+
+const CURRENCY: &str = "BRL";
+const DATE_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// OFX 2.2 bank statement, the XML flavour. The opening balance goes in the balance list,
+/// since the statement response has no field of its own for it.
+pub struct OfxWriter {
+    period: Option<(NaiveDate, NaiveDate, Money)>,
+}
+
+impl Default for OfxWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OfxWriter {
+    pub fn new() -> Self {
+        Self { period: None }
+    }
+}
+
+fn date_time(date_time: NaiveDateTime) -> String {
+    date_time.format(DATE_FORMAT).to_string()
+}
+
+fn end_of_day(date: NaiveDate) -> String {
+    date_time(date.and_time(NaiveTime::from_hms_opt(23, 59, 59).expect("valid time")))
+}
+
+/// Formats a number with its check digit as `number-digit`, the way statements print them.
+fn with_digit(number: Option<i64>, digit: Option<i32>) -> Option<String> {
+    number.map(|number| match digit {
+        Some(digit) => format!("{}-{}", number, digit),
+        None => number.to_string(),
+    })
+}
+
+fn transaction_type(entry: &StatementEntry) -> &'static str {
+    match entry.operation {
+        TransactionOperation::Deposit => "DEP",
+        TransactionOperation::Fee => "FEE",
+        TransactionOperation::Interest => "INT",
+        TransactionOperation::Payment => "PAYMENT",
+        TransactionOperation::Transfer => "XFER",
+        TransactionOperation::Withdrawal => "CASH",
+        TransactionOperation::Reversal if entry.amount.is_negative() => "DEBIT",
+        TransactionOperation::Reversal => "CREDIT",
+    }
+}
+
+impl StatementWriter for OfxWriter {
+    fn opening(
+        &mut self,
+        account: &Account,
+        query: &StatementQuery,
+        opening_balance: Money,
+        _closing_balance: Option<Money>,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.period = Some((query.from, query.to, opening_balance));
+        let now = date_time(chrono::Utc::now().naive_utc());
+        let branch = with_digit(
+            account.bank_agency_number.map(i64::from),
+            account.bank_agency_digit,
+        )
+        .map(|branch| format!("<BRANCHID>{}</BRANCHID>", branch))
+        .unwrap_or_default();
+        let account_number = with_digit(account.bank_account_number, account.bank_account_digit)
+            .unwrap_or_else(|| account.id.to_string());
+
+        Ok(format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#,
+                "\n",
+                r#"<?OFX OFXHEADER="200" VERSION="220" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>"#,
+                "\n<OFX>\n",
+                "<SIGNONMSGSRSV1><SONRS><STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>",
+                "<DTSERVER>{now}</DTSERVER><LANGUAGE>POR</LANGUAGE></SONRS></SIGNONMSGSRSV1>\n",
+                "<BANKMSGSRSV1><STMTTRNRS><TRNUID>{account_id}</TRNUID>",
+                "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>\n",
+                "<STMTRS><CURDEF>{currency}</CURDEF>\n",
+                "<BANKACCTFROM><BANKID>{bank_id:03}</BANKID>{branch}<ACCTID>{account_number}</ACCTID>",
+                "<ACCTTYPE>CHECKING</ACCTTYPE></BANKACCTFROM>\n",
+                "<BANKTRANLIST><DTSTART>{start}</DTSTART><DTEND>{end}</DTEND>\n",
+            ),
+            now = now,
+            account_id = account.id,
+            currency = CURRENCY,
+            bank_id = account.bank_id.unwrap_or_default(),
+            branch = branch,
+            account_number = account_number,
+            start = date_time(query.from.and_time(NaiveTime::MIN)),
+            end = end_of_day(query.to),
+        )
+        .into_bytes())
+    }
+
+    fn entry(&mut self, entry: &StatementEntry) -> anyhow::Result<Vec<u8>> {
+        let memo = entry
+            .counterparty_account_id
+            .map(|account_id| format!("<MEMO>{}</MEMO>", account_id))
+            .unwrap_or_default();
+
+        Ok(format!(
+            concat!(
+                "<STMTTRN><TRNTYPE>{}</TRNTYPE><DTPOSTED>{}</DTPOSTED><TRNAMT>{}</TRNAMT>",
+                "<FITID>{}</FITID><NAME>{}</NAME>{}</STMTTRN>\n",
+            ),
+            transaction_type(entry),
+            date_time(entry.posted_at),
+            entry.amount,
+            entry.transaction_id,
+            entry.operation.as_str(),
+            memo,
+        )
+        .into_bytes())
+    }
+
+    fn closing(&mut self, closing_balance: Money) -> anyhow::Result<Vec<u8>> {
+        let (from, to, opening_balance) = self
+            .period
+            .ok_or_else(|| anyhow::anyhow!("Statement was closed before it was opened"))?;
+
+        Ok(format!(
+            concat!(
+                "</BANKTRANLIST>\n",
+                "<LEDGERBAL><BALAMT>{}</BALAMT><DTASOF>{}</DTASOF></LEDGERBAL>\n",
+                "<BALLIST><BAL><NAME>Opening balance</NAME><DESC>Balance at the start of the period</DESC>",
+                "<BALTYPE>DOLLAR</BALTYPE><VALUE>{}</VALUE><DTASOF>{}</DTASOF></BAL></BALLIST>\n",
+                "</STMTRS></STMTTRNRS></BANKMSGSRSV1>\n</OFX>\n",
+            ),
+            closing_balance,
+            end_of_day(to),
+            opening_balance,
+            date_time(from.and_time(NaiveTime::MIN)),
+        )
+        .into_bytes())
+    }
+}
@@ -1,2 +1,3 @@
 pub mod encrypted_field;
+pub mod money;
 pub mod range;
@@ -1,7 +1,14 @@
+use std::error::Error;
+
 use cipher::InvalidLength;
 use serde::{ser::StdError, Deserialize, Serialize};
+use sqlx::encode::IsNull;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type};
 use thiserror::Error;
 
+use super::money::Money;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EncryptedField<T> {
     pub nonce: Vec<u8>,
@@ -18,6 +25,11 @@ impl<T> EncryptedField<T> {
             _marker: std::marker::PhantomData,
         }
     }
+
+    /// Reinterprets the ciphertext as holding a `U`, for payloads written under an older type.
+    pub fn cast<U>(&self) -> EncryptedField<U> {
+        EncryptedField::new(self.nonce.clone(), self.ciphertext.clone())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -32,11 +44,6 @@ pub enum EncryptionError {
 
 macro_rules! impl_sqlx_for_encrypted_field {
     ($t:ty) => {
-        use sqlx::encode::IsNull;
-        use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
-        use sqlx::{Decode, Encode, Postgres, Type};
-        use std::error::Error;
-
         impl Type<Postgres> for EncryptedField<$t> {
             fn type_info() -> PgTypeInfo {
                 PgTypeInfo::with_name("BYTEA")
@@ -47,7 +54,7 @@ macro_rules! impl_sqlx_for_encrypted_field {
             fn encode_by_ref(
                 &self,
                 buf: &mut PgArgumentBuffer,
-            ) -> Result<IsNull, Box<(dyn StdError + Send + Sync + 'static)>> {
+            ) -> Result<IsNull, Box<dyn StdError + Send + Sync + 'static>> {
                 match bincode::serialize(self) {
                     Ok(serialized) => {
                         <Vec<u8> as Encode<Postgres>>::encode_by_ref(&serialized, buf)
@@ -74,4 +81,5 @@ macro_rules! impl_sqlx_for_encrypted_field {
     };
 }
 
-impl_sqlx_for_encrypted_field!(f64);
+impl_sqlx_for_encrypted_field!(Money);
+impl_sqlx_for_encrypted_field!(i64);
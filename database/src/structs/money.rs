@@ -0,0 +1,384 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::Type;
+use thiserror::Error;
+
+use crate::{structs::encrypted_field::EncryptedField, traits::encryptable::Encryptable};
+
+/// Number of decimal places kept for every amount.
+pub const MONEY_SCALE: u32 = 2;
+const MINOR_UNITS_PER_UNIT: i64 = 10_i64.pow(MONEY_SCALE);
+
+/// An exact monetary amount, stored as a signed number of minor units (cents).
+///
+/// Values with more than [`MONEY_SCALE`] decimal places are rounded half to even
+/// (banker's rounding), so `0.125` becomes `0.12` and `0.135` becomes `0.14`.
+/// JSON renders amounts as decimal strings (`"10.50"`) and accepts either
+/// strings or numbers; binary formats such as the encrypted payloads use the
+/// raw `i64` minor units, as do the unencrypted `BIGINT` columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Type)]
+#[sqlx(transparent)]
+pub struct Money(i64);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MoneyError {
+    #[error("Invalid amount: {0}")]
+    Invalid(String),
+    #[error("Amount out of range")]
+    Overflow,
+}
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_minor_units(minor_units: i64) -> Self {
+        Self(minor_units)
+    }
+
+    pub const fn minor_units(&self) -> i64 {
+        self.0
+    }
+
+    /// Rounds a float to the nearest minor unit, half to even.
+    ///
+    /// The float is first rendered with its shortest round-trip representation,
+    /// so `1.005` rounds as the decimal `1.005` and not as its binary approximation.
+    pub fn from_f64(value: f64) -> Result<Self, MoneyError> {
+        if !value.is_finite() {
+            return Err(MoneyError::Invalid(value.to_string()));
+        }
+
+        value.to_string().parse()
+    }
+
+    /// Rounds `value / 10^scale` to the nearest minor unit, half to even.
+    pub fn from_scaled(value: i128, scale: u32) -> Result<Self, MoneyError> {
+        let minor_units = match scale.cmp(&MONEY_SCALE) {
+            std::cmp::Ordering::Less => value
+                .checked_mul(10_i128.pow(MONEY_SCALE - scale))
+                .ok_or(MoneyError::Overflow)?,
+            std::cmp::Ordering::Equal => value,
+            std::cmp::Ordering::Greater => round_half_even(value, 10_i128.pow(scale - MONEY_SCALE)),
+        };
+
+        i64::try_from(minor_units)
+            .map(Self)
+            .map_err(|_| MoneyError::Overflow)
+    }
+
+    pub fn checked_add(self, rhs: Money) -> Option<Money> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    pub fn checked_sub(self, rhs: Money) -> Option<Money> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    pub fn abs(self) -> Money {
+        Self(self.0.abs())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+}
+
+/// Divides `value` by `divisor`, rounding the quotient half to even.
+pub fn round_half_even(value: i128, divisor: i128) -> i128 {
+    let quotient = value.div_euclid(divisor);
+    let remainder = value.rem_euclid(divisor);
+
+    match (remainder * 2).cmp(&divisor) {
+        std::cmp::Ordering::Less => quotient,
+        std::cmp::Ordering::Greater => quotient + 1,
+        std::cmp::Ordering::Equal => quotient + (quotient & 1),
+    }
+}
+
+impl FromStr for Money {
+    type Err = MoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || MoneyError::Invalid(s.to_string());
+        let trimmed = s.trim();
+
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if (integer.is_empty() && fraction.is_empty())
+            || !integer.bytes().all(|b| b.is_ascii_digit())
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let mut value: i128 = 0;
+        for digit in integer.bytes().chain(fraction.bytes()) {
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add((digit - b'0') as i128))
+                .ok_or(MoneyError::Overflow)?;
+        }
+
+        if negative {
+            value = -value;
+        }
+
+        Self::from_scaled(value, fraction.len() as u32)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let units = self.0.unsigned_abs();
+        let per_unit = MINOR_UNITS_PER_UNIT as u64;
+
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            units / per_unit,
+            units % per_unit,
+            width = MONEY_SCALE as usize
+        )
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Self(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_i64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MoneyVisitor;
+
+        impl de::Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a decimal amount as a string or number")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+                value.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Money, E> {
+                Money::from_scaled(value as i128, 0).map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Money, E> {
+                Money::from_scaled(value as i128, 0).map_err(E::custom)
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Money, E> {
+                Money::from_f64(value).map_err(E::custom)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(MoneyVisitor)
+        } else {
+            i64::deserialize(deserializer).map(Money)
+        }
+    }
+}
+
+/// How an encrypted amount was serialized before it was encrypted.
+///
+/// Rows written before amounts moved to minor units hold an `f64` payload and
+/// are tagged [`MoneyEncoding::LegacyFloat`]; they are still readable and are
+/// re-encrypted on startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[repr(i16)]
+pub enum MoneyEncoding {
+    LegacyFloat = 0,
+    #[default]
+    MinorUnits = 1,
+}
+
+impl MoneyEncoding {
+    pub fn decrypt(&self, field: &EncryptedField<Money>, key: &[u8]) -> anyhow::Result<Money> {
+        match self {
+            MoneyEncoding::LegacyFloat => Ok(Money::from_f64(f64::decrypt(&field.cast(), key)?)?),
+            MoneyEncoding::MinorUnits => Ok(Money::decrypt(field, key)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(s: &str) -> Money {
+        s.parse().expect("Invalid amount")
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(money("10").minor_units(), 1000);
+        assert_eq!(money("10.5").minor_units(), 1050);
+        assert_eq!(money("-0.01").minor_units(), -1);
+        assert_eq!(money(".75").minor_units(), 75);
+        assert_eq!(money("1234.56").to_string(), "1234.56");
+        assert_eq!(money("-7.05").to_string(), "-7.05");
+        assert_eq!(Money::ZERO.to_string(), "0.00");
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert!("".parse::<Money>().is_err());
+        assert!(".".parse::<Money>().is_err());
+        assert!("1,00".parse::<Money>().is_err());
+        assert!("1e3".parse::<Money>().is_err());
+        assert!("--1".parse::<Money>().is_err());
+        assert_eq!(
+            "99999999999999999999".parse::<Money>(),
+            Err(MoneyError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_rounding_is_half_even() {
+        assert_eq!(money("0.125"), money("0.12"));
+        assert_eq!(money("0.135"), money("0.14"));
+        assert_eq!(money("0.1251"), money("0.13"));
+        assert_eq!(money("-0.125"), money("-0.12"));
+        assert_eq!(money("-0.135"), money("-0.14"));
+        assert_eq!(money("2.499"), money("2.50"));
+    }
+
+    #[test]
+    fn test_from_f64_uses_decimal_representation() {
+        assert_eq!(Money::from_f64(0.1 + 0.2).unwrap(), money("0.30"));
+        assert_eq!(Money::from_f64(1.005).unwrap(), money("1.00"));
+        assert_eq!(Money::from_f64(1000.0).unwrap(), money("1000"));
+        assert!(Money::from_f64(f64::NAN).is_err());
+        assert!(Money::from_f64(f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_is_exact() {
+        let total: Money = std::iter::repeat_n(money("0.10"), 10).sum();
+        assert_eq!(total, money("1.00"));
+        assert_eq!(money("100") - money("99.99"), money("0.01"));
+        assert_eq!(
+            Money::from_minor_units(i64::MAX).checked_add(money("0.01")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = serde_json::to_string(&money("12.30")).unwrap();
+        assert_eq!(json, r#""12.30""#);
+
+        assert_eq!(
+            serde_json::from_str::<Money>(r#""12.30""#).unwrap(),
+            money("12.30")
+        );
+        assert_eq!(
+            serde_json::from_str::<Money>("12.3").unwrap(),
+            money("12.30")
+        );
+        assert_eq!(serde_json::from_str::<Money>("12").unwrap(), money("12.00"));
+    }
+
+    #[test]
+    fn test_encrypted_payload_uses_minor_units() {
+        let key = [0u8; 32];
+        let amount = money("42.42");
+
+        let encrypted_field = amount.encrypt(&key).expect("Encryption failed");
+        let decrypted = MoneyEncoding::MinorUnits
+            .decrypt(&encrypted_field, &key)
+            .expect("Decryption failed");
+
+        assert_eq!(amount, decrypted);
+        assert_eq!(bincode::serialize(&amount).unwrap(), 4242_i64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_decrypt_legacy_float_payload() {
+        let key = [0u8; 32];
+        let legacy: EncryptedField<f64> = 19.99_f64.encrypt(&key).expect("Encryption failed");
+
+        let decrypted = MoneyEncoding::LegacyFloat
+            .decrypt(&legacy.cast(), &key)
+            .expect("Decryption failed");
+
+        assert_eq!(decrypted, money("19.99"));
+    }
+}
@@ -0,0 +1,59 @@
+use sqlx::PgPool;
+
+use crate::{
+    models::{
+        account_dto::{Account, AccountCreate},
+        user_dto::{User, UserCreate},
+    },
+    services::{account::Service as AccountService, user::Service as UserService},
+    structs::money::Money,
+};
+
+/// Creates one user per balance, each owning a single account opened with that balance.
+pub async fn create_accounts(db_pool: &PgPool, balances: &[Money]) -> Vec<(User, Account)> {
+    let mut tx = db_pool.begin().await.unwrap();
+    let mut users = Vec::new();
+    for index in 0..balances.len() {
+        let user = UserService::new()
+            .create(
+                &mut tx,
+                &UserCreate {
+                    name: format!("user {}", index),
+                    email: format!("user{}@localhost", index),
+                    active: Some(true),
+                    password: Some("password".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+        users.push(user);
+    }
+    tx.commit().await.unwrap();
+
+    let mut tx = db_pool.begin().await.unwrap();
+    let mut accounts = Vec::new();
+    for (user, balance) in users.into_iter().zip(balances) {
+        let account = AccountService::new()
+            .create(
+                db_pool,
+                &mut tx,
+                &user.id,
+                AccountCreate {
+                    user_id: user.id,
+                    bank_id: None,
+                    bank_account_number: None,
+                    bank_account_digit: None,
+                    bank_agency_number: None,
+                    bank_agency_digit: None,
+                    bank_account_type: None,
+                    balance: *balance,
+                },
+            )
+            .await
+            .unwrap();
+        accounts.push((user, account));
+    }
+    tx.commit().await.unwrap();
+
+    accounts
+}
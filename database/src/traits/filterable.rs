@@ -5,6 +5,8 @@ macro_rules! impl_filterable {
         exact = [$($exact_field:ident),*],
         range = [$($range_field:ident),*],
         multi_match = [ $( ( $value_field:ident, [ $( $table_field:ident ),* $(,)? ] ) ),* $(,)? ],
+        search = [ $( ( $search_field:ident, [ $( $search_column:ident ),* $(,)? ] ) ),* $(,)? ],
+        soft_delete = [ $( $include_deleted_field:ident )? ],
         order_by = [ $( ($order_field:ident, $order_direction:ident) ),* $(,)? ]
     ) => {
         use sqlx::{postgres::PgArguments, Arguments};
@@ -47,6 +49,23 @@ macro_rules! impl_filterable {
                     }
                 )*
 
+                $(
+                    if self.$search_field.is_some() {
+                        let mut search_conditions = Vec::new();
+                        $(
+                            search_conditions.push(format!("{} ILIKE ${}", stringify!($search_column), conditions.len() + 1));
+                        )*
+                        conditions.push(format!("({})", search_conditions.join(" OR ")));
+                    }
+                )*
+
+                $(
+                    // takes no argument, so it goes after every numbered condition
+                    if self.$include_deleted_field != Some(true) {
+                        conditions.push("deleted_at IS NULL".to_string());
+                    }
+                )?
+
                 let where_clause = if conditions.is_empty() {
                     String::new()
                 } else {
@@ -113,6 +132,23 @@ macro_rules! impl_filterable {
                     }
                 )*
 
+                $(
+                    if self.$search_field.is_some() {
+                        let mut search_conditions = Vec::new();
+                        $(
+                            search_conditions.push(format!("{} ILIKE ${}", stringify!($search_column), conditions.len() + 1));
+                        )*
+                        conditions.push(format!("({})", search_conditions.join(" OR ")));
+                    }
+                )*
+
+                $(
+                    // takes no argument, so it goes after every numbered condition
+                    if self.$include_deleted_field != Some(true) {
+                        conditions.push("deleted_at IS NULL".to_string());
+                    }
+                )?
+
                 let where_clause = if conditions.is_empty() {
                     String::new()
                 } else {
@@ -152,6 +188,17 @@ macro_rules! impl_filterable {
                     }
                 )*
 
+                $(
+                    if let Some(ref value) = self.$search_field {
+                        // matches the text anywhere, taking wildcards in it literally
+                        let escaped = value
+                            .replace('\\', "\\\\")
+                            .replace('%', "\\%")
+                            .replace('_', "\\_");
+                        let _ = args.add(format!("%{}%", escaped));
+                    }
+                )*
+
                 args
             }
 
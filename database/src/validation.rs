@@ -0,0 +1,314 @@
+//! Check digits of bank agencies and accounts. Each bank computes them its own way, so the
+//! algorithm is picked by `bank_id`; banks not listed here only need digits from 0 to 9.
+//!
+//! Some banks print a check digit of 10 as a letter (X at Banco do Brasil, P at Bradesco),
+//! which is given as 10.
+
+use thiserror::Error;
+
+pub const BANCO_DO_BRASIL: i32 = 1;
+pub const CAIXA: i32 = 104;
+pub const BRADESCO: i32 = 237;
+pub const ITAU: i32 = 341;
+
+const AGENCY_LENGTH: u32 = 4;
+
+/// A field of the bank details that failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("Invalid bank details")]
+pub struct InvalidBankDetails(pub Vec<FieldError>);
+
+/// The agency and account an account is held under.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BankDetails {
+    pub bank_id: Option<i32>,
+    pub agency_number: Option<i32>,
+    pub agency_digit: Option<i32>,
+    pub account_number: Option<i64>,
+    pub account_digit: Option<i32>,
+}
+
+/// How a bank numbers its agencies and accounts.
+struct Layout {
+    name: &'static str,
+    account_length: u32,
+    /// Left out for banks whose agencies have no check digit.
+    agency_digit: Option<fn(&[u32]) -> i32>,
+    /// Works out the check digit of an account from its agency and number.
+    account_digit: fn(&[u32], &[u32]) -> i32,
+}
+
+fn layout(bank_id: i32) -> Option<Layout> {
+    match bank_id {
+        BANCO_DO_BRASIL => Some(Layout {
+            name: "Banco do Brasil",
+            account_length: 8,
+            agency_digit: Some(|agency| mod11(agency, &[5, 4, 3, 2])),
+            account_digit: |_, account| mod11(account, &[9, 8, 7, 6, 5, 4, 3, 2]),
+        }),
+        ITAU => Some(Layout {
+            name: "Itaú",
+            account_length: 5,
+            agency_digit: None,
+            account_digit: |agency, account| mod10(&[agency, account].concat()),
+        }),
+        BRADESCO => Some(Layout {
+            name: "Bradesco",
+            account_length: 7,
+            agency_digit: Some(|agency| mod11(agency, &[5, 4, 3, 2])),
+            account_digit: |_, account| mod11(account, &[2, 7, 6, 5, 4, 3, 2]),
+        }),
+        // the account number starts with the three digits of the operation, such as 001 for
+        // checking and 013 for savings accounts of people
+        CAIXA => Some(Layout {
+            name: "Caixa",
+            account_length: 11,
+            agency_digit: None,
+            account_digit: |agency, account| {
+                let weights = [8, 7, 6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2];
+                let sum: u32 = [agency, account]
+                    .concat()
+                    .iter()
+                    .zip(weights)
+                    .map(|(digit, weight)| digit * weight)
+                    .sum();
+                match sum * 10 % 11 {
+                    10 => 0,
+                    check_digit => check_digit as i32,
+                }
+            },
+        }),
+        _ => None,
+    }
+}
+
+/// Weights the digits from the left; a result of 11 is 0 and one of 10 stays 10.
+fn mod11(digits: &[u32], weights: &[u32]) -> i32 {
+    let sum: u32 = digits.iter().zip(weights).map(|(d, w)| d * w).sum();
+    match 11 - sum % 11 {
+        11 => 0,
+        check_digit => check_digit as i32,
+    }
+}
+
+/// Weights the digits 2, 1, 2, ... from the left, adding up the digits of each product.
+fn mod10(digits: &[u32]) -> i32 {
+    let sum: u32 = digits
+        .iter()
+        .zip([2, 1].iter().cycle())
+        .map(|(digit, weight)| {
+            let product = digit * weight;
+            product / 10 + product % 10
+        })
+        .sum();
+
+    ((10 - sum % 10) % 10) as i32
+}
+
+/// The digits of `number` padded with zeros to `length`, when it fits.
+fn digits(number: i64, length: u32) -> Option<Vec<u32>> {
+    if number < 0 || number >= 10_i64.pow(length) {
+        return None;
+    }
+
+    Some(
+        format!("{:0width$}", number, width = length as usize)
+            .bytes()
+            .map(|b| (b - b'0') as u32)
+            .collect(),
+    )
+}
+
+/// Checks the agency and account of `details` against the rules of their bank, reporting
+/// every field that fails.
+pub fn validate(details: &BankDetails) -> Result<(), InvalidBankDetails> {
+    let mut errors = Vec::new();
+    let mut error =
+        |field: &'static str, message: String| errors.push(FieldError { field, message });
+
+    let Some(layout) = details.bank_id.and_then(layout) else {
+        for (field, digit) in [
+            ("bank_agency_digit", details.agency_digit),
+            ("bank_account_digit", details.account_digit),
+        ] {
+            if digit.is_some_and(|digit| !(0..=9).contains(&digit)) {
+                error(field, "Check digits go from 0 to 9".to_string());
+            }
+        }
+        return finish(errors);
+    };
+
+    let agency = details
+        .agency_number
+        .map(|number| digits(number.into(), AGENCY_LENGTH));
+    match (&agency, layout.agency_digit, details.agency_digit) {
+        (Some(None), _, _) => error(
+            "bank_agency_number",
+            format!(
+                "{} agencies have up to {} digits",
+                layout.name, AGENCY_LENGTH
+            ),
+        ),
+        (Some(Some(agency)), Some(check), Some(digit)) if digit != check(agency) => error(
+            "bank_agency_digit",
+            "Check digit does not match the agency number".to_string(),
+        ),
+        (Some(_), Some(_), None) => error(
+            "bank_agency_digit",
+            format!("{} agencies have a check digit", layout.name),
+        ),
+        (_, None, Some(_)) => error(
+            "bank_agency_digit",
+            format!("{} agencies have no check digit", layout.name),
+        ),
+        _ => {}
+    }
+
+    let Some(account_number) = details.account_number else {
+        if details.account_digit.is_some() {
+            error(
+                "bank_account_number",
+                "A check digit needs its account number".to_string(),
+            );
+        }
+        return finish(errors);
+    };
+    let Some(account) = digits(account_number, layout.account_length) else {
+        error(
+            "bank_account_number",
+            format!(
+                "{} accounts have up to {} digits",
+                layout.name, layout.account_length
+            ),
+        );
+        return finish(errors);
+    };
+    match (details.account_digit, &agency) {
+        (None, _) => error(
+            "bank_account_digit",
+            format!("{} accounts have a check digit", layout.name),
+        ),
+        // accounts whose digit covers the agency cannot be checked without it
+        (Some(_), None) if layout.agency_digit.is_none() => error(
+            "bank_agency_number",
+            format!(
+                "{} accounts are checked along with their agency",
+                layout.name
+            ),
+        ),
+        (Some(digit), agency) => {
+            let agency = match agency {
+                Some(Some(agency)) => agency.as_slice(),
+                _ => &[],
+            };
+            if digit != (layout.account_digit)(agency, &account) {
+                error(
+                    "bank_account_digit",
+                    "Check digit does not match the account number".to_string(),
+                );
+            }
+        }
+    }
+
+    finish(errors)
+}
+
+fn finish(errors: Vec<FieldError>) -> Result<(), InvalidBankDetails> {
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(InvalidBankDetails(errors)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(bank_id: i32, agency: (i32, Option<i32>), account: (i64, i32)) -> BankDetails {
+        BankDetails {
+            bank_id: Some(bank_id),
+            agency_number: Some(agency.0),
+            agency_digit: agency.1,
+            account_number: Some(account.0),
+            account_digit: Some(account.1),
+        }
+    }
+
+    fn fields(result: Result<(), InvalidBankDetails>) -> Vec<&'static str> {
+        result
+            .unwrap_err()
+            .0
+            .into_iter()
+            .map(|error| error.field)
+            .collect()
+    }
+
+    #[test]
+    fn test_check_digits_of_each_bank() {
+        for valid in [
+            details(BANCO_DO_BRASIL, (1584, Some(9)), (210169, 6)),
+            details(ITAU, (2545, None), (2366, 1)),
+            details(BRADESCO, (1425, Some(7)), (238069, 2)),
+            details(CAIXA, (2004, None), (100000448, 6)),
+            // operation 023, past what a 32-bit account number holds
+            details(CAIXA, (2004, None), (2300012345, 1)),
+        ] {
+            assert_eq!(validate(&valid), Ok(()), "{:?}", valid);
+        }
+
+        assert_eq!(
+            fields(validate(&details(
+                BANCO_DO_BRASIL,
+                (1584, Some(8)),
+                (210169, 5)
+            ))),
+            ["bank_agency_digit", "bank_account_digit"]
+        );
+        assert_eq!(
+            fields(validate(&details(ITAU, (2545, None), (2366, 2)))),
+            ["bank_account_digit"]
+        );
+        assert_eq!(
+            fields(validate(&details(ITAU, (2545, Some(1)), (123456, 1)))),
+            ["bank_agency_digit", "bank_account_number"]
+        );
+        assert_eq!(
+            fields(validate(&details(CAIXA, (2004, None), (100000448, 7)))),
+            ["bank_account_digit"]
+        );
+    }
+
+    #[test]
+    fn test_letter_digits_are_given_as_ten() {
+        // 6 * 2 = 12, and 11 - 12 % 11 = 10
+        assert_eq!(mod11(&[0, 0, 0, 6], &[5, 4, 3, 2]), 10);
+
+        let bradesco = |agency_digit| BankDetails {
+            bank_id: Some(BRADESCO),
+            agency_number: Some(6),
+            agency_digit: Some(agency_digit),
+            ..Default::default()
+        };
+        assert_eq!(validate(&bradesco(10)), Ok(()));
+        assert_eq!(fields(validate(&bradesco(1))), ["bank_agency_digit"]);
+    }
+
+    #[test]
+    fn test_other_banks_only_need_decimal_digits() {
+        let other = BankDetails {
+            bank_id: Some(33),
+            agency_number: Some(1),
+            agency_digit: Some(12),
+            account_number: Some(1),
+            account_digit: Some(3),
+        };
+        assert_eq!(fields(validate(&other)), ["bank_agency_digit"]);
+        assert_eq!(validate(&BankDetails::default()), Ok(()));
+    }
+}
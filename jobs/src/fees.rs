@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use database::services::fee::Service as FeeService;
+use sqlx::PgPool;
+
+use crate::scheduler::{interval_from_env, Job};
+
+/// Runs hourly unless `MONTHLY_FEES_INTERVAL_SECS` says otherwise; each account is only
+/// charged once a month, however often it runs.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Charges the monthly maintenance fee of the accounts' fee schedules.
+pub struct MonthlyFeeJob {
+    interval: Duration,
+}
+
+impl Default for MonthlyFeeJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MonthlyFeeJob {
+    pub fn new() -> Self {
+        Self {
+            interval: interval_from_env("MONTHLY_FEES_INTERVAL_SECS", DEFAULT_INTERVAL),
+        }
+    }
+}
+
+#[async_trait]
+impl Job for MonthlyFeeJob {
+    fn name(&self) -> &'static str {
+        "monthly_fees"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, db_pool: &PgPool) -> anyhow::Result<()> {
+        let summary = FeeService::new()
+            .charge_monthly_fees(db_pool, chrono::Utc::now().date_naive())
+            .await?;
+        if summary.total() > 0 {
+            println!(
+                "Settled {} monthly fees: {} charged, {} waived, {} skipped for lack of funds, {} failed.",
+                summary.total(),
+                summary.charged,
+                summary.waived,
+                summary.skipped,
+                summary.failed
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use database::services::hold::Service as HoldService;
+use sqlx::PgPool;
+
+use crate::scheduler::{interval_from_env, Job};
+
+/// Runs every minute unless `HOLD_EXPIRY_INTERVAL_SECS` says otherwise.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Marks holds past their expiry as expired.
+///
+/// Expired holds stop reserving funds as soon as they pass `expires_at`; this only
+/// settles their status.
+pub struct HoldExpiryJob {
+    interval: Duration,
+}
+
+impl Default for HoldExpiryJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HoldExpiryJob {
+    pub fn new() -> Self {
+        Self {
+            interval: interval_from_env("HOLD_EXPIRY_INTERVAL_SECS", DEFAULT_INTERVAL),
+        }
+    }
+}
+
+#[async_trait]
+impl Job for HoldExpiryJob {
+    fn name(&self) -> &'static str {
+        "hold_expiry"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, db_pool: &PgPool) -> anyhow::Result<()> {
+        let expired = HoldService::new().expire(db_pool).await?;
+        if expired > 0 {
+            println!("Expired {} holds.", expired);
+        }
+
+        Ok(())
+    }
+}
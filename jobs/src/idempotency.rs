@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use database::services::idempotency::Service as IdempotencyService;
+use sqlx::PgPool;
+
+use crate::scheduler::{interval_from_env, Job};
+
+/// Runs every hour unless `IDEMPOTENCY_CLEANUP_INTERVAL_SECS` says otherwise.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Deletes idempotency keys whose replay window has passed.
+pub struct IdempotencyCleanupJob {
+    interval: Duration,
+}
+
+impl Default for IdempotencyCleanupJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdempotencyCleanupJob {
+    pub fn new() -> Self {
+        Self {
+            interval: interval_from_env("IDEMPOTENCY_CLEANUP_INTERVAL_SECS", DEFAULT_INTERVAL),
+        }
+    }
+}
+
+#[async_trait]
+impl Job for IdempotencyCleanupJob {
+    fn name(&self) -> &'static str {
+        "idempotency_cleanup"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, db_pool: &PgPool) -> anyhow::Result<()> {
+        let purged = IdempotencyService::new().purge_expired(db_pool).await?;
+        if purged > 0 {
+            println!("Purged {} expired idempotency keys.", purged);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use database::services::interest::Service as InterestService;
+use sqlx::PgPool;
+
+use crate::scheduler::{interval_from_env, Job};
+
+/// Runs hourly unless `INTEREST_ACCRUAL_INTERVAL_SECS` says otherwise; each run only
+/// accrues the days that have fully gone by, so running more often than daily is harmless.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Accrues interest on every account with an interest product and pays out what is due.
+pub struct InterestAccrualJob {
+    interval: Duration,
+}
+
+impl Default for InterestAccrualJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InterestAccrualJob {
+    pub fn new() -> Self {
+        Self {
+            interval: interval_from_env("INTEREST_ACCRUAL_INTERVAL_SECS", DEFAULT_INTERVAL),
+        }
+    }
+}
+
+#[async_trait]
+impl Job for InterestAccrualJob {
+    fn name(&self) -> &'static str {
+        "interest_accrual"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, db_pool: &PgPool) -> anyhow::Result<()> {
+        let summary = InterestService::new()
+            .accrue_all(db_pool, chrono::Utc::now().date_naive())
+            .await?;
+        if summary.payouts > 0 || summary.failed > 0 {
+            println!(
+                "Accrued interest on {} accounts: {} payouts posted, {} failed.",
+                summary.accounts, summary.payouts, summary.failed
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -1,14 +1,8 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub mod fees;
+pub mod holds;
+pub mod idempotency;
+pub mod interest;
+pub mod overdrafts;
+pub mod reconciliation;
+pub mod scheduler;
+pub mod schedules;
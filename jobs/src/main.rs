@@ -0,0 +1,28 @@
+use database::{get_database_pool, load_master_key};
+use dotenv::dotenv;
+use jobs::{
+    fees::MonthlyFeeJob, holds::HoldExpiryJob, idempotency::IdempotencyCleanupJob,
+    interest::InterestAccrualJob, overdrafts::OverdraftInterestJob,
+    reconciliation::ReconciliationJob, scheduler::Scheduler, schedules::ScheduledTransferJob,
+};
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+
+    let db_pool = get_database_pool(Some(1), Some(4)).await;
+    load_master_key().expect("Failed to load master key");
+
+    println!("Starting jobs...");
+
+    Scheduler::new(db_pool)
+        .with_job(ReconciliationJob::new())
+        .with_job(IdempotencyCleanupJob::new())
+        .with_job(HoldExpiryJob::new())
+        .with_job(ScheduledTransferJob::new())
+        .with_job(InterestAccrualJob::new())
+        .with_job(MonthlyFeeJob::new())
+        .with_job(OverdraftInterestJob::new())
+        .run()
+        .await;
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use database::services::overdraft::Service as OverdraftService;
+use sqlx::PgPool;
+
+use crate::scheduler::{interval_from_env, Job};
+
+/// Runs hourly unless `OVERDRAFT_INTEREST_INTERVAL_SECS` says otherwise; each run only
+/// accrues the days that have fully gone by.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Accrues interest on overdrawn balances and charges it on the first day of each month.
+pub struct OverdraftInterestJob {
+    interval: Duration,
+}
+
+impl Default for OverdraftInterestJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OverdraftInterestJob {
+    pub fn new() -> Self {
+        Self {
+            interval: interval_from_env("OVERDRAFT_INTEREST_INTERVAL_SECS", DEFAULT_INTERVAL),
+        }
+    }
+}
+
+#[async_trait]
+impl Job for OverdraftInterestJob {
+    fn name(&self) -> &'static str {
+        "overdraft_interest"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, db_pool: &PgPool) -> anyhow::Result<()> {
+        let summary = OverdraftService::new()
+            .charge_all(db_pool, chrono::Utc::now().date_naive())
+            .await?;
+        if summary.charged > 0 || summary.failed > 0 {
+            println!(
+                "Accrued overdraft interest on {} accounts: {} charged, {} failed.",
+                summary.accounts, summary.charged, summary.failed
+            );
+        }
+
+        Ok(())
+    }
+}
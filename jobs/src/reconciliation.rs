@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use database::services::reconciliation::Service as ReconciliationService;
+use sqlx::PgPool;
+
+use crate::scheduler::{interval_from_env, Job};
+
+/// Runs once a day unless `RECONCILIATION_INTERVAL_SECS` says otherwise.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Recomputes every account balance from its history and reports the ones that drifted.
+pub struct ReconciliationJob {
+    interval: Duration,
+}
+
+impl Default for ReconciliationJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReconciliationJob {
+    pub fn new() -> Self {
+        Self {
+            interval: interval_from_env("RECONCILIATION_INTERVAL_SECS", DEFAULT_INTERVAL),
+        }
+    }
+}
+
+#[async_trait]
+impl Job for ReconciliationJob {
+    fn name(&self) -> &'static str {
+        "reconciliation"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, db_pool: &PgPool) -> anyhow::Result<()> {
+        let run = ReconciliationService::new().reconcile_all(db_pool).await?;
+        let discrepancies: Vec<_> = run
+            .reports
+            .iter()
+            .filter(|report| !report.is_consistent())
+            .collect();
+
+        for report in &discrepancies {
+            eprintln!(
+                "Account {} balance is {} but its {} transactions add up to {} (difference {}).",
+                report.account_id,
+                report.actual_balance,
+                report.transactions,
+                report.expected_balance,
+                report.difference
+            );
+        }
+        for failure in &run.failures {
+            eprintln!(
+                "Account {} could not be reconciled: {}",
+                failure.account_id, failure.error
+            );
+        }
+        println!(
+            "Reconciled {} accounts, {} with discrepancies, {} failed.",
+            run.reports.len(),
+            discrepancies.len(),
+            run.failures.len()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tokio::{signal::ctrl_c, task::JoinSet, time::MissedTickBehavior};
+
+#[async_trait]
+pub trait Job: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// How long to wait between two runs; the first run starts right away.
+    fn interval(&self) -> Duration;
+
+    async fn run(&self, db_pool: &PgPool) -> anyhow::Result<()>;
+}
+
+/// Reads an interval in seconds from `var`, falling back to `default` when unset or invalid.
+pub fn interval_from_env(var: &str, default: Duration) -> Duration {
+    std::env::var(var)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(default)
+}
+
+pub struct Scheduler {
+    db_pool: PgPool,
+    jobs: Vec<Arc<dyn Job>>,
+}
+
+impl Scheduler {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            jobs: Vec::new(),
+        }
+    }
+
+    pub fn with_job(mut self, job: impl Job + 'static) -> Self {
+        self.jobs.push(Arc::new(job));
+        self
+    }
+
+    /// Runs every job on its own interval until CTRL-C is received.
+    ///
+    /// A failing run is reported and retried on the next tick; runs of the same
+    /// job never overlap, a tick that fires while the job is still busy is skipped.
+    pub async fn run(self) {
+        let mut tasks = JoinSet::new();
+
+        for job in self.jobs {
+            let db_pool = self.db_pool.clone();
+            tasks.spawn(async move {
+                let mut interval = tokio::time::interval(job.interval());
+                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = job.run(&db_pool).await {
+                        eprintln!("Job {} failed: {}", job.name(), e);
+                    }
+                }
+            });
+        }
+
+        ctrl_c().await.expect("Failed to install CTRL-C handler");
+
+        tasks.abort_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_from_env_falls_back_to_default() {
+        let default = Duration::from_secs(60);
+
+        std::env::set_var("JOBS_TEST_INTERVAL", "15");
+        assert_eq!(
+            interval_from_env("JOBS_TEST_INTERVAL", default),
+            Duration::from_secs(15)
+        );
+
+        std::env::set_var("JOBS_TEST_INTERVAL", "0");
+        assert_eq!(interval_from_env("JOBS_TEST_INTERVAL", default), default);
+
+        std::env::set_var("JOBS_TEST_INTERVAL", "soon");
+        assert_eq!(interval_from_env("JOBS_TEST_INTERVAL", default), default);
+
+        std::env::remove_var("JOBS_TEST_INTERVAL");
+        assert_eq!(interval_from_env("JOBS_TEST_INTERVAL", default), default);
+    }
+}
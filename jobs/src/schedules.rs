@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use database::services::schedule::Service as ScheduleService;
+use sqlx::PgPool;
+
+use crate::scheduler::{interval_from_env, Job};
+
+/// Runs every minute unless `SCHEDULED_TRANSFERS_INTERVAL_SECS` says otherwise.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Executes the scheduled and recurring transfers that are due.
+pub struct ScheduledTransferJob {
+    interval: Duration,
+}
+
+impl Default for ScheduledTransferJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScheduledTransferJob {
+    pub fn new() -> Self {
+        Self {
+            interval: interval_from_env("SCHEDULED_TRANSFERS_INTERVAL_SECS", DEFAULT_INTERVAL),
+        }
+    }
+}
+
+#[async_trait]
+impl Job for ScheduledTransferJob {
+    fn name(&self) -> &'static str {
+        "scheduled_transfers"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, db_pool: &PgPool) -> anyhow::Result<()> {
+        let summary = ScheduleService::new().run_due(db_pool).await?;
+        if summary.total() > 0 {
+            println!(
+                "Ran {} scheduled transfers: {} executed, {} skipped for lack of funds, {} to retry, {} failed.",
+                summary.total(),
+                summary.executed,
+                summary.skipped,
+                summary.retrying,
+                summary.failed
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+-- Ciphertexts already re-encrypted as minor units cannot be read as f64 again.
+DROP INDEX IF EXISTS transactions_amount_encoding_idx;
+DROP INDEX IF EXISTS accounts_balance_encoding_idx;
+ALTER TABLE transactions DROP COLUMN amount_encoding;
+ALTER TABLE accounts DROP COLUMN balance_encoding;
@@ -0,0 +1,10 @@
+-- 0 = legacy f64 payload, 1 = i64 minor units (cents).
+-- Existing rows keep the legacy marker until the api re-encrypts them on startup.
+ALTER TABLE accounts ADD COLUMN balance_encoding SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE accounts ALTER COLUMN balance_encoding SET DEFAULT 1;
+
+ALTER TABLE transactions ADD COLUMN amount_encoding SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE transactions ALTER COLUMN amount_encoding SET DEFAULT 1;
+
+CREATE INDEX accounts_balance_encoding_idx ON accounts(balance_encoding) WHERE balance_encoding = 0;
+CREATE INDEX transactions_amount_encoding_idx ON transactions(amount_encoding) WHERE amount_encoding = 0;
@@ -0,0 +1,4 @@
+DROP TABLE journal_postings;
+DROP TABLE journal_entries;
+DROP TYPE posting_direction;
+DROP TYPE system_account;
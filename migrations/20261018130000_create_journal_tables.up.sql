@@ -0,0 +1,35 @@
+CREATE TYPE system_account AS ENUM (
+    'fees',
+    'interest',
+    'cash_in',
+    'cash_out'
+);
+
+CREATE TYPE posting_direction AS ENUM (
+    'debit',
+    'credit'
+);
+
+CREATE TABLE journal_entries (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL UNIQUE REFERENCES transactions(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Each posting belongs to exactly one customer account or one system account.
+-- Amounts are encrypted with the account owner's key, or the master key for system accounts.
+CREATE TABLE journal_postings (
+    id UUID PRIMARY KEY,
+    journal_entry_id UUID NOT NULL REFERENCES journal_entries(id) ON DELETE CASCADE,
+    account_id UUID NULL REFERENCES accounts(id),
+    system_account system_account NULL,
+    direction posting_direction NOT NULL,
+    amount BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((account_id IS NULL) <> (system_account IS NULL))
+);
+
+CREATE INDEX journal_entries_created_at_idx ON journal_entries(created_at);
+CREATE INDEX journal_postings_journal_entry_id_idx ON journal_postings(journal_entry_id);
+CREATE INDEX journal_postings_account_id_idx ON journal_postings(account_id);
+CREATE INDEX journal_postings_system_account_idx ON journal_postings(system_account);
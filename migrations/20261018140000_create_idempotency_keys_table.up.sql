@@ -0,0 +1,13 @@
+-- Keys are scoped to the user that sent them. The response is kept as a reference to the
+-- transaction it created, so replays are rebuilt from the encrypted row instead of stored in clear.
+CREATE TABLE idempotency_keys (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    key VARCHAR(255) NOT NULL,
+    fingerprint BYTEA NOT NULL,
+    transaction_id UUID NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
+
+CREATE INDEX idempotency_keys_expires_at_idx ON idempotency_keys(expires_at);
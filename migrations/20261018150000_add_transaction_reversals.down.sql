@@ -0,0 +1,14 @@
+DELETE FROM transactions WHERE reverses_transaction_id IS NOT NULL;
+ALTER TABLE transactions DROP COLUMN reverses_transaction_id;
+
+ALTER TYPE transaction_operation RENAME TO transaction_operation_old;
+CREATE TYPE transaction_operation AS ENUM (
+    'deposit',
+    'withdrawal',
+    'transfer',
+    'payment',
+    'fee',
+    'interest'
+);
+ALTER TABLE transactions ALTER COLUMN operation TYPE transaction_operation USING operation::text::transaction_operation;
+DROP TYPE transaction_operation_old;
@@ -0,0 +1,9 @@
+ALTER TYPE transaction_operation ADD VALUE 'reversal';
+
+-- A reversal keeps the accounts of the transaction it reverses and undoes all or part of its amount.
+-- The new enum value cannot be used until this migration commits, hence the text comparison.
+ALTER TABLE transactions ADD COLUMN reverses_transaction_id UUID NULL REFERENCES transactions(id) ON DELETE CASCADE;
+ALTER TABLE transactions ADD CONSTRAINT transactions_reversal_check
+    CHECK ((operation::text = 'reversal') = (reverses_transaction_id IS NOT NULL));
+
+CREATE INDEX transactions_reverses_transaction_id_idx ON transactions(reverses_transaction_id);
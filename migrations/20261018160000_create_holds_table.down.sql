@@ -0,0 +1,2 @@
+DROP TABLE holds;
+DROP TYPE hold_status;
@@ -0,0 +1,24 @@
+CREATE TYPE hold_status AS ENUM (
+    'active',
+    'captured',
+    'released',
+    'expired'
+);
+
+-- Amounts are encrypted with the key of the held account's owner.
+-- A hold with a to_account_id is captured as a transfer to it, otherwise as a payment.
+CREATE TABLE holds (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    to_account_id UUID NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    amount BYTEA NOT NULL,
+    captured_amount BYTEA NULL,
+    status hold_status NOT NULL DEFAULT 'active',
+    transaction_id UUID NULL REFERENCES transactions(id) ON DELETE SET NULL,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL
+);
+
+CREATE INDEX holds_account_id_idx ON holds(account_id);
+CREATE INDEX holds_active_expires_at_idx ON holds(expires_at) WHERE status = 'active';
@@ -0,0 +1,5 @@
+DROP TABLE notifications;
+DROP TYPE notification_kind;
+DROP TABLE schedules;
+DROP TYPE schedule_status;
+DROP TYPE schedule_frequency;
@@ -0,0 +1,61 @@
+CREATE TYPE schedule_frequency AS ENUM (
+    'once',
+    'daily',
+    'weekly',
+    'monthly',
+    'cron'
+);
+
+CREATE TYPE schedule_status AS ENUM (
+    'active',
+    'paused',
+    'completed',
+    'failed'
+);
+
+-- Amounts are encrypted with the key of the schedule owner, who also owns from_account_id.
+-- next_run_at is the next regular run; retry_at, when set, brings a failed run forward.
+CREATE TABLE schedules (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    from_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    to_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    amount BYTEA NOT NULL,
+    frequency schedule_frequency NOT NULL,
+    cron_expression TEXT NULL,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NULL,
+    next_run_at TIMESTAMP NULL,
+    retry_at TIMESTAMP NULL,
+    status schedule_status NOT NULL DEFAULT 'active',
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT NULL,
+    last_run_at TIMESTAMP NULL,
+    last_transaction_id UUID NULL REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL,
+    CONSTRAINT schedules_cron_expression_check
+        CHECK ((frequency = 'cron') = (cron_expression IS NOT NULL)),
+    CONSTRAINT schedules_distinct_accounts_check
+        CHECK (from_account_id <> to_account_id)
+);
+
+CREATE INDEX schedules_user_id_idx ON schedules(user_id);
+CREATE INDEX schedules_due_idx ON schedules(COALESCE(retry_at, next_run_at)) WHERE status = 'active';
+
+CREATE TYPE notification_kind AS ENUM (
+    'schedule_skipped',
+    'schedule_failed'
+);
+
+CREATE TABLE notifications (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind notification_kind NOT NULL,
+    message TEXT NOT NULL,
+    schedule_id UUID NULL REFERENCES schedules(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    read_at TIMESTAMP NULL
+);
+
+CREATE INDEX notifications_user_id_idx ON notifications(user_id, created_at DESC);
@@ -0,0 +1,4 @@
+DROP TABLE account_interest;
+DROP TABLE interest_products;
+DROP TYPE compounding_frequency;
+DROP TYPE day_count_convention;
@@ -0,0 +1,38 @@
+CREATE TYPE day_count_convention AS ENUM (
+    'actual_360',
+    'actual_365',
+    'actual_actual',
+    'thirty_360'
+);
+
+CREATE TYPE compounding_frequency AS ENUM (
+    'daily',
+    'monthly',
+    'quarterly',
+    'annually'
+);
+
+-- annual_rate_bps is the nominal annual rate in basis points (hundredths of a percent).
+CREATE TABLE interest_products (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    annual_rate_bps INTEGER NOT NULL CHECK (annual_rate_bps >= 0),
+    day_count day_count_convention NOT NULL,
+    compounding compounding_frequency NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL
+);
+
+-- accrued holds interest earned but not paid yet, in millionths of a minor unit, encrypted
+-- with the key of the account owner. Every day up to accrued_through has been accrued.
+CREATE TABLE account_interest (
+    account_id UUID PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES interest_products(id) ON DELETE RESTRICT,
+    accrued BYTEA NOT NULL,
+    accrued_through DATE NOT NULL,
+    next_payout_on DATE NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL
+);
+
+CREATE INDEX account_interest_product_id_idx ON account_interest(product_id);
@@ -0,0 +1,2 @@
+DROP TABLE fee_charges;
+DROP TABLE fee_schedules;
@@ -0,0 +1,27 @@
+-- A fee schedule applies to the accounts of its bank_account_type; the one without a type is
+-- the default for every other account. Amounts are plain minor units: they belong to the
+-- bank, not to any customer. operation_fees maps operations to a flat fee and waivers lists
+-- the share of every fee waived from a minimum balance up.
+CREATE TABLE fee_schedules (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    bank_account_type INTEGER NULL UNIQUE,
+    monthly_fee BIGINT NOT NULL DEFAULT 0 CHECK (monthly_fee >= 0),
+    operation_fees JSONB NOT NULL DEFAULT '[]',
+    waivers JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL
+);
+
+CREATE UNIQUE INDEX fee_schedules_default_idx ON fee_schedules ((bank_account_type IS NULL))
+    WHERE bank_account_type IS NULL;
+
+-- One row per account and month whose maintenance fee was settled, so the job charges it
+-- once. transaction_id is NULL when the fee was waived.
+CREATE TABLE fee_charges (
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    period DATE NOT NULL,
+    transaction_id UUID NULL REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (account_id, period)
+);
@@ -0,0 +1,5 @@
+DROP TABLE overdraft_interest;
+
+ALTER TABLE accounts
+    DROP COLUMN overdraft_rate_bps,
+    DROP COLUMN overdraft_limit;
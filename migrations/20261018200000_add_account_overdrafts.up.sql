@@ -0,0 +1,14 @@
+ALTER TABLE accounts
+    ADD COLUMN overdraft_limit BIGINT NOT NULL DEFAULT 0 CHECK (overdraft_limit >= 0),
+    ADD COLUMN overdraft_rate_bps INTEGER NOT NULL DEFAULT 0 CHECK (overdraft_rate_bps >= 0);
+
+-- accrued holds overdraft interest owed but not charged yet, in millionths of a minor unit,
+-- encrypted with the key of the account owner. Every day up to accrued_through has been accrued.
+CREATE TABLE overdraft_interest (
+    account_id UUID PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+    accrued BYTEA NOT NULL,
+    accrued_through DATE NOT NULL,
+    next_charge_on DATE NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL
+);
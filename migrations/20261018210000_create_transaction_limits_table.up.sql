@@ -0,0 +1,21 @@
+-- A limit caps the amount of one operation an account may initiate, per operation and over a
+-- rolling day. It applies to one account, to every account of one user, or, with neither set,
+-- to every user; the most specific limit for an operation is the one enforced. Amounts are
+-- plain minor units: they are bank policy, not customer data.
+CREATE TABLE transaction_limits (
+    id UUID PRIMARY KEY,
+    account_id UUID NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    user_id UUID NULL REFERENCES users(id) ON DELETE CASCADE,
+    operation transaction_operation NOT NULL,
+    per_operation BIGINT NULL CHECK (per_operation > 0),
+    daily BIGINT NULL CHECK (daily > 0),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL,
+    CHECK (account_id IS NULL OR user_id IS NULL),
+    CHECK (per_operation IS NOT NULL OR daily IS NOT NULL)
+);
+
+CREATE UNIQUE INDEX transaction_limits_scope_idx ON transaction_limits (
+    COALESCE(account_id, user_id, '00000000-0000-0000-0000-000000000000'),
+    operation
+);
@@ -0,0 +1,3 @@
+DROP TABLE remittance_items;
+DROP TABLE remittances;
+DROP TYPE remittance_status;
@@ -0,0 +1,28 @@
+CREATE TYPE remittance_status AS ENUM (
+    'pending',
+    'scheduled',
+    'paid',
+    'rejected'
+);
+
+-- A CNAB 240 remittance file sent to the bank of an account, numbered per account.
+CREATE TABLE remittances (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    file_sequence INTEGER NOT NULL CHECK (file_sequence > 0),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (account_id, file_sequence)
+);
+
+-- A transfer sent in a remittance, with what the bank's return files said about it.
+-- A transfer is remitted once.
+CREATE TABLE remittance_items (
+    remittance_id UUID NOT NULL REFERENCES remittances(id) ON DELETE CASCADE,
+    sequence INTEGER NOT NULL CHECK (sequence > 0),
+    transaction_id UUID NOT NULL UNIQUE REFERENCES transactions(id) ON DELETE CASCADE,
+    status remittance_status NOT NULL DEFAULT 'pending',
+    bank_reference VARCHAR(20) NULL,
+    occurrences VARCHAR(10) NULL,
+    updated_at TIMESTAMP NULL,
+    PRIMARY KEY (remittance_id, sequence)
+);
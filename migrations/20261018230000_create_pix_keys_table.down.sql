@@ -0,0 +1,2 @@
+DROP TABLE pix_keys;
+DROP TYPE pix_key_type;
@@ -0,0 +1,14 @@
+-- A payment key is an alias that names the account a transfer goes to: the owner's email, a
+-- phone number, a tax ID (CPF or CNPJ) or a random key the bank generates. Keys are stored in
+-- their normalized form, so each one points to a single account.
+CREATE TYPE pix_key_type AS ENUM ('email', 'phone', 'tax_id', 'random');
+
+CREATE TABLE pix_keys (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    key_type pix_key_type NOT NULL,
+    key VARCHAR(77) NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX pix_keys_account_id_idx ON pix_keys(account_id);
@@ -0,0 +1,3 @@
+DROP TABLE boleto_payments;
+DROP TABLE boletos;
+DROP TYPE boleto_status;
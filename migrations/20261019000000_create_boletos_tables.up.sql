@@ -0,0 +1,37 @@
+CREATE TYPE boleto_status AS ENUM ('open', 'paid');
+
+-- A payment slip issued for receiving into an account. Its amount is printed in the barcode,
+-- so it is kept in the clear. Our number counts the slips of each account.
+CREATE TABLE boletos (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    our_number BIGINT NOT NULL CHECK (our_number > 0),
+    barcode VARCHAR(44) NOT NULL UNIQUE,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    due_date DATE NOT NULL,
+    fine_bps INTEGER NOT NULL DEFAULT 0 CHECK (fine_bps BETWEEN 0 AND 10000),
+    interest_bps INTEGER NOT NULL DEFAULT 0 CHECK (interest_bps BETWEEN 0 AND 10000),
+    status boleto_status NOT NULL DEFAULT 'open',
+    transaction_id UUID NULL REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NULL,
+    UNIQUE (account_id, our_number)
+);
+
+-- A slip paid from an account, issued here or by another bank. The transaction moved the
+-- amount plus the fine and interest: to the issuing account for slips issued here, out of the
+-- bank as a payment otherwise.
+CREATE TABLE boleto_payments (
+    transaction_id UUID PRIMARY KEY REFERENCES transactions(id) ON DELETE CASCADE,
+    from_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    boleto_id UUID NULL UNIQUE REFERENCES boletos(id) ON DELETE SET NULL,
+    barcode VARCHAR(44) NOT NULL,
+    payee VARCHAR(100) NOT NULL,
+    due_date DATE NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    fine BIGINT NOT NULL DEFAULT 0 CHECK (fine >= 0),
+    interest BIGINT NOT NULL DEFAULT 0 CHECK (interest >= 0),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX boleto_payments_from_account_id_idx ON boleto_payments(from_account_id);
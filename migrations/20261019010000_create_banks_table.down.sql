@@ -0,0 +1,2 @@
+ALTER TABLE accounts DROP CONSTRAINT accounts_bank_id_fkey;
+DROP TABLE banks;
@@ -0,0 +1,51 @@
+-- Banks accounts can be held at, keyed by their COMPE code and seeded with the largest
+-- participants of the Brazilian payment system. The registry is refreshed from the participant
+-- list the central bank publishes; banks that leave it are kept, inactive, for the accounts
+-- still pointing at them.
+CREATE TABLE banks (
+    id INTEGER PRIMARY KEY,
+    ispb CHAR(8) NULL UNIQUE,
+    short_name VARCHAR(100) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP
+);
+
+CREATE TRIGGER update_banks_updated_at
+BEFORE UPDATE ON banks
+FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at();
+
+INSERT INTO banks (id, ispb, short_name, name) VALUES
+    (1, '00000000', 'BCO DO BRASIL S.A.', 'Banco do Brasil S.A.'),
+    (4, '07237373', 'BCO DO NORDESTE DO BRASIL S.A.', 'Banco do Nordeste do Brasil S.A.'),
+    (33, '90400888', 'BCO SANTANDER (BRASIL) S.A.', 'Banco Santander (Brasil) S.A.'),
+    (41, '92702067', 'BCO DO ESTADO DO RS S.A.', 'Banco do Estado do Rio Grande do Sul S.A.'),
+    (70, '00000208', 'BRB - BCO DE BRASILIA S.A.', 'BRB - Banco de Brasília S.A.'),
+    (77, '00416968', 'BANCO INTER', 'Banco Inter S.A.'),
+    (104, '00360305', 'CAIXA ECONOMICA FEDERAL', 'Caixa Econômica Federal'),
+    (208, '30306294', 'BANCO BTG PACTUAL S.A.', 'Banco BTG Pactual S.A.'),
+    (212, '92894922', 'BANCO ORIGINAL', 'Banco Original S.A.'),
+    (237, '60746948', 'BCO BRADESCO S.A.', 'Banco Bradesco S.A.'),
+    (260, '18236120', 'NU PAGAMENTOS - IP', 'Nu Pagamentos S.A. - Instituição de Pagamento'),
+    (290, '08561701', 'PAGSEGURO INTERNET IP S.A.', 'PagSeguro Internet Instituição de Pagamento S.A.'),
+    (323, '10573521', 'MERCADO PAGO IP LTDA.', 'Mercado Pago Instituição de Pagamento Ltda.'),
+    (336, '31872495', 'BCO C6 S.A.', 'Banco C6 S.A.'),
+    (341, '60701190', 'ITAÚ UNIBANCO S.A.', 'Itaú Unibanco S.A.'),
+    (380, '22896431', 'PICPAY', 'PicPay Instituição de Pagamento S.A.'),
+    (422, '58160789', 'BCO SAFRA S.A.', 'Banco Safra S.A.'),
+    (655, '59588111', 'BCO VOTORANTIM S.A.', 'Banco Votorantim S.A.'),
+    (745, '33479023', 'BCO CITIBANK S.A.', 'Banco Citibank S.A.'),
+    (748, '01181521', 'BCO COOPERATIVO SICREDI S.A.', 'Banco Cooperativo Sicredi S.A.'),
+    (756, '02038232', 'BANCO SICOOB S.A.', 'Banco Cooperativo Sicoob S.A.');
+
+-- accounts may already name banks outside the seed; they stay valid until the registry is
+-- refreshed with them
+INSERT INTO banks (id, short_name, name, active)
+SELECT DISTINCT bank_id, 'BANK ' || bank_id, 'Bank ' || bank_id, FALSE
+FROM accounts
+WHERE bank_id IS NOT NULL
+ON CONFLICT (id) DO NOTHING;
+
+ALTER TABLE accounts ADD CONSTRAINT accounts_bank_id_fkey FOREIGN KEY (bank_id) REFERENCES banks(id);
@@ -0,0 +1,21 @@
+DROP TABLE account_employers;
+
+ALTER TABLE fee_schedules ALTER COLUMN bank_account_type TYPE INTEGER USING (
+    CASE bank_account_type
+        WHEN 'checking' THEN 1
+        WHEN 'savings' THEN 2
+        WHEN 'salary' THEN 3
+        WHEN 'payment' THEN 4
+    END
+);
+
+ALTER TABLE accounts ALTER COLUMN bank_account_type TYPE INTEGER USING (
+    CASE bank_account_type
+        WHEN 'checking' THEN 1
+        WHEN 'savings' THEN 2
+        WHEN 'salary' THEN 3
+        WHEN 'payment' THEN 4
+    END
+);
+
+DROP TYPE account_type;
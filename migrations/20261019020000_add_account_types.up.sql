@@ -0,0 +1,46 @@
+-- Account types replace the integer bank_account_type codes, which map 1 to 4 onto checking,
+-- savings, salary and payment accounts. Any other code stops the migration, to be fixed by hand
+-- rather than silently cleared.
+DO $$
+DECLARE
+    accounts_left BIGINT := (
+        SELECT COUNT(*) FROM accounts WHERE bank_account_type NOT BETWEEN 1 AND 4
+    );
+    fee_schedules_left BIGINT := (
+        SELECT COUNT(*) FROM fee_schedules WHERE bank_account_type NOT BETWEEN 1 AND 4
+    );
+BEGIN
+    IF accounts_left > 0 OR fee_schedules_left > 0 THEN
+        RAISE EXCEPTION '% accounts and % fee schedules have a bank_account_type other than 1 to 4',
+            accounts_left, fee_schedules_left;
+    END IF;
+END
+$$;
+
+CREATE TYPE account_type AS ENUM ('checking', 'savings', 'salary', 'payment');
+
+ALTER TABLE accounts ALTER COLUMN bank_account_type TYPE account_type USING (
+    CASE bank_account_type
+        WHEN 1 THEN 'checking'
+        WHEN 2 THEN 'savings'
+        WHEN 3 THEN 'salary'
+        WHEN 4 THEN 'payment'
+    END
+)::account_type;
+
+ALTER TABLE fee_schedules ALTER COLUMN bank_account_type TYPE account_type USING (
+    CASE bank_account_type
+        WHEN 1 THEN 'checking'
+        WHEN 2 THEN 'savings'
+        WHEN 3 THEN 'salary'
+        WHEN 4 THEN 'payment'
+    END
+)::account_type;
+
+-- The accounts allowed to pay into a salary account.
+CREATE TABLE account_employers (
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    employer_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (account_id, employer_account_id)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE accounts
+    DROP COLUMN closed_at,
+    DROP COLUMN status;
+
+DROP TYPE account_status;
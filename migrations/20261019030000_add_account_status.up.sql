@@ -0,0 +1,6 @@
+-- Accounts are frozen and closed instead of deleted, so their history is always kept.
+CREATE TYPE account_status AS ENUM ('active', 'frozen', 'closing', 'closed');
+
+ALTER TABLE accounts
+    ADD COLUMN status account_status NOT NULL DEFAULT 'active',
+    ADD COLUMN closed_at TIMESTAMP NULL;
@@ -0,0 +1,40 @@
+-- Every change made through the API, in the order it was recorded. Each record carries the
+-- hash of the one before it, so editing or removing a record breaks the chain from there on.
+-- Snapshots are kept as JSON rather than JSONB so they hash back to the same text.
+CREATE TABLE audit_log (
+    sequence BIGINT PRIMARY KEY,
+    actor_id UUID NULL,
+    scopes TEXT[] NOT NULL,
+    action VARCHAR(255) NOT NULL,
+    target_type VARCHAR(50) NULL,
+    target_id UUID NULL,
+    status SMALLINT NOT NULL,
+    before JSON NULL,
+    after JSON NULL,
+    ip VARCHAR(45) NULL,
+    request_id VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    previous_hash CHAR(64) NULL,
+    hash CHAR(64) NOT NULL UNIQUE
+);
+
+CREATE INDEX audit_log_actor_id_idx ON audit_log(actor_id);
+CREATE INDEX audit_log_target_idx ON audit_log(target_type, target_id);
+CREATE INDEX audit_log_created_at_idx ON audit_log(created_at);
+
+CREATE FUNCTION reject_audit_log_changes()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reject_audit_log_changes
+BEFORE UPDATE OR DELETE ON audit_log
+FOR EACH ROW
+    EXECUTE FUNCTION reject_audit_log_changes();
+
+CREATE TRIGGER reject_audit_log_truncate
+BEFORE TRUNCATE ON audit_log
+FOR EACH STATEMENT
+    EXECUTE FUNCTION reject_audit_log_changes();
@@ -0,0 +1,7 @@
+-- The GrpHdr/MsgId of each pain.001 file a user had applied, so the same file is never paid twice.
+CREATE TABLE payment_files (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    message_id VARCHAR(35) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, message_id)
+);
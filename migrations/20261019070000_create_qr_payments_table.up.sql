@@ -0,0 +1,9 @@
+-- Dynamic BR Codes are paid once: the transaction id of each one paid is kept with the account it
+-- paid into, which issued it, and the payment made.
+CREATE TABLE qr_payments (
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    qr_transaction_id VARCHAR(25) NOT NULL,
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (account_id, qr_transaction_id)
+);
@@ -0,0 +1 @@
+ALTER TABLE accounts ALTER COLUMN bank_account_number TYPE INTEGER;
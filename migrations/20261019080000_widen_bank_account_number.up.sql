@@ -0,0 +1,3 @@
+-- Caixa account numbers lead with a three-digit operation, so 11 digits do not fit in an INTEGER
+-- from operation 022 on.
+ALTER TABLE accounts ALTER COLUMN bank_account_number TYPE BIGINT;
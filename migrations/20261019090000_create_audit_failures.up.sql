@@ -0,0 +1,19 @@
+-- Audit entries that could not be appended to audit_log, kept aside with the error so they
+-- can be looked into. Their snapshots are sealed like those of the log.
+CREATE TABLE audit_failures (
+    id UUID PRIMARY KEY,
+    actor_id UUID NULL,
+    scopes TEXT[] NOT NULL,
+    action VARCHAR(255) NOT NULL,
+    target_type VARCHAR(50) NULL,
+    target_id UUID NULL,
+    status SMALLINT NOT NULL,
+    before JSON NULL,
+    after JSON NULL,
+    ip VARCHAR(45) NULL,
+    request_id VARCHAR(255) NOT NULL,
+    error TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX audit_failures_request_id_idx ON audit_failures(request_id);